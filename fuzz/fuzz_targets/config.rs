@@ -0,0 +1,13 @@
+//! Read arbitrary text as config.toml and build the configuration from it,
+//! which must never panic whatever the values.
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use yat::config::{parse_config, Config};
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(text) = std::str::from_utf8(data) {
+        if let Ok(buffer) = parse_config(text) {
+            let _ = buffer.config(Config::default());
+        }
+    }
+});
@@ -0,0 +1,12 @@
+//! Load arbitrary text as a save file, which must either give a list that
+//! saves back to text or an error, and never panic.
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(text) = std::str::from_utf8(data) {
+        if let Ok(root) = yat::parse_list(text) {
+            let _ = root.borrow().save_string();
+        }
+    }
+});
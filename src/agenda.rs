@@ -0,0 +1,250 @@
+/// Printable agenda of the tasks due each day, as plain fixed-width text.
+use crate::due::Due;
+use crate::locale::Locale;
+use crate::todo::{Priority, ToDo, MAX_PRIORITY_LEVELS};
+use chrono::{Duration, NaiveDate};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+/// Width of the agenda, in characters.
+const WIDTH: usize = 80;
+/// Lines per printed page, after which a form feed starts a new page.
+const PAGE_LENGTH: usize = 66;
+/// Indent of task titles, leaving room for the time.
+const INDENT: usize = 13;
+
+/// How many days the agenda covers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Span {
+    Day,
+    Week,
+}
+
+impl Span {
+    /// Number of days in the span.
+    fn days(self) -> i64 {
+        match self {
+            Span::Day => 1,
+            Span::Week => 7,
+        }
+    }
+}
+
+/// A heading followed by the lines of each task under it, where the lines
+/// of a task are kept on the same page. A section starting a new week has
+/// a divider above the heading.
+struct Section {
+    week: Option<String>,
+    heading: String,
+    tasks: Vec<Vec<String>>,
+}
+
+/// Format the agenda of the open tasks under root for a span of days
+/// starting today, with overdue tasks first, split into pages.
+pub(crate) fn agenda(
+    root: &Rc<RefCell<ToDo>>,
+    span: Span,
+    today: NaiveDate,
+    list: &str,
+    locale: &Locale,
+    week_numbers: bool,
+) -> String {
+    let mut tasks = Vec::new();
+    collect(root, &mut tasks);
+    tasks.sort_by_key(|task| {
+        // Tasks with a due time come first, in order
+        let task = task.borrow();
+        let deadline = task.due.map(|due| due.deadline());
+        (deadline.is_none(), deadline, priority_rank(&task.priority))
+    });
+
+    let mut sections = Vec::new();
+    let overdue: Vec<_> = tasks
+        .iter()
+        .filter(|task| task.borrow().due.is_some_and(|due| due.day() < today))
+        .map(|task| entry(&task.borrow(), true, locale))
+        .collect();
+    if !overdue.is_empty() {
+        sections.push(Section {
+            week: None,
+            heading: String::from(locale.tr("Overdue")),
+            tasks: overdue,
+        });
+    }
+    for offset in 0..span.days() {
+        let day = today + Duration::days(offset);
+        let due: Vec<_> = tasks
+            .iter()
+            .map(|task| task.borrow())
+            .filter(|task| match task.due {
+                Some(due) => due.day() == day,
+                None => day == today && task.today == Some(today),
+            })
+            .map(|task| entry(&task, false, locale))
+            .collect();
+        let week = match (offset > 0 && locale.week_start(day) == day, week_numbers) {
+            (true, true) => Some(format!("{} {}", locale.tr("Week"), locale.week_number(day))),
+            (true, false) => Some(String::from(locale.tr("Next week"))),
+            (false, _) => None,
+        };
+        sections.push(Section {
+            week,
+            heading: locale.format_date(day, "%A %-d %B"),
+            tasks: due,
+        });
+    }
+
+    let title = match span {
+        Span::Day => "Agenda for",
+        Span::Week => "Agenda for the week of",
+    };
+    let date = locale.format_date(today, "%A %-d %B %Y");
+    let mut title = format!("{} {}", locale.tr(title), date);
+    if week_numbers {
+        let week = locale.week_number(today);
+        title = format!("{} ({} {})", title, locale.tr("week"), week);
+    }
+    paginate(&title, list, &sections, locale)
+}
+
+/// Collect the open tasks under a task.
+pub(crate) fn collect(task: &Rc<RefCell<ToDo>>, tasks: &mut Vec<Rc<RefCell<ToDo>>>) {
+    for sub_task in task.borrow().sub_tasks.iter() {
+        if !sub_task.borrow().complete {
+            tasks.push(Rc::clone(sub_task));
+            collect(sub_task, tasks);
+        }
+    }
+}
+
+/// Order of priorities, highest first and none last.
+pub(crate) fn priority_rank(priority: &Option<Priority>) -> u8 {
+    match priority {
+        Some(priority) => priority.rank(),
+        None => MAX_PRIORITY_LEVELS,
+    }
+}
+
+/// Lines of a task in the agenda: the time (or date, if overdue) it is due,
+/// its priority and title, wrapped, then the project it belongs to.
+fn entry(task: &ToDo, overdue: bool, locale: &Locale) -> Vec<String> {
+    let when = match (task.due, overdue) {
+        (Some(Due::DateTime(time)), false) => time.format("%H:%M").to_string(),
+        (Some(due), true) => due.day().format("%Y-%m-%d").to_string(),
+        _ => String::new(),
+    };
+    let priority = match task.priority {
+        Some(priority) => format!("{} ", priority.label()),
+        None => String::new(),
+    };
+    let text = format!("{}{}", priority, task.task);
+
+    let mut lines = Vec::new();
+    for (i, line) in wrap(&text, WIDTH - INDENT).into_iter().enumerate() {
+        let left = if i == 0 { when.as_str() } else { "" };
+        lines.push(format!("  {:<10} {}", left, line));
+    }
+    let path = project(task);
+    if !path.is_empty() {
+        for line in wrap(&locale.fill("in {}", &path), WIDTH - INDENT - 2) {
+            lines.push(format!("{}  {}", " ".repeat(INDENT), line));
+        }
+    }
+    lines
+}
+
+/// Titles of the ancestors of a task (other than the root), outermost
+/// first.
+pub(crate) fn project(task: &ToDo) -> String {
+    let mut titles = Vec::new();
+    let mut parent = task.parent.upgrade();
+    while let Some(task) = parent {
+        let task = task.borrow();
+        if task.parent.upgrade().is_some() {
+            titles.push(task.task.clone());
+        }
+        parent = task.parent.upgrade();
+    }
+    titles.reverse();
+    titles.join(" > ")
+}
+
+/// Wrap text into lines of at most width characters, breaking long words.
+fn wrap(text: &str, width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    for word in text.split_whitespace() {
+        let mut word: Vec<char> = word.chars().collect();
+        let line_len = line.chars().count();
+        if line_len > 0 && line_len + 1 + word.len() > width {
+            lines.push(std::mem::take(&mut line));
+        }
+        while word.len() > width {
+            let rest = word.split_off(width);
+            lines.push(word.into_iter().collect());
+            word = rest;
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.extend(word);
+    }
+    if !line.is_empty() || lines.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+
+/// Lay out sections in pages, separated by form feeds, so that a task is
+/// never split between pages and a heading is never left at the bottom of
+/// one. Headings are repeated at the top of a page continuing a section.
+fn paginate(title: &str, list: &str, sections: &[Section], locale: &Locale) -> String {
+    let mut pages: Vec<Vec<String>> = Vec::new();
+    let space = WIDTH.saturating_sub(title.chars().count() + list.chars().count());
+    let mut page = vec![
+        format!("{}{}{}", title, " ".repeat(space.max(2)), list),
+        "=".repeat(WIDTH),
+    ];
+
+    for section in sections.iter() {
+        let heading = |continued: bool| {
+            let text = match continued {
+                true => format!("{} ({})", section.heading, locale.tr("continued")),
+                false => section.heading.clone(),
+            };
+            let underline = "-".repeat(text.chars().count());
+            let mut lines = vec![String::new(), text, underline];
+            if let (Some(week), false) = (&section.week, continued) {
+                let divider = format!("{:=^width$}", format!(" {} ", week), width = WIDTH);
+                lines.splice(0..0, [String::new(), divider]);
+            }
+            lines
+        };
+        let empty = vec![format!("  {}", locale.tr("Nothing due"))];
+        let tasks = match section.tasks.is_empty() {
+            true => std::slice::from_ref(&empty),
+            false => section.tasks.as_slice(),
+        };
+
+        // The heading is held back until it can go with the first task
+        let mut lines = heading(false);
+        for task in tasks.iter() {
+            if page.len() + lines.len() + task.len() > PAGE_LENGTH {
+                pages.push(std::mem::take(&mut page));
+                if lines.is_empty() {
+                    lines = heading(true);
+                }
+                lines.remove(0);
+            }
+            page.append(&mut lines);
+            page.extend(task.iter().cloned());
+        }
+    }
+    pages.push(page);
+
+    let pages: Vec<String> = pages.iter().map(|page| page.join("\n")).collect();
+    format!("{}\n", pages.join("\n\x0c"))
+}
+
+#[cfg(test)]
+mod tests;
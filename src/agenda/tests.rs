@@ -0,0 +1,140 @@
+/// Tests of the printed agenda, and of how it is laid out in pages.
+use super::{agenda, wrap, Span, PAGE_LENGTH, WIDTH};
+use crate::parse_list;
+use crate::tests::{day, english};
+
+/// Agenda of a list for a span of days from 2024-02-29, a Thursday.
+fn agenda_of(list: &str, span: Span) -> String {
+    let root = parse_list(list).unwrap();
+    agenda(
+        &root,
+        span,
+        day("2024-02-29"),
+        "todo.txt",
+        &english(),
+        false,
+    )
+}
+
+/// Pages of an agenda, each split into lines.
+fn pages(agenda: &str) -> Vec<Vec<&str>> {
+    let agenda = agenda.strip_suffix('\n').expect("no newline at the end");
+    agenda
+        .split("\n\x0c")
+        .map(|page| page.split('\n').collect())
+        .collect()
+}
+
+#[test]
+fn day_agenda() {
+    let list = "\
+[ ] ( ) Call Bob due:2024-02-29T09:30
+[ ] (A) Home
+    [ ] ( ) Pay rent due:2024-02-28
+    [ ] ( ) Water plants today:2024-02-29
+    [X] ( ) Mow the lawn due:2024-02-29
+[ ] ( ) Book the dentist due:2024-03-01
+";
+    assert_eq!(
+        agenda_of(list, Span::Day),
+        "Agenda for Thursday 29 February 2024                                    todo.txt\n\
+         ================================================================================\n\
+         \n\
+         Overdue\n\
+         -------\n\
+         \x20 2024-02-28 Pay rent\n\
+         \x20              in Home\n\
+         \n\
+         Thursday 29 February\n\
+         --------------------\n\
+         \x20 09:30      Call Bob\n\
+         \x20            Water plants\n\
+         \x20              in Home\n"
+    );
+}
+
+#[test]
+fn week_agenda() {
+    let agenda = agenda_of("[ ] ( ) Call Bob due:2024-03-04\n", Span::Week);
+    let pages = pages(&agenda);
+    assert_eq!(pages.len(), 1);
+    let lines = &pages[0];
+    assert!(lines[0].starts_with("Agenda for the week of Thursday 29 February 2024"));
+    assert_eq!(
+        lines
+            .iter()
+            .filter(|line| **line == "  Nothing due")
+            .count(),
+        6
+    );
+    let monday = lines
+        .iter()
+        .position(|line| *line == "Monday 4 March")
+        .unwrap();
+    assert_eq!(lines[monday - 2], format!("{:=^80}", " Next week "));
+    assert_eq!(lines[monday + 2], "             Call Bob");
+    assert_eq!(*lines.last().unwrap(), "  Nothing due");
+}
+
+#[test]
+fn tasks_kept_whole_on_pages() {
+    // Tasks of two lines each, which can't all fit on one page
+    let mut list = String::from("[ ] ( ) Home\n");
+    for i in 0..60 {
+        list.push_str(&format!("    [ ] ( ) Task {} due:2024-02-29\n", i));
+    }
+    let agenda = agenda_of(&list, Span::Day);
+    let pages = pages(&agenda);
+    assert_eq!(pages.len(), 2);
+    for page in pages.iter() {
+        assert!(page.len() <= PAGE_LENGTH);
+        assert!(page.iter().all(|line| line.chars().count() <= WIDTH));
+        // Ending with the last line of a task
+        assert!(page.last().unwrap().ends_with("in Home"));
+    }
+    assert_eq!(pages[1][0], "Thursday 29 February (continued)");
+    assert_eq!(pages[1][1], "-".repeat(pages[1][0].len()));
+    assert!(pages[1][2].contains("Task"));
+    let tasks = agenda.matches(" Task ").count();
+    assert_eq!(tasks, 60);
+}
+
+#[test]
+fn headings_not_left_at_the_bottom() {
+    // Fill the first page to just short of a heading and its first task
+    let mut list = String::new();
+    for i in 0..58 {
+        list.push_str(&format!("[ ] ( ) Overdue {} due:2024-02-28\n", i));
+    }
+    list.push_str("[ ] ( ) Today due:2024-02-29\n");
+    let agenda = agenda_of(&list, Span::Day);
+    let pages = pages(&agenda);
+    assert_eq!(pages.len(), 2);
+    assert!(pages[0].last().unwrap().contains("Overdue 57"));
+    assert_eq!(
+        pages[1],
+        [
+            "Thursday 29 February",
+            "--------------------",
+            "             Today"
+        ]
+    );
+}
+
+#[test]
+fn words_wrapped() {
+    assert_eq!(wrap("", 10), [""]);
+    assert_eq!(
+        wrap("Call Bob about the weekend", 10),
+        ["Call Bob", "about the", "weekend"]
+    );
+    assert_eq!(
+        wrap("Supercalifragilistic", 8),
+        ["Supercal", "ifragili", "stic"]
+    );
+    assert_eq!(
+        wrap("a  Supercalifragilistic", 8),
+        ["a", "Supercal", "ifragili", "stic"]
+    );
+    assert_eq!(wrap("caf\u{e9} caf\u{e9}", 4), ["caf\u{e9}", "caf\u{e9}"]);
+}
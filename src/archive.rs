@@ -0,0 +1,103 @@
+/// Archiving of completed tasks, moving them out of the save file into an
+/// append-only archive file beside it, which is only read when the archive
+/// is opened.
+use crate::todo::ToDo;
+use chrono::NaiveDate;
+use std::cell::RefCell;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::rc::{Rc, Weak};
+
+/// Location of the archive of a save file, e.g. save.archive.txt beside
+/// save.txt.
+pub fn path(save_file: &Path) -> PathBuf {
+    let stem = save_file.file_stem().map_or(String::from("save"), |stem| {
+        stem.to_string_lossy().into_owned()
+    });
+    let name = match save_file.extension() {
+        Some(extension) => format!("{}.archive.{}", stem, extension.to_string_lossy()),
+        None => format!("{}.archive", stem),
+    };
+    save_file.with_file_name(name)
+}
+
+/// Find the tasks under a task which were completed before a day, along
+/// with all of their sub-tasks. Tasks with unfinished sub-tasks, or with no
+/// completion date, are kept.
+pub fn find_old(task: &ToDo, before: NaiveDate) -> Vec<Rc<RefCell<ToDo>>> {
+    find(task, &|todo| {
+        todo.completed.is_some_and(|stamp| stamp.day() < before)
+    })
+}
+
+/// Find all the completed tasks under a task, along with all of their
+/// sub-tasks. Tasks with unfinished sub-tasks are kept.
+pub fn find_completed(task: &ToDo) -> Vec<Rc<RefCell<ToDo>>> {
+    find(task, &|todo| todo.complete)
+}
+
+/// Find the tasks under a task meeting a condition, with no unfinished
+/// sub-tasks, without looking inside those found.
+fn find(task: &ToDo, condition: &dyn Fn(&ToDo) -> bool) -> Vec<Rc<RefCell<ToDo>>> {
+    let mut found = Vec::new();
+    for sub_task in task.sub_tasks.iter() {
+        let todo = sub_task.borrow();
+        let finished = condition(&todo) && todo.count_in_tree(&|t| !t.complete) == 0;
+        match finished {
+            true => found.push(Rc::clone(sub_task)),
+            false => found.extend(find(&todo, condition)),
+        }
+    }
+    found
+}
+
+/// Append tasks, with their sub-tasks, notes and comments, to an archive
+/// file in the save file format.
+pub fn append(path: &Path, tasks: &[Rc<RefCell<ToDo>>]) -> io::Result<()> {
+    let mut holder = ToDo::new("", Weak::new());
+    holder.sub_tasks = tasks.to_vec();
+    let buffer = holder.save_string();
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    file.write_all(buffer.as_bytes())
+}
+
+/// Read an archive file, which is empty if nothing has been archived yet.
+pub fn load(path: &Path) -> io::Result<String> {
+    match fs::read_to_string(path) {
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(String::new()),
+        result => result,
+    }
+}
+
+/// Collect the archived tasks under a task, depth first, as rows of (depth,
+/// text) showing when each was completed.
+pub fn outline(task: &ToDo, depth: usize, rows: &mut Vec<(usize, String)>) {
+    for sub_task in task.sub_tasks.iter() {
+        let todo = sub_task.borrow();
+        let mark = if todo.complete { 'X' } else { ' ' };
+        let text = match todo.completed {
+            Some(stamp) => format!(
+                "[{}] {} ({})",
+                mark,
+                todo.task,
+                stamp.day().format("%Y-%m-%d")
+            ),
+            None => format!("[{}] {}", mark, todo.task),
+        };
+        rows.push((depth, text));
+        outline(&todo, depth + 1, rows);
+    }
+}
+
+/// Remove tasks from the tree they belong to.
+pub fn remove(tasks: &[Rc<RefCell<ToDo>>]) {
+    for task in tasks.iter() {
+        if let Some(parent) = task.borrow().parent.upgrade() {
+            parent
+                .borrow_mut()
+                .sub_tasks
+                .retain(|sub_task| !Rc::ptr_eq(sub_task, task));
+        }
+    }
+}
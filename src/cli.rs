@@ -0,0 +1,192 @@
+/// Command line argument handling.
+use crate::agenda::Span;
+use crate::digest::Delivery;
+use crate::link;
+use std::path::PathBuf;
+
+/// Usage information.
+pub const USAGE: &str = "\
+Usage: yat [OPTIONS] [SAVE_FILE]
+       yat agenda [day|week] [SAVE_FILE]
+       yat digest [print|notify|email] [SAVE_FILE]
+       yat doctor [SAVE_FILE]
+       yat import EXPORT [SAVE_FILE]
+       yat daemon [OPTIONS] [SAVE_FILE]
+       yat add [--daemon] TASK [SAVE_FILE]
+       yat open yat://task/ID [SAVE_FILE]
+
+Arguments:
+  SAVE_FILE      todo list to load (default: ~/.todo/save.txt)
+                 or on a server over SSH, e.g. user@host:todo.txt
+
+Commands:
+  agenda         print the tasks due today (or this week), overdue
+                 tasks first, as plain text ready for printing
+  digest         summarise the tasks due today and overdue, printing
+                 it or sending it as a desktop notification or by
+                 email (nothing is sent when nothing is due)
+  doctor         check the save file for problems, offering to repair
+                 those which can be safely fixed
+  import         add the tasks exported from Apple Reminders (JSON or
+                 CSV) or Things 3, or a Markdown or org checklist, to
+                 the todo list, a task per list
+  daemon         run as usual, also adding the tasks sent to it with
+                 yat add --daemon as they arrive
+  add            add a task (or a task per line) to the todo list;
+                 with --daemon, to the yat daemon running with it
+  open           open at the task a link copied from yat is to
+
+Options:
+  --demo         try yat with a sample todo list (nothing is saved)
+  --goto TASK    open at a task, given its id or part of its title
+  -c COMMAND     run a command at startup, as if typed after ':',
+                 e.g. -c ':filter +work' -c ':sort due' (repeatable)
+  --watch        show the whole list read-only, reloading it when the
+                 save file changes (for a shared screen)
+  --profile NAME use the configuration and save file of a profile
+                 defined in ~/.todo/profiles.toml
+  -h, --help     print this help
+
+Exit status:
+  0 on success, 1 if yat doctor finds problems left unfixed, 2 for
+  invalid options, otherwise as in sysexits.h: 65 for a save file or
+  export which can't be read, 66 for a missing save file, 69 for a
+  server which can't be reached, a digest which can't be sent or no
+  daemon running, 71 if the terminal can't be set up, 74 for errors
+  writing files, 75 if a daemon is already running and 78 for missing
+  configuration";
+
+/// Options given on the command line.
+pub struct Options {
+    /// Save file to load.
+    pub save_file: Option<PathBuf>,
+    /// Whether to load the demo todo list.
+    pub demo: bool,
+    /// Id or part of the title of a task to open the list at.
+    pub goto: Option<String>,
+    /// Commands to run at startup, as if entered after ':'.
+    pub commands: Vec<String>,
+    /// Whether to only display the list, reloading it when it changes.
+    pub watch: bool,
+    /// Name of profile to use.
+    pub profile: Option<String>,
+    /// Span of the agenda to print, instead of running interactively.
+    pub agenda: Option<Span>,
+    /// Where to send a digest of the tasks due today, instead of running
+    /// interactively.
+    pub digest: Option<Delivery>,
+    /// Whether to check the save file for problems.
+    pub doctor: bool,
+    /// File exported from another app to import tasks from.
+    pub import: Option<PathBuf>,
+    /// Whether to listen for tasks sent with yat add --daemon.
+    pub daemon: bool,
+    /// Task to add without starting the interface.
+    pub add: Option<String>,
+    /// Whether to send the task to add to a running daemon.
+    pub to_daemon: bool,
+    /// Whether to print usage information.
+    pub help: bool,
+}
+
+impl Options {
+    /// Parse command line arguments (excluding the program name).
+    pub fn parse<I: Iterator<Item = String>>(mut args: I) -> Result<Options, String> {
+        let mut options = Options {
+            save_file: None,
+            demo: false,
+            goto: None,
+            commands: Vec::new(),
+            watch: false,
+            profile: None,
+            agenda: None,
+            digest: None,
+            doctor: false,
+            import: None,
+            daemon: false,
+            add: None,
+            to_daemon: false,
+            help: false,
+        };
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--demo" => options.demo = true,
+                "--watch" => options.watch = true,
+                "--goto" => match args.next() {
+                    Some(target) => options.goto = Some(target),
+                    None => return Err(String::from("Missing task to go to")),
+                },
+                "-c" | "--command" => match args.next() {
+                    Some(command) => options.commands.push(command),
+                    None => return Err(String::from("Missing command to run")),
+                },
+                "--profile" => match args.next() {
+                    Some(name) => options.profile = Some(name),
+                    None => return Err(String::from("Missing profile name")),
+                },
+                "-h" | "--help" => options.help = true,
+                "agenda" if options.agenda.is_none() && options.save_file.is_none() => {
+                    options.agenda = Some(Span::Day);
+                }
+                "digest" if options.digest.is_none() && options.save_file.is_none() => {
+                    options.digest = Some(Delivery::Print);
+                }
+                "print" | "notify" | "email"
+                    if options.digest.is_some() && options.save_file.is_none() =>
+                {
+                    options.digest = Some(match arg.as_str() {
+                        "notify" => Delivery::Notify,
+                        "email" => Delivery::Email,
+                        _ => Delivery::Print,
+                    });
+                }
+                "doctor" if !options.doctor && options.save_file.is_none() => {
+                    options.doctor = true;
+                }
+                "import" if options.import.is_none() && options.save_file.is_none() => {
+                    match args.next() {
+                        Some(export) => options.import = Some(PathBuf::from(export)),
+                        None => return Err(String::from("Missing file to import")),
+                    }
+                }
+                "daemon" if !options.daemon && options.save_file.is_none() => {
+                    options.daemon = true;
+                }
+                "add" if options.add.is_none() && options.save_file.is_none() => {
+                    let mut task = args.next();
+                    if task.as_deref() == Some("--daemon") {
+                        options.to_daemon = true;
+                        task = args.next();
+                    }
+                    match task {
+                        Some(task) => options.add = Some(task),
+                        None => return Err(String::from("Missing task to add")),
+                    }
+                }
+                "open" if options.goto.is_none() && options.save_file.is_none() => {
+                    match args.next().as_deref().map(link::parse) {
+                        Some(Some(id)) => options.goto = Some(id.to_string()),
+                        Some(None) => return Err(String::from("Invalid task link")),
+                        None => return Err(String::from("Missing task link to open")),
+                    }
+                }
+                "--daemon" if options.add.is_some() => options.to_daemon = true,
+                "day" | "week" if options.agenda.is_some() && options.save_file.is_none() => {
+                    options.agenda = Some(match arg.as_str() {
+                        "week" => Span::Week,
+                        _ => Span::Day,
+                    });
+                }
+                _ if arg.starts_with('-') => {
+                    return Err(format!("Unknown option: {}", arg));
+                }
+                _ if options.save_file.is_none() => {
+                    options.save_file = Some(PathBuf::from(arg));
+                }
+                _ => return Err(format!("Unexpected argument: {}", arg)),
+            }
+        }
+        Ok(options)
+    }
+}
@@ -0,0 +1,130 @@
+/// Columns displayed for each task in the task panels.
+use log::warn;
+use unicode_width::UnicodeWidthStr;
+
+/// Kind of information displayed in a column.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColumnKind {
+    /// Task completion, e.g. [X].
+    Checkbox,
+    /// Task priority, e.g. (A).
+    Priority,
+    /// Whether the task is flagged for today, shown by *.
+    Today,
+    /// Task description, taking up the remaining width.
+    Title,
+    /// Due date, or a countdown if imminent.
+    Due,
+    /// How long ago the task was added, or completed if it's complete.
+    Age,
+    /// Effort left on the task and everything under it, e.g. 2h30m.
+    Estimate,
+}
+
+/// Alignment of text within a column.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Align {
+    Left,
+    Right,
+}
+
+/// A column of the task panels.
+#[derive(Debug, Clone, Copy)]
+pub struct Column {
+    pub kind: ColumnKind,
+    /// Width of column (if not the default for its kind).
+    pub width: Option<usize>,
+}
+
+/// Columns displayed by default.
+pub const DEFAULT_COLUMNS: [Column; 4] = [
+    Column {
+        kind: ColumnKind::Checkbox,
+        width: None,
+    },
+    Column {
+        kind: ColumnKind::Today,
+        width: None,
+    },
+    Column {
+        kind: ColumnKind::Title,
+        width: None,
+    },
+    Column {
+        kind: ColumnKind::Due,
+        width: None,
+    },
+];
+
+impl Column {
+    /// Parse column from config.toml format, "name" or "name:width".
+    pub fn parse(spec: &str) -> Option<Column> {
+        let mut parts = spec.splitn(2, ':');
+        let kind = match parts.next().map(str::trim) {
+            Some("checkbox") => ColumnKind::Checkbox,
+            Some("priority") => ColumnKind::Priority,
+            Some("today") => ColumnKind::Today,
+            Some("title") => ColumnKind::Title,
+            Some("due") => ColumnKind::Due,
+            Some("age") => ColumnKind::Age,
+            Some("estimate") => ColumnKind::Estimate,
+            _ => {
+                warn!("Unknown column: {}", spec);
+                return None;
+            }
+        };
+        let width = match parts.next().map(|w| w.trim().parse::<usize>()) {
+            Some(Ok(w)) => Some(w),
+            Some(Err(_)) => {
+                warn!("Invalid column width: {}", spec);
+                return None;
+            }
+            None => None,
+        };
+        Some(Column { kind, width })
+    }
+
+    /// Width of column, where None means it takes up the remaining width.
+    pub fn width(&self) -> Option<usize> {
+        match (self.width, self.kind) {
+            (Some(w), _) => Some(w),
+            (None, ColumnKind::Checkbox) => Some(3),
+            (None, ColumnKind::Priority) => Some(3),
+            (None, ColumnKind::Today) => Some(1),
+            (None, ColumnKind::Title) => None,
+            (None, ColumnKind::Due) => Some(10),
+            (None, ColumnKind::Age) => Some(12),
+            (None, ColumnKind::Estimate) => Some(6),
+        }
+    }
+
+    /// Alignment of text in column.
+    pub fn align(&self) -> Align {
+        match self.kind {
+            ColumnKind::Checkbox | ColumnKind::Priority | ColumnKind::Today | ColumnKind::Title => {
+                Align::Left
+            }
+            ColumnKind::Due | ColumnKind::Age | ColumnKind::Estimate => Align::Right,
+        }
+    }
+}
+
+/// Pad or truncate text to exactly fill width, with given alignment.
+pub fn fit(text: &str, width: usize, align: Align) -> String {
+    let mut fitted = String::new();
+    let mut len = 0;
+    for ch in text.chars() {
+        let w = UnicodeWidthStr::width(ch.encode_utf8(&mut [0; 4]) as &str);
+        if len + w > width {
+            break;
+        }
+        fitted.push(ch);
+        len += w;
+    }
+
+    let padding = " ".repeat(width - len);
+    match align {
+        Align::Left => fitted + &padding,
+        Align::Right => padding + &fitted,
+    }
+}
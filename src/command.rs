@@ -0,0 +1,131 @@
+/// Commands entered after ':', for changing many tasks at once.
+use chrono::Duration;
+
+/// Usage of each command, shown when one can't be understood.
+pub const USAGE: &str = "Commands: postpone overdue +<n>d|w, filter @context|+tag...|all, \
+                         sort priority|due, group tag|priority|due|off, archive completed";
+
+/// First words of the built-in commands, which custom commands can't use.
+const BUILT_IN: [&str; 5] = ["postpone", "filter", "sort", "group", "archive"];
+
+/// Command entered by the user.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    /// Shift the due dates of all overdue tasks by an interval.
+    PostponeOverdue(Duration),
+    /// Only show the tasks with contexts (e.g. "@phone") and tags (e.g.
+    /// "+work"), all of them when several are chained, or all tasks if
+    /// None.
+    Filter(Option<String>),
+    /// Sort the current task's sub-tasks.
+    Sort(SortKey),
+    /// Show the tasks under a header for each group, or ungrouped if None.
+    Group(Option<GroupKey>),
+    /// Move all completed tasks to the archive file.
+    ArchiveCompleted,
+    /// Run built-in commands in turn, as defined in config.toml.
+    Sequence(Vec<Command>),
+}
+
+/// What to sort tasks by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortKey {
+    /// Highest priority first, then soonest due.
+    Priority,
+    /// Soonest due first, then highest priority.
+    Due,
+}
+
+/// What to group tasks by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GroupKey {
+    /// Their first tag.
+    Tag,
+    /// Their priority, the highest first.
+    Priority,
+    /// The week they are due, the soonest first.
+    Due,
+}
+
+impl Command {
+    /// Parse a command, such as "postpone overdue +1d", with or without a
+    /// leading ':', or the name of a custom command.
+    pub fn parse(text: &str, custom: &[(String, Vec<Command>)]) -> Result<Command, &'static str> {
+        let text = text.trim_start().strip_prefix(':').unwrap_or(text);
+        let words: Vec<&str> = text.split_whitespace().collect();
+        if let [name] = words.as_slice() {
+            if let Some((_, commands)) = custom.iter().find(|(custom, _)| custom == name) {
+                return Ok(Command::Sequence(commands.clone()));
+            }
+        }
+        match words.as_slice() {
+            ["postpone", "overdue", interval] => match parse_interval(interval) {
+                Some(interval) => Ok(Command::PostponeOverdue(interval)),
+                None => Err("Invalid interval: use e.g. +1d or +2w"),
+            },
+            ["archive", "completed"] => Ok(Command::ArchiveCompleted),
+            ["filter"] | ["filter", "all"] => Ok(Command::Filter(None)),
+            ["filter", labels @ ..]
+                if labels
+                    .iter()
+                    .all(|word| word.len() > 1 && word.starts_with(['@', '+', '#'])) =>
+            {
+                Ok(Command::Filter(Some(labels.join(" "))))
+            }
+            ["filter", ..] => Err("Invalid filter: use e.g. @phone, +work, +work @phone or all"),
+            ["sort"] | ["sort", "priority"] => Ok(Command::Sort(SortKey::Priority)),
+            ["sort", "due"] => Ok(Command::Sort(SortKey::Due)),
+            ["sort", _] => Err("Invalid sort: use priority or due"),
+            ["group", "tag"] => Ok(Command::Group(Some(GroupKey::Tag))),
+            ["group", "priority"] => Ok(Command::Group(Some(GroupKey::Priority))),
+            ["group", "due"] => Ok(Command::Group(Some(GroupKey::Due))),
+            ["group"] | ["group", "off"] => Ok(Command::Group(None)),
+            ["group", ..] => Err("Invalid grouping: use tag, priority, due or off"),
+            _ => Err(USAGE),
+        }
+    }
+
+    /// What the command changes in the list, to be undone, or None if it
+    /// only changes how the list is shown. Archiving can't be undone, as
+    /// the tasks are already in the archive.
+    pub fn change(&self) -> Option<&'static str> {
+        match self {
+            Command::PostponeOverdue(_) => Some("postpone"),
+            Command::Sort(_) => Some("sort"),
+            Command::Filter(_) | Command::Group(_) | Command::ArchiveCompleted => None,
+            Command::Sequence(commands) => match commands
+                .iter()
+                .any(|command| matches!(command, Command::ArchiveCompleted))
+            {
+                true => None,
+                false => commands.iter().find_map(Command::change),
+            },
+        }
+    }
+}
+
+/// Parse the definition of a custom command: built-in commands separated
+/// by '|', such as "filter +inbox | sort due".
+pub fn parse_sequence(text: &str) -> Result<Vec<Command>, &'static str> {
+    text.split('|')
+        .map(|part| Command::parse(part, &[]))
+        .collect()
+}
+
+/// Whether a custom command can be called a name: a single word, not that
+/// of a built-in command.
+pub fn valid_name(name: &str) -> bool {
+    !name.is_empty() && !name.contains(char::is_whitespace) && !BUILT_IN.contains(&name)
+}
+
+/// Parse an interval of whole days or weeks, such as "+1d" or "2w".
+pub fn parse_interval(text: &str) -> Option<Duration> {
+    let text = text.strip_prefix('+').unwrap_or(text);
+    let (number, unit) = text.split_at(text.len().checked_sub(1)?);
+    let number: i64 = number.parse().ok().filter(|&n| n > 0)?;
+    match unit {
+        "d" => Some(Duration::days(number)),
+        "w" => Some(Duration::weeks(number)),
+        _ => None,
+    }
+}
@@ -1,8 +1,17 @@
 /// Configuration functionality for controlling appearance and keybindings.
+use crate::column::{Column, DEFAULT_COLUMNS};
+use crate::command::{self, Command};
+use crate::escalate::Rule;
+use crate::layout::{self, Placement};
+use crate::todo::MAX_PRIORITY_LEVELS;
+use crate::tui;
 use dirs::home_dir;
 use log::{info, warn};
 use serde::Deserialize;
+use std::collections::HashMap;
+use std::env;
 use std::fs::read_to_string;
+use std::path::{Path, PathBuf};
 use termion::color;
 use termion::event::Key;
 
@@ -10,10 +19,54 @@ use termion::event::Key;
 #[derive(Deserialize, Debug)]
 struct TomlConfig {
     borders: Option<Borders>,
+    layout: Option<Layout>,
     colours: Option<Colours>,
+    light_colours: Option<Colours>,
+    background: Option<String>,
+    priority_colours: Option<PriorityColours>,
+    title_style: Option<TitleStyle>,
+    tag_style: Option<TagStyle>,
+    border_colours: Option<BorderColours>,
     keys: Option<Keys>,
     save_on_exit: Option<bool>,
     print_priority: Option<bool>,
+    priority_levels: Option<u8>,
+    ascii: Option<bool>,
+    colour_blind: Option<bool>,
+    screen_reader: Option<bool>,
+    bell: Option<bool>,
+    flash: Option<bool>,
+    focus_subtasks: Option<bool>,
+    auto_rollover: Option<bool>,
+    auto_complete_parents: Option<bool>,
+    auto_sort: Option<bool>,
+    dim_upcoming: Option<bool>,
+    title_bar: Option<bool>,
+    duplicate_threshold: Option<f64>,
+    week_numbers: Option<bool>,
+    archive_after: Option<u32>,
+    trash_days: Option<u32>,
+    lazy_depth: Option<usize>,
+    max_depth: Option<usize>,
+    journal: Option<bool>,
+    columns: Option<Vec<String>>,
+    shell_commands: Option<HashMap<String, String>>,
+    commands: Option<HashMap<String, String>>,
+    tag_colours: Option<HashMap<String, TomlColour>>,
+    heatmap_colours: Option<Vec<TomlColour>>,
+    save_file: Option<String>,
+    spell_check: Option<String>,
+    locale: Option<String>,
+    first_day_of_week: Option<String>,
+    jump_labels: Option<String>,
+    contexts: Option<Vec<String>>,
+    escalate: Option<Vec<Escalate>>,
+    storage: Option<StorageConfig>,
+    habitica: Option<HabiticaConfig>,
+    email: Option<EmailConfig>,
+    webhooks: Option<WebhooksConfig>,
+    next_action: Option<Weights>,
+    confirm: Option<Confirm>,
 }
 
 /// Layout of [border] section of config.toml file.
@@ -27,6 +80,14 @@ struct Borders {
     lrcorner: Option<String>,
 }
 
+/// Layout of [layout] section of config.toml file.
+#[derive(Deserialize, Debug)]
+struct Layout {
+    ellipsis: Option<String>,
+    ellipsis_at: Option<String>,
+    margin: Option<usize>,
+}
+
 /// Layout of [colours] section of config.toml file.
 #[derive(Deserialize, Debug)]
 struct Colours {
@@ -42,24 +103,511 @@ struct Colours {
     colourbg: Option<Vec<u8>>,
 }
 
-/// Layout of [keys] section of config.toml file.
+/// Layout of [priority_colours] section of config.toml file.
+#[derive(Deserialize, Debug)]
+struct PriorityColours {
+    high: Option<TomlColour>,
+    medium: Option<TomlColour>,
+    low: Option<TomlColour>,
+    /// Colour for each level, from A.
+    levels: Option<Vec<TomlColour>>,
+}
+
+/// Layout of [border_colours] section of config.toml file.
+#[derive(Deserialize, Debug)]
+struct BorderColours {
+    normal: Option<TomlColour>,
+    edit: Option<TomlColour>,
+    visual: Option<TomlColour>,
+    filter: Option<TomlColour>,
+    unsaved: Option<TomlColour>,
+}
+
+/// Layout of [title_style] section of config.toml file.
+#[derive(Deserialize, Debug)]
+struct TitleStyle {
+    fg: Option<TomlColour>,
+    bg: Option<TomlColour>,
+    bold: Option<bool>,
+}
+
+/// Layout of [tag_style] section of config.toml file.
+#[derive(Deserialize, Debug)]
+struct TagStyle {
+    fg: Option<TomlColour>,
+}
+
+/// Layout of [storage] section of config.toml file, for keeping the todo
+/// list on a server.
+#[derive(Deserialize, Debug, Clone)]
+pub struct StorageConfig {
+    /// Kind of storage: "ssh" or "webdav".
+    #[serde(rename = "type")]
+    pub kind: String,
+    /// Location of the todo list, e.g. "user@host:path" or a WebDAV URL.
+    pub url: String,
+    pub user: Option<String>,
+    pub password: Option<String>,
+    /// Command which prints the password, e.g. from a password manager.
+    pub password_command: Option<String>,
+}
+
+/// Layout of [habitica] section of config.toml file, for scoring habits on
+/// Habitica when tasks with certain tags are completed.
+#[derive(Deserialize, Debug, Clone)]
+pub struct HabiticaConfig {
+    /// Habitica user id.
+    pub user: String,
+    /// API token.
+    pub token: Option<String>,
+    /// Command which prints the API token, e.g. from a password manager.
+    pub token_command: Option<String>,
+    /// Habit to score up (by id or alias) for each tag.
+    pub habits: HashMap<String, String>,
+}
+
+/// Layout of [email] section of config.toml file, for sending tasks by
+/// email, through sendmail or an SMTP server.
+#[derive(Deserialize, Debug, Clone)]
+pub struct EmailConfig {
+    /// Address to send tasks to (offered when sending).
+    pub to: String,
+    /// Address to send tasks from (if not the default of sendmail).
+    pub from: Option<String>,
+    /// SMTP server to send through, e.g. "smtps://smtp.example.com", rather
+    /// than sendmail.
+    pub smtp_url: Option<String>,
+    /// Command run to send through sendmail (by default "sendmail -t").
+    pub sendmail: Option<String>,
+    pub user: Option<String>,
+    pub password: Option<String>,
+    /// Command which prints the password, e.g. from a password manager.
+    pub password_command: Option<String>,
+}
+
+/// Layout of each [[escalate]] section of config.toml file: a rule raising
+/// the priority of tasks due soon or created long ago.
+#[derive(Deserialize, Debug)]
+struct Escalate {
+    due_within: Option<String>,
+    older_than: Option<String>,
+    priority: String,
+}
+
+/// Layout of [webhooks] section of config.toml file: URLs to POST a JSON
+/// description of a task to when it is added or completed.
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct WebhooksConfig {
+    pub add: Vec<String>,
+    pub complete: Vec<String>,
+}
+
+/// Layout of [next_action] section of config.toml file: weights given to
+/// each part of the ranking used to suggest the next task.
+#[derive(Deserialize, Debug, Clone, Copy)]
+#[serde(default)]
+pub struct Weights {
+    /// Weight of the task priority.
+    pub priority: f64,
+    /// Weight of how soon the task is due.
+    pub due: f64,
+    /// Weight of the task being flagged for today.
+    pub today: f64,
+}
+
+impl Default for Weights {
+    fn default() -> Weights {
+        Weights {
+            priority: 1.0,
+            due: 1.0,
+            today: 0.5,
+        }
+    }
+}
+
+/// Layout of [confirm] section of config.toml file: which actions ask for
+/// confirmation first.
+#[derive(Deserialize, Debug, Clone, Copy)]
+#[serde(default)]
+pub struct Confirm {
+    /// Deleting a task.
+    pub delete: bool,
+    /// Quitting with unsaved changes (unless saving on exit).
+    pub quit_unsaved: bool,
+    /// Changing several marked tasks, or running a command on many tasks.
+    pub bulk: bool,
+    /// Archiving old tasks at startup.
+    pub archive: bool,
+}
+
+impl Default for Confirm {
+    fn default() -> Confirm {
+        Confirm {
+            delete: true,
+            quit_unsaved: false,
+            bulk: false,
+            archive: false,
+        }
+    }
+}
+
+/// Convert an RGB value in config.toml file, checking it has three parts.
+fn rgb(x: &[u8]) -> Option<color::Rgb> {
+    match x {
+        [r, g, b] => Some(color::Rgb(*r, *g, *b)),
+        _ => {
+            warn!("Invalid colour {:?}: must be [r, g, b].", x);
+            None
+        }
+    }
+}
+
+/// A colour in config.toml file, either a colour slot or RGB value.
+#[derive(Deserialize, Debug)]
+#[serde(untagged)]
+enum TomlColour {
+    Slot(usize),
+    Rgb(Vec<u8>),
+}
+
+impl TomlColour {
+    /// Convert to a colour choice, checking the colour slot is valid.
+    fn choice(self) -> Option<ColourChoice> {
+        match self {
+            TomlColour::Slot(n) if n <= 8 => Some(ColourChoice::Slot(n)),
+            TomlColour::Slot(n) => {
+                warn!("Invalid colour slot {}: must be between 0 and 8.", n);
+                None
+            }
+            TomlColour::Rgb(x) => rgb(&x).map(ColourChoice::Rgb),
+        }
+    }
+}
+
+impl Colours {
+    /// Fill in colours missing from this colour-scheme from another.
+    fn or(self, other: Option<Colours>) -> Colours {
+        match other {
+            Some(other) => Colours {
+                colour0: self.colour0.or(other.colour0),
+                colour1: self.colour1.or(other.colour1),
+                colour2: self.colour2.or(other.colour2),
+                colour3: self.colour3.or(other.colour3),
+                colour4: self.colour4.or(other.colour4),
+                colour5: self.colour5.or(other.colour5),
+                colour6: self.colour6.or(other.colour6),
+                colour7: self.colour7.or(other.colour7),
+                colourfg: self.colourfg.or(other.colourfg),
+                colourbg: self.colourbg.or(other.colourbg),
+            },
+            None => self,
+        }
+    }
+}
+
+/// Layout of [keys] section of config.toml file: the keys of the task
+/// list, with those only for one mode in a section of their own, e.g.
+/// [keys.visual].
 #[derive(Deserialize, Debug)]
 struct Keys {
+    #[serde(flatten)]
+    bindings: KeyBindings,
+    normal: Option<KeyBindings>,
+    visual: Option<KeyBindings>,
+    edit: Option<EditBindings>,
+    search: Option<SearchBindings>,
+}
+
+/// Layout of the keys of the task list in [keys] section of config.toml
+/// file, or of the keys for one mode in e.g. [keys.visual].
+#[derive(Deserialize, Debug)]
+pub struct KeyBindings {
     quit: Option<char>,
     back: Option<char>,
     save: Option<char>,
     add: Option<char>,
     edit: Option<char>,
+    notes: Option<char>,
+    comment: Option<char>,
+    details: Option<char>,
+    context: Option<char>,
+    upcoming: Option<char>,
+    due: Option<char>,
+    start: Option<char>,
+    estimate: Option<char>,
+    today: Option<char>,
     delete: Option<char>,
     task_up: Option<char>,
     task_down: Option<char>,
     up: Option<char>,
     down: Option<char>,
     focus: Option<char>,
+    focus_mode: Option<char>,
     complete: Option<char>,
     increase: Option<char>,
     decrease: Option<char>,
     sort: Option<char>,
+    rollover: Option<char>,
+    export: Option<char>,
+    review: Option<char>,
+    report: Option<char>,
+    graph: Option<char>,
+    next_action: Option<char>,
+    mark: Option<char>,
+    command: Option<char>,
+    jump: Option<char>,
+    email_task: Option<char>,
+    archive: Option<char>,
+    tag_filter: Option<char>,
+    share: Option<char>,
+    block: Option<char>,
+    age: Option<char>,
+    skip: Option<char>,
+    defaults: Option<char>,
+    timer: Option<char>,
+    heatmap: Option<char>,
+    theme: Option<char>,
+    undo: Option<char>,
+    redo: Option<char>,
+    link: Option<char>,
+    url: Option<char>,
+    open_url: Option<char>,
+    remind: Option<char>,
+    trash: Option<char>,
+    duplicate: Option<char>,
+    find: Option<char>,
+    move_to: Option<char>,
+    cut: Option<char>,
+    paste: Option<char>,
+    target: Option<char>,
+}
+
+/// Layout of [keys.edit] section of config.toml file.
+#[derive(Deserialize, Debug)]
+struct EditBindings {
+    accept: Option<char>,
+    complete: Option<char>,
+}
+
+/// Layout of [keys.search] section of config.toml file.
+#[derive(Deserialize, Debug)]
+struct SearchBindings {
+    accept: Option<char>,
+    previous: Option<char>,
+    next: Option<char>,
+}
+
+/// Keys of the task list in one mode, each for an action.
+#[derive(Clone, Copy)]
+pub struct KeyMap {
+    /// Key to quit yat.
+    pub quit: Key,
+    /// Key to return focus to parent.
+    pub back: Key,
+    /// Key to write list to save file.
+    pub save: Key,
+    /// Key to add new task.
+    pub add: Key,
+    /// Key to edit selected task.
+    pub edit: Key,
+    /// Key to edit notes of selected task in external editor.
+    pub notes: Key,
+    /// Add timestamped comment to task.
+    pub comment: Key,
+    /// Toggle details of task in place of sub-tasks.
+    pub details: Key,
+    /// Switch context, showing only tasks in it.
+    pub context: Key,
+    /// Toggle showing tasks which haven't started yet.
+    pub upcoming: Key,
+    /// Set due date of task.
+    pub due: Key,
+    /// Set start date of task, before which it is hidden.
+    pub start: Key,
+    /// Set effort estimate of selected task.
+    pub estimate: Key,
+    /// Flag task to be done today.
+    pub today: Key,
+    /// Key to delete selected task.
+    pub delete: Key,
+    /// Key to move selected task up.
+    pub task_up: Key,
+    /// Key to move selected task down.
+    pub task_down: Key,
+    /// Key to move selection up.
+    pub up: Key,
+    /// Key to move selection down.
+    pub down: Key,
+    /// Key to focus on selected sub-task.
+    pub focus: Key,
+    /// Enter focus mode on selected task.
+    pub focus_mode: Key,
+    /// Key to mark task completed.
+    pub complete: Key,
+    /// Key to increase task priority.
+    pub increase: Key,
+    /// Key to decrease task priority.
+    pub decrease: Key,
+    /// Key to sort tasks by priority.
+    pub sort: Key,
+    /// Carry unfinished tasks over from previous days.
+    pub rollover: Key,
+    /// Key to export todo list to HTML.
+    pub export: Key,
+    /// Start weekly review of projects.
+    pub review: Key,
+    /// Show report of estimated against actual time.
+    pub report: Key,
+    /// Show dependency graph of selected task.
+    pub graph: Key,
+    /// Jump to the suggested next task.
+    pub next_action: Key,
+    /// Mark selected task, for changing several at once.
+    pub mark: Key,
+    /// Enter a command, e.g. "postpone overdue +1d".
+    pub command: Key,
+    /// Label tasks, to select one by typing its label.
+    pub jump: Key,
+    /// Send the selected task by email
+    pub email_task: Key,
+    /// Key to open the archive of completed tasks.
+    pub archive: Key,
+    /// Key to show only the tasks with a tag.
+    pub tag_filter: Key,
+    /// Key to copy the selected task and its sub-tasks as a snippet.
+    pub share: Key,
+    /// Key to make the selected task wait on other tasks.
+    pub block: Key,
+    /// Toggle showing how long ago tasks were added or completed.
+    pub age: Key,
+    /// Key to skip an occurrence of the selected recurring task.
+    pub skip: Key,
+    /// Key to set the defaults given to tasks added under the selected task.
+    pub defaults: Key,
+    /// Key to start or stop the timer for time spent on the selected task.
+    pub timer: Key,
+    /// Show heatmap of completions per day over the last year.
+    pub heatmap: Key,
+    /// Pick a theme, previewing each.
+    pub theme: Key,
+    /// Undo the last change to the list.
+    pub undo: Key,
+    /// Redo the last change undone.
+    pub redo: Key,
+    /// Copy a link to the selected task.
+    pub link: Key,
+    /// Attach a URL to the selected task, or change or remove it.
+    pub url: Key,
+    /// Open the URL of the selected task in the browser.
+    pub open_url: Key,
+    /// Set how long before its due date to be reminded of the selected task.
+    pub remind: Key,
+    /// Browse the trash to restore deleted tasks.
+    pub trash: Key,
+    /// Duplicate the selected task and its sub-tasks, as not yet done.
+    pub duplicate: Key,
+    /// Find tasks by the words in them, as they are typed.
+    pub find: Key,
+    /// Pick up the selected task, then put it down under another task.
+    pub move_to: Key,
+    /// Cut the selected task and its sub-tasks, to paste elsewhere.
+    pub cut: Key,
+    /// Paste the tasks cut or copied last under the current task.
+    pub paste: Key,
+    /// Set the target date of the selected task, a softer date than a deadline.
+    pub target: Key,
+}
+
+impl KeyMap {
+    /// The keys with those given in the section for a mode, e.g.
+    /// [keys.visual], in place of them.
+    fn with(mut self, bindings: &KeyBindings) -> KeyMap {
+        let bind = |key: &mut Key, ch: Option<char>| {
+            if let Some(ch) = ch {
+                *key = Key::Char(ch);
+            }
+        };
+        bind(&mut self.quit, bindings.quit);
+        bind(&mut self.back, bindings.back);
+        bind(&mut self.save, bindings.save);
+        bind(&mut self.add, bindings.add);
+        bind(&mut self.edit, bindings.edit);
+        bind(&mut self.notes, bindings.notes);
+        bind(&mut self.comment, bindings.comment);
+        bind(&mut self.details, bindings.details);
+        bind(&mut self.context, bindings.context);
+        bind(&mut self.upcoming, bindings.upcoming);
+        bind(&mut self.due, bindings.due);
+        bind(&mut self.start, bindings.start);
+        bind(&mut self.estimate, bindings.estimate);
+        bind(&mut self.today, bindings.today);
+        bind(&mut self.delete, bindings.delete);
+        bind(&mut self.task_up, bindings.task_up);
+        bind(&mut self.task_down, bindings.task_down);
+        bind(&mut self.up, bindings.up);
+        bind(&mut self.down, bindings.down);
+        bind(&mut self.focus, bindings.focus);
+        bind(&mut self.focus_mode, bindings.focus_mode);
+        bind(&mut self.complete, bindings.complete);
+        bind(&mut self.increase, bindings.increase);
+        bind(&mut self.decrease, bindings.decrease);
+        bind(&mut self.sort, bindings.sort);
+        bind(&mut self.rollover, bindings.rollover);
+        bind(&mut self.export, bindings.export);
+        bind(&mut self.review, bindings.review);
+        bind(&mut self.report, bindings.report);
+        bind(&mut self.graph, bindings.graph);
+        bind(&mut self.next_action, bindings.next_action);
+        bind(&mut self.mark, bindings.mark);
+        bind(&mut self.command, bindings.command);
+        bind(&mut self.jump, bindings.jump);
+        bind(&mut self.email_task, bindings.email_task);
+        bind(&mut self.archive, bindings.archive);
+        bind(&mut self.tag_filter, bindings.tag_filter);
+        bind(&mut self.share, bindings.share);
+        bind(&mut self.block, bindings.block);
+        bind(&mut self.age, bindings.age);
+        bind(&mut self.skip, bindings.skip);
+        bind(&mut self.defaults, bindings.defaults);
+        bind(&mut self.timer, bindings.timer);
+        bind(&mut self.heatmap, bindings.heatmap);
+        bind(&mut self.theme, bindings.theme);
+        bind(&mut self.undo, bindings.undo);
+        bind(&mut self.redo, bindings.redo);
+        bind(&mut self.link, bindings.link);
+        bind(&mut self.url, bindings.url);
+        bind(&mut self.open_url, bindings.open_url);
+        bind(&mut self.remind, bindings.remind);
+        bind(&mut self.trash, bindings.trash);
+        bind(&mut self.duplicate, bindings.duplicate);
+        bind(&mut self.find, bindings.find);
+        bind(&mut self.move_to, bindings.move_to);
+        bind(&mut self.cut, bindings.cut);
+        bind(&mut self.paste, bindings.paste);
+        bind(&mut self.target, bindings.target);
+        self
+    }
+}
+
+/// Keys of the dialogues for entering text (edit mode).
+#[derive(Clone, Copy)]
+pub struct EditKeys {
+    /// Accept the text entered, or the completion chosen.
+    pub accept: Key,
+    /// Complete the word being typed.
+    pub complete: Key,
+}
+
+/// Keys of the search for a task (search mode).
+#[derive(Clone, Copy)]
+pub struct SearchKeys {
+    /// Go to the task found under the cursor.
+    pub accept: Key,
+    /// Move the cursor to the task found before.
+    pub previous: Key,
+    /// Move the cursor to the task found after.
+    pub next: Key,
 }
 
 /// Yat's configuration.
@@ -78,6 +626,14 @@ pub struct Config<'a> {
     /// Lower right border character(s)
     pub lrcorner: &'a str,
 
+    /// Layout configuration.
+    /// Marker for text which has been cut off
+    pub ellipsis: &'a str,
+    /// Where the marker goes in text which has been cut off
+    pub ellipsis_at: Placement,
+    /// Columns left blank inside each side of the panels
+    pub margin: usize,
+
     /// Colour-scheme configuration.
     /// Black colour.
     pub colour0: &'a dyn color::Color,
@@ -99,49 +655,132 @@ pub struct Config<'a> {
     pub colourfg: &'a dyn color::Color,
     /// Background colour.
     pub colourbg: &'a dyn color::Color,
+    /// High priority colour.
+    pub priority_high: &'a dyn color::Color,
+    /// Medium priority colour.
+    pub priority_medium: &'a dyn color::Color,
+    /// Low priority colour.
+    pub priority_low: &'a dyn color::Color,
+    /// Colour for each priority level, from A, over the high, medium and
+    /// low colours.
+    pub priority_colours: &'a [ColourChoice],
+    /// Title bar foreground colour.
+    pub title_fg: &'a dyn color::Color,
+    /// Title bar background colour.
+    pub title_bg: &'a dyn color::Color,
+    /// Title bar in bold.
+    pub title_bold: bool,
+    /// Colour of tags in tasks.
+    pub tag_fg: &'a dyn color::Color,
+    /// Border colour normally.
+    pub border_normal: &'a dyn color::Color,
+    /// Border colour while editing or entering text.
+    pub border_edit: &'a dyn color::Color,
+    /// Border colour while tasks are marked.
+    pub border_visual: &'a dyn color::Color,
+    /// Border colour while only some tasks are shown.
+    pub border_filter: &'a dyn color::Color,
+    /// Border colour while there are unsaved changes.
+    pub border_unsaved: &'a dyn color::Color,
 
-    /// Keybinding configuration.
-    /// Key to quit yat.
-    pub quit: Key,
-    /// Key to return focus to parent.
-    pub back: Key,
-    /// Key to write list to save file.
-    pub save: Key,
-    /// Key to add new task.
-    pub add: Key,
-    /// Key to edit selected task.
-    pub edit: Key,
-    /// Key to delete selected task.
-    pub delete: Key,
-    /// Key to move selected task up.
-    pub task_up: Key,
-    /// Key to move selected task down.
-    pub task_down: Key,
-    /// Key to move selection up.
-    pub up: Key,
-    /// Key to move selection down.
-    pub down: Key,
-    /// Key to focus on selected sub-task.
-    pub focus: Key,
-    /// Key to mark task completed.
-    pub complete: Key,
-    /// Key to increase task priority.
-    pub increase: Key,
-    /// Key to decrease task priority.
-    pub decrease: Key,
-    /// Key to sort tasks by priority.
-    pub sort: Key,
+    /// Keybinding configuration, for each mode.
+    /// Keys of the task list (normal mode)
+    pub keys: KeyMap,
+    /// Keys of the task list while tasks are marked (visual mode)
+    pub visual_keys: KeyMap,
+    /// Keys of the dialogues for entering text (edit mode)
+    pub edit_keys: EditKeys,
+    /// Keys of the search for a task (search mode)
+    pub search_keys: SearchKeys,
 
     /// Miscellaneous settings
     /// Whether to save on exit
     pub save_on_exit: bool,
     /// Whether to print priority explicity
     pub print_priority: bool,
+    /// Number of priority levels, lettered from A.
+    pub priority_levels: u8,
+    /// Whether to only use ASCII characters (for terminals without unicode)
+    pub ascii: bool,
+    /// Give every state shown by colour another cue, e.g. a marker or bold text
+    pub colour_blind: bool,
+    /// Whether to use screen-reader friendly display
+    pub screen_reader: bool,
+    /// Ring the terminal bell when a task falls due.
+    pub bell: bool,
+    /// Flash the status bar when a task falls due.
+    pub flash: bool,
+    /// Show sub-tasks of the task in focus mode.
+    pub focus_subtasks: bool,
+    /// Carry unfinished tasks over from previous days at launch.
+    pub auto_rollover: bool,
+    /// Mark a task complete once all of its sub-tasks are.
+    pub auto_complete_parents: bool,
+    /// Sort tasks again whenever their priority or due date changes.
+    pub auto_sort: bool,
+    /// Show tasks which haven't started yet dimmed, rather than hidden.
+    pub dim_upcoming: bool,
+    /// Show a title bar with a summary of the list.
+    pub title_bar: bool,
+    /// How similar (from 0 to 1) a new task must be to an open task to be a likely duplicate.
+    pub duplicate_threshold: f64,
+    /// Whether to show ISO week numbers
+    pub week_numbers: bool,
+    /// Days after completion to archive tasks at startup (0 never)
+    pub archive_after: u32,
+    /// Days to keep deleted tasks in the trash (0 deletes them straight away)
+    pub trash_days: u32,
+    /// Depth below which tasks are only read when focused on (0 reads all at startup)
+    pub lazy_depth: usize,
+    /// Deepest tasks can be nested, checked when adding or moving them (0 no limit)
+    pub max_depth: usize,
+    /// Record each change in a journal beside the save file
+    pub journal: bool,
+    /// Columns displayed for each task
+    pub columns: &'a [Column],
+    /// Shell command templates run by key
+    pub shell_commands: &'a [(Key, String)],
+    /// Custom commands entered after ':', each running built-in commands
+    pub commands: &'a [(String, Vec<Command>)],
+    /// Colours of tasks with the given tags (without '+' or '#')
+    pub tag_colours: &'a [(String, ColourChoice)],
+    /// Colours of the heatmap, from days with no completions to the most
+    pub heatmap_colours: &'a [ColourChoice],
+    /// Location of the save file (if not the default)
+    pub save_file: Option<&'a str>,
+    /// Word list for spell checking new and edited tasks, if any
+    pub spell_check: Option<&'a str>,
+    /// Language of the interface and dates, e.g. "de" (if not from the
+    /// environment)
+    pub locale: Option<&'a str>,
+    /// First day of the week, e.g. "sunday" (if not the usual one for the
+    /// locale)
+    pub first_day_of_week: Option<&'a str>,
+    /// Characters of the labels for jumping to tasks, or "numbers"
+    pub jump_labels: &'a str,
+    /// Contexts always offered when switching context, e.g. "@home"
+    pub contexts: &'a [String],
+    /// Rules raising the priority of tasks as they fall due or grow old
+    pub escalate: &'a [Rule],
+    /// Remote storage of the todo list (if any)
+    pub storage: Option<&'a StorageConfig>,
+    /// Habits to score on Habitica when tagged tasks are completed (if any)
+    pub habitica: Option<&'a HabiticaConfig>,
+    /// Account to send tasks by email from (if any)
+    pub email: Option<&'a EmailConfig>,
+    /// URLs told about tasks being added or completed (if any)
+    pub webhooks: Option<&'a WebhooksConfig>,
+    /// Weights of the ranking used to suggest the next task
+    pub weights: Weights,
+    /// Actions which ask for confirmation first
+    pub confirm: Confirm,
+    /// Location of the configuration file, which a chosen theme is written to
+    pub config_file: Option<&'a Path>,
 }
 
-impl<'a> Config<'a> {
+impl Default for Config<'static> {
     /// Create default configuration.
-    pub fn default() -> Config<'static> {
+    fn default() -> Config<'static> {
         // Default border characters
         let hline = "─";
         let vline = "│";
@@ -150,6 +789,11 @@ impl<'a> Config<'a> {
         let llcorner = "└";
         let lrcorner = "┘";
 
+        // Default layout
+        let ellipsis = layout::ELLIPSIS;
+        let ellipsis_at = Placement::End;
+        let margin = 0;
+
         // Default ANSI terminal colours
         let colour0 = &color::Black;
         let colour1 = &color::Red;
@@ -164,26 +808,198 @@ impl<'a> Config<'a> {
         let colourfg = &color::Reset;
         let colourbg = &color::Reset;
 
+        // Default priority colours
+        let priority_high = colour1;
+        let priority_medium = colour3;
+        let priority_low = colour2;
+        let priority_colours = &[];
+
+        // Default title bar style
+        let title_fg = colour0;
+        let title_bg = colour4;
+        let title_bold = true;
+
+        // Default tag colour
+        let tag_fg = colour6;
+
+        // Default border colours, all the same
+        let border_normal = colourfg;
+        let border_edit = colourfg;
+        let border_visual = colourfg;
+        let border_filter = colourfg;
+        let border_unsaved = colourfg;
+
         // Default keybindings
         let quit = Key::Char('q');
         let back = Key::Char('b');
         let save = Key::Char('w');
         let add = Key::Char('a');
         let edit = Key::Char('e');
+        let notes = Key::Char('N');
+        let comment = Key::Char('c');
+        let details = Key::Char('i');
+        let context = Key::Char('@');
+        let upcoming = Key::Char('U');
+        let due = Key::Char('D');
+        let start = Key::Char('S');
+        let estimate = Key::Char('E');
+        let today = Key::Char('t');
         let delete = Key::Char('d');
         let task_up = Key::Char('u');
         let task_down = Key::Char('n');
         let up = Key::Up;
         let down = Key::Down;
         let focus = Key::Char('\n');
+        let focus_mode = Key::Char('F');
         let complete = Key::Char(' ');
         let increase = Key::Char('>');
         let decrease = Key::Char('<');
         let sort = Key::Char('r');
+        let rollover = Key::Char('R');
+        let export = Key::Char('x');
+        let review = Key::Char('W');
+        let report = Key::Char('T');
+        let graph = Key::Char('G');
+        let next_action = Key::Char('!');
+        let mark = Key::Char('v');
+        let command = Key::Char(':');
+        let jump = Key::Char('f');
+        let email_task = Key::Char('M');
+        let archive = Key::Char('A');
+        let tag_filter = Key::Char('#');
+        let share = Key::Char('y');
+        let block = Key::Char('B');
+        let age = Key::Char('O');
+        let skip = Key::Char('s');
+        let defaults = Key::Char('I');
+        let timer = Key::Char('C');
+        let heatmap = Key::Char('H');
+        let theme = Key::Char('P');
+        let undo = Key::Char('z');
+        let redo = Key::Char('Z');
+        let link = Key::Char('L');
+        let url = Key::Char('g');
+        let open_url = Key::Char('o');
+        let remind = Key::Char('m');
+        let trash = Key::Char('X');
+        let duplicate = Key::Char('Y');
+        let find = Key::Char('/');
+        let move_to = Key::Char('J');
+        let cut = Key::Char('K');
+        let paste = Key::Char('p');
+        let target = Key::Char('V');
+        let keys = KeyMap {
+            quit,
+            back,
+            save,
+            add,
+            edit,
+            notes,
+            comment,
+            details,
+            context,
+            upcoming,
+            due,
+            start,
+            estimate,
+            today,
+            delete,
+            task_up,
+            task_down,
+            up,
+            down,
+            focus,
+            focus_mode,
+            complete,
+            increase,
+            decrease,
+            sort,
+            rollover,
+            export,
+            review,
+            report,
+            graph,
+            next_action,
+            mark,
+            command,
+            jump,
+            email_task,
+            archive,
+            tag_filter,
+            share,
+            block,
+            age,
+            skip,
+            defaults,
+            timer,
+            heatmap,
+            theme,
+            undo,
+            redo,
+            link,
+            url,
+            open_url,
+            remind,
+            trash,
+            duplicate,
+            find,
+            move_to,
+            cut,
+            paste,
+            target,
+        };
+        let visual_keys = keys;
+        let edit_keys = EditKeys {
+            accept: Key::Char('\n'),
+            complete: Key::Char('\t'),
+        };
+        let search_keys = SearchKeys {
+            accept: Key::Char('\n'),
+            previous: Key::Up,
+            next: Key::Down,
+        };
 
         // Misc
         let save_on_exit = false;
         let print_priority = false;
+        let priority_levels = 3;
+        let ascii = !unicode_locale();
+        let colour_blind = false;
+        let screen_reader = false;
+        let bell = false;
+        let flash = true;
+        let focus_subtasks = true;
+        let auto_rollover = false;
+        let auto_complete_parents = false;
+        let auto_sort = false;
+        let dim_upcoming = false;
+        let title_bar = false;
+        let duplicate_threshold = 0.8;
+        let week_numbers = false;
+        let archive_after = 0;
+        let trash_days = 30;
+        let lazy_depth = 0;
+        let max_depth = 0;
+        let journal = false;
+        let columns = &DEFAULT_COLUMNS;
+        let shell_commands = &[];
+        let commands = &[];
+        let tag_colours = &[];
+        let heatmap_colours = &[];
+        let save_file = None;
+        let spell_check = None;
+        let locale = None;
+        let first_day_of_week = None;
+        let jump_labels = "asdfghjkl";
+        let contexts = &[];
+        let escalate = &[];
+        let storage = None;
+        let habitica = None;
+        let email = None;
+        let webhooks = None;
+        let weights = Weights::default();
+        let confirm = Confirm::default();
+        let config_file = None;
 
         Config {
             hline,
@@ -192,6 +1008,9 @@ impl<'a> Config<'a> {
             urcorner,
             llcorner,
             lrcorner,
+            ellipsis,
+            ellipsis_at,
+            margin,
             colour0,
             colour1,
             colour2,
@@ -202,27 +1021,209 @@ impl<'a> Config<'a> {
             colour7,
             colourfg,
             colourbg,
-            quit,
-            back,
-            save,
-            add,
-            edit,
-            delete,
-            task_up,
-            task_down,
-            up,
-            down,
-            focus,
-            complete,
-            increase,
-            decrease,
-            sort,
+            priority_high,
+            priority_medium,
+            priority_low,
+            priority_colours,
+            title_fg,
+            title_bg,
+            title_bold,
+            tag_fg,
+            border_normal,
+            border_edit,
+            border_visual,
+            border_filter,
+            border_unsaved,
+            keys,
+            visual_keys,
+            edit_keys,
+            search_keys,
             save_on_exit,
             print_priority,
+            priority_levels,
+            ascii,
+            colour_blind,
+            screen_reader,
+            bell,
+            flash,
+            focus_subtasks,
+            auto_rollover,
+            auto_complete_parents,
+            auto_sort,
+            dim_upcoming,
+            title_bar,
+            duplicate_threshold,
+            week_numbers,
+            archive_after,
+            trash_days,
+            lazy_depth,
+            max_depth,
+            journal,
+            columns,
+            shell_commands,
+            commands,
+            tag_colours,
+            heatmap_colours,
+            save_file,
+            spell_check,
+            locale,
+            first_day_of_week,
+            jump_labels,
+            contexts,
+            escalate,
+            storage,
+            habitica,
+            email,
+            webhooks,
+            weights,
+            confirm,
+            config_file,
+        }
+    }
+}
+
+/// Borders and colours of a configuration, which make up its theme.
+#[derive(Clone, Copy)]
+pub struct Theme<'a> {
+    borders: [&'a str; 6],
+    colours: [&'a dyn color::Color; 10],
+    /// Colours taken from the colour-scheme, for priorities, the title bar,
+    /// tags and borders.
+    uses: [&'a dyn color::Color; 11],
+}
+
+impl<'a> Config<'a> {
+    /// Find the location of the save file, defaulting to ~/.todo/save.txt.
+    pub fn save_file_path(&self) -> Option<PathBuf> {
+        match self.save_file {
+            Some(path) => expand_home(path),
+            None => {
+                let mut filename = home_dir()?;
+                filename.push(".todo/save.txt");
+                Some(filename)
+            }
+        }
+    }
+
+    /// Find the location of the spell checking word list, if any.
+    pub fn spell_check_path(&self) -> Option<PathBuf> {
+        expand_home(self.spell_check?)
+    }
+
+    /// Replace box-drawing border characters with ASCII equivalents.
+    pub fn use_ascii(&mut self) {
+        self.hline = "-";
+        self.vline = "|";
+        self.ulcorner = "+";
+        self.urcorner = "+";
+        self.llcorner = "+";
+        self.lrcorner = "+";
+        if !self.ellipsis.is_ascii() {
+            self.ellipsis = layout::ELLIPSIS;
+        }
+    }
+
+    /// Replace decorative border characters with blank space.
+    pub fn use_plain(&mut self) {
+        self.hline = " ";
+        self.vline = " ";
+        self.ulcorner = " ";
+        self.urcorner = " ";
+        self.llcorner = " ";
+        self.lrcorner = " ";
+    }
+
+    /// The theme in use.
+    pub fn theme(&self) -> Theme<'a> {
+        Theme {
+            borders: [
+                self.hline,
+                self.vline,
+                self.ulcorner,
+                self.urcorner,
+                self.llcorner,
+                self.lrcorner,
+            ],
+            colours: [
+                self.colour0,
+                self.colour1,
+                self.colour2,
+                self.colour3,
+                self.colour4,
+                self.colour5,
+                self.colour6,
+                self.colour7,
+                self.colourfg,
+                self.colourbg,
+            ],
+            uses: [
+                self.priority_high,
+                self.priority_medium,
+                self.priority_low,
+                self.title_fg,
+                self.title_bg,
+                self.tag_fg,
+                self.border_normal,
+                self.border_edit,
+                self.border_visual,
+                self.border_filter,
+                self.border_unsaved,
+            ],
+        }
+    }
+
+    /// Use the borders and colours of a theme, keeping to ASCII or plain
+    /// borders if set.
+    pub fn set_theme(&mut self, theme: &Theme<'a>) {
+        [
+            self.hline,
+            self.vline,
+            self.ulcorner,
+            self.urcorner,
+            self.llcorner,
+            self.lrcorner,
+        ] = theme.borders;
+        [
+            self.colour0,
+            self.colour1,
+            self.colour2,
+            self.colour3,
+            self.colour4,
+            self.colour5,
+            self.colour6,
+            self.colour7,
+            self.colourfg,
+            self.colourbg,
+        ] = theme.colours;
+        [
+            self.priority_high,
+            self.priority_medium,
+            self.priority_low,
+            self.title_fg,
+            self.title_bg,
+            self.tag_fg,
+            self.border_normal,
+            self.border_edit,
+            self.border_visual,
+            self.border_filter,
+            self.border_unsaved,
+        ] = theme.uses;
+        if self.ascii {
+            self.use_ascii();
+        }
+        if self.screen_reader {
+            self.use_plain();
         }
     }
 }
 
+/// A colour chosen in configuration, either a colour slot (0-7 for the
+/// colour-scheme, 8 for foreground) or RGB value.
+pub enum ColourChoice {
+    Slot(usize),
+    Rgb(color::Rgb),
+}
+
 /// A buffer that can hold loaded configuration.
 pub struct ConfigBuffer {
     pub hline: Option<String>,
@@ -231,6 +1232,9 @@ pub struct ConfigBuffer {
     pub urcorner: Option<String>,
     pub llcorner: Option<String>,
     pub lrcorner: Option<String>,
+    pub ellipsis: Option<String>,
+    pub ellipsis_at: Option<Placement>,
+    pub margin: Option<usize>,
     pub colour0: Option<color::Rgb>,
     pub colour1: Option<color::Rgb>,
     pub colour2: Option<color::Rgb>,
@@ -241,23 +1245,123 @@ pub struct ConfigBuffer {
     pub colour7: Option<color::Rgb>,
     pub colourfg: Option<color::Rgb>,
     pub colourbg: Option<color::Rgb>,
+    pub priority_high: Option<ColourChoice>,
+    pub priority_medium: Option<ColourChoice>,
+    pub priority_low: Option<ColourChoice>,
+    pub priority_colours: Option<Vec<ColourChoice>>,
+    pub title_fg: Option<ColourChoice>,
+    pub title_bg: Option<ColourChoice>,
+    pub title_bold: Option<bool>,
+    pub tag_fg: Option<ColourChoice>,
+    pub border_normal: Option<ColourChoice>,
+    pub border_edit: Option<ColourChoice>,
+    pub border_visual: Option<ColourChoice>,
+    pub border_filter: Option<ColourChoice>,
+    pub border_unsaved: Option<ColourChoice>,
     pub quit: Option<Key>,
     pub back: Option<Key>,
     pub save: Option<Key>,
     pub add: Option<Key>,
     pub edit: Option<Key>,
+    pub notes: Option<Key>,
+    pub comment: Option<Key>,
+    pub details: Option<Key>,
+    pub context: Option<Key>,
+    pub upcoming: Option<Key>,
+    pub due: Option<Key>,
+    pub start: Option<Key>,
+    pub estimate: Option<Key>,
+    pub today: Option<Key>,
     pub delete: Option<Key>,
     pub task_up: Option<Key>,
     pub task_down: Option<Key>,
     pub up: Option<Key>,
     pub down: Option<Key>,
     pub focus: Option<Key>,
+    pub focus_mode: Option<Key>,
     pub complete: Option<Key>,
     pub increase: Option<Key>,
     pub decrease: Option<Key>,
     pub sort: Option<Key>,
+    pub rollover: Option<Key>,
+    pub export: Option<Key>,
+    pub review: Option<Key>,
+    pub report: Option<Key>,
+    pub graph: Option<Key>,
+    pub next_action: Option<Key>,
+    pub mark: Option<Key>,
+    pub command: Option<Key>,
+    pub jump: Option<Key>,
+    pub email_task: Option<Key>,
+    pub archive: Option<Key>,
+    pub tag_filter: Option<Key>,
+    pub share: Option<Key>,
+    pub block: Option<Key>,
+    pub age: Option<Key>,
+    pub skip: Option<Key>,
+    pub defaults: Option<Key>,
+    pub timer: Option<Key>,
+    pub heatmap: Option<Key>,
+    pub theme: Option<Key>,
+    pub undo: Option<Key>,
+    pub redo: Option<Key>,
+    pub link: Option<Key>,
+    pub url: Option<Key>,
+    pub open_url: Option<Key>,
+    pub remind: Option<Key>,
+    pub trash: Option<Key>,
+    pub duplicate: Option<Key>,
+    pub find: Option<Key>,
+    pub move_to: Option<Key>,
+    pub cut: Option<Key>,
+    pub paste: Option<Key>,
+    pub target: Option<Key>,
+    pub normal_keys: Option<KeyBindings>,
+    pub visual_keys: Option<KeyBindings>,
+    pub edit_accept: Option<Key>,
+    pub edit_complete: Option<Key>,
+    pub search_accept: Option<Key>,
+    pub search_previous: Option<Key>,
+    pub search_next: Option<Key>,
     pub save_on_exit: Option<bool>,
     pub print_priority: Option<bool>,
+    pub priority_levels: Option<u8>,
+    pub ascii: Option<bool>,
+    pub colour_blind: Option<bool>,
+    pub screen_reader: Option<bool>,
+    pub bell: Option<bool>,
+    pub flash: Option<bool>,
+    pub focus_subtasks: Option<bool>,
+    pub auto_rollover: Option<bool>,
+    pub auto_complete_parents: Option<bool>,
+    pub auto_sort: Option<bool>,
+    pub dim_upcoming: Option<bool>,
+    pub title_bar: Option<bool>,
+    pub duplicate_threshold: Option<f64>,
+    pub week_numbers: Option<bool>,
+    pub archive_after: Option<u32>,
+    pub trash_days: Option<u32>,
+    pub lazy_depth: Option<usize>,
+    pub max_depth: Option<usize>,
+    pub journal: Option<bool>,
+    pub columns: Option<Vec<Column>>,
+    pub shell_commands: Option<Vec<(Key, String)>>,
+    pub commands: Option<Vec<(String, Vec<Command>)>>,
+    pub tag_colours: Option<Vec<(String, ColourChoice)>>,
+    pub heatmap_colours: Option<Vec<ColourChoice>>,
+    pub save_file: Option<String>,
+    pub spell_check: Option<String>,
+    pub locale: Option<String>,
+    pub first_day_of_week: Option<String>,
+    pub jump_labels: Option<String>,
+    pub contexts: Option<Vec<String>>,
+    pub escalate: Option<Vec<Rule>>,
+    pub storage: Option<StorageConfig>,
+    pub habitica: Option<HabiticaConfig>,
+    pub email: Option<EmailConfig>,
+    pub webhooks: Option<WebhooksConfig>,
+    pub weights: Option<Weights>,
+    pub confirm: Option<Confirm>,
 }
 
 impl ConfigBuffer {
@@ -283,6 +1387,9 @@ impl ConfigBuffer {
         let llcorner = choose_config!(llcorner, "llcorner");
         let lrcorner = choose_config!(lrcorner, "lrcorner");
 
+        // Layout
+        let ellipsis = choose_config!(ellipsis, "ellipsis");
+
         // Colours
         let colour0 = choose_config!(colour0, "colour0");
         let colour1 = choose_config!(colour1, "colour1");
@@ -295,6 +1402,51 @@ impl ConfigBuffer {
         let colourfg = choose_config!(colourfg, "colourfg");
         let colourbg = choose_config!(colourbg, "colourbg");
 
+        macro_rules! choose_colour {
+            ($attr:ident, $name:expr, $default:expr) => {
+                match &self.$attr {
+                    Some(ColourChoice::Slot(n)) => {
+                        info!("Using custom {}.", $name);
+                        match n {
+                            0 => colour0,
+                            1 => colour1,
+                            2 => colour2,
+                            3 => colour3,
+                            4 => colour4,
+                            5 => colour5,
+                            6 => colour6,
+                            7 => colour7,
+                            _ => colourfg,
+                        }
+                    }
+                    Some(ColourChoice::Rgb(rgb)) => {
+                        info!("Using custom {}.", $name);
+                        rgb
+                    }
+                    None => $default,
+                }
+            };
+        }
+
+        // Priority colours (defaulting to the colour-scheme)
+        let priority_high = choose_colour!(priority_high, "priority_high", colour1);
+        let priority_medium = choose_colour!(priority_medium, "priority_medium", colour3);
+        let priority_low = choose_colour!(priority_low, "priority_low", colour2);
+
+        // Title bar style (defaulting to the colour-scheme)
+        let title_fg = choose_colour!(title_fg, "title_fg", colour0);
+        let title_bg = choose_colour!(title_bg, "title_bg", colour4);
+
+        // Tag colour (defaulting to the colour-scheme)
+        let tag_fg = choose_colour!(tag_fg, "tag_fg", colour6);
+
+        // Border colours (defaulting to the foreground colour)
+        let border_normal = choose_colour!(border_normal, "border_normal", colourfg);
+        let border_edit = choose_colour!(border_edit, "border_edit", colourfg);
+        let border_visual = choose_colour!(border_visual, "border_visual", colourfg);
+        let border_filter = choose_colour!(border_filter, "border_filter", colourfg);
+        let border_unsaved = choose_colour!(border_unsaved, "border_unsaved", colourfg);
+
         macro_rules! choose_config_val {
             ($attr:ident, $name:expr) => {
                 match self.$attr {
@@ -307,24 +1459,319 @@ impl ConfigBuffer {
             };
         }
 
+        macro_rules! choose_key {
+            ($attr:ident, $name:expr) => {
+                match self.$attr {
+                    Some(val) => {
+                        info!("Using custom {}.", $name);
+                        val
+                    }
+                    None => default.keys.$attr,
+                }
+            };
+        }
+
         // Keys
-        let quit = choose_config_val!(quit, "quit key");
-        let back = choose_config_val!(back, "back key");
-        let save = choose_config_val!(save, "save key");
-        let add = choose_config_val!(add, "add key");
-        let edit = choose_config_val!(edit, "edit key");
-        let delete = choose_config_val!(delete, "delete key");
-        let task_up = choose_config_val!(task_up, "task_up key");
-        let task_down = choose_config_val!(task_down, "task_down key");
-        let up = choose_config_val!(up, "up key");
-        let down = choose_config_val!(down, "down key");
-        let focus = choose_config_val!(focus, "focus key");
-        let complete = choose_config_val!(complete, "complete key");
-        let increase = choose_config_val!(increase, "increase key");
-        let decrease = choose_config_val!(decrease, "decrease key");
-        let sort = choose_config_val!(sort, "sort key");
+        let quit = choose_key!(quit, "quit key");
+        let back = choose_key!(back, "back key");
+        let save = choose_key!(save, "save key");
+        let add = choose_key!(add, "add key");
+        let edit = choose_key!(edit, "edit key");
+        let notes = choose_key!(notes, "notes key");
+        let comment = choose_key!(comment, "comment key");
+        let details = choose_key!(details, "details key");
+        let context = choose_key!(context, "context key");
+        let upcoming = choose_key!(upcoming, "upcoming key");
+        let due = choose_key!(due, "due key");
+        let start = choose_key!(start, "start key");
+        let estimate = choose_key!(estimate, "estimate key");
+        let today = choose_key!(today, "today key");
+        let delete = choose_key!(delete, "delete key");
+        let task_up = choose_key!(task_up, "task_up key");
+        let task_down = choose_key!(task_down, "task_down key");
+        let up = choose_key!(up, "up key");
+        let down = choose_key!(down, "down key");
+        let focus = choose_key!(focus, "focus key");
+        let focus_mode = choose_key!(focus_mode, "focus_mode key");
+        let complete = choose_key!(complete, "complete key");
+        let increase = choose_key!(increase, "increase key");
+        let decrease = choose_key!(decrease, "decrease key");
+        let sort = choose_key!(sort, "sort key");
+        let rollover = choose_key!(rollover, "rollover key");
+        let export = choose_key!(export, "export key");
+        let review = choose_key!(review, "review key");
+        let report = choose_key!(report, "report key");
+        let graph = choose_key!(graph, "graph key");
+        let next_action = choose_key!(next_action, "next_action key");
+        let mark = choose_key!(mark, "mark key");
+        let command = choose_key!(command, "command key");
+        let jump = choose_key!(jump, "jump key");
+        let email_task = choose_key!(email_task, "email key");
+        let archive = choose_key!(archive, "archive key");
+        let tag_filter = choose_key!(tag_filter, "tag_filter key");
+        let share = choose_key!(share, "share key");
+        let block = choose_key!(block, "block key");
+        let age = choose_key!(age, "age key");
+        let skip = choose_key!(skip, "skip key");
+        let defaults = choose_key!(defaults, "defaults key");
+        let timer = choose_key!(timer, "timer key");
+        let heatmap = choose_key!(heatmap, "heatmap key");
+        let theme = choose_key!(theme, "theme key");
+        let undo = choose_key!(undo, "undo key");
+        let redo = choose_key!(redo, "redo key");
+        let link = choose_key!(link, "link key");
+        let url = choose_key!(url, "url key");
+        let open_url = choose_key!(open_url, "open_url key");
+        let remind = choose_key!(remind, "remind key");
+        let trash = choose_key!(trash, "trash key");
+        let duplicate = choose_key!(duplicate, "duplicate key");
+        let find = choose_key!(find, "find key");
+        let move_to = choose_key!(move_to, "move_to key");
+        let cut = choose_key!(cut, "cut key");
+        let paste = choose_key!(paste, "paste key");
+        let target = choose_key!(target, "target key");
+        let keys = KeyMap {
+            quit,
+            back,
+            save,
+            add,
+            edit,
+            notes,
+            comment,
+            details,
+            context,
+            upcoming,
+            due,
+            start,
+            estimate,
+            today,
+            delete,
+            task_up,
+            task_down,
+            up,
+            down,
+            focus,
+            focus_mode,
+            complete,
+            increase,
+            decrease,
+            sort,
+            rollover,
+            export,
+            review,
+            report,
+            graph,
+            next_action,
+            mark,
+            command,
+            jump,
+            email_task,
+            archive,
+            tag_filter,
+            share,
+            block,
+            age,
+            skip,
+            defaults,
+            timer,
+            heatmap,
+            theme,
+            undo,
+            redo,
+            link,
+            url,
+            open_url,
+            remind,
+            trash,
+            duplicate,
+            find,
+            move_to,
+            cut,
+            paste,
+            target,
+        };
+        let keys = match &self.normal_keys {
+            Some(bindings) => {
+                info!("Using custom normal mode keys.");
+                keys.with(bindings)
+            }
+            None => keys,
+        };
+        let visual_keys = match &self.visual_keys {
+            Some(bindings) => {
+                info!("Using custom visual mode keys.");
+                keys.with(bindings)
+            }
+            None => keys,
+        };
+        let edit_keys = EditKeys {
+            accept: self.edit_accept.unwrap_or(default.edit_keys.accept),
+            complete: self.edit_complete.unwrap_or(default.edit_keys.complete),
+        };
+        let search_keys = SearchKeys {
+            accept: self.search_accept.unwrap_or(default.search_keys.accept),
+            previous: self.search_previous.unwrap_or(default.search_keys.previous),
+            next: self.search_next.unwrap_or(default.search_keys.next),
+        };
         let save_on_exit = choose_config_val!(save_on_exit, "save_on_exit");
         let print_priority = choose_config_val!(print_priority, "print_priority");
+        let priority_levels = choose_config_val!(priority_levels, "priority_levels");
+        let ascii = choose_config_val!(ascii, "ascii");
+        let colour_blind = choose_config_val!(colour_blind, "colour_blind");
+        let screen_reader = choose_config_val!(screen_reader, "screen_reader");
+        let bell = choose_config_val!(bell, "bell");
+        let flash = choose_config_val!(flash, "flash");
+        let focus_subtasks = choose_config_val!(focus_subtasks, "focus_subtasks");
+        let auto_rollover = choose_config_val!(auto_rollover, "auto_rollover");
+        let auto_complete_parents =
+            choose_config_val!(auto_complete_parents, "auto_complete_parents");
+        let auto_sort = choose_config_val!(auto_sort, "auto_sort");
+        let dim_upcoming = choose_config_val!(dim_upcoming, "dim_upcoming");
+        let title_bar = choose_config_val!(title_bar, "title_bar");
+        let duplicate_threshold = choose_config_val!(duplicate_threshold, "duplicate_threshold");
+        let week_numbers = choose_config_val!(week_numbers, "week_numbers");
+        let archive_after = choose_config_val!(archive_after, "archive_after");
+        let trash_days = choose_config_val!(trash_days, "trash_days");
+        let lazy_depth = choose_config_val!(lazy_depth, "lazy_depth");
+        let max_depth = choose_config_val!(max_depth, "max_depth");
+        let journal = choose_config_val!(journal, "journal");
+        let title_bold = choose_config_val!(title_bold, "title_bold");
+        let ellipsis_at = choose_config_val!(ellipsis_at, "ellipsis_at");
+        let margin = choose_config_val!(margin, "margin");
+        let columns = match &self.columns {
+            Some(val) => {
+                info!("Using custom columns.");
+                val.as_slice()
+            }
+            None => default.columns,
+        };
+        let shell_commands = match &self.shell_commands {
+            Some(val) => {
+                info!("Using custom shell commands.");
+                val.as_slice()
+            }
+            None => default.shell_commands,
+        };
+        let commands = match &self.commands {
+            Some(val) => {
+                info!("Using {} custom commands.", val.len());
+                val.as_slice()
+            }
+            None => default.commands,
+        };
+        let priority_colours = match &self.priority_colours {
+            Some(val) => {
+                info!("Using priority level colours.");
+                val.as_slice()
+            }
+            None => default.priority_colours,
+        };
+        let tag_colours = match &self.tag_colours {
+            Some(val) => {
+                info!("Using tag colours.");
+                val.as_slice()
+            }
+            None => default.tag_colours,
+        };
+        let heatmap_colours = match &self.heatmap_colours {
+            Some(val) => {
+                info!("Using heatmap colours.");
+                val.as_slice()
+            }
+            None => default.heatmap_colours,
+        };
+        let save_file = match &self.save_file {
+            Some(val) => {
+                info!("Using custom save_file.");
+                Some(val.as_str())
+            }
+            None => default.save_file,
+        };
+        let spell_check = match &self.spell_check {
+            Some(val) => {
+                info!("Using spell_check word list.");
+                Some(val.as_str())
+            }
+            None => default.spell_check,
+        };
+        let locale = match &self.locale {
+            Some(val) => {
+                info!("Using custom locale.");
+                Some(val.as_str())
+            }
+            None => default.locale,
+        };
+        let first_day_of_week = match &self.first_day_of_week {
+            Some(val) => {
+                info!("Using custom first_day_of_week.");
+                Some(val.as_str())
+            }
+            None => default.first_day_of_week,
+        };
+        let jump_labels = match &self.jump_labels {
+            Some(val) => {
+                info!("Using custom jump_labels.");
+                val.as_str()
+            }
+            None => default.jump_labels,
+        };
+        let contexts = match &self.contexts {
+            Some(val) => {
+                info!("Using {} contexts.", val.len());
+                val.as_slice()
+            }
+            None => default.contexts,
+        };
+        let escalate = match &self.escalate {
+            Some(val) => {
+                info!("Using {} escalation rules.", val.len());
+                val.as_slice()
+            }
+            None => default.escalate,
+        };
+        let storage = match &self.storage {
+            Some(val) => {
+                info!("Using {} storage.", val.kind);
+                Some(val)
+            }
+            None => default.storage,
+        };
+        let habitica = match &self.habitica {
+            Some(val) => {
+                info!("Scoring Habitica habits for {} tags.", val.habits.len());
+                Some(val)
+            }
+            None => default.habitica,
+        };
+        let email = match &self.email {
+            Some(val) => {
+                info!("Sending tasks by email to {}.", val.to);
+                Some(val)
+            }
+            None => default.email,
+        };
+        let webhooks = match &self.webhooks {
+            Some(val) => {
+                let count = val.add.len() + val.complete.len();
+                info!("Using {} webhooks.", count);
+                Some(val)
+            }
+            None => default.webhooks,
+        };
+        let weights = match self.weights {
+            Some(val) => {
+                info!("Using custom next_action weights.");
+                val
+            }
+            None => default.weights,
+        };
+        let confirm = match self.confirm {
+            Some(val) => {
+                info!("Using custom confirmation settings.");
+                val
+            }
+            None => default.confirm,
+        };
 
         Config {
             hline,
@@ -333,6 +1780,9 @@ impl ConfigBuffer {
             urcorner,
             llcorner,
             lrcorner,
+            ellipsis,
+            ellipsis_at,
+            margin,
             colour0,
             colour1,
             colour2,
@@ -343,31 +1793,131 @@ impl ConfigBuffer {
             colour7,
             colourfg,
             colourbg,
-            quit,
-            back,
-            save,
-            add,
-            edit,
-            delete,
-            task_up,
-            task_down,
-            up,
-            down,
-            focus,
-            complete,
-            increase,
-            decrease,
-            sort,
+            priority_high,
+            priority_medium,
+            priority_low,
+            priority_colours,
+            title_fg,
+            title_bg,
+            title_bold,
+            tag_fg,
+            border_normal,
+            border_edit,
+            border_visual,
+            border_filter,
+            border_unsaved,
+            keys,
+            visual_keys,
+            edit_keys,
+            search_keys,
             save_on_exit,
             print_priority,
+            priority_levels,
+            ascii,
+            colour_blind,
+            screen_reader,
+            bell,
+            flash,
+            focus_subtasks,
+            auto_rollover,
+            auto_complete_parents,
+            auto_sort,
+            dim_upcoming,
+            title_bar,
+            duplicate_threshold,
+            week_numbers,
+            archive_after,
+            trash_days,
+            lazy_depth,
+            max_depth,
+            journal,
+            columns,
+            shell_commands,
+            commands,
+            tag_colours,
+            heatmap_colours,
+            save_file,
+            spell_check,
+            locale,
+            first_day_of_week,
+            jump_labels,
+            contexts,
+            escalate,
+            storage,
+            habitica,
+            email,
+            webhooks,
+            weights,
+            confirm,
+            config_file: default.config_file,
+        }
+    }
+}
+
+/// Check whether the locale (from LC_ALL, LC_CTYPE or LANG, in order of
+/// precedence) uses a unicode character encoding.
+fn unicode_locale() -> bool {
+    for var in ["LC_ALL", "LC_CTYPE", "LANG"].iter() {
+        if let Ok(locale) = env::var(var) {
+            if !locale.is_empty() {
+                let locale = locale.to_lowercase();
+                return locale.contains("utf-8") || locale.contains("utf8");
+            }
+        }
+    }
+    false
+}
+
+/// Decide whether the terminal has a light background, either as set in
+/// the configuration ("light", "dark" or "auto") or by detection, first
+/// using the COLORFGBG hint and then by asking the terminal.
+fn light_background(setting: Option<&str>) -> bool {
+    match setting {
+        Some("light") => return true,
+        Some("dark") => return false,
+        Some("auto") | None => (),
+        Some(other) => warn!("Unknown background setting {}: using auto.", other),
+    }
+
+    // COLORFGBG is of the form "fg;bg" (or "fg;default;bg"), using the ANSI
+    // colour numbers, where 7 and 9-15 are light colours.
+    if let Ok(colorfgbg) = env::var("COLORFGBG") {
+        if let Some(Ok(bg)) = colorfgbg.rsplit(';').next().map(str::parse::<u8>) {
+            info!("Background colour {} from COLORFGBG.", bg);
+            return bg == 7 || bg > 8;
+        }
+    }
+
+    match tui::light_background() {
+        Some(light) => light,
+        None => {
+            info!("Unable to detect background colour: assuming dark.");
+            false
         }
     }
 }
 
-/// Check for file at ~/.todo/config.toml and if present load
-/// user configuration.
-pub fn check_for_config() -> Option<ConfigBuffer> {
-    // Check for config file at ~/.todo/config.toml
+/// Expand a leading ~ in a path to the home directory.
+pub fn expand_home(path: &str) -> Option<PathBuf> {
+    if path == "~" || path.starts_with("~/") {
+        let mut filename = match home_dir() {
+            Some(dir) => dir,
+            None => {
+                warn!("Unable to locate home directory.");
+                return None;
+            }
+        };
+        if path.len() > 2 {
+            filename.push(&path[2..]);
+        }
+        Some(filename)
+    } else {
+        Some(PathBuf::from(path))
+    }
+}
+
+/// Find the location of the configuration file, ~/.todo/config.toml.
+pub fn config_path() -> Option<PathBuf> {
     let mut filename = match home_dir() {
         Some(dir) => dir,
         None => {
@@ -376,29 +1926,90 @@ pub fn check_for_config() -> Option<ConfigBuffer> {
         }
     };
     filename.push(".todo/config.toml");
+    Some(filename)
+}
+
+/// Layout of a profile in profiles.toml file.
+#[derive(Deserialize, Debug)]
+pub struct Profile {
+    /// Location of the profile's configuration file.
+    pub config: Option<String>,
+    /// Location of the profile's save file.
+    pub save_file: Option<String>,
+}
+
+impl Profile {
+    /// Find the location of the profile's configuration file, defaulting to
+    /// ~/.todo/<name>/config.toml.
+    pub fn config_path(&self, name: &str) -> Option<PathBuf> {
+        match &self.config {
+            Some(path) => expand_home(path),
+            None => expand_home(&format!("~/.todo/{}/config.toml", name)),
+        }
+    }
+
+    /// Find the location of the profile's save file, defaulting to
+    /// ~/.todo/<name>/save.txt.
+    pub fn save_file(&self, name: &str) -> String {
+        match &self.save_file {
+            Some(path) => path.clone(),
+            None => format!("~/.todo/{}/save.txt", name),
+        }
+    }
+}
 
+/// Load the named profile from ~/.todo/profiles.toml.
+pub fn load_profile(name: &str) -> Option<Profile> {
+    let filename = expand_home("~/.todo/profiles.toml")?;
     let buffer = match read_to_string(filename) {
-        Ok(buf) => {
-            info!("Configuration file at ~/.todo/config.toml read!");
-            buf
+        Ok(buf) => buf,
+        Err(err) => {
+            warn!("Unable to read ~/.todo/profiles.toml: {}", err);
+            return None;
         }
+    };
+
+    let mut profiles: HashMap<String, Profile> = match toml::from_str(&buffer) {
+        Ok(toml) => toml,
         Err(err) => {
-            warn!("Unable to read ~/.todo/config.toml: {}", err);
+            warn!("Unable to parse ~/.todo/profiles.toml: {}", err);
             return None;
         }
     };
 
-    let toml_config: TomlConfig = match toml::from_str(&buffer) {
-        Ok(toml) => {
-            info!("Configuration parsed from file.");
-            toml
+    let profile = profiles.remove(name);
+    match profile {
+        Some(_) => info!("Using profile {}.", name),
+        None => warn!("No profile {} in ~/.todo/profiles.toml.", name),
+    }
+    profile
+}
+
+/// Check for configuration file and if present load user configuration.
+pub fn check_for_config(filename: &Path) -> Option<ConfigBuffer> {
+    let buffer = match read_to_string(filename) {
+        Ok(buf) => {
+            info!("Configuration file at {} read!", filename.display());
+            buf
         }
         Err(err) => {
-            warn!("Unable to parse ~/.todo/config.toml: {}", err);
+            warn!("Unable to read {}: {}", filename.display(), err);
             return None;
         }
     };
 
+    parse_config(&buffer)
+        .map_err(|err| {
+            warn!("Unable to parse {}: {}", filename.display(), err);
+        })
+        .ok()
+}
+
+/// Parse configuration in config.toml format.
+pub fn parse_config(buffer: &str) -> Result<ConfigBuffer, toml::de::Error> {
+    let toml_config: TomlConfig = toml::from_str(buffer)?;
+    info!("Configuration parsed from file.");
+
     let (hline, vline, ulcorner, urcorner, llcorner, lrcorner) = match toml_config.borders {
         Some(border) => (
             border.hline,
@@ -410,6 +2021,30 @@ pub fn check_for_config() -> Option<ConfigBuffer> {
         ),
         None => (None, None, None, None, None, None),
     };
+
+    let (ellipsis, ellipsis_at, margin) = match toml_config.layout {
+        Some(layout) => {
+            let ellipsis_at = layout.ellipsis_at.and_then(|text| {
+                let placement = Placement::parse(&text);
+                if placement.is_none() {
+                    warn!("Invalid ellipsis_at: {} (use end, middle or start)", text);
+                }
+                placement
+            });
+            (layout.ellipsis, ellipsis_at, layout.margin)
+        }
+        None => (None, None, None),
+    };
+
+    // Use light variant of the colour-scheme on light terminal backgrounds
+    let colours = match toml_config.light_colours {
+        Some(light) if light_background(toml_config.background.as_deref()) => {
+            info!("Using light colour-scheme.");
+            Some(light.or(toml_config.colours))
+        }
+        _ => toml_config.colours,
+    };
+
     let (
         colour0,
         colour1,
@@ -421,7 +2056,7 @@ pub fn check_for_config() -> Option<ConfigBuffer> {
         colour7,
         colourfg,
         colourbg,
-    ) = match toml_config.colours {
+    ) = match colours {
         Some(colours) => (
             colours.colour0,
             colours.colour1,
@@ -437,82 +2072,452 @@ pub fn check_for_config() -> Option<ConfigBuffer> {
         None => (None, None, None, None, None, None, None, None, None, None),
     };
 
+    let (priority_high, priority_medium, priority_low, priority_colours) =
+        match toml_config.priority_colours {
+            Some(priority_colours) => (
+                priority_colours.high.and_then(TomlColour::choice),
+                priority_colours.medium.and_then(TomlColour::choice),
+                priority_colours.low.and_then(TomlColour::choice),
+                priority_colours
+                    .levels
+                    .map(|levels| levels.into_iter().filter_map(TomlColour::choice).collect()),
+            ),
+            None => (None, None, None, None),
+        };
+
+    let (title_fg, title_bg, title_bold) = match toml_config.title_style {
+        Some(title_style) => (
+            title_style.fg.and_then(TomlColour::choice),
+            title_style.bg.and_then(TomlColour::choice),
+            title_style.bold,
+        ),
+        None => (None, None, None),
+    };
+
+    let tag_fg = toml_config
+        .tag_style
+        .and_then(|tag_style| tag_style.fg)
+        .and_then(TomlColour::choice);
+
+    let (border_normal, border_edit, border_visual, border_filter, border_unsaved) =
+        match toml_config.border_colours {
+            Some(border_colours) => (
+                border_colours.normal.and_then(TomlColour::choice),
+                border_colours.edit.and_then(TomlColour::choice),
+                border_colours.visual.and_then(TomlColour::choice),
+                border_colours.filter.and_then(TomlColour::choice),
+                border_colours.unsaved.and_then(TomlColour::choice),
+            ),
+            None => (None, None, None, None, None),
+        };
+
+    let (bindings, normal_keys, visual_keys, edit_keys, search_keys) = match toml_config.keys {
+        Some(keys) => (
+            Some(keys.bindings),
+            keys.normal,
+            keys.visual,
+            keys.edit,
+            keys.search,
+        ),
+        None => (None, None, None, None, None),
+    };
+    let (edit_accept, edit_complete) = match edit_keys {
+        Some(edit) => (edit.accept, edit.complete),
+        None => (None, None),
+    };
+    let (search_accept, search_previous, search_next) = match search_keys {
+        Some(search) => (search.accept, search.previous, search.next),
+        None => (None, None, None),
+    };
     let (
         quit,
         back,
         save,
         add,
         edit,
+        notes,
+        comment,
+        details,
+        context,
+        upcoming,
+        due,
+        start,
+        estimate,
+        today,
         delete,
         task_up,
         task_down,
         up,
         down,
         focus,
+        focus_mode,
         complete,
         increase,
         decrease,
         sort,
-    ) = match toml_config.keys {
+        rollover,
+        export,
+        review,
+        report,
+        graph,
+        next_action,
+        mark,
+        command,
+        jump,
+        email_task,
+        archive,
+        tag_filter,
+        share,
+        block,
+        age,
+        skip,
+        defaults,
+        timer,
+        heatmap,
+        theme,
+        undo,
+        redo,
+        link,
+        url,
+        open_url,
+        remind,
+        trash,
+        duplicate,
+        find,
+        move_to,
+        cut,
+        paste,
+        target,
+    ) = match bindings {
         Some(keys) => (
             keys.quit,
             keys.back,
             keys.save,
             keys.add,
             keys.edit,
+            keys.notes,
+            keys.comment,
+            keys.details,
+            keys.context,
+            keys.upcoming,
+            keys.due,
+            keys.start,
+            keys.estimate,
+            keys.today,
             keys.delete,
             keys.task_up,
             keys.task_down,
             keys.up,
             keys.down,
             keys.focus,
+            keys.focus_mode,
             keys.complete,
             keys.increase,
             keys.decrease,
             keys.sort,
+            keys.rollover,
+            keys.export,
+            keys.review,
+            keys.report,
+            keys.graph,
+            keys.next_action,
+            keys.mark,
+            keys.command,
+            keys.jump,
+            keys.email_task,
+            keys.archive,
+            keys.tag_filter,
+            keys.share,
+            keys.block,
+            keys.age,
+            keys.skip,
+            keys.defaults,
+            keys.timer,
+            keys.heatmap,
+            keys.theme,
+            keys.undo,
+            keys.redo,
+            keys.link,
+            keys.url,
+            keys.open_url,
+            keys.remind,
+            keys.trash,
+            keys.duplicate,
+            keys.find,
+            keys.move_to,
+            keys.cut,
+            keys.paste,
+            keys.target,
         ),
         None => (
             None, None, None, None, None, None, None, None, None, None, None, None, None, None,
-            None,
+            None, None, None, None, None, None, None, None, None, None, None, None, None, None,
+            None, None, None, None, None, None, None, None, None, None, None, None, None, None,
+            None, None, None, None, None, None, None, None, None, None, None, None, None, None,
+            None, None,
         ),
     };
 
     let save_on_exit = toml_config.save_on_exit;
     let print_priority = toml_config.print_priority;
+    let priority_levels = toml_config.priority_levels.map(|levels| {
+        let clamped = levels.clamp(1, MAX_PRIORITY_LEVELS);
+        if clamped != levels {
+            warn!(
+                "priority_levels must be from 1 to {}: {}",
+                MAX_PRIORITY_LEVELS, levels
+            );
+        }
+        clamped
+    });
+    let ascii = toml_config.ascii;
+    let colour_blind = toml_config.colour_blind;
+    let screen_reader = toml_config.screen_reader;
+    let bell = toml_config.bell;
+    let flash = toml_config.flash;
+    let focus_subtasks = toml_config.focus_subtasks;
+    let auto_rollover = toml_config.auto_rollover;
+    let auto_complete_parents = toml_config.auto_complete_parents;
+    let auto_sort = toml_config.auto_sort;
+    let dim_upcoming = toml_config.dim_upcoming;
+    let title_bar = toml_config.title_bar;
+    let duplicate_threshold = toml_config.duplicate_threshold;
+    let week_numbers = toml_config.week_numbers;
+    let archive_after = toml_config.archive_after;
+    let trash_days = toml_config.trash_days;
+    let lazy_depth = toml_config.lazy_depth;
+    let max_depth = toml_config.max_depth;
+    let journal = toml_config.journal;
+    let columns = toml_config
+        .columns
+        .map(|columns| columns.iter().filter_map(|c| Column::parse(c)).collect());
+    let shell_commands = toml_config.shell_commands.map(|commands| {
+        commands
+            .into_iter()
+            .filter_map(|(key, command)| {
+                let mut chars = key.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(ch), None) => Some((Key::Char(ch), command)),
+                    _ => {
+                        warn!("Shell command key must be a single character: {}", key);
+                        None
+                    }
+                }
+            })
+            .collect()
+    });
+    let commands = toml_config.commands.map(|commands| {
+        let mut commands: Vec<(String, Vec<Command>)> = commands
+            .into_iter()
+            .filter_map(|(name, definition)| {
+                if !command::valid_name(&name) {
+                    warn!("Invalid custom command name: {}", name);
+                    return None;
+                }
+                match command::parse_sequence(&definition) {
+                    Ok(sequence) => Some((name, sequence)),
+                    Err(err) => {
+                        warn!("Invalid custom command {}: {}", name, err);
+                        None
+                    }
+                }
+            })
+            .collect();
+        commands.sort_by(|(a, _), (b, _)| a.cmp(b));
+        commands
+    });
+    let tag_colours = toml_config.tag_colours.map(|rules| {
+        let mut rules: Vec<(String, ColourChoice)> = rules
+            .into_iter()
+            .filter_map(|(tag, colour)| {
+                let tag = tag.trim_start_matches(['+', '#']).to_string();
+                colour.choice().map(|colour| (tag, colour))
+            })
+            .collect();
+        rules.sort_by(|a, b| a.0.cmp(&b.0));
+        rules
+    });
+    let heatmap_colours = toml_config
+        .heatmap_colours
+        .map(|colours| colours.into_iter().filter_map(TomlColour::choice).collect());
+    let save_file = toml_config.save_file;
+    let spell_check = toml_config.spell_check;
+    let locale = toml_config.locale;
+    let first_day_of_week = toml_config.first_day_of_week;
+    let jump_labels = toml_config.jump_labels;
+    let contexts = toml_config.contexts.map(|contexts| {
+        contexts
+            .into_iter()
+            .filter(|context| {
+                let valid = context.len() > 1
+                    && context.starts_with('@')
+                    && !context.contains(char::is_whitespace);
+                if !valid {
+                    warn!("Context must be one word starting with '@': {}", context);
+                }
+                valid
+            })
+            .collect()
+    });
+    let escalate = toml_config.escalate.map(|rules| {
+        rules
+            .into_iter()
+            .filter_map(|rule| {
+                let parsed = Rule::parse(
+                    rule.due_within.as_deref(),
+                    rule.older_than.as_deref(),
+                    &rule.priority,
+                    priority_levels.unwrap_or(Config::default().priority_levels),
+                );
+                parsed
+                    .map_err(|err| warn!("Invalid escalation rule: {}", err))
+                    .ok()
+            })
+            .collect()
+    });
+    let storage = toml_config.storage;
+    let habitica = toml_config.habitica;
+    let email = toml_config.email;
+    let webhooks = toml_config.webhooks;
+    let weights = toml_config.next_action;
+    let confirm = toml_config.confirm;
 
-    Some(ConfigBuffer {
+    Ok(ConfigBuffer {
         hline,
         vline,
         ulcorner,
         urcorner,
         llcorner,
         lrcorner,
-        colour0: colour0.map(|x| color::Rgb(x[0], x[1], x[2])),
-        colour1: colour1.map(|x| color::Rgb(x[0], x[1], x[2])),
-        colour2: colour2.map(|x| color::Rgb(x[0], x[1], x[2])),
-        colour3: colour3.map(|x| color::Rgb(x[0], x[1], x[2])),
-        colour4: colour4.map(|x| color::Rgb(x[0], x[1], x[2])),
-        colour5: colour5.map(|x| color::Rgb(x[0], x[1], x[2])),
-        colour6: colour6.map(|x| color::Rgb(x[0], x[1], x[2])),
-        colour7: colour7.map(|x| color::Rgb(x[0], x[1], x[2])),
-        colourfg: colourfg.map(|x| color::Rgb(x[0], x[1], x[2])),
-        colourbg: colourbg.map(|x| color::Rgb(x[0], x[1], x[2])),
+        ellipsis,
+        ellipsis_at,
+        margin,
+        colour0: colour0.as_deref().and_then(rgb),
+        colour1: colour1.as_deref().and_then(rgb),
+        colour2: colour2.as_deref().and_then(rgb),
+        colour3: colour3.as_deref().and_then(rgb),
+        colour4: colour4.as_deref().and_then(rgb),
+        colour5: colour5.as_deref().and_then(rgb),
+        colour6: colour6.as_deref().and_then(rgb),
+        colour7: colour7.as_deref().and_then(rgb),
+        colourfg: colourfg.as_deref().and_then(rgb),
+        colourbg: colourbg.as_deref().and_then(rgb),
+        priority_high,
+        priority_medium,
+        priority_low,
+        priority_colours,
+        title_fg,
+        title_bg,
+        title_bold,
+        tag_fg,
+        border_normal,
+        border_edit,
+        border_visual,
+        border_filter,
+        border_unsaved,
         quit: quit.map(Key::Char),
         back: back.map(Key::Char),
         save: save.map(Key::Char),
         add: add.map(Key::Char),
         edit: edit.map(Key::Char),
+        notes: notes.map(Key::Char),
+        comment: comment.map(Key::Char),
+        details: details.map(Key::Char),
+        context: context.map(Key::Char),
+        upcoming: upcoming.map(Key::Char),
+        due: due.map(Key::Char),
+        start: start.map(Key::Char),
+        estimate: estimate.map(Key::Char),
+        today: today.map(Key::Char),
         delete: delete.map(Key::Char),
         task_up: task_up.map(Key::Char),
         task_down: task_down.map(Key::Char),
         up: up.map(Key::Char),
         down: down.map(Key::Char),
         focus: focus.map(Key::Char),
+        focus_mode: focus_mode.map(Key::Char),
         complete: complete.map(Key::Char),
         increase: increase.map(Key::Char),
         decrease: decrease.map(Key::Char),
         sort: sort.map(Key::Char),
+        rollover: rollover.map(Key::Char),
+        export: export.map(Key::Char),
+        review: review.map(Key::Char),
+        report: report.map(Key::Char),
+        graph: graph.map(Key::Char),
+        next_action: next_action.map(Key::Char),
+        mark: mark.map(Key::Char),
+        command: command.map(Key::Char),
+        jump: jump.map(Key::Char),
+        email_task: email_task.map(Key::Char),
+        archive: archive.map(Key::Char),
+        tag_filter: tag_filter.map(Key::Char),
+        share: share.map(Key::Char),
+        block: block.map(Key::Char),
+        age: age.map(Key::Char),
+        skip: skip.map(Key::Char),
+        defaults: defaults.map(Key::Char),
+        timer: timer.map(Key::Char),
+        heatmap: heatmap.map(Key::Char),
+        theme: theme.map(Key::Char),
+        undo: undo.map(Key::Char),
+        redo: redo.map(Key::Char),
+        link: link.map(Key::Char),
+        url: url.map(Key::Char),
+        open_url: open_url.map(Key::Char),
+        remind: remind.map(Key::Char),
+        trash: trash.map(Key::Char),
+        duplicate: duplicate.map(Key::Char),
+        find: find.map(Key::Char),
+        move_to: move_to.map(Key::Char),
+        cut: cut.map(Key::Char),
+        paste: paste.map(Key::Char),
+        target: target.map(Key::Char),
+        normal_keys,
+        visual_keys,
+        edit_accept: edit_accept.map(Key::Char),
+        edit_complete: edit_complete.map(Key::Char),
+        search_accept: search_accept.map(Key::Char),
+        search_previous: search_previous.map(Key::Char),
+        search_next: search_next.map(Key::Char),
         save_on_exit,
-        print_priority
+        print_priority,
+        priority_levels,
+        ascii,
+        colour_blind,
+        screen_reader,
+        bell,
+        flash,
+        focus_subtasks,
+        auto_rollover,
+        auto_complete_parents,
+        auto_sort,
+        dim_upcoming,
+        title_bar,
+        duplicate_threshold,
+        week_numbers,
+        archive_after,
+        trash_days,
+        lazy_depth,
+        max_depth,
+        journal,
+        columns,
+        shell_commands,
+        commands,
+        tag_colours,
+        heatmap_colours,
+        save_file,
+        spell_check,
+        locale,
+        first_day_of_week,
+        jump_labels,
+        contexts,
+        escalate,
+        storage,
+        habitica,
+        email,
+        webhooks,
+        weights,
+        confirm,
     })
 }
@@ -0,0 +1,102 @@
+/// Quick-add from outside the terminal: a running yat listens on a socket
+/// beside its save file, and `yat add --daemon` sends tasks to it.
+use crate::error::Error;
+use log::{info, warn};
+use std::fs;
+use std::io::{self, Read, Write};
+use std::os::unix::io::{AsRawFd, RawFd};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Longest wait for the rest of the tasks once someone has connected.
+const READ_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Location of the socket for a save file, e.g. save.sock beside save.txt.
+pub fn path(save_file: &Path) -> PathBuf {
+    save_file.with_extension("sock")
+}
+
+/// Socket a running yat receives tasks on, removed when dropped.
+pub struct Listener {
+    listener: UnixListener,
+    path: PathBuf,
+}
+
+impl Listener {
+    /// Listen for tasks to add to a save file. A socket left behind by a
+    /// yat which stopped unexpectedly is replaced, but not one still in
+    /// use.
+    pub fn bind(save_file: &Path) -> Result<Listener, Error> {
+        let path = path(save_file);
+        if UnixStream::connect(&path).is_ok() {
+            return Err(Error::Busy(path));
+        }
+        let _ = fs::remove_file(&path);
+        let listener = UnixListener::bind(&path).map_err(|err| Error::Write(path.clone(), err))?;
+        listener
+            .set_nonblocking(true)
+            .map_err(|err| Error::Write(path.clone(), err))?;
+        info!("Listening for tasks on {}", path.display());
+        Ok(Listener { listener, path })
+    }
+
+    /// File descriptor which is readable when tasks have been sent.
+    pub fn fd(&self) -> RawFd {
+        self.listener.as_raw_fd()
+    }
+
+    /// The tasks sent since last checked, a line each.
+    pub fn receive(&self) -> Vec<String> {
+        let mut tasks = Vec::new();
+        loop {
+            match self.listener.accept() {
+                Ok((stream, _)) => match read_tasks(stream) {
+                    Ok(text) => tasks.extend(
+                        text.lines()
+                            .map(str::trim)
+                            .filter(|line| !line.is_empty())
+                            .map(String::from),
+                    ),
+                    Err(err) => warn!("Unable to read tasks sent: {}", err),
+                },
+                Err(err) if err.kind() == io::ErrorKind::WouldBlock => break,
+                Err(err) => {
+                    warn!("Unable to accept connection: {}", err);
+                    break;
+                }
+            }
+        }
+        tasks
+    }
+}
+
+impl Drop for Listener {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// Everything sent on a connection, up to when the sender closes it.
+fn read_tasks(mut stream: UnixStream) -> io::Result<String> {
+    stream.set_nonblocking(false)?;
+    stream.set_read_timeout(Some(READ_TIMEOUT))?;
+    let mut text = String::new();
+    stream.read_to_string(&mut text)?;
+    Ok(text)
+}
+
+/// Send tasks, a line each, to the yat running with a save file.
+pub fn send(save_file: &Path, tasks: &str) -> Result<(), Error> {
+    let path = path(save_file);
+    let mut stream = UnixStream::connect(&path).map_err(|err| {
+        Error::Delivery(format!(
+            "No yat daemon is running for {} ({})",
+            save_file.display(),
+            err
+        ))
+    })?;
+    stream
+        .write_all(tasks.as_bytes())
+        .map_err(|err| Error::Delivery(format!("Unable to send task: {}", err)))
+}
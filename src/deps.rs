@@ -0,0 +1,224 @@
+/// Dependencies between tasks, where a task is blocked by others, referred
+/// to by their ids.
+use crate::todo::ToDo;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+
+/// Dependency graph of the tasks in a tree.
+pub struct Graph {
+    /// Tasks in the tree, in tree order.
+    pub tasks: Vec<Rc<RefCell<ToDo>>>,
+    /// Indices of the tasks blocking each task.
+    blockers: Vec<Vec<usize>>,
+    /// Descriptions of the tasks blocking each task from outside the tree.
+    pub outside: Vec<Vec<String>>,
+}
+
+impl Graph {
+    /// Build the dependency graph of a tree, where blockers are looked up
+    /// among all tasks under root.
+    pub fn new(tree: &Rc<RefCell<ToDo>>, root: &Rc<RefCell<ToDo>>) -> Graph {
+        let mut all = Vec::new();
+        collect(root, &mut all);
+        let ids: HashMap<u32, Rc<RefCell<ToDo>>> = all
+            .iter()
+            .filter_map(|task| task.borrow().id.map(|id| (id, Rc::clone(task))))
+            .collect();
+
+        let mut tasks = Vec::new();
+        collect(tree, &mut tasks);
+        let mut blockers = Vec::new();
+        let mut outside = Vec::new();
+        for task in tasks.iter() {
+            let mut inside_blockers = Vec::new();
+            let mut outside_blockers = Vec::new();
+            for id in task.borrow().blocked_by.iter() {
+                match ids.get(id) {
+                    Some(blocker) => match tasks.iter().position(|t| Rc::ptr_eq(t, blocker)) {
+                        Some(index) => inside_blockers.push(index),
+                        None => outside_blockers.push(blocker.borrow().task.clone()),
+                    },
+                    None => outside_blockers.push(format!("#{} (missing)", id)),
+                }
+            }
+            blockers.push(inside_blockers);
+            outside.push(outside_blockers);
+        }
+
+        Graph {
+            tasks,
+            blockers,
+            outside,
+        }
+    }
+
+    /// Whether any task in the tree is blocked by another.
+    pub fn is_empty(&self) -> bool {
+        self.blockers.iter().all(Vec::is_empty) && self.outside.iter().all(Vec::is_empty)
+    }
+
+    /// Indices of the tasks blocking a task within the tree.
+    pub fn blockers(&self, index: usize) -> &[usize] {
+        &self.blockers[index]
+    }
+
+    /// Whether a task is (indirectly) blocked by itself.
+    pub fn in_cycle(&self, index: usize) -> bool {
+        let mut seen = vec![false; self.tasks.len()];
+        let mut stack = self.blockers[index].clone();
+        while let Some(next) = stack.pop() {
+            if next == index {
+                return true;
+            }
+            if !seen[next] {
+                seen[next] = true;
+                stack.extend(self.blockers[next].iter());
+            }
+        }
+        false
+    }
+
+    /// Tasks sorted so each comes after its blockers, with the level of
+    /// each (the length of the longest chain of blockers before it). Tasks
+    /// in, or blocked by, a cycle can't be sorted, and come last.
+    pub fn sorted(&self) -> Vec<(usize, usize)> {
+        let mut remaining: Vec<usize> = self.blockers.iter().map(Vec::len).collect();
+        let mut level = vec![0; self.tasks.len()];
+        let mut placed = vec![false; self.tasks.len()];
+        let mut sorted = Vec::new();
+        loop {
+            let ready: Vec<usize> = (0..self.tasks.len())
+                .filter(|&i| !placed[i] && remaining[i] == 0)
+                .collect();
+            if ready.is_empty() {
+                break;
+            }
+            for &index in ready.iter() {
+                placed[index] = true;
+                sorted.push((index, level[index]));
+                for (dependent, blockers) in self.blockers.iter().enumerate() {
+                    for &blocker in blockers.iter().filter(|&&b| b == index) {
+                        remaining[dependent] -= 1;
+                        level[dependent] = level[dependent].max(level[blocker] + 1);
+                    }
+                }
+            }
+        }
+        sorted.extend(
+            (0..self.tasks.len())
+                .filter(|&i| !placed[i])
+                .map(|i| (i, 0)),
+        );
+        sorted
+    }
+
+    /// Longest chain of unfinished tasks, each blocked by the one before,
+    /// which sets how soon everything in the tree can be finished.
+    pub fn critical_path(&self) -> Vec<usize> {
+        let sorted = self.sorted();
+        let mut length = vec![0; self.tasks.len()];
+        let mut previous: Vec<Option<usize>> = vec![None; self.tasks.len()];
+        for &(index, _) in sorted.iter() {
+            if self.in_cycle(index) {
+                continue;
+            }
+            let (best, before) = self.blockers[index]
+                .iter()
+                .map(|&blocker| (length[blocker], Some(blocker)))
+                .max_by_key(|&(len, _)| len)
+                .unwrap_or((0, None));
+            let weight = !self.tasks[index].borrow().complete as usize;
+            length[index] = best + weight;
+            previous[index] = before;
+        }
+
+        let mut path = Vec::new();
+        let mut next = (0..self.tasks.len())
+            .filter(|&i| length[i] > 1)
+            .max_by_key(|&i| length[i]);
+        while let Some(index) = next {
+            if !self.tasks[index].borrow().complete {
+                path.push(index);
+            }
+            next = previous[index];
+        }
+        path.reverse();
+        path
+    }
+}
+
+/// Collect a task and all of its sub-tasks.
+pub fn collect(task: &Rc<RefCell<ToDo>>, tasks: &mut Vec<Rc<RefCell<ToDo>>>) {
+    tasks.push(Rc::clone(task));
+    for sub_task in task.borrow().sub_tasks.iter() {
+        collect(sub_task, tasks);
+    }
+}
+
+/// Whether a task is waiting on any unfinished tasks, given their ids.
+pub fn is_blocked(task: &ToDo, unfinished: &HashSet<u32>) -> bool {
+    task.blocked_by.iter().any(|id| unfinished.contains(id))
+}
+
+/// Id of a task, first giving it one not used by any task under root if
+/// it has none, so that other tasks can be blocked by it.
+pub fn ensure_id(task: &Rc<RefCell<ToDo>>, root: &Rc<RefCell<ToDo>>) -> u32 {
+    if let Some(id) = task.borrow().id {
+        return id;
+    }
+    let mut tasks = Vec::new();
+    collect(root, &mut tasks);
+    let id = tasks
+        .iter()
+        .filter_map(|task| task.borrow().id)
+        .max()
+        .map_or(1, |max| max.saturating_add(1));
+    task.borrow_mut().id = Some(id);
+    id
+}
+
+/// Ids of the unfinished tasks under root, which block any task waiting on
+/// them.
+pub fn unfinished(root: &Rc<RefCell<ToDo>>) -> HashSet<u32> {
+    let mut tasks = Vec::new();
+    collect(root, &mut tasks);
+    tasks
+        .iter()
+        .map(|task| task.borrow())
+        .filter(|task| !task.complete)
+        .filter_map(|task| task.id)
+        .collect()
+}
+
+/// Give the tasks in a tree taken out of the list whose ids have since
+/// been used in it new ones, updating the blockers within the tree to
+/// match, before putting it back.
+pub fn renumber_clashes(task: &Rc<RefCell<ToDo>>, root: &Rc<RefCell<ToDo>>) {
+    let mut used = Vec::new();
+    collect(root, &mut used);
+    let used: HashSet<u32> = used.iter().filter_map(|task| task.borrow().id).collect();
+    let mut tasks = Vec::new();
+    collect(task, &mut tasks);
+
+    let mut next = used
+        .iter()
+        .copied()
+        .chain(tasks.iter().filter_map(|task| task.borrow().id))
+        .max()
+        .map_or(1, |max| max.saturating_add(1));
+    let mut renumbered = HashMap::new();
+    for task in tasks.iter() {
+        let mut task = task.borrow_mut();
+        if let Some(id) = task.id.filter(|id| used.contains(id)) {
+            renumbered.insert(id, next);
+            task.id = Some(next);
+            next = next.saturating_add(1);
+        }
+    }
+    for task in tasks.iter() {
+        for id in task.borrow_mut().blocked_by.iter_mut() {
+            *id = renumbered.get(id).copied().unwrap_or(*id);
+        }
+    }
+}
@@ -0,0 +1,110 @@
+/// Daily digest of the tasks due today and overdue, e.g. for cron to send
+/// each morning.
+use crate::agenda::{collect, priority_rank, project};
+use crate::locale::Locale;
+use crate::todo::ToDo;
+use chrono::NaiveDate;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+/// Where to send the digest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Delivery {
+    /// Print to stdout.
+    Print,
+    /// Show as a desktop notification, with notify-send.
+    Notify,
+    /// Send by email, with the [email] settings.
+    Email,
+}
+
+/// A digest: a one line summary and the tasks in it.
+pub struct Digest {
+    pub summary: String,
+    pub body: String,
+    /// Whether there is anything due.
+    pub empty: bool,
+}
+
+/// Summarise the open tasks under root which are overdue, due today or
+/// flagged for today, highest priority first.
+pub(crate) fn digest(root: &Rc<RefCell<ToDo>>, today: NaiveDate, locale: &Locale) -> Digest {
+    let mut tasks = Vec::new();
+    collect(root, &mut tasks);
+    tasks.sort_by_key(|task| {
+        let task = task.borrow();
+        let deadline = task.due.map(|due| due.deadline());
+        (priority_rank(&task.priority), deadline.is_none(), deadline)
+    });
+
+    let mut overdue = Vec::new();
+    let mut due = Vec::new();
+    for task in tasks.iter() {
+        let task = task.borrow();
+        match task.due.map(|due| due.day()) {
+            Some(day) if day < today => overdue.push(entry(&task, locale)),
+            Some(day) if day == today => due.push(entry(&task, locale)),
+            _ if task.today == Some(today) => due.push(entry(&task, locale)),
+            _ => (),
+        }
+    }
+
+    let mut counts = Vec::new();
+    if !due.is_empty() {
+        counts.push(locale.fill("{} due today", &count(due.len(), locale)));
+    }
+    if !overdue.is_empty() {
+        counts.push(locale.fill("{} overdue", &count(overdue.len(), locale)));
+    }
+    let summary = match counts.is_empty() {
+        true => String::from(locale.tr("Nothing due today")),
+        false => counts.join(", "),
+    };
+
+    let mut lines = Vec::new();
+    for (heading, entries) in [("Overdue", &overdue), ("Due today", &due)] {
+        if !entries.is_empty() {
+            if !lines.is_empty() {
+                lines.push(String::new());
+            }
+            lines.push(format!("{}:", locale.tr(heading)));
+            lines.extend(entries.iter().cloned());
+        }
+    }
+    Digest {
+        summary,
+        body: lines.join("\n"),
+        empty: overdue.is_empty() && due.is_empty(),
+    }
+}
+
+/// A task in the digest, e.g. "  (A) Call Bob (due 2024-01-01, in Work)".
+fn entry(task: &ToDo, locale: &Locale) -> String {
+    let priority = match task.priority {
+        Some(priority) => format!("{} ", priority.label()),
+        None => String::new(),
+    };
+    let mut details = Vec::new();
+    if let Some(due) = task.due {
+        details.push(locale.fill("due {}", &due.to_string()));
+    }
+    let path = project(task);
+    if !path.is_empty() {
+        details.push(locale.fill("in {}", &path));
+    }
+    match details.is_empty() {
+        true => format!("  {}{}", priority, task.task),
+        false => format!("  {}{} ({})", priority, task.task, details.join(", ")),
+    }
+}
+
+/// A number of tasks, e.g. "1 task" or "2 tasks".
+fn count(count: usize, locale: &Locale) -> String {
+    match count {
+        1 => String::from(locale.tr("1 task")),
+        _ => locale.fill("{} tasks", &count.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,56 @@
+/// Tests of the daily digest: which tasks it lists, in what order, and its
+/// summary.
+use super::{digest, Digest};
+use crate::parse_list;
+use crate::tests::{day, english};
+
+const LIST: &str = "\
+[ ] ( ) Call Bob due:2024-02-29
+[ ] (B) Pay rent due:2024-02-28
+[ ] (A) Home
+    [ ] ( ) Water plants today:2024-02-29
+    [X] ( ) Mow the lawn due:2024-02-29
+    [ ] (A) Fix the gate due:2024-02-29T17:30
+[ ] ( ) Book the dentist due:2024-03-01
+[ ] ( ) Post the card today:2024-02-28
+";
+
+/// Digest of a list on a day, from "YYYY-MM-DD".
+fn digest_of(list: &str, today: &str) -> Digest {
+    digest(&parse_list(list).unwrap(), day(today), &english())
+}
+
+#[test]
+fn due_today_and_overdue() {
+    let digest = digest_of(LIST, "2024-02-29");
+    assert!(!digest.empty);
+    assert_eq!(digest.summary, "3 tasks due today, 1 task overdue");
+    assert_eq!(
+        digest.body,
+        "Overdue:\n\
+         \x20 (B) Pay rent (due 2024-02-28)\n\
+         \n\
+         Due today:\n\
+         \x20 (A) Fix the gate (due 2024-02-29T17:30, in Home)\n\
+         \x20 Call Bob (due 2024-02-29)\n\
+         \x20 Water plants (in Home)"
+    );
+}
+
+#[test]
+fn nothing_due() {
+    for (list, today) in [(LIST, "2024-01-01"), ("", "2024-02-29")] {
+        let digest = digest_of(list, today);
+        assert!(digest.empty);
+        assert_eq!(digest.summary, "Nothing due today");
+        assert_eq!(digest.body, "");
+    }
+}
+
+#[test]
+fn only_overdue() {
+    let digest = digest_of("[ ] ( ) Call Bob due:2024-02-28\n", "2024-02-29");
+    assert!(!digest.empty);
+    assert_eq!(digest.summary, "1 task overdue");
+    assert_eq!(digest.body, "Overdue:\n  Call Bob (due 2024-02-28)");
+}
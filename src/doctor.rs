@@ -0,0 +1,237 @@
+/// Health checks of a save file, finding problems which stop it loading
+/// properly, and repairing those which can be fixed without losing anything.
+use crate::todo::ToDo;
+use std::collections::BTreeMap;
+use std::rc::Weak;
+
+/// Fields which are stored as "key:value" words after a task.
+const FIELDS: [&str; 15] = [
+    "due",
+    "repeat",
+    "skip",
+    "moved",
+    "defaults",
+    "start",
+    "today",
+    "carried",
+    "estimate",
+    "spent",
+    "timer",
+    "id",
+    "blocked-by",
+    "created",
+    "completed",
+];
+
+/// A problem found in a save file.
+pub struct Problem {
+    /// Line of the save file it is on (counting from one), if any.
+    pub line: Option<usize>,
+    pub description: String,
+    /// Whether the repaired save file fixes it.
+    pub fixable: bool,
+}
+
+/// Problems found in a save file, with the save file as it would be once
+/// repaired.
+#[derive(Default)]
+pub struct Report {
+    pub problems: Vec<Problem>,
+    pub repaired: String,
+}
+
+impl Report {
+    /// Record a problem.
+    fn add(&mut self, line: Option<usize>, description: String, fixable: bool) {
+        self.problems.push(Problem {
+            line,
+            description,
+            fixable,
+        });
+    }
+
+    /// Number of problems which can be repaired.
+    pub fn fixable(&self) -> usize {
+        self.problems
+            .iter()
+            .filter(|problem| problem.fixable)
+            .count()
+    }
+}
+
+/// Check the contents of a save file line by line.
+pub fn check(bytes: &[u8]) -> Report {
+    let mut report = Report::default();
+    let mut bytes = bytes;
+    if let Some(rest) = bytes.strip_prefix(b"\xef\xbb\xbf") {
+        let description = String::from("byte order mark at the start of the file");
+        report.add(Some(1), description, true);
+        bytes = rest;
+    }
+
+    let mut lines = Vec::new();
+    let mut ids: BTreeMap<u32, Vec<usize>> = BTreeMap::new();
+    let mut blocked_by = Vec::new();
+    let mut crlf = 0;
+    let mut tabs = 0;
+    // Indentation of the previous task, if there is one
+    let mut level: Option<usize> = None;
+    let mut pieces: Vec<&[u8]> = bytes.split(|&byte| byte == b'\n').collect();
+    if pieces.last().is_some_and(|piece| piece.is_empty()) {
+        pieces.pop();
+    }
+
+    for (i, piece) in pieces.into_iter().enumerate() {
+        let number = Some(i + 1);
+        let mut line = match std::str::from_utf8(piece) {
+            Ok(line) => String::from(line),
+            Err(_) => {
+                let description = String::from("not valid UTF-8 text");
+                report.add(number, description, true);
+                String::from_utf8_lossy(piece).into_owned()
+            }
+        };
+        if line.ends_with('\r') {
+            line.pop();
+            crlf += 1;
+        }
+        if line.trim().is_empty() {
+            report.add(number, String::from("blank line"), true);
+            continue;
+        }
+
+        let content = line.trim_start();
+        let indent = &line[..line.len() - content.len()];
+        if indent.contains('\t') {
+            tabs += 1;
+        }
+
+        // Notes and comments belong to the task before them, so are
+        // indented with it
+        if content.starts_with('|') || content.starts_with('>') {
+            let mut content = String::from(content);
+            if level.is_none() {
+                let description = String::from("note or comment before the first task");
+                report.add(number, description, false);
+            } else if content.starts_with('>')
+                && ToDo::new("", Weak::new())
+                    .add_comment_from_string(&content)
+                    .is_err()
+            {
+                let description = String::from("comment without a valid time, kept as a note");
+                report.add(number, description, true);
+                content = format!("| {}", content[1..].trim_start());
+            }
+            let depth = level.unwrap_or(0);
+            lines.push(format!("{}{}", " ".repeat(4 * depth), content));
+            continue;
+        }
+
+        let spaces = indent
+            .chars()
+            .map(|c| if c == '\t' { 4 } else { 1 })
+            .sum::<usize>();
+        if spaces % 4 != 0 {
+            let description = format!("indented by {} spaces, not a multiple of 4", spaces);
+            report.add(number, description, true);
+        }
+        let mut depth = spaces / 4;
+        let most = level.map_or(0, |level| level + 1);
+        if depth > most {
+            let description = String::from("indented further than any parent task");
+            report.add(number, description, true);
+            depth = most;
+        }
+        level = Some(depth);
+
+        let content = match task_prefix(content) {
+            (true, true) => String::from(content),
+            (true, false) => {
+                let description = String::from("task without a priority, given none");
+                report.add(number, description, true);
+                format!("{}( ) {}", &content[..4], &content[4..])
+            }
+            (false, _) => {
+                let description = String::from("line is not a task, made into one");
+                report.add(number, description, true);
+                format!("[ ] ( ) {}", content)
+            }
+        };
+
+        // Fields which couldn't be read are left in the task description,
+        // unlike words escaped as text, which start with a backslash
+        let todo = ToDo::from_string(&content, Weak::new());
+        let mut kept: Vec<&str> = todo.task.split(' ').collect();
+        for word in content.split(' ') {
+            let (key, value) = match word.split_once(':') {
+                Some((key, value)) if FIELDS.contains(&key) => (key, value),
+                _ => continue,
+            };
+            if let Some(index) = kept.iter().position(|&kept| kept == word) {
+                kept.remove(index);
+                let description = format!("invalid or repeated {} \"{}\"", key, value);
+                report.add(number, description, false);
+            }
+        }
+        if let Some(id) = todo.id {
+            ids.entry(id).or_default().push(i + 1);
+        }
+        blocked_by.extend(todo.blocked_by.iter().map(|&id| (i + 1, id)));
+
+        lines.push(format!("{}{}", " ".repeat(4 * depth), content));
+    }
+
+    if crlf > 0 {
+        let description = format!("Windows line endings on {}", count_lines(crlf));
+        report.add(None, description, true);
+    }
+    if tabs > 0 {
+        let description = format!("tabs in the indentation of {}", count_lines(tabs));
+        report.add(None, description, true);
+    }
+    for (id, numbers) in ids.iter() {
+        for &number in numbers.iter().skip(1) {
+            let description = format!("id:{} is already used on line {}", id, numbers[0]);
+            report.add(Some(number), description, false);
+        }
+    }
+    for &(number, id) in blocked_by.iter() {
+        if !ids.contains_key(&id) {
+            let description = format!("blocked by id:{}, which no task has", id);
+            report.add(Some(number), description, false);
+        }
+    }
+
+    report.problems.sort_by_key(|problem| problem.line);
+    if !lines.is_empty() {
+        report.repaired = lines.join("\n") + "\n";
+    }
+    report
+}
+
+/// Whether a line starts with a checkbox, e.g. "[ ] ", and then a priority,
+/// e.g. "(A) ", as every task in a save file does.
+fn task_prefix(line: &str) -> (bool, bool) {
+    let bytes = line.as_bytes();
+    let checkbox = bytes.len() >= 4
+        && bytes[0] == b'['
+        && (bytes[1] == b' ' || bytes[1] == b'X')
+        && &bytes[2..4] == b"] ";
+    let priority = checkbox
+        && bytes.len() >= 8
+        && bytes[4] == b'('
+        && bytes[5].is_ascii()
+        && &bytes[6..8] == b") ";
+    (checkbox, priority)
+}
+
+/// A number of lines, e.g. "1 line" or "2 lines".
+fn count_lines(count: usize) -> String {
+    match count {
+        1 => String::from("1 line"),
+        _ => format!("{} lines", count),
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,76 @@
+/// Tests of the save file health checks.
+use super::check;
+
+/// Descriptions of the problems found in a save file.
+fn problems(text: &str) -> Vec<String> {
+    check(text.as_bytes())
+        .problems
+        .into_iter()
+        .map(|problem| problem.description)
+        .collect()
+}
+
+#[test]
+fn escaped_field_words_are_not_problems() {
+    assert!(problems("[ ] ( ) read \\due:friday and \\id:3 id:4\n").is_empty());
+    assert_eq!(
+        problems("[ ] ( ) read \\due:friday due:friday\n"),
+        ["invalid or repeated due \"friday\""]
+    );
+    assert_eq!(
+        problems("[ ] ( ) call due:2024-03-01 due:2024-03-02\n"),
+        ["invalid or repeated due \"2024-03-02\""]
+    );
+}
+
+#[test]
+fn empty_save_files() {
+    for text in ["", "\n", "\u{feff}"] {
+        let report = check(text.as_bytes());
+        assert_eq!(report.repaired, "", "{:?}", text);
+        assert_eq!(report.problems.len(), report.fixable(), "{:?}", text);
+    }
+    assert!(problems("").is_empty());
+    assert_eq!(problems("\n"), ["blank line"]);
+}
+
+#[test]
+fn malformed_save_files() {
+    let report =
+        check(b"\xef\xbb\xbf| stray note\r\n[ ] first\r\n\t\t[ ] ( ) too deep\r\n  \xff\r\n");
+    let problems: Vec<(Option<usize>, &str)> = report
+        .problems
+        .iter()
+        .map(|problem| (problem.line, problem.description.as_str()))
+        .collect();
+    assert_eq!(
+        problems,
+        [
+            (None, "Windows line endings on 4 lines"),
+            (None, "tabs in the indentation of 1 line"),
+            (Some(1), "byte order mark at the start of the file"),
+            (Some(1), "note or comment before the first task"),
+            (Some(2), "task without a priority, given none"),
+            (Some(3), "indented further than any parent task"),
+            (Some(4), "not valid UTF-8 text"),
+            (Some(4), "indented by 2 spaces, not a multiple of 4"),
+            (Some(4), "line is not a task, made into one"),
+        ]
+    );
+    assert_eq!(report.fixable(), 8);
+    assert_eq!(
+        report.repaired,
+        "| stray note\n[ ] ( ) first\n    [ ] ( ) too deep\n[ ] ( ) \u{fffd}\n"
+    );
+}
+
+#[test]
+fn ids_and_dependencies() {
+    assert_eq!(
+        problems("[ ] ( ) one id:1\n[ ] ( ) two id:1\n[ ] ( ) three blocked-by:2\n"),
+        [
+            "id:1 is already used on line 1",
+            "blocked by id:2, which no task has"
+        ]
+    );
+}
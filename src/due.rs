@@ -0,0 +1,231 @@
+/// Task due dates, and how they are displayed.
+use chrono::{
+    Datelike, Days, Duration, Local, Month, Months, NaiveDate, NaiveDateTime, NaiveTime, Weekday,
+};
+use std::fmt;
+
+/// Deadline of a task, either a whole day or a specific time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Due {
+    Date(NaiveDate),
+    DateTime(NaiveDateTime),
+}
+
+impl Due {
+    /// Parse due date from "YYYY-MM-DD", "YYYY-MM-DD HH:MM" or
+    /// "YYYY-MM-DDTHH:MM".
+    pub fn parse(text: &str) -> Option<Due> {
+        let text = text.trim();
+        if let Ok(date) = NaiveDate::parse_from_str(text, "%Y-%m-%d") {
+            return Some(Due::Date(date));
+        }
+        for format in ["%Y-%m-%dT%H:%M", "%Y-%m-%d %H:%M"].iter() {
+            if let Ok(datetime) = NaiveDateTime::parse_from_str(text, format) {
+                return Some(Due::DateTime(datetime));
+            }
+        }
+        None
+    }
+
+    /// Parse a due date as typed, relative to now: a date as in save files,
+    /// or words such as "today", "tomorrow", "fri", "next fri", "next
+    /// week", "in 3 days", "+2w" or "oct 20", optionally followed by a time
+    /// (HH:MM). A weekday on its own is the next one from today, so "fri"
+    /// is today on a Friday, while "next fri" is a week later.
+    pub fn parse_natural(text: &str, now: NaiveDateTime) -> Option<Due> {
+        if let Some(due) = Due::parse(text) {
+            return Some(due);
+        }
+        let text = text.trim().to_lowercase();
+        let mut words: Vec<&str> = text.split_whitespace().collect();
+        let time = words
+            .last()
+            .and_then(|word| NaiveTime::parse_from_str(word, "%H:%M").ok());
+        if time.is_some() {
+            words.pop();
+            if words.last() == Some(&"at") {
+                words.pop();
+            }
+        }
+
+        let today = now.date();
+        let date = match words.as_slice() {
+            [] if time.is_some() => today,
+            ["today"] => today,
+            ["tomorrow"] | ["tmr"] => today.succ_opt()?,
+            ["next", "week"] => later(today, 1, "week")?,
+            ["next", "month"] => later(today, 1, "month")?,
+            ["next", "year"] => later(today, 1, "year")?,
+            ["next", day] => next_weekday(today, day.parse().ok()?, 1),
+            ["in", number, unit] => later(today, number.parse().ok()?, unit)?,
+            [word] => match word.parse::<Weekday>() {
+                Ok(day) => next_weekday(today, day, 0),
+                Err(_) => {
+                    // e.g. "+3d" or "2w"
+                    let word = word.strip_prefix('+').unwrap_or(word);
+                    let split = word.find(|c: char| !c.is_ascii_digit())?;
+                    let (number, unit) = word.split_at(split);
+                    later(today, number.parse().ok()?, unit)?
+                }
+            },
+            [first, second] => {
+                // e.g. "oct 20" or "20 oct"
+                let (month, day) = match first.parse::<Month>() {
+                    Ok(month) => (month, second.parse().ok()?),
+                    Err(_) => (second.parse::<Month>().ok()?, first.parse().ok()?),
+                };
+                let date = |year| NaiveDate::from_ymd_opt(year, month.number_from_month(), day);
+                // Next year if the day has passed or isn't in this year,
+                // e.g. "feb 29"
+                match date(today.year()) {
+                    Some(date) if date >= today => date,
+                    _ => date(today.year() + 1)?,
+                }
+            }
+            _ => return None,
+        };
+        match time {
+            Some(time) => Some(Due::DateTime(date.and_time(time))),
+            None => Some(Due::Date(date)),
+        }
+    }
+
+    /// The moment the task is due, where a whole day is due at its end.
+    pub fn deadline(&self) -> NaiveDateTime {
+        match self {
+            Due::Date(date) => date.and_time(NaiveTime::MIN) + Duration::days(1),
+            Due::DateTime(datetime) => *datetime,
+        }
+    }
+
+    /// Day the task is due.
+    pub fn day(&self) -> NaiveDate {
+        match self {
+            Due::Date(date) => *date,
+            Due::DateTime(datetime) => datetime.date(),
+        }
+    }
+
+    /// Time left until the deadline (negative if overdue).
+    pub fn remaining(&self) -> Duration {
+        self.deadline() - Local::now().naive_local()
+    }
+
+    /// Whether the deadline is within the next 24 hours, when a countdown
+    /// is displayed instead of the date.
+    pub fn imminent(&self) -> bool {
+        let remaining = self.remaining();
+        remaining > Duration::zero() && remaining <= Duration::hours(24)
+    }
+
+    /// Text to display in the due column: the date, a countdown for
+    /// imminent deadlines, or "overdue".
+    pub fn display(&self) -> String {
+        let remaining = self.remaining();
+        if remaining <= Duration::zero() {
+            String::from("overdue")
+        } else if self.imminent() {
+            let secs = remaining.num_seconds();
+            format!("{:02}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+        } else {
+            self.date()
+        }
+    }
+
+    /// Same deadline, later by an interval.
+    pub fn postpone(&self, interval: Duration) -> Due {
+        match self {
+            Due::Date(date) => Due::Date(*date + interval),
+            Due::DateTime(datetime) => Due::DateTime(*datetime + interval),
+        }
+    }
+
+    /// Human readable date (and time).
+    pub fn date(&self) -> String {
+        match self {
+            Due::Date(date) => date.format("%Y-%m-%d").to_string(),
+            Due::DateTime(datetime) => datetime.format("%Y-%m-%d %H:%M").to_string(),
+        }
+    }
+}
+
+impl fmt::Display for Due {
+    /// Save file format, without spaces so it forms a single word.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Due::Date(date) => write!(f, "{}", date.format("%Y-%m-%d")),
+            Due::DateTime(datetime) => write!(f, "{}", datetime.format("%Y-%m-%dT%H:%M")),
+        }
+    }
+}
+
+/// Take a due date typed as part of a task, such as "pay rent due:next
+/// fri", out of it, returning the rest of the task along with the date.
+/// The task is left as it is if there's no due date which can be read.
+pub fn extract(task: &str, now: NaiveDateTime) -> (String, Option<Due>) {
+    let words: Vec<&str> = task.split_whitespace().collect();
+    for (i, word) in words.iter().enumerate() {
+        let first = match word.strip_prefix("due:") {
+            Some(first) if !first.is_empty() => first,
+            _ => continue,
+        };
+        // The longest run of words which reads as a date, e.g. "next fri
+        // 17:00" rather than just "next fri"
+        for end in (i + 1..=words.len().min(i + 4)).rev() {
+            let mut text = String::from(first);
+            for word in words[i + 1..end].iter() {
+                text.push(' ');
+                text.push_str(word);
+            }
+            if let Some(due) = Due::parse_natural(&text, now) {
+                let rest: Vec<&str> = words[..i].iter().chain(&words[end..]).copied().collect();
+                return (rest.join(" "), Some(due));
+            }
+        }
+    }
+    (String::from(task), None)
+}
+
+/// Minutes in a day, the unit reminders are given in when whole.
+const DAY: u32 = 24 * 60;
+
+/// Parse how long before the deadline to be reminded of a task, such as
+/// "1d", "2h" or "1h30m", in minutes.
+pub fn parse_lead(text: &str) -> Option<u32> {
+    let text = text.trim();
+    let minutes = match text.strip_suffix('d') {
+        Some(days) => days.parse::<u32>().ok()?.checked_mul(DAY)?,
+        None => crate::effort::parse(text)?,
+    };
+    Some(minutes).filter(|&minutes| minutes > 0)
+}
+
+/// Format a reminder's lead time, in days when it is whole days.
+pub fn format_lead(minutes: u32) -> String {
+    match minutes % DAY {
+        0 => format!("{}d", minutes / DAY),
+        _ => crate::effort::format(minutes),
+    }
+}
+
+/// The next given day of the week, at least a number of days from today.
+fn next_weekday(today: NaiveDate, day: Weekday, min: u64) -> NaiveDate {
+    let from = today + Days::new(min);
+    let ahead = (7 + day.num_days_from_monday() - from.weekday().num_days_from_monday()) % 7;
+    from + Days::new(ahead.into())
+}
+
+/// A number of days, weeks, months or years after a date, given the unit
+/// in full or as a letter.
+fn later(date: NaiveDate, number: u32, unit: &str) -> Option<NaiveDate> {
+    match unit {
+        "d" | "day" | "days" => date.checked_add_days(Days::new(number.into())),
+        "w" | "week" | "weeks" => date.checked_add_days(Days::new(u64::from(number) * 7)),
+        "m" | "month" | "months" => date.checked_add_months(Months::new(number)),
+        "y" | "year" | "years" => date.checked_add_months(Months::new(number.checked_mul(12)?)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests;
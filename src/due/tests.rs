@@ -0,0 +1,146 @@
+/// Tests of due dates as typed and as saved, around the ends of months and
+/// leap days.
+use super::{extract, parse_lead, Due};
+use crate::tests::day;
+use chrono::{NaiveDate, NaiveDateTime};
+
+/// Ten in the morning on a day.
+fn morning(text: &str) -> NaiveDateTime {
+    day(text).and_hms_opt(10, 0, 0).unwrap()
+}
+
+/// A due date typed on a day, as the day it is due.
+fn typed(text: &str, today: &str) -> Option<NaiveDate> {
+    Due::parse_natural(text, morning(today)).map(|due| due.day())
+}
+
+#[test]
+fn saved_dates() {
+    assert_eq!(Due::parse("2024-02-29"), Some(Due::Date(day("2024-02-29"))));
+    assert_eq!(
+        Due::parse(" 2024-02-29T23:59 "),
+        Some(Due::DateTime(
+            day("2024-02-29").and_hms_opt(23, 59, 0).unwrap()
+        ))
+    );
+    for text in [
+        "2023-02-29",
+        "2024-04-31",
+        "2024-13-01",
+        "2024-02-29T24:00",
+        "29/02/2024",
+        "",
+    ] {
+        assert_eq!(Due::parse(text), None, "{:?}", text);
+    }
+}
+
+#[test]
+fn months_later_end_on_the_last_day() {
+    assert_eq!(typed("in 1 month", "2024-01-31"), Some(day("2024-02-29")));
+    assert_eq!(typed("next month", "2023-01-31"), Some(day("2023-02-28")));
+    assert_eq!(typed("+1m", "2024-03-31"), Some(day("2024-04-30")));
+    assert_eq!(typed("in 13 months", "2023-01-31"), Some(day("2024-02-29")));
+    assert_eq!(typed("1y", "2024-02-29"), Some(day("2025-02-28")));
+    assert_eq!(typed("in 4 years", "2024-02-29"), Some(day("2028-02-29")));
+    assert_eq!(typed("tomorrow", "2024-02-28"), Some(day("2024-02-29")));
+    assert_eq!(typed("tomorrow", "2023-12-31"), Some(day("2024-01-01")));
+    assert_eq!(typed("in 2 weeks", "2024-02-20"), Some(day("2024-03-05")));
+}
+
+#[test]
+fn leap_days_by_name() {
+    assert_eq!(typed("feb 29", "2024-01-31"), Some(day("2024-02-29")));
+    assert_eq!(typed("29 feb", "2024-02-29"), Some(day("2024-02-29")));
+    // Not in this year, nor passed yet, so the next one
+    assert_eq!(typed("feb 29", "2023-06-01"), Some(day("2024-02-29")));
+    // More than a year away
+    assert_eq!(typed("feb 29", "2024-03-01"), None);
+    assert_eq!(typed("dec 31", "2024-12-31"), Some(day("2024-12-31")));
+    assert_eq!(typed("jan 1", "2024-12-31"), Some(day("2025-01-01")));
+}
+
+#[test]
+fn weekdays_across_month_ends() {
+    // 2024-02-29 is a Thursday
+    assert_eq!(typed("thu", "2024-02-29"), Some(day("2024-02-29")));
+    assert_eq!(typed("next thu", "2024-02-29"), Some(day("2024-03-07")));
+    assert_eq!(typed("fri", "2024-02-29"), Some(day("2024-03-01")));
+    assert_eq!(
+        Due::parse_natural("fri at 09:30", morning("2024-02-29")),
+        Some(Due::DateTime(
+            day("2024-03-01").and_hms_opt(9, 30, 0).unwrap()
+        ))
+    );
+}
+
+#[test]
+fn invalid_dates_typed() {
+    for text in [
+        "",
+        "feb 30",
+        "31 apr",
+        "feb",
+        "someday",
+        "next",
+        "next fortnight",
+        "in x days",
+        "in 3 fortnights",
+        "in 3",
+        "+",
+        "3",
+        "25:00",
+        "today 9:75",
+        "in 4294967295 years",
+        "in 99999999999 days",
+    ] {
+        assert_eq!(typed(text, "2024-01-31"), None, "{:?}", text);
+    }
+}
+
+#[test]
+fn whole_days_due_at_midnight() {
+    let due = Due::Date(day("2024-02-28"));
+    assert_eq!(
+        due.deadline(),
+        day("2024-02-29").and_hms_opt(0, 0, 0).unwrap()
+    );
+    let due = Due::Date(day("2024-12-31"));
+    assert_eq!(
+        due.deadline(),
+        day("2025-01-01").and_hms_opt(0, 0, 0).unwrap()
+    );
+}
+
+#[test]
+fn dates_taken_out_of_tasks() {
+    let now = morning("2024-01-31");
+    assert_eq!(
+        extract("pay rent due:next month 17:00 please", now),
+        (
+            String::from("pay rent please"),
+            Some(Due::DateTime(
+                day("2024-02-29").and_hms_opt(17, 0, 0).unwrap()
+            ))
+        )
+    );
+    assert_eq!(
+        extract("pay rent due:feb 29", now),
+        (String::from("pay rent"), Some(Due::Date(day("2024-02-29"))))
+    );
+    for task in [
+        "pay rent due:feb 30",
+        "pay rent due:",
+        "pay rent due:someday",
+    ] {
+        assert_eq!(extract(task, now), (String::from(task), None));
+    }
+}
+
+#[test]
+fn reminder_leads() {
+    assert_eq!(parse_lead("1d"), Some(24 * 60));
+    assert_eq!(parse_lead("0d"), None);
+    assert_eq!(parse_lead("d"), None);
+    assert_eq!(parse_lead("99999999d"), None);
+}
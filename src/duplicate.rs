@@ -0,0 +1,73 @@
+/// Detection of tasks which are probably duplicates of others, by how
+/// closely their text matches.
+use crate::todo::ToDo;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+/// How similar two task descriptions are, from 0 (nothing in common) to 1
+/// (the same, ignoring case, spacing, tags and contexts), based on the edit
+/// distance between them.
+pub fn similarity(a: &str, b: &str) -> f64 {
+    let a: Vec<char> = normalise(a).chars().collect();
+    let b: Vec<char> = normalise(b).chars().collect();
+    let longest = a.len().max(b.len());
+    if a.is_empty() || b.is_empty() {
+        return 0.0;
+    }
+    1.0 - distance(&a, &b) as f64 / longest as f64
+}
+
+/// Lower case words separated by single spaces, without tags or contexts.
+fn normalise(text: &str) -> String {
+    text.split_whitespace()
+        .filter(|word| !word.starts_with(['+', '#', '@']))
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Levenshtein distance: the number of characters inserted, deleted or
+/// replaced to turn one string into the other.
+pub fn distance(a: &[char], b: &[char]) -> usize {
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+    for (i, ca) in a.iter().enumerate() {
+        current[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let replace = previous[j] + (ca != cb) as usize;
+            current[j + 1] = replace.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+    previous[b.len()]
+}
+
+/// The open task under root most similar to some text, if any is at least
+/// as similar as the threshold.
+pub fn find(root: &Rc<RefCell<ToDo>>, text: &str, threshold: f64) -> Option<Rc<RefCell<ToDo>>> {
+    let mut best = None;
+    search(root, text, threshold, &mut best);
+    best.map(|(task, _)| task)
+}
+
+/// Search the sub-tasks of a task for the most similar open task.
+fn search(
+    task: &Rc<RefCell<ToDo>>,
+    text: &str,
+    threshold: f64,
+    best: &mut Option<(Rc<RefCell<ToDo>>, f64)>,
+) {
+    for sub_task in task.borrow().sub_tasks.iter() {
+        if !sub_task.borrow().complete {
+            let score = similarity(&sub_task.borrow().task, text);
+            let better = match best {
+                Some((_, best_score)) => score > *best_score,
+                None => true,
+            };
+            if score >= threshold && better {
+                *best = Some((Rc::clone(sub_task), score));
+            }
+        }
+        search(sub_task, text, threshold, best);
+    }
+}
@@ -0,0 +1,121 @@
+/// Effort estimates and time spent on tasks, in whole minutes.
+use crate::todo::ToDo;
+
+/// Parse a duration such as "1h30m", "2h", "45m" or "45" (minutes).
+pub fn parse(text: &str) -> Option<u32> {
+    let text = text.trim();
+    if text.is_empty() {
+        return None;
+    }
+    if let Ok(minutes) = text.parse() {
+        return Some(minutes);
+    }
+
+    let (hours, rest) = match text.split_once('h') {
+        Some((hours, rest)) => (hours.parse::<u32>().ok()?, rest),
+        None => (0, text),
+    };
+    let minutes = match rest {
+        "" => 0,
+        rest => rest.strip_suffix('m').unwrap_or(rest).parse::<u32>().ok()?,
+    };
+    hours.checked_mul(60)?.checked_add(minutes)
+}
+
+/// Format a duration as e.g. "1h30m", without spaces so it forms a single
+/// word in the save file.
+pub fn format(minutes: u32) -> String {
+    match (minutes / 60, minutes % 60) {
+        (0, m) => format!("{}m", m),
+        (h, 0) => format!("{}h", h),
+        (h, m) => format!("{}h{:02}m", h, m),
+    }
+}
+
+/// Format the difference between time spent and estimated, e.g. "+15m".
+pub fn format_difference(estimate: u32, spent: u32) -> String {
+    match spent >= estimate {
+        true => format!("+{}", format(spent - estimate)),
+        false => format!("-{}", format(estimate - spent)),
+    }
+}
+
+/// Time left on a task itself, if unfinished: its estimate less the time
+/// already spent on it.
+pub fn left(todo: &ToDo) -> u32 {
+    match (todo.complete, todo.estimate) {
+        (false, Some(estimate)) => estimate.saturating_sub(todo.spent),
+        _ => 0,
+    }
+}
+
+/// Time left on the unfinished tasks under a task: their estimates less the
+/// time already spent on them.
+pub fn remaining(todo: &ToDo) -> u32 {
+    todo.sub_tasks
+        .iter()
+        .map(|sub_task| {
+            let sub_task = sub_task.borrow();
+            left(&sub_task) + remaining(&sub_task)
+        })
+        .sum()
+}
+
+/// Estimated and actual time of the tasks in a tree.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Totals {
+    /// Sum of estimates.
+    pub estimate: u32,
+    /// Sum of time spent.
+    pub spent: u32,
+    /// Number of completed tasks with an estimate.
+    pub done: u32,
+    /// Sum of estimates of completed tasks with an estimate.
+    pub done_estimate: u32,
+    /// Time spent on completed tasks with an estimate.
+    pub done_spent: u32,
+}
+
+impl Totals {
+    /// Add up the time of a task and its sub-tasks which satisfy a
+    /// condition.
+    pub fn add_tree(&mut self, todo: &ToDo, condition: &dyn Fn(&ToDo) -> bool) {
+        if condition(todo) {
+            self.add(todo);
+        }
+        for sub_task in todo.sub_tasks.iter() {
+            self.add_tree(&sub_task.borrow(), condition);
+        }
+    }
+
+    /// Add the time of a single task.
+    fn add(&mut self, todo: &ToDo) {
+        self.spent += todo.spent;
+        if let Some(estimate) = todo.estimate {
+            self.estimate += estimate;
+            if todo.complete {
+                self.done += 1;
+                self.done_estimate += estimate;
+                self.done_spent += todo.spent;
+            }
+        }
+    }
+
+    /// Combine totals of two trees.
+    pub fn combine(&mut self, other: &Totals) {
+        self.estimate += other.estimate;
+        self.spent += other.spent;
+        self.done += other.done;
+        self.done_estimate += other.done_estimate;
+        self.done_spent += other.done_spent;
+    }
+
+    /// How long completed tasks took, as a percentage of their estimates,
+    /// for calibrating future estimates.
+    pub fn accuracy(&self) -> Option<u32> {
+        match self.done_estimate {
+            0 => None,
+            estimate => Some(self.done_spent * 100 / estimate),
+        }
+    }
+}
@@ -0,0 +1,195 @@
+/// Sending tasks by email, through sendmail or an SMTP server (using curl).
+use crate::config::EmailConfig;
+use crate::storage::{curl_escape, password_from_command};
+use crate::todo::ToDo;
+use chrono::Local;
+use log::{info, warn};
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+/// Write a task as a plain text email: the title as the subject, then the
+/// due date and notes in the body.
+pub fn message(config: &EmailConfig, to: &str, todo: &ToDo) -> String {
+    let mut body = vec![todo.task.clone()];
+    if let Some(due) = todo.due {
+        body.push(format!("Due: {}", due));
+    }
+    if !todo.notes.is_empty() {
+        body.push(String::new());
+        body.extend(todo.notes.lines().map(String::from));
+    }
+    compose(config, to, &todo.task, &body.join("\n"))
+}
+
+/// Write a plain text email with a subject and body.
+pub fn compose(config: &EmailConfig, to: &str, subject: &str, body: &str) -> String {
+    let mut headers = vec![format!("To: {}", to)];
+    if let Some(from) = &config.from {
+        headers.push(format!("From: {}", from));
+    }
+    headers.push(format!("Subject: {}", encode_header(subject)));
+    headers.push(format!("Date: {}", Local::now().to_rfc2822()));
+    headers.push(String::from("MIME-Version: 1.0"));
+    headers.push(String::from("Content-Type: text/plain; charset=utf-8"));
+    headers.push(String::from("Content-Transfer-Encoding: 8bit"));
+
+    // Lines end in CRLF, as mail servers expect
+    let body: Vec<&str> = body.lines().collect();
+    format!("{}\r\n\r\n{}\r\n", headers.join("\r\n"), body.join("\r\n"))
+}
+
+/// Encode a header value which isn't plain ASCII as an RFC 2047 encoded
+/// word, so that the subject survives the trip.
+fn encode_header(text: &str) -> String {
+    if text.chars().all(|c| c.is_ascii() && !c.is_ascii_control()) {
+        return String::from(text);
+    }
+    let mut encoded = String::from("=?UTF-8?Q?");
+    for byte in text.bytes() {
+        match byte {
+            b' ' => encoded.push('_'),
+            b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' => encoded.push(byte as char),
+            _ => encoded.push_str(&format!("={:02X}", byte)),
+        }
+    }
+    encoded.push_str("?=");
+    encoded
+}
+
+/// Send an email through the configured SMTP server, or sendmail if there
+/// is none.
+pub fn send(config: &EmailConfig, to: &str, message: &str) -> Result<(), String> {
+    match &config.smtp_url {
+        Some(url) => send_smtp(config, url, to, message),
+        None => {
+            let command = config.sendmail.as_deref().unwrap_or("sendmail -t");
+            send_sendmail(command, message)
+        }
+    }
+}
+
+/// Pipe an email to a sendmail command, which reads the recipients from the
+/// headers.
+fn send_sendmail(command: &str, message: &str) -> Result<(), String> {
+    let child = Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn();
+    let mut child = match child {
+        Ok(child) => child,
+        Err(err) => return Err(format!("unable to run {}: {}", command, err)),
+    };
+    if let Some(mut stdin) = child.stdin.take() {
+        // sendmail expects local line endings
+        let message = message.replace("\r\n", "\n");
+        stdin
+            .write_all(message.as_bytes())
+            .unwrap_or_else(|err| warn!("Unable to write to {}: {}", command, err));
+    }
+    match child.wait_with_output() {
+        Ok(output) if output.status.success() => {
+            info!("Sent email with {}.", command);
+            Ok(())
+        }
+        Ok(output) => Err(failure(command, &output.stderr)),
+        Err(err) => Err(format!("unable to run {}: {}", command, err)),
+    }
+}
+
+/// Send an email to an SMTP server with curl, passing the credentials on
+/// stdin so they don't appear in the process list, and the email in a
+/// temporary file.
+fn send_smtp(config: &EmailConfig, url: &str, to: &str, message: &str) -> Result<(), String> {
+    let from = match config.from.as_ref().or(config.user.as_ref()) {
+        Some(from) => address(from),
+        None => return Err(String::from("no from address configured")),
+    };
+    let mut password = config.password.clone();
+    if let Some(command) = &config.password_command {
+        password = password_from_command(command).or(password);
+    }
+
+    let upload = tempfile::Builder::new()
+        .prefix("yat-email-")
+        .suffix(".eml")
+        .tempfile()
+        .and_then(|mut file| file.write_all(message.as_bytes()).map(|_| file));
+    let upload = match upload {
+        Ok(upload) => upload,
+        Err(err) => return Err(format!("unable to write temporary file: {}", err)),
+    };
+    let mut curl_config = format!(
+        "url = \"{}\"\nmail-from = \"{}\"\nupload-file = \"{}\"\n",
+        curl_escape(url),
+        curl_escape(&from),
+        curl_escape(&upload.path().to_string_lossy()),
+    );
+    for rcpt in to.split(',').map(address).filter(|rcpt| !rcpt.is_empty()) {
+        curl_config.push_str(&format!("mail-rcpt = \"{}\"\n", curl_escape(&rcpt)));
+    }
+    if let Some(user) = &config.user {
+        // Never send the password in the clear
+        curl_config.push_str("ssl-reqd\n");
+        let password = password.unwrap_or_default();
+        curl_config.push_str(&format!(
+            "user = \"{}:{}\"\n",
+            curl_escape(user),
+            curl_escape(&password)
+        ));
+    }
+
+    let result = curl(&curl_config);
+    upload.close().unwrap_or_else(|err| {
+        warn!("Unable to remove temporary file: {}", err);
+    });
+    if result.is_ok() {
+        info!("Sent email through {}.", url);
+    }
+    result
+}
+
+/// Run curl with a config given on stdin.
+fn curl(curl_config: &str) -> Result<(), String> {
+    let child = Command::new("curl")
+        .args(["--silent", "--show-error", "--connect-timeout", "10"])
+        .args(["--config", "-"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn();
+    let mut child = match child {
+        Ok(child) => child,
+        Err(err) => return Err(format!("unable to run curl: {}", err)),
+    };
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin
+            .write_all(curl_config.as_bytes())
+            .unwrap_or_else(|err| warn!("Unable to configure curl: {}", err));
+    }
+    match child.wait_with_output() {
+        Ok(output) if output.status.success() => Ok(()),
+        Ok(output) => Err(failure("curl", &output.stderr)),
+        Err(err) => Err(format!("unable to run curl: {}", err)),
+    }
+}
+
+/// The bare address in e.g. "Name <name@example.com>".
+fn address(text: &str) -> String {
+    let text = text.trim();
+    match (text.rfind('<'), text.rfind('>')) {
+        (Some(start), Some(end)) if start < end => String::from(&text[start + 1..end]),
+        _ => String::from(text),
+    }
+}
+
+/// Describe a failed command by its first line of error output.
+fn failure(command: &str, stderr: &[u8]) -> String {
+    let stderr = String::from_utf8_lossy(stderr);
+    match stderr.lines().find(|line| !line.trim().is_empty()) {
+        Some(line) => format!("{} failed: {}", command, line.trim()),
+        None => format!("{} failed", command),
+    }
+}
@@ -0,0 +1,64 @@
+/// Errors which stop yat from doing what was asked, each with a message for
+/// stderr and an exit code.
+use std::io;
+use std::path::PathBuf;
+use thiserror::Error;
+
+/// Something yat was unable to do.
+#[derive(Debug, Error)]
+pub enum Error {
+    /// The terminal couldn't be set up for the interface.
+    #[error("Unable to set up the terminal: {0}")]
+    Terminal(#[source] io::Error),
+    /// There's no home directory to keep the save file in.
+    #[error("Unable to find home directory")]
+    NoHome,
+    /// The save file doesn't exist.
+    #[error("{} does not exist", .0.display())]
+    NoSaveFile(PathBuf),
+    /// A file couldn't be read.
+    #[error("Unable to read {}: {}", .0.display(), .1)]
+    Read(PathBuf, #[source] io::Error),
+    /// A file couldn't be written.
+    #[error("Unable to write {}: {}", .0.display(), .1)]
+    Write(PathBuf, #[source] io::Error),
+    /// The save file couldn't be parsed.
+    #[error("Unable to parse save file (try yat doctor): {0}")]
+    Parse(&'static str),
+    /// Tasks exported from another app couldn't be read.
+    #[error("Unable to import {}: {}", .0.display(), .1)]
+    Import(PathBuf, String),
+    /// A remote todo list couldn't be downloaded or uploaded.
+    #[error("Unable to reach {0}: {1}")]
+    Remote(String, String),
+    /// Something couldn't be sent on, e.g. a digest.
+    #[error("{0}")]
+    Delivery(String),
+    /// Another yat is already listening for tasks on a socket.
+    #[error("Another yat is already listening on {}", .0.display())]
+    Busy(PathBuf),
+    /// Text couldn't be edited in an external editor.
+    #[error("Unable to edit notes: {0}")]
+    Editor(String),
+    /// A setting needed is missing from config.toml.
+    #[error("{0}")]
+    Config(&'static str),
+    /// Printing to or reading from the terminal failed.
+    #[error("{0}")]
+    Io(#[from] io::Error),
+}
+
+impl Error {
+    /// Exit code for the error, following sysexits.h.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Error::Parse(_) | Error::Import(..) => 65,
+            Error::NoSaveFile(_) | Error::Read(..) => 66,
+            Error::Remote(..) | Error::Delivery(_) | Error::Editor(_) => 69,
+            Error::Terminal(_) => 71,
+            Error::Busy(_) => 75,
+            Error::Write(..) | Error::Io(_) => 74,
+            Error::NoHome | Error::Config(_) => 78,
+        }
+    }
+}
@@ -0,0 +1,95 @@
+/// Rules raising the priority of tasks as they fall due or grow old, set
+/// in the [[escalate]] sections of config.toml.
+use crate::due;
+use crate::todo::{Priority, Stamp, ToDo};
+use chrono::{Duration, NaiveDateTime, NaiveTime};
+
+/// When a rule raises the priority of a task.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Trigger {
+    /// Once its deadline is less than a number of minutes away, or past.
+    DueWithin(u32),
+    /// Once it was created more than a number of minutes ago.
+    OlderThan(u32),
+}
+
+/// Rule raising unfinished tasks to a priority, unless they are higher.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rule {
+    pub trigger: Trigger,
+    pub priority: Priority,
+}
+
+impl Rule {
+    /// Parse a rule from its lead time before the deadline or age, such as
+    /// "2d" or "4h", and priority letter, exactly one of the two times
+    /// being given. The priority must be one of a number of levels.
+    pub fn parse(
+        due_within: Option<&str>,
+        older_than: Option<&str>,
+        priority: &str,
+        levels: u8,
+    ) -> Result<Rule, String> {
+        let lowest = Priority::lowest(levels);
+        let mut letters = priority.chars();
+        let priority = match (
+            letters.next().and_then(Priority::from_letter),
+            letters.next(),
+        ) {
+            (Some(priority), None) if priority >= lowest => priority,
+            _ => {
+                return Err(format!(
+                    "Invalid priority {}: use a letter A-{}",
+                    priority,
+                    lowest.letter()
+                ))
+            }
+        };
+        let lead = |text: &str| {
+            due::parse_lead(text).ok_or_else(|| format!("Invalid time {}: use e.g. 2d or 4h", text))
+        };
+        let trigger = match (due_within, older_than) {
+            (Some(text), None) => Trigger::DueWithin(lead(text)?),
+            (None, Some(text)) => Trigger::OlderThan(lead(text)?),
+            _ => return Err(String::from("Give one of due_within and older_than")),
+        };
+        Ok(Rule { trigger, priority })
+    }
+
+    /// Whether the rule raises a task at a time.
+    pub fn applies(&self, todo: &ToDo, now: NaiveDateTime) -> bool {
+        if todo.complete || todo.priority.is_some_and(|p| p >= self.priority) {
+            return false;
+        }
+        match self.trigger {
+            Trigger::DueWithin(minutes) => todo
+                .due
+                .is_some_and(|due| due.deadline() - Duration::minutes(minutes.into()) <= now),
+            Trigger::OlderThan(minutes) => todo.created.is_some_and(|created| {
+                let created = match created {
+                    Stamp::DateTime(time) => time,
+                    Stamp::Date(day) => day.and_time(NaiveTime::MIN),
+                };
+                created + Duration::minutes(minutes.into()) <= now
+            }),
+        }
+    }
+}
+
+/// Raise the priority of the tasks under a task which the rules apply to,
+/// to the highest of those rules, collecting their titles.
+pub fn raise(rules: &[Rule], todo: &ToDo, now: NaiveDateTime, raised: &mut Vec<String>) {
+    for sub_task_rc in todo.sub_tasks.iter() {
+        let mut sub_task = sub_task_rc.borrow_mut();
+        let priority = rules
+            .iter()
+            .filter(|rule| rule.applies(&sub_task, now))
+            .map(|rule| rule.priority)
+            .max();
+        if priority.is_some() {
+            sub_task.priority = priority;
+            raised.push(sub_task.task.clone());
+        }
+        raise(rules, &sub_task, now, raised);
+    }
+}
@@ -0,0 +1,138 @@
+/// Functionality for exporting todo lists to other formats.
+use crate::link;
+use crate::todo::ToDo;
+use log::{info, warn};
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::Path;
+
+/// Page header for HTML export, including the stylesheet.
+const HTML_HEAD: &str = r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>Todo list</title>
+<style>
+body { font-family: sans-serif; max-width: 50em; margin: 2em auto; color: #333; }
+ul { list-style: none; padding-left: 1.5em; }
+li { margin: 0.25em 0; }
+summary { cursor: pointer; }
+.complete > .task, .complete > details > summary > .task { text-decoration: line-through; color: #999; }
+a.task { color: inherit; text-decoration: none; }
+.priority { font-family: monospace; font-weight: bold; }
+.high { color: #dc322f; }
+.medium { color: #b58900; }
+.low { color: #859900; }
+.notes { margin: 0.25em 0 0.5em 1.5em; color: #666; white-space: pre-wrap; }
+</style>
+</head>
+<body>
+<h1>Todo list</h1>
+"#;
+
+/// Page footer for HTML export.
+const HTML_FOOT: &str = "</body>\n</html>\n";
+
+/// Convert todo list tree into a standalone HTML page, with collapsible
+/// sections for tasks with sub-tasks. Priorities are classed as high,
+/// medium or low on a scale with a number of levels.
+pub fn html(root: &ToDo, levels: u8) -> String {
+    let mut buffer = String::from(HTML_HEAD);
+    html_tasks(root, levels, &mut buffer);
+    buffer.push_str(HTML_FOOT);
+    buffer
+}
+
+/// Convert all sub-tasks to HTML list items.
+fn html_tasks(todo: &ToDo, levels: u8, buf: &mut String) {
+    if todo.sub_tasks.is_empty() {
+        return;
+    }
+
+    buf.push_str("<ul>\n");
+    for sub_task_rc in todo.sub_tasks.iter() {
+        let sub_task = sub_task_rc.borrow();
+        let class = if sub_task.complete {
+            "complete"
+        } else {
+            "open"
+        };
+        buf.push_str(&format!("<li class=\"{}\">", class));
+
+        let mut label = String::new();
+        label.push_str(if sub_task.complete { "[X] " } else { "[ ] " });
+        if let Some(priority) = sub_task.priority {
+            label.push_str(&format!(
+                "<span class=\"priority {}\">{}</span> ",
+                priority.name(levels),
+                priority.label()
+            ));
+        }
+        match sub_task.id {
+            Some(id) => label.push_str(&format!(
+                "<a class=\"task\" href=\"{}\">{}</a>",
+                link::to_task(id),
+                escape_html(&sub_task.task)
+            )),
+            None => label.push_str(&format!(
+                "<span class=\"task\">{}</span>",
+                escape_html(&sub_task.task)
+            )),
+        }
+
+        if sub_task.sub_tasks.is_empty() {
+            buf.push_str(&label);
+            html_notes(&sub_task, buf);
+        } else {
+            buf.push_str(&format!("<details open><summary>{}</summary>\n", label));
+            html_notes(&sub_task, buf);
+            html_tasks(&sub_task, levels, buf);
+            buf.push_str("</details>");
+        }
+        buf.push_str("</li>\n");
+    }
+    buf.push_str("</ul>\n");
+}
+
+/// Convert notes of a task to HTML.
+fn html_notes(todo: &ToDo, buf: &mut String) {
+    if !todo.notes.is_empty() {
+        buf.push_str(&format!(
+            "<div class=\"notes\">{}</div>\n",
+            escape_html(&todo.notes)
+        ));
+    }
+}
+
+/// Escape characters with special meaning in HTML.
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for ch in text.chars() {
+        match ch {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(ch),
+        }
+    }
+    escaped
+}
+
+/// Write exported todo list to file.
+pub fn write(filename: &Path, buffer: &str) -> io::Result<()> {
+    let mut file = File::create(filename).inspect_err(|err| {
+        warn!("Unable to open file to export: {}.", err);
+    })?;
+    match file.write_all(buffer.as_bytes()) {
+        Ok(()) => {
+            info!("Todo list exported to {}.", filename.display());
+            Ok(())
+        }
+        Err(err) => {
+            warn!("Unable to write to export file: {}", err);
+            Err(err)
+        }
+    }
+}
@@ -0,0 +1,94 @@
+/// Bridge to Habitica, scoring a habit there when a task with a matching
+/// tag is completed.
+use crate::config::HabiticaConfig;
+use crate::storage::{curl_escape, password_from_command};
+use crate::todo::ToDo;
+use log::{info, warn};
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::thread;
+
+/// Address of the Habitica API.
+const API: &str = "https://habitica.com/api/v3";
+
+/// Habits (by id or alias) to score for a completed task, from its tags.
+pub fn habits<'a>(config: &'a HabiticaConfig, todo: &ToDo) -> Vec<&'a str> {
+    let mut habits = Vec::new();
+    for tag in todo.tags() {
+        let habit = config
+            .habits
+            .iter()
+            .find(|(name, _)| name.trim_start_matches(['+', '#']) == tag)
+            .map(|(_, habit)| habit.as_str());
+        if let Some(habit) = habit.filter(|habit| !habits.contains(habit)) {
+            habits.push(habit);
+        }
+    }
+    habits
+}
+
+/// Score habits up on Habitica in the background, so the interface isn't
+/// held up waiting for the server.
+pub fn score(config: &HabiticaConfig, habits: &[&str]) {
+    let config = config.clone();
+    let habits: Vec<String> = habits.iter().map(|habit| String::from(*habit)).collect();
+    thread::spawn(move || {
+        let mut token = config.token.clone();
+        if let Some(command) = &config.token_command {
+            token = password_from_command(command).or(token);
+        }
+        let token = match token {
+            Some(token) => token,
+            None => return warn!("No Habitica API token configured."),
+        };
+        for habit in habits.iter() {
+            score_habit(&config.user, &token, habit);
+        }
+    });
+}
+
+/// Score a habit up, passing the credentials to curl on stdin so they don't
+/// appear in the process list.
+fn score_habit(user: &str, token: &str, habit: &str) {
+    let url = format!("{}/tasks/{}/score/up", API, habit);
+    let curl_config = format!(
+        "url = \"{}\"\nheader = \"x-api-user: {}\"\nheader = \"x-api-key: {}\"\n\
+         header = \"x-client: {}-yat\"\n",
+        curl_escape(&url),
+        curl_escape(user),
+        curl_escape(token),
+        curl_escape(user),
+    );
+
+    let child = Command::new("curl")
+        .args([
+            "--silent",
+            "--show-error",
+            "--fail",
+            "--connect-timeout",
+            "10",
+        ])
+        .args(["--request", "POST", "--config", "-"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn();
+    let mut child = match child {
+        Ok(child) => child,
+        Err(err) => return warn!("Unable to run curl: {}", err),
+    };
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin
+            .write_all(curl_config.as_bytes())
+            .unwrap_or_else(|err| warn!("Unable to configure curl: {}", err));
+    }
+    match child.wait_with_output() {
+        Ok(output) if output.status.success() => info!("Scored Habitica habit {}.", habit),
+        Ok(output) => warn!(
+            "Unable to score Habitica habit {}: {}",
+            habit,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ),
+        Err(err) => warn!("Unable to run curl: {}", err),
+    }
+}
@@ -0,0 +1,91 @@
+/// Heatmap of how many tasks were completed each day over the last year,
+/// a week to a column, like a contribution graph.
+use crate::todo::ToDo;
+use chrono::{Duration, NaiveDate};
+use std::collections::HashMap;
+
+/// Number of weeks in the heatmap, a year's worth.
+pub const WEEKS: usize = 53;
+
+/// Number of shades, from no completions to the most.
+pub const LEVELS: usize = 5;
+
+/// Shades of each level, in unicode block characters or plain ASCII.
+const SHADES: [char; LEVELS] = ['·', '░', '▒', '▓', '█'];
+const ASCII_SHADES: [char; LEVELS] = ['.', '-', '+', '*', '#'];
+
+/// Completions per day over the weeks up to a day.
+pub struct Heatmap {
+    /// First day of the first week.
+    pub first: NaiveDate,
+    /// Last day counted, e.g. today.
+    pub last: NaiveDate,
+    counts: HashMap<NaiveDate, u32>,
+}
+
+impl Heatmap {
+    /// Empty heatmap of the weeks up to a day, starting from the first day
+    /// of its week.
+    pub fn new(week_start: NaiveDate, last: NaiveDate) -> Heatmap {
+        Heatmap {
+            first: week_start - Duration::weeks(WEEKS as i64 - 1),
+            last,
+            counts: HashMap::new(),
+        }
+    }
+
+    /// Count the completed tasks under a task, by the day they were
+    /// completed. Tasks completed before completion times were kept are
+    /// left out.
+    pub fn add_tree(&mut self, todo: &ToDo) {
+        for sub_task in todo.sub_tasks.iter() {
+            let sub_task = sub_task.borrow();
+            if let Some(stamp) = sub_task.completed.filter(|_| sub_task.complete) {
+                let day = stamp.day();
+                if day >= self.first && day <= self.last {
+                    *self.counts.entry(day).or_insert(0) += 1;
+                }
+            }
+            self.add_tree(&sub_task);
+        }
+    }
+
+    /// Number of tasks completed on a day.
+    pub fn count(&self, day: NaiveDate) -> u32 {
+        self.counts.get(&day).copied().unwrap_or(0)
+    }
+
+    /// Most tasks completed on any one day.
+    pub fn most(&self) -> u32 {
+        self.counts.values().copied().max().unwrap_or(0)
+    }
+
+    /// Number of tasks completed over the whole heatmap.
+    pub fn total(&self) -> u32 {
+        self.counts.values().sum()
+    }
+
+    /// Shade of a day, 0 for no completions, up to LEVELS - 1 for the most,
+    /// with any completions at least 1.
+    pub fn level(&self, day: NaiveDate) -> usize {
+        let count = self.count(day) as usize;
+        let most = self.most() as usize;
+        match count {
+            0 => 0,
+            count => (count * (LEVELS - 1)).div_ceil(most).clamp(1, LEVELS - 1),
+        }
+    }
+
+    /// Day in a week and row of the heatmap, if it is not after the last
+    /// day.
+    pub fn day(&self, week: usize, row: usize) -> Option<NaiveDate> {
+        let day = self.first + Duration::days((week * 7 + row) as i64);
+        (day <= self.last).then_some(day)
+    }
+}
+
+/// Character for a shade.
+pub fn shade(level: usize, ascii: bool) -> char {
+    let shades = if ascii { &ASCII_SHADES } else { &SHADES };
+    shades[level.min(LEVELS - 1)]
+}
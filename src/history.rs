@@ -0,0 +1,71 @@
+/// History of changes to the list, for undoing and redoing them: states
+/// of the list to go back to, and forward to again.
+#[derive(Default)]
+pub struct History {
+    undo: Vec<Step>,
+    redo: Vec<Step>,
+}
+
+/// Most changes kept to undo, dropping the oldest beyond it.
+const LIMIT: usize = 100;
+
+/// State of the list before or after a change: the list in save file
+/// format, with the selected task and what the change was.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Step {
+    pub list: String,
+    /// Tasks deleted to the trash, in the trash file format, so that undoing
+    /// a deletion takes the task out of the trash again.
+    pub trash: String,
+    pub selection: Option<usize>,
+    pub change: &'static str,
+}
+
+impl History {
+    /// Record a change, given the state before it and the list after it,
+    /// unless nothing changed. A new change can't be redone past.
+    pub fn record(&mut self, before: Step, after: &str) {
+        if before.list == after {
+            return;
+        }
+        self.undo.push(before);
+        if self.undo.len() > LIMIT {
+            self.undo.remove(0);
+        }
+        self.redo.clear();
+    }
+
+    /// State to undo the latest change to, given the current state to
+    /// redo it from.
+    pub fn undo(&mut self, current: Step) -> Option<Step> {
+        let step = self.undo.pop()?;
+        self.redo.push(Step {
+            change: step.change,
+            ..current
+        });
+        Some(step)
+    }
+
+    /// State to redo the latest change undone to, given the current state
+    /// to undo it from again.
+    pub fn redo(&mut self, current: Step) -> Option<Step> {
+        let step = self.redo.pop()?;
+        self.undo.push(Step {
+            change: step.change,
+            ..current
+        });
+        Some(step)
+    }
+
+    /// Put back a step taken from the history when it couldn't be applied.
+    pub fn cancel_undo(&mut self, step: Step) {
+        self.redo.pop();
+        self.undo.push(step);
+    }
+
+    /// Put back a step taken from the history when it couldn't be applied.
+    pub fn cancel_redo(&mut self, step: Step) {
+        self.undo.pop();
+        self.redo.push(step);
+    }
+}
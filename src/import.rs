@@ -0,0 +1,817 @@
+/// Importing tasks exported from other todo apps: Apple Reminders (as JSON
+/// or CSV), Things 3 (as JSON, or CSV) and Remember the Milk (as JSON), or
+/// kept as Markdown or org checklists.
+use crate::deps;
+use crate::due::Due;
+use crate::todo::{Priority, Stamp, ToDo};
+use chrono::{Local, NaiveDate, NaiveDateTime, TimeZone};
+use log::warn;
+use std::cell::RefCell;
+use std::rc::{Rc, Weak};
+
+/// A parsed JSON value.
+#[derive(Debug, Clone, PartialEq)]
+enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    /// Value of a member of an object, matching the name loosely (ignoring
+    /// case, spaces, dashes and underscores).
+    fn get(&self, name: &str) -> Option<&Json> {
+        match self {
+            Json::Object(members) => members
+                .iter()
+                .find(|(key, _)| loose(key) == loose(name))
+                .map(|(_, value)| value),
+            _ => None,
+        }
+    }
+
+    /// Value of the first of several possible members of an object.
+    fn any(&self, names: &[&str]) -> Option<&Json> {
+        names.iter().find_map(|name| self.get(name))
+    }
+
+    /// Text of a string (or a number, as text).
+    fn text(&self) -> Option<String> {
+        match self {
+            Json::String(text) => Some(text.clone()),
+            Json::Number(number) => Some(number.to_string()),
+            _ => None,
+        }
+    }
+
+    /// Truth of a boolean, or of a string such as "yes" or "completed".
+    fn truth(&self) -> bool {
+        match self {
+            Json::Bool(value) => *value,
+            Json::Number(number) => *number != 0.0,
+            Json::String(text) => truthy(text),
+            _ => false,
+        }
+    }
+
+    /// Items of an array (nothing for any other value).
+    fn items(&self) -> &[Json] {
+        match self {
+            Json::Array(items) => items,
+            _ => &[],
+        }
+    }
+}
+
+/// Name of a field, ignoring case and separators, so "Due Date", "dueDate"
+/// and "due-date" are the same.
+fn loose(name: &str) -> String {
+    name.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Whether a text value means true, e.g. "yes", "1", "completed".
+fn truthy(text: &str) -> bool {
+    matches!(
+        text.trim().to_lowercase().as_str(),
+        "true" | "yes" | "1" | "x" | "completed" | "complete" | "done"
+    )
+}
+
+/// Parse a JSON document.
+fn parse_json(text: &str) -> Result<Json, String> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut parser = JsonParser { chars, pos: 0 };
+    let value = parser.value()?;
+    parser.whitespace();
+    match parser.pos == parser.chars.len() {
+        true => Ok(value),
+        false => Err(parser.error("unexpected text after the end")),
+    }
+}
+
+/// Position in JSON text being parsed.
+struct JsonParser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl JsonParser {
+    /// Describe a problem at the current position.
+    fn error(&self, message: &str) -> String {
+        format!("Invalid JSON at character {}: {}", self.pos, message)
+    }
+
+    /// Skip whitespace.
+    fn whitespace(&mut self) {
+        while self.pos < self.chars.len() && self.chars[self.pos].is_whitespace() {
+            self.pos += 1;
+        }
+    }
+
+    /// Consume an expected piece of text.
+    fn expect(&mut self, text: &str) -> Result<(), String> {
+        for ch in text.chars() {
+            if self.chars.get(self.pos) != Some(&ch) {
+                return Err(self.error(&format!("expected '{}'", text)));
+            }
+            self.pos += 1;
+        }
+        Ok(())
+    }
+
+    /// Parse any value.
+    fn value(&mut self) -> Result<Json, String> {
+        self.whitespace();
+        match self.chars.get(self.pos) {
+            Some('{') => self.object(),
+            Some('[') => self.array(),
+            Some('"') => self.string().map(Json::String),
+            Some('t') => self.expect("true").map(|_| Json::Bool(true)),
+            Some('f') => self.expect("false").map(|_| Json::Bool(false)),
+            Some('n') => self.expect("null").map(|_| Json::Null),
+            Some(_) => self.number(),
+            None => Err(self.error("unexpected end")),
+        }
+    }
+
+    /// Parse an object of members.
+    fn object(&mut self) -> Result<Json, String> {
+        self.expect("{")?;
+        let mut members = Vec::new();
+        self.whitespace();
+        if self.chars.get(self.pos) == Some(&'}') {
+            self.pos += 1;
+            return Ok(Json::Object(members));
+        }
+        loop {
+            self.whitespace();
+            let key = self.string()?;
+            self.whitespace();
+            self.expect(":")?;
+            members.push((key, self.value()?));
+            self.whitespace();
+            match self.chars.get(self.pos) {
+                Some(',') => self.pos += 1,
+                Some('}') => {
+                    self.pos += 1;
+                    return Ok(Json::Object(members));
+                }
+                _ => return Err(self.error("expected ',' or '}'")),
+            }
+        }
+    }
+
+    /// Parse an array of values.
+    fn array(&mut self) -> Result<Json, String> {
+        self.expect("[")?;
+        let mut items = Vec::new();
+        self.whitespace();
+        if self.chars.get(self.pos) == Some(&']') {
+            self.pos += 1;
+            return Ok(Json::Array(items));
+        }
+        loop {
+            items.push(self.value()?);
+            self.whitespace();
+            match self.chars.get(self.pos) {
+                Some(',') => self.pos += 1,
+                Some(']') => {
+                    self.pos += 1;
+                    return Ok(Json::Array(items));
+                }
+                _ => return Err(self.error("expected ',' or ']'")),
+            }
+        }
+    }
+
+    /// Parse a string, with its escapes.
+    fn string(&mut self) -> Result<String, String> {
+        self.expect("\"")?;
+        let mut text = String::new();
+        loop {
+            let ch = match self.chars.get(self.pos) {
+                Some(&ch) => ch,
+                None => return Err(self.error("unterminated string")),
+            };
+            self.pos += 1;
+            match ch {
+                '"' => return Ok(text),
+                '\\' => {
+                    let escape = self.chars.get(self.pos).copied();
+                    self.pos += 1;
+                    match escape {
+                        Some('n') => text.push('\n'),
+                        Some('t') => text.push('\t'),
+                        Some('r') => text.push('\r'),
+                        Some('b') => text.push('\u{8}'),
+                        Some('f') => text.push('\u{c}'),
+                        Some('u') => text.push(self.unicode_escape()?),
+                        Some(ch) => text.push(ch),
+                        None => return Err(self.error("unterminated string")),
+                    }
+                }
+                ch => text.push(ch),
+            }
+        }
+    }
+
+    /// Parse the hex digits of a \u escape, including surrogate pairs.
+    fn unicode_escape(&mut self) -> Result<char, String> {
+        let first = self.hex4()?;
+        let pair = self.chars.get(self.pos..self.pos + 2) == Some(&['\\', 'u'][..]);
+        if (0xD800..0xDC00).contains(&first) && pair {
+            self.pos += 2;
+            let second = self.hex4()?;
+            let code = 0x10000 + ((first - 0xD800) << 10) + (second.wrapping_sub(0xDC00) & 0x3FF);
+            return Ok(char::from_u32(code).unwrap_or('\u{FFFD}'));
+        }
+        Ok(char::from_u32(first).unwrap_or('\u{FFFD}'))
+    }
+
+    /// Parse four hex digits.
+    fn hex4(&mut self) -> Result<u32, String> {
+        let digits: String = self.chars.iter().skip(self.pos).take(4).collect();
+        self.pos += 4;
+        u32::from_str_radix(&digits, 16).map_err(|_| self.error("invalid \\u escape"))
+    }
+
+    /// Parse a number.
+    fn number(&mut self) -> Result<Json, String> {
+        let start = self.pos;
+        while self.pos < self.chars.len()
+            && matches!(
+                self.chars[self.pos],
+                '0'..='9' | '-' | '+' | '.' | 'e' | 'E'
+            )
+        {
+            self.pos += 1;
+        }
+        let text: String = self.chars[start..self.pos].iter().collect();
+        text.parse()
+            .map(Json::Number)
+            .map_err(|_| self.error("expected a value"))
+    }
+}
+
+/// Parse CSV text into records of (header, value) pairs, taking the names of
+/// the fields from the first row.
+fn parse_csv(text: &str) -> Vec<Json> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = text.trim_start_matches('\u{feff}').chars().peekable();
+    while let Some(ch) = chars.next() {
+        match (ch, quoted) {
+            ('"', true) if chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            ('"', true) => quoted = false,
+            ('"', false) if field.is_empty() => quoted = true,
+            (',', false) => row.push(std::mem::take(&mut field)),
+            ('\r', false) => (),
+            ('\n', false) => {
+                row.push(std::mem::take(&mut field));
+                rows.push(std::mem::take(&mut row));
+            }
+            (ch, _) => field.push(ch),
+        }
+    }
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push(row);
+    }
+
+    let mut rows = rows.into_iter();
+    let header = rows.next().unwrap_or_default();
+    rows.filter(|row| row.iter().any(|field| !field.trim().is_empty()))
+        .map(|row| {
+            let members = header
+                .iter()
+                .cloned()
+                .zip(row.into_iter().map(Json::String))
+                .collect();
+            Json::Object(members)
+        })
+        .collect()
+}
+
+/// Parse a due date such as "2024-03-01", "2024-03-01 09:30",
+/// "2024-03-01T09:30:00Z" or "03/01/2024".
+fn parse_due(text: &str) -> Option<Due> {
+    let text = text.trim().trim_end_matches('Z');
+    let text = text.split('+').next().unwrap_or(text);
+    for format in [
+        "%Y-%m-%dT%H:%M:%S%.f",
+        "%Y-%m-%d %H:%M:%S",
+        "%m/%d/%Y %H:%M",
+    ]
+    .iter()
+    {
+        if let Ok(time) = NaiveDateTime::parse_from_str(text, format) {
+            return Some(Due::DateTime(time));
+        }
+    }
+    if let Some(due) = Due::parse(text) {
+        return Some(due);
+    }
+    NaiveDate::parse_from_str(text, "%m/%d/%Y")
+        .ok()
+        .map(Due::Date)
+}
+
+/// Priority from Apple Reminders (1-4 high, 5 medium, 6-9 low, 0 none) or
+/// a name such as "high".
+fn parse_priority(value: &Json) -> Option<Priority> {
+    let text = value.text()?.trim().to_lowercase();
+    match text.as_str() {
+        "high" | "!!!" => return Some(Priority::HIGH),
+        "medium" | "!!" => return Some(Priority::MEDIUM),
+        "low" | "!" => return Some(Priority::LOW),
+        _ => (),
+    }
+    match text.parse::<f64>().ok()? as u32 {
+        1..=4 => Some(Priority::HIGH),
+        5 => Some(Priority::MEDIUM),
+        6..=9 => Some(Priority::LOW),
+        _ => None,
+    }
+}
+
+/// Tasks imported from another app, grouped by list.
+pub struct Import {
+    /// Lists (or projects), in the order they were first seen, holding
+    /// their tasks.
+    lists: Vec<ToDo>,
+    /// Tasks not in any list.
+    loose: Vec<Rc<RefCell<ToDo>>>,
+    /// Checklist items named by a footnote reference, e.g. [^design], by
+    /// their label.
+    labels: Vec<(String, Rc<RefCell<ToDo>>)>,
+    /// Checklist items blocked by others, with the labels of those.
+    blocked: Vec<(Rc<RefCell<ToDo>>, Vec<String>)>,
+}
+
+impl Import {
+    /// Task holding a list, creating the list if new.
+    fn list(&mut self, name: String) -> &mut ToDo {
+        let index = match self.lists.iter().position(|list| list.task == name) {
+            Some(index) => index,
+            None => {
+                self.lists.push(ToDo::new(&name, Weak::new()));
+                self.lists.len() - 1
+            }
+        };
+        &mut self.lists[index]
+    }
+
+    /// Add a task to a list (if named).
+    fn add(&mut self, list: Option<String>, todo: Rc<RefCell<ToDo>>) {
+        match list.filter(|name| !name.trim().is_empty()) {
+            Some(list) => self.list(list).sub_tasks.push(todo),
+            None => self.loose.push(todo),
+        }
+    }
+
+    /// Number of tasks imported, not counting lists.
+    pub fn count(&self) -> usize {
+        let all = |_: &ToDo| true;
+        let in_lists: usize = self.lists.iter().map(|list| list.count_in_tree(&all)).sum();
+        let loose: usize = self
+            .loose
+            .iter()
+            .map(|todo| 1 + todo.borrow().count_in_tree(&all))
+            .sum();
+        in_lists + loose
+    }
+
+    /// Number of levels of imported tasks, counting lists as a level.
+    pub fn height(&self) -> usize {
+        let lists = self.lists.iter().map(|list| 1 + list.height());
+        let loose = self.loose.iter().map(|todo| 1 + todo.borrow().height());
+        lists.chain(loose).max().unwrap_or(0)
+    }
+
+    /// Add the imported tasks under a task, with each list as a task
+    /// holding its tasks, and the tasks blocked by others waiting on them.
+    pub fn add_to(self, root: &Rc<RefCell<ToDo>>) {
+        let lists = self
+            .lists
+            .into_iter()
+            .map(|list| Rc::new(RefCell::new(list)));
+        root.borrow_mut().sub_tasks.extend(lists.chain(self.loose));
+        set_parents(root);
+
+        // Ids are given once the tasks are in the tree, so they are unique
+        for (task, labels) in self.blocked.iter() {
+            for label in labels.iter() {
+                let blocker = self.labels.iter().find(|(name, _)| name == label);
+                match blocker {
+                    Some((_, blocker)) if !Rc::ptr_eq(blocker, task) => {
+                        let id = deps::ensure_id(blocker, root);
+                        let mut task = task.borrow_mut();
+                        if !task.blocked_by.contains(&id) {
+                            task.blocked_by.push(id);
+                        }
+                    }
+                    _ => warn!(
+                        "No task labelled {} for {} to be blocked by.",
+                        label,
+                        task.borrow().task
+                    ),
+                }
+            }
+        }
+    }
+}
+
+/// Point the sub-tasks in a tree back at their parents.
+fn set_parents(task: &Rc<RefCell<ToDo>>) {
+    for sub_task in task.borrow().sub_tasks.iter() {
+        sub_task.borrow_mut().parent = Rc::downgrade(task);
+        set_parents(sub_task);
+    }
+}
+
+/// Import tasks from the contents of an exported file, recognising the
+/// format from the contents.
+pub fn import(text: &str) -> Result<Import, String> {
+    let mut import = Import {
+        lists: Vec::new(),
+        loose: Vec::new(),
+        labels: Vec::new(),
+        blocked: Vec::new(),
+    };
+    let trimmed = text.trim_start_matches('\u{feff}').trim_start();
+    if trimmed.starts_with('[') || trimmed.starts_with('{') {
+        let json = parse_json(trimmed)?;
+        if let Some(task) = json.get("tasks").and_then(|tasks| tasks.items().first()) {
+            if task.get("list_id").is_some() {
+                remember_the_milk(&mut import, &json);
+                return Ok(import);
+            }
+        }
+        let items = match &json {
+            Json::Array(items) => items.as_slice(),
+            object => match object.any(&["items", "reminders", "tasks", "lists"]) {
+                Some(items) => items.items(),
+                None => std::slice::from_ref(object),
+            },
+        };
+        for item in items.iter() {
+            json_item(&mut import, item, None);
+        }
+    } else if text.lines().any(|line| {
+        checkbox(line).is_some()
+            || org_heading(line).is_some_and(|(_, keyword, _)| keyword.is_some())
+    }) {
+        checklist(&mut import, text);
+    } else {
+        for record in parse_csv(text).iter() {
+            let list = record
+                .any(&["list", "project", "area", "calendar"])
+                .and_then(Json::text);
+            if let Some(todo) = record_task(record) {
+                import.add(list, Rc::new(RefCell::new(todo)));
+            }
+        }
+    }
+    Ok(import)
+}
+
+/// Import an item of a JSON export: a Things 3 project, heading or to-do
+/// (with its attributes), a list of reminders, or a single reminder.
+fn json_item(import: &mut Import, item: &Json, list: Option<String>) {
+    let kind = item.get("type").and_then(Json::text).unwrap_or_default();
+    let attributes = item.get("attributes").unwrap_or(item);
+    let title = attributes.any(&["title", "name"]).and_then(Json::text);
+    let children = attributes.any(&["items", "reminders", "tasks", "to-dos"]);
+
+    match (kind.as_str(), children) {
+        // Projects, areas and lists of reminders become lists
+        ("project", _) | ("area", _) | (_, Some(_)) => {
+            let name = title.or(list);
+            if let Some(name) = &name {
+                let list = import.list(name.clone());
+                if let Some(notes) = attributes.get("notes").and_then(Json::text) {
+                    list.notes = notes.trim().replace("\r\n", "\n");
+                }
+            }
+            for child in children.map_or(&[][..], Json::items) {
+                json_item(import, child, name.clone());
+            }
+        }
+        // Headings only group the to-dos after them within a project
+        ("heading", None) => (),
+        _ => {
+            let list = attributes
+                .any(&["list", "project", "area", "calendar"])
+                .and_then(Json::text)
+                .or(list);
+            if let Some(todo) = record_task(attributes) {
+                import.add(list, Rc::new(RefCell::new(todo)));
+            }
+        }
+    }
+}
+
+/// Make a task from the fields of a reminder, to-do or CSV row, with its
+/// checklist items as sub-tasks.
+fn record_task(record: &Json) -> Option<ToDo> {
+    let title = record
+        .any(&["title", "name", "task"])
+        .and_then(Json::text)?;
+    let title = title.split_whitespace().collect::<Vec<_>>().join(" ");
+    if title.is_empty() {
+        return None;
+    }
+
+    let mut todo = ToDo::new(&title, Weak::new());
+    if let Some(notes) = record
+        .any(&["notes", "body", "description"])
+        .and_then(Json::text)
+    {
+        todo.notes = notes.trim().replace("\r\n", "\n");
+    }
+    todo.due = record
+        .any(&["due date", "due", "deadline"])
+        .and_then(Json::text)
+        .and_then(|text| parse_due(&text));
+    // When a Things to-do is scheduled for, rather than its deadline
+    match record.get("when").and_then(Json::text) {
+        Some(when) if when == "today" || when == "evening" => {
+            todo.today = Some(Local::now().date_naive());
+        }
+        Some(when) => todo.start = parse_due(&when).map(|due| due.day()),
+        None => (),
+    }
+    todo.priority = record.get("priority").and_then(parse_priority);
+    todo.complete = record
+        .any(&["completed", "is completed", "status", "done"])
+        .is_some_and(Json::truth);
+
+    let checklist = record.any(&["checklist-items", "checklist", "subtasks"]);
+    for item in checklist.map_or(&[][..], Json::items) {
+        let attributes = item.get("attributes").unwrap_or(item);
+        if let Some(sub_task) = record_task(attributes) {
+            todo.sub_tasks.push(Rc::new(RefCell::new(sub_task)));
+        }
+    }
+    Some(todo)
+}
+
+/// Import a Remember the Milk backup, where tasks refer to their list (and
+/// parent task, if a sub-task) by id, and notes refer to their task.
+fn remember_the_milk(import: &mut Import, json: &Json) {
+    let id = |item: &Json, name: &str| item.get(name).and_then(Json::text);
+    let lists: Vec<(String, String)> = json
+        .get("lists")
+        .map_or(&[][..], Json::items)
+        .iter()
+        .filter_map(|list| Some((id(list, "id")?, id(list, "name")?)))
+        .collect();
+
+    let mut tasks = Vec::new();
+    for task in json.get("tasks").map_or(&[][..], Json::items) {
+        if task
+            .get("date_trashed")
+            .is_some_and(|date| *date != Json::Null)
+        {
+            continue;
+        }
+        let mut todo = match record_task(task) {
+            Some(todo) => todo,
+            None => continue,
+        };
+        for tag in task.get("tags").map_or(&[][..], Json::items) {
+            if let Some(tag) = tag.text() {
+                todo.task = format!("{} +{}", todo.task, tag);
+            }
+        }
+        let has_time = task.get("date_due_has_time").is_some_and(Json::truth);
+        todo.due = match (timestamp(task.get("date_due")), has_time) {
+            (Some(due), true) => Some(Due::DateTime(due)),
+            (Some(due), false) => Some(Due::Date(due.date())),
+            (None, _) => None,
+        };
+        todo.priority = match id(task, "priority").as_deref() {
+            Some("P1") => Some(Priority::HIGH),
+            Some("P2") => Some(Priority::MEDIUM),
+            Some("P3") => Some(Priority::LOW),
+            _ => None,
+        };
+        todo.completed = timestamp(task.get("date_completed")).map(Stamp::DateTime);
+        todo.complete = todo.completed.is_some();
+        let series = id(task, "series_id");
+        let notes: Vec<String> = json
+            .get("notes")
+            .map_or(&[][..], Json::items)
+            .iter()
+            .filter(|note| series.is_some() && id(note, "series_id") == series)
+            .filter_map(|note| {
+                let title = id(note, "title").unwrap_or_default();
+                let content = id(note, "content").unwrap_or_default();
+                let note = format!("{}\n{}", title, content);
+                Some(String::from(note.trim())).filter(|note| !note.is_empty())
+            })
+            .collect();
+        if !notes.is_empty() {
+            todo.notes = notes.join("\n\n");
+        }
+        tasks.push((task, Rc::new(RefCell::new(todo))));
+    }
+
+    // Sub-tasks go under their parent, and the rest in their list
+    for (task, todo) in tasks.iter() {
+        let parent = id(task, "parent_id").and_then(|parent| {
+            tasks
+                .iter()
+                .find(|(other, _)| id(other, "id").as_ref() == Some(&parent))
+        });
+        match parent {
+            Some((_, parent)) => parent.borrow_mut().sub_tasks.push(Rc::clone(todo)),
+            None => {
+                let list = id(task, "list_id").and_then(|list| {
+                    lists
+                        .iter()
+                        .find(|(id, _)| *id == list)
+                        .map(|(_, name)| name.clone())
+                });
+                import.add(list, Rc::clone(todo));
+            }
+        }
+    }
+}
+
+/// Item of a Markdown or org checklist, e.g. "- [ ] Task" or "- [x] Task",
+/// as its indent, whether it is checked and its title.
+fn checkbox(line: &str) -> Option<(usize, bool, &str)> {
+    let text = line.trim_start();
+    let indent = line[..line.len() - text.len()]
+        .chars()
+        .map(|ch| if ch == '\t' { 4 } else { 1 })
+        .sum();
+    let text = text
+        .strip_prefix(['-', '*', '+'])?
+        .strip_prefix(' ')?
+        .trim_start();
+    let checked = match text.get(..3)? {
+        "[ ]" => false,
+        "[x]" | "[X]" => true,
+        _ => return None,
+    };
+    Some((indent, checked, text[3..].trim()))
+}
+
+/// Org heading, e.g. "** TODO Task", as its level, whether it is done if
+/// it has a TODO or DONE keyword, and its title.
+fn org_heading(line: &str) -> Option<(usize, Option<bool>, &str)> {
+    let level = line.chars().take_while(|&ch| ch == '*').count();
+    let title = line[level..]
+        .strip_prefix(' ')
+        .filter(|_| level > 0)?
+        .trim();
+    match title.split_once(' ').unwrap_or((title, "")) {
+        ("TODO", rest) => Some((level, Some(false), rest.trim())),
+        ("DONE", rest) => Some((level, Some(true), rest.trim())),
+        _ => Some((level, None, title)),
+    }
+}
+
+/// Title of a Markdown heading, e.g. "## Garden".
+fn markdown_heading(line: &str) -> Option<&str> {
+    let title = line.trim_start_matches('#');
+    match title.len() < line.len() {
+        true => title.strip_prefix(' ').map(str::trim),
+        false => None,
+    }
+}
+
+/// Label of a footnote reference, e.g. "design" from "[^design]" (in
+/// Markdown) or "[fn:design]" (in org), with the text before it.
+fn footnote(word: &str) -> Option<(&str, &str)> {
+    let inside = word.strip_suffix(']')?;
+    let (before, label) = inside
+        .rsplit_once("[^")
+        .or_else(|| inside.rsplit_once("[fn:"))?;
+    Some((before, label)).filter(|(_, label)| !label.is_empty())
+}
+
+/// Import a Markdown or org checklist. Items nest under those indented
+/// less, or under org headings with fewer stars, and headings without a
+/// TODO keyword name the list the items after them go in. An item can be
+/// named by a footnote reference, e.g. "Draw up plans[^plans]", for others
+/// to wait on with e.g. "blocked-by:plans".
+fn checklist(import: &mut Import, text: &str) {
+    let mut list = None;
+    let mut level = 0;
+    // Items later ones can go under, with their place in the outline
+    let mut open: Vec<(_, Rc<RefCell<ToDo>>)> = Vec::new();
+    for line in text.lines() {
+        let (place, complete, title) = if let Some((indent, checked, title)) = checkbox(line) {
+            ((level, indent + 1), checked, title)
+        } else if let Some((stars, keyword, title)) = org_heading(line) {
+            level = stars;
+            match keyword {
+                Some(done) => ((stars, 0), done, title),
+                None => {
+                    list = Some(String::from(title));
+                    open.clear();
+                    continue;
+                }
+            }
+        } else if let Some(title) = markdown_heading(line) {
+            list = Some(String::from(title));
+            level = 0;
+            open.clear();
+            continue;
+        } else {
+            // Org deadlines go on the line after their heading
+            let deadline = line.trim().strip_prefix("DEADLINE: <");
+            let due = deadline.and_then(|date| parse_due(date.get(..10)?));
+            if let (Some(due), Some((_, task))) = (due, open.last()) {
+                task.borrow_mut().due = Some(due);
+            }
+            continue;
+        };
+
+        let todo = match checklist_task(import, title, complete) {
+            Some(todo) => todo,
+            None => continue,
+        };
+        while open.last().is_some_and(|(above, _)| *above >= place) {
+            open.pop();
+        }
+        match open.last() {
+            Some((_, parent)) => parent.borrow_mut().sub_tasks.push(Rc::clone(&todo)),
+            None => import.add(list.clone(), Rc::clone(&todo)),
+        }
+        open.push((place, todo));
+    }
+}
+
+/// Make a task from the title of a checklist item, taking out an org
+/// priority, e.g. [#A], a footnote reference naming it and any
+/// blocked-by: labels of the items it waits on, separated by commas.
+fn checklist_task(import: &mut Import, title: &str, complete: bool) -> Option<Rc<RefCell<ToDo>>> {
+    let mut words = Vec::new();
+    let mut label = None;
+    let mut blockers = Vec::new();
+    let mut priority = None;
+    for word in title.split_whitespace() {
+        if let Some(names) = word.strip_prefix("blocked-by:") {
+            let names = names.split(',').filter(|name| !name.is_empty());
+            blockers.extend(names.map(|name| {
+                let label = footnote(name).map_or(name, |(_, label)| label);
+                String::from(label)
+            }));
+        } else if let Some(letter) = word.strip_prefix("[#").and_then(|w| w.strip_suffix(']')) {
+            priority = letter.chars().next().and_then(Priority::from_letter);
+        } else if let Some((before, name)) = footnote(word) {
+            label.get_or_insert_with(|| String::from(name));
+            if !before.is_empty() {
+                words.push(before);
+            }
+        } else {
+            words.push(word);
+        }
+    }
+    if words.is_empty() {
+        return None;
+    }
+
+    let mut todo = ToDo::new(&words.join(" "), Weak::new());
+    todo.complete = complete;
+    todo.priority = priority;
+    let todo = Rc::new(RefCell::new(todo));
+    if let Some(label) = label {
+        import.labels.push((label, Rc::clone(&todo)));
+    }
+    if !blockers.is_empty() {
+        import.blocked.push((Rc::clone(&todo), blockers));
+    }
+    Some(todo)
+}
+
+/// Local time of a timestamp in milliseconds since 1970.
+fn timestamp(value: Option<&Json>) -> Option<NaiveDateTime> {
+    let millis = match value? {
+        Json::Number(millis) => *millis as i64,
+        Json::String(text) => text.parse().ok()?,
+        _ => return None,
+    };
+    Local
+        .timestamp_millis_opt(millis)
+        .single()
+        .map(|time| time.naive_local())
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,205 @@
+/// Tests of importing exports from other apps, including empty and
+/// malformed files.
+use super::{import, parse_due, parse_json, Json};
+use crate::due::Due;
+use crate::tests::day;
+use crate::todo::{Priority, ToDo};
+use std::cell::RefCell;
+use std::rc::{Rc, Weak};
+
+/// Tasks imported from a file, under a root task.
+fn imported(text: &str) -> Rc<RefCell<ToDo>> {
+    let root = Rc::new(RefCell::new(ToDo::new("", Weak::new())));
+    import(text).unwrap().add_to(&root);
+    root
+}
+
+/// Titles of the tasks under a task.
+fn titles(task: &Rc<RefCell<ToDo>>) -> Vec<String> {
+    let task = task.borrow();
+    task.sub_tasks
+        .iter()
+        .map(|sub| sub.borrow().task.clone())
+        .collect()
+}
+
+#[test]
+fn empty_files() {
+    for text in [
+        "",
+        "\u{feff}",
+        "\n\n",
+        "  \r\n",
+        "[]",
+        "{}",
+        "{\"items\": []}",
+    ] {
+        let tasks = import(text).unwrap();
+        assert_eq!(tasks.count(), 0, "{:?}", text);
+        assert_eq!(tasks.height(), 0, "{:?}", text);
+    }
+}
+
+#[test]
+fn malformed_json() {
+    for text in [
+        "[",
+        "{",
+        "[{\"title\": \"Milk\"",
+        "[{\"title\": \"Milk}]",
+        "{\"title\" \"Milk\"}",
+        "{\"title\": }",
+        "[1 2]",
+        "[] []",
+        "{\"title\": \"\\u12\"}",
+        "[tru]",
+    ] {
+        match import(text) {
+            Err(err) => assert!(err.starts_with("Invalid JSON at character"), "{}", err),
+            Ok(_) => panic!("{:?} imported", text),
+        }
+    }
+    assert_eq!(
+        parse_json("[] x"),
+        Err(String::from(
+            "Invalid JSON at character 3: unexpected text after the end"
+        ))
+    );
+}
+
+#[test]
+fn json_of_the_wrong_shape() {
+    for text in [
+        "{\"items\": \"oops\"}",
+        "[null, 3, \"text\", []]",
+        "[{\"title\": \"   \"}]",
+        "[{\"name\": null}]",
+        "[{\"type\": \"heading\", \"title\": \"Later\"}]",
+        "{\"tasks\": [{\"list_id\": 1}]}",
+    ] {
+        assert_eq!(import(text).unwrap().count(), 0, "{:?}", text);
+    }
+}
+
+#[test]
+fn reminders_json() {
+    let root = imported(
+        r#"{"lists": [{"title": "Home", "reminders": [
+            {"title": "Buy\n  milk", "notes": "two\r\nlines", "dueDate": "2024-02-29T09:30:00Z",
+             "priority": 1, "isCompleted": true},
+            {"title": "Leap", "due date": "02/29/2024", "priority": "0"},
+            {"title": "Never", "due date": "2023-02-29", "priority": "lots"}
+        ]}]}"#,
+    );
+    assert_eq!(titles(&root), ["Home"]);
+    let list = Rc::clone(&root.borrow().sub_tasks[0]);
+    assert_eq!(titles(&list), ["Buy milk", "Leap", "Never"]);
+
+    let list = list.borrow();
+    let milk = list.sub_tasks[0].borrow();
+    assert_eq!(milk.notes, "two\nlines");
+    assert_eq!(
+        milk.due,
+        Some(Due::DateTime(
+            day("2024-02-29").and_hms_opt(9, 30, 0).unwrap()
+        ))
+    );
+    assert_eq!(milk.priority, Some(Priority::HIGH));
+    assert!(milk.complete);
+
+    let leap = list.sub_tasks[1].borrow();
+    assert_eq!(leap.due, Some(Due::Date(day("2024-02-29"))));
+    assert_eq!(leap.priority, None);
+    assert!(!leap.complete);
+
+    let never = list.sub_tasks[2].borrow();
+    assert_eq!(never.due, None);
+    assert_eq!(never.priority, None);
+}
+
+#[test]
+fn things_json() {
+    let root = imported(
+        r#"[{"type": "project", "attributes": {"title": "Garden", "notes": "Spring",
+            "items": [
+                {"type": "heading", "attributes": {"title": "Beds"}},
+                {"type": "to-do", "attributes": {"title": "Dig", "deadline": "2024-03-31",
+                 "checklist-items": [{"type": "checklist-item", "attributes": {"title": "Spade"}},
+                                     {"type": "checklist-item", "attributes": {}}]}}
+            ]}},
+            {"type": "to-do", "attributes": {"title": "Loose", "when": "2024-02-30"}}]"#,
+    );
+    assert_eq!(titles(&root), ["Garden", "Loose"]);
+    let garden = Rc::clone(&root.borrow().sub_tasks[0]);
+    assert_eq!(garden.borrow().notes, "Spring");
+    assert_eq!(titles(&garden), ["Dig"]);
+    let dig = Rc::clone(&garden.borrow().sub_tasks[0]);
+    assert_eq!(dig.borrow().due, Some(Due::Date(day("2024-03-31"))));
+    assert_eq!(titles(&dig), ["Spade"]);
+    assert_eq!(root.borrow().sub_tasks[1].borrow().start, None);
+}
+
+#[test]
+fn csv_files() {
+    let text = "\u{feff}Title,Notes,Due Date,List,Completed\r\n\
+                \"Buy \"\"milk\"\", eggs\",\"two\nlines\",02/29/2024 18:00,Home,no\r\n\
+                ,,,,\r\n\
+                Short row\r\n\
+                Bad date,,31/02/2024,,yes";
+    let tasks = import(text).unwrap();
+    assert_eq!(tasks.count(), 3);
+    let root = Rc::new(RefCell::new(ToDo::new("", Weak::new())));
+    tasks.add_to(&root);
+    assert_eq!(titles(&root), ["Home", "Short row", "Bad date"]);
+
+    let home = Rc::clone(&root.borrow().sub_tasks[0]);
+    assert_eq!(titles(&home), ["Buy \"milk\", eggs"]);
+    let milk = Rc::clone(&home.borrow().sub_tasks[0]);
+    assert_eq!(milk.borrow().notes, "two\nlines");
+    assert_eq!(
+        milk.borrow().due,
+        Some(Due::DateTime(
+            day("2024-02-29").and_hms_opt(18, 0, 0).unwrap()
+        ))
+    );
+    let bad = Rc::clone(&root.borrow().sub_tasks[2]);
+    assert_eq!(bad.borrow().due, None);
+    assert!(bad.borrow().complete);
+
+    // A header without rows, and an unterminated quote
+    assert_eq!(import("Title,Due Date\n").unwrap().count(), 0);
+    let root = imported("Title\n\"Unfinished, quote\nstill quoted");
+    assert_eq!(titles(&root), ["Unfinished, quote still quoted"]);
+}
+
+#[test]
+fn due_dates() {
+    assert_eq!(parse_due("2024-02-29"), Some(Due::Date(day("2024-02-29"))));
+    assert_eq!(
+        parse_due("2024-02-29T23:59:59.500+01:00"),
+        Some(Due::DateTime(
+            day("2024-02-29")
+                .and_hms_milli_opt(23, 59, 59, 500)
+                .unwrap()
+        ))
+    );
+    for text in ["", "2023-02-29", "02/30/2024", "13/01/2024", "tomorrow"] {
+        assert_eq!(parse_due(text), None, "{:?}", text);
+    }
+}
+
+#[test]
+fn json_values() {
+    assert_eq!(
+        parse_json(" {\"a\": [true, null, -1.5e2, \"\\u00e9\\n\"]} "),
+        Ok(Json::Object(vec![(
+            String::from("a"),
+            Json::Array(vec![
+                Json::Bool(true),
+                Json::Null,
+                Json::Number(-150.0),
+                Json::String(String::from("é\n")),
+            ])
+        )]))
+    );
+}
@@ -0,0 +1,129 @@
+/// Inverted index of the words in task titles and notes, so that tasks can
+/// be found as a search is typed without reading every task at each key.
+/// The index is brought up to date from the tree before searching, reading
+/// again only the tasks which were added or changed since.
+use crate::deps;
+use crate::duplicate;
+use crate::todo::ToDo;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::rc::{Rc, Weak};
+
+/// Shortest word in a search which can match a word misspelt by a letter.
+const FUZZY_LENGTH: usize = 4;
+
+/// Index of the words in a todo list, by lower case word, with the tasks
+/// identified by their address.
+#[derive(Default)]
+pub struct Index {
+    words: BTreeMap<String, HashSet<usize>>,
+    /// Indexed tasks, with the text they were indexed with.
+    tasks: HashMap<usize, (Weak<RefCell<ToDo>>, String)>,
+}
+
+impl Index {
+    /// Index the tasks under root which are new or changed since the
+    /// last update, and forget those no longer in the tree.
+    pub fn update(&mut self, root: &Rc<RefCell<ToDo>>) {
+        let mut tasks = Vec::new();
+        deps::collect(root, &mut tasks);
+        let mut present = HashSet::with_capacity(tasks.len());
+        for task in tasks.iter().skip(1) {
+            let key = Rc::as_ptr(task) as usize;
+            present.insert(key);
+            let text = {
+                let todo = task.borrow();
+                format!("{}\n{}", todo.task, todo.notes)
+            };
+            // Holding on to the task keeps its address from being reused
+            if self.tasks.get(&key).is_some_and(|(_, old)| *old == text) {
+                continue;
+            }
+            self.remove(key);
+            for word in words(&text) {
+                self.words.entry(word).or_default().insert(key);
+            }
+            self.tasks.insert(key, (Rc::downgrade(task), text));
+        }
+
+        let gone: Vec<usize> = self
+            .tasks
+            .keys()
+            .filter(|key| !present.contains(key))
+            .copied()
+            .collect();
+        for key in gone {
+            self.remove(key);
+        }
+    }
+
+    /// Forget a task.
+    fn remove(&mut self, key: usize) {
+        if let Some((_, text)) = self.tasks.remove(&key) {
+            for word in words(&text) {
+                if let Some(keys) = self.words.get_mut(&word) {
+                    keys.remove(&key);
+                    if keys.is_empty() {
+                        self.words.remove(&word);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Tasks with a word starting with each of the words searched for, or
+    /// if none does, a word one letter different from it. Unfinished tasks
+    /// come first, then in order of their titles.
+    pub fn search(&self, query: &str) -> Vec<Rc<RefCell<ToDo>>> {
+        let mut found: Option<HashSet<usize>> = None;
+        for word in words(query) {
+            let matches = self.matching(&word);
+            found = Some(match found {
+                Some(found) => found.intersection(&matches).copied().collect(),
+                None => matches,
+            });
+        }
+
+        let mut tasks: Vec<Rc<RefCell<ToDo>>> = found
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|key| self.tasks.get(key))
+            .filter_map(|(task, _)| task.upgrade())
+            .collect();
+        tasks.sort_by_cached_key(|task| {
+            let todo = task.borrow();
+            (todo.complete, todo.task.to_lowercase())
+        });
+        tasks
+    }
+
+    /// Tasks with a word starting with a word, or else one letter away.
+    fn matching(&self, word: &str) -> HashSet<usize> {
+        let mut matches: HashSet<usize> = self
+            .words
+            .range(String::from(word)..)
+            .take_while(|(indexed, _)| indexed.starts_with(word))
+            .flat_map(|(_, keys)| keys.iter().copied())
+            .collect();
+        if matches.is_empty() && word.chars().count() >= FUZZY_LENGTH {
+            let word: Vec<char> = word.chars().collect();
+            for (indexed, keys) in self.words.iter() {
+                let indexed: Vec<char> = indexed.chars().collect();
+                if indexed.len().abs_diff(word.len()) <= 1
+                    && duplicate::distance(&word, &indexed) <= 1
+                {
+                    matches.extend(keys.iter().copied());
+                }
+            }
+        }
+        matches
+    }
+}
+
+/// Lower case words in some text, e.g. "home" and "outside" in "+home
+/// @outside".
+fn words(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+}
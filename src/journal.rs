@@ -0,0 +1,227 @@
+/// Journal of changes to the todo list, kept beside the save file so that
+/// every change is on disk as soon as it is made, without rewriting the
+/// whole list each time. Now and then the journal is compacted into the
+/// save file.
+///
+/// A journal starts with the checksum of the save file it applies to, so a
+/// journal left behind by an interrupted compaction is ignored:
+///
+/// ```text
+/// # yat journal of 1234567890
+/// @3 -1 +2
+/// [X] ( ) Call Bob completed:2024-03-01
+/// [ ] ( ) Email Carol
+/// ```
+///
+/// Each record replaces lines of the list, here line 3, with those after it.
+use crate::saver;
+use log::{info, warn};
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// Number of records after which the journal is compacted.
+const COMPACT_AFTER: usize = 200;
+
+/// Journal of a save file, along with the list as last recorded.
+pub struct Journal {
+    path: PathBuf,
+    /// Checksum of the save file the journal applies to.
+    base: u64,
+    /// Lines of the list as last recorded.
+    lines: Vec<String>,
+    /// Number of records in the journal.
+    records: usize,
+    /// Whether the journal has a record which can't be read.
+    damaged: bool,
+}
+
+impl Journal {
+    /// Open the journal of a save file, given the save file's contents,
+    /// returning it with the list as recorded in it.
+    pub fn open(save_file: &Path, base: &str) -> (Journal, String) {
+        let mut journal = Journal {
+            path: path(save_file),
+            base: checksum(base),
+            lines: base.lines().map(String::from).collect(),
+            records: 0,
+            damaged: false,
+        };
+        match fs::read_to_string(&journal.path) {
+            Ok(text) => journal.replay(&text),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => (),
+            Err(err) => warn!("Unable to read {}: {}", journal.path.display(), err),
+        }
+        let list = journal.list();
+        (journal, list)
+    }
+
+    /// Apply the records in a journal to the list, stopping at any which
+    /// can't be read, such as one cut short by a crash.
+    fn replay(&mut self, text: &str) {
+        let mut lines = text.lines();
+        match lines
+            .next()
+            .and_then(|line| line.strip_prefix("# yat journal of "))
+        {
+            Some(base) if base.parse() == Ok(self.base) => (),
+            _ => {
+                info!("Ignoring journal of an earlier save file.");
+                return;
+            }
+        }
+        while let Some(header) = lines.next() {
+            let (start, removed, added) = match parse_header(header) {
+                Some(hunk)
+                    if hunk
+                        .0
+                        .checked_add(hunk.1)
+                        .is_some_and(|end| end <= self.lines.len()) =>
+                {
+                    hunk
+                }
+                _ => {
+                    warn!("Unable to read journal record: {}", header);
+                    self.damaged = true;
+                    return;
+                }
+            };
+            let new: Vec<String> = lines.by_ref().take(added).map(String::from).collect();
+            if new.len() < added {
+                warn!("Journal record cut short.");
+                self.damaged = true;
+                return;
+            }
+            self.lines.splice(start..start + removed, new);
+            self.records += 1;
+        }
+        info!("Replayed {} journal records.", self.records);
+    }
+
+    /// The list as last recorded, in save file format.
+    fn list(&self) -> String {
+        let mut list = self.lines.join("\n");
+        if !list.is_empty() {
+            list.push('\n');
+        }
+        list
+    }
+
+    /// Whether the journal has a record which can't be read, after which
+    /// nothing more can be recorded until it is compacted.
+    pub fn damaged(&self) -> bool {
+        self.damaged
+    }
+
+    /// Whether the journal has grown enough to be compacted.
+    pub fn full(&self) -> bool {
+        self.records >= COMPACT_AFTER
+    }
+
+    /// Append a record of the lines changed since the list was last
+    /// recorded, returning whether there were any.
+    pub fn record(&mut self, list: &str) -> io::Result<bool> {
+        let lines: Vec<&str> = list.lines().collect();
+        let prefix = self
+            .lines
+            .iter()
+            .zip(lines.iter())
+            .take_while(|(a, b)| a == *b)
+            .count();
+        if prefix == lines.len() && prefix == self.lines.len() {
+            return Ok(false);
+        }
+        let suffix = self.lines[prefix..]
+            .iter()
+            .rev()
+            .zip(lines[prefix..].iter().rev())
+            .take_while(|(a, b)| a == *b)
+            .count();
+        let removed = self.lines.len() - prefix - suffix;
+        let added = &lines[prefix..lines.len() - suffix];
+
+        let mut record = String::new();
+        if self.records == 0 {
+            record.push_str(&format!("# yat journal of {}\n", self.base));
+        }
+        record.push_str(&format!("@{} -{} +{}\n", prefix, removed, added.len()));
+        for line in added.iter() {
+            record.push_str(line);
+            record.push('\n');
+        }
+
+        // A fresh journal replaces any left behind by an earlier save file
+        let mut file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .append(self.records > 0)
+            .truncate(self.records == 0)
+            .open(&self.path)?;
+        file.write_all(record.as_bytes())?;
+        file.sync_data()?;
+
+        self.lines.splice(
+            prefix..prefix + removed,
+            added.iter().map(|line| String::from(*line)),
+        );
+        self.records += 1;
+        Ok(true)
+    }
+
+    /// Write the whole list to the save file and start a new journal.
+    pub fn compact(&mut self, save_file: &Path, list: &str) -> io::Result<()> {
+        saver::write(save_file, list)?;
+        self.base = checksum(list);
+        self.lines = list.lines().map(String::from).collect();
+        self.records = 0;
+        self.damaged = false;
+        match fs::remove_file(&self.path) {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err),
+            _ => {
+                info!("Compacted journal into {}.", save_file.display());
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Location of the journal of a save file, e.g. save.journal.txt beside
+/// save.txt.
+pub fn path(save_file: &Path) -> PathBuf {
+    let stem = save_file.file_stem().map_or(String::from("save"), |stem| {
+        stem.to_string_lossy().into_owned()
+    });
+    let name = match save_file.extension() {
+        Some(extension) => format!("{}.journal.{}", stem, extension.to_string_lossy()),
+        None => format!("{}.journal", stem),
+    };
+    save_file.with_file_name(name)
+}
+
+/// Apply the journal of a save file, if any, to its contents.
+pub fn replay(save_file: &Path, base: &str) -> String {
+    Journal::open(save_file, base).1
+}
+
+/// Parse a record header, e.g. "@3 -1 +2", into (start, removed, added).
+fn parse_header(header: &str) -> Option<(usize, usize, usize)> {
+    let mut words = header.strip_prefix('@')?.split(' ');
+    let start = words.next()?.parse().ok()?;
+    let removed = words.next()?.strip_prefix('-')?.parse().ok()?;
+    let added = words.next()?.strip_prefix('+')?.parse().ok()?;
+    match words.next() {
+        Some(_) => None,
+        None => Some((start, removed, added)),
+    }
+}
+
+/// Checksum of a save file (64-bit FNV-1a), the same from one version of
+/// yat to the next.
+fn checksum(text: &str) -> u64 {
+    text.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,110 @@
+/// Tests of the journal's records as written to disk, and of replaying
+/// them, including journals cut short or left behind by an earlier save.
+use super::{checksum, parse_header, path, replay, Journal};
+use std::fs;
+use std::path::Path;
+
+const LIST: &str = "\
+[ ] ( ) Call Bob
+[ ] ( ) Email Carol
+[ ] ( ) Water plants
+";
+
+#[test]
+fn journal_beside_the_save_file() {
+    assert_eq!(
+        path(Path::new("/home/me/.todo/save.txt")),
+        Path::new("/home/me/.todo/save.journal.txt")
+    );
+    assert_eq!(path(Path::new("todo")), Path::new("todo.journal"));
+    assert_eq!(path(Path::new("a.b.txt")), Path::new("a.b.journal.txt"));
+}
+
+#[test]
+fn record_headers() {
+    assert_eq!(parse_header("@3 -1 +2"), Some((3, 1, 2)));
+    assert_eq!(parse_header("@0 -0 +0"), Some((0, 0, 0)));
+    for header in [
+        "",
+        "3 -1 +2",
+        "@3 1 +2",
+        "@3 -1 2",
+        "@3 -1",
+        "@3 -1 +2 x",
+        "@-1 -1 +2",
+        "@3  -1 +2",
+    ] {
+        assert_eq!(parse_header(header), None, "{:?}", header);
+    }
+}
+
+#[test]
+fn records_written_and_replayed() {
+    let dir = tempfile::tempdir().unwrap();
+    let save_file = dir.path().join("save.txt");
+    fs::write(&save_file, LIST).unwrap();
+
+    let (mut journal, list) = Journal::open(&save_file, LIST);
+    assert_eq!(list, LIST);
+    assert!(!journal.record(LIST).unwrap());
+    assert!(!path(&save_file).exists());
+
+    let completed = LIST.replace("[ ] ( ) Email", "[X] ( ) Email");
+    assert!(journal.record(&completed).unwrap());
+    let added = format!("{}[ ] ( ) Book the dentist\n", completed);
+    assert!(journal.record(&added).unwrap());
+    let removed = added.replace("[ ] ( ) Call Bob\n", "");
+    assert!(journal.record(&removed).unwrap());
+    assert_eq!(
+        fs::read_to_string(path(&save_file)).unwrap(),
+        format!(
+            "# yat journal of {}\n\
+             @1 -1 +1\n\
+             [X] ( ) Email Carol\n\
+             @3 -0 +1\n\
+             [ ] ( ) Book the dentist\n\
+             @0 -1 +0\n",
+            checksum(LIST)
+        )
+    );
+    assert_eq!(replay(&save_file, LIST), removed);
+
+    // Compacted into the save file, with a fresh journal after
+    journal.compact(&save_file, &removed).unwrap();
+    assert_eq!(fs::read_to_string(&save_file).unwrap(), removed);
+    assert!(!path(&save_file).exists());
+    assert!(journal.record("").unwrap());
+    assert_eq!(
+        fs::read_to_string(path(&save_file)).unwrap(),
+        format!("# yat journal of {}\n@0 -3 +0\n", checksum(&removed))
+    );
+    assert_eq!(replay(&save_file, &removed), "");
+}
+
+#[test]
+fn journal_of_an_earlier_save_file_ignored() {
+    let dir = tempfile::tempdir().unwrap();
+    let save_file = dir.path().join("save.txt");
+    let journal = format!("# yat journal of {}\n@0 -1 +0\n", checksum("other"));
+    fs::write(path(&save_file), journal).unwrap();
+    let (journal, list) = Journal::open(&save_file, LIST);
+    assert_eq!(list, LIST);
+    assert!(!journal.damaged());
+}
+
+#[test]
+fn damaged_journal_replayed_up_to_the_damage() {
+    let dir = tempfile::tempdir().unwrap();
+    let save_file = dir.path().join("save.txt");
+    let header = format!(
+        "# yat journal of {}\n@0 -1 +1\n[X] ( ) Call Bob\n",
+        checksum(LIST)
+    );
+    let completed = LIST.replace("[ ] ( ) Call", "[X] ( ) Call");
+    for damage in ["@1 -1 +2\n[X] ( ) Email Carol\n", "@2 -5 +0\n", "@x\n"] {
+        fs::write(path(&save_file), format!("{}{}", header, damage)).unwrap();
+        let (journal, list) = Journal::open(&save_file, LIST);
+        assert_eq!(list, completed, "{:?}", damage);
+        assert!(journal.damaged(), "{:?}", damage);
+    }
+}
@@ -0,0 +1,129 @@
+/// Layout of the main screen: where the task panels go, the space inside
+/// them, and how text too long for its space is cut off.
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+/// Columns taken by a panel's border.
+pub const BORDER: usize = 1;
+
+/// Columns at the left of a task panel, inside its border, for the
+/// selection marker and the mark on marked tasks.
+pub const GUTTER: usize = 2;
+
+/// Marker for text which has been cut off, by default.
+pub const ELLIPSIS: &str = "...";
+
+/// Where the marker goes in text which has been cut off.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Placement {
+    /// Keeping the start of the text.
+    End,
+    /// Keeping the start and the end.
+    Middle,
+    /// Keeping the end of the text.
+    Start,
+}
+
+impl Placement {
+    /// Parse a placement from config.toml format.
+    pub fn parse(text: &str) -> Option<Placement> {
+        match text {
+            "end" => Some(Placement::End),
+            "middle" => Some(Placement::Middle),
+            "start" => Some(Placement::Start),
+            _ => None,
+        }
+    }
+}
+
+/// Space for text in a panel: the column it starts at and its width.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Pane {
+    pub x: usize,
+    pub width: usize,
+}
+
+/// The left (tasks) and right (sub-tasks or details) panels of a screen
+/// xmax columns wide, leaving a margin inside each on both sides.
+pub fn task_panes(xmax: usize, margin: usize) -> (Pane, Pane) {
+    let half = xmax / 2;
+    let width = half.saturating_sub(BORDER * 2 + GUTTER + margin * 2);
+    let left = Pane {
+        x: BORDER + GUTTER + margin,
+        width,
+    };
+    let right = Pane {
+        x: half + BORDER + GUTTER + margin,
+        width,
+    };
+    (left, right)
+}
+
+/// Space for text in a panel spanning a screen xmax columns wide, such as
+/// the selection, after its border and a blank column.
+pub fn line(xmax: usize, margin: usize) -> Pane {
+    Pane {
+        x: BORDER + 1 + margin,
+        width: xmax.saturating_sub(BORDER * 2 + 1 + margin * 2),
+    }
+}
+
+/// Cut text down to fit in width columns, with a marker where it was cut
+/// off. Room for the marker is kept whenever the text reaches it, so text
+/// only just fitting is also cut off.
+pub fn truncate(text: &str, width: usize, ellipsis: &str, placement: Placement) -> String {
+    let len = UnicodeWidthStr::width(text);
+    let marker = UnicodeWidthStr::width(ellipsis);
+    if len + marker <= width {
+        return String::from(text);
+    }
+    if width <= marker {
+        return head(text, width).0;
+    }
+    let room = width - marker;
+    match placement {
+        Placement::End => {
+            let (head, used) = head(text, room);
+            format!("{}{}{}", head, " ".repeat(room - used), ellipsis)
+        }
+        Placement::Start => {
+            let (tail, used) = tail(text, room);
+            format!("{}{}{}", ellipsis, " ".repeat(room - used), tail)
+        }
+        Placement::Middle => {
+            let (head, head_used) = head(text, room - room / 2);
+            let (tail, tail_used) = tail(text, room / 2);
+            let gap = " ".repeat(room - head_used - tail_used);
+            format!("{}{}{}{}", head, ellipsis, gap, tail)
+        }
+    }
+}
+
+/// The start of some text, up to width columns, and the columns it takes.
+fn head(text: &str, width: usize) -> (String, usize) {
+    let mut used = 0;
+    let mut head = String::new();
+    for ch in text.chars() {
+        let w = ch.width().unwrap_or(0);
+        if used + w > width {
+            break;
+        }
+        head.push(ch);
+        used += w;
+    }
+    (head, used)
+}
+
+/// The end of some text, up to width columns, and the columns it takes.
+fn tail(text: &str, width: usize) -> (String, usize) {
+    let mut used = 0;
+    let mut start = text.len();
+    for (i, ch) in text.char_indices().rev() {
+        let w = ch.width().unwrap_or(0);
+        if used + w > width {
+            break;
+        }
+        start = i;
+        used += w;
+    }
+    (String::from(&text[start..]), used)
+}
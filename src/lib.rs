@@ -1,74 +1,390 @@
 /// Functionality for creating todo list using terminal user interface.
+pub mod agenda;
+mod archive;
+pub mod cli;
+mod column;
+mod command;
 pub mod config;
+mod daemon;
+mod deps;
+pub mod digest;
+mod doctor;
+mod due;
+mod duplicate;
+mod effort;
+mod email;
+pub mod error;
+mod escalate;
+mod export;
+mod habitica;
+mod heatmap;
+mod history;
+mod import;
+mod index;
+mod journal;
+mod layout;
+mod link;
+mod locale;
 pub mod logger;
+mod nesting;
+mod next;
+mod pipeline;
+mod recur;
+mod review;
+mod saver;
+mod snippet;
+mod spelling;
+mod storage;
+mod template;
+mod theme;
 mod todo;
+mod trash;
 mod tui;
+mod webhook;
+pub mod wizard;
+mod worker;
 
-use dirs::home_dir;
+use chrono::{Datelike, Local, NaiveDate, NaiveDateTime, Timelike};
+use column::{Column, ColumnKind};
+use config::ColourChoice;
+use due::Due;
+use error::Error;
+use history::{History, Step};
+use journal::Journal;
+use locale::Locale;
 use log::{info, warn};
+use saver::Saver;
+use spelling::Dictionary;
 use std::cell::RefCell;
-use std::env::Args;
-use std::fs::{create_dir, metadata, File};
-use std::io::{self, Read};
-use std::path::PathBuf;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeSet, HashSet};
+use std::env;
+use std::fs::{self, create_dir_all, metadata};
+use std::hash::{Hash, Hasher};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process::Command;
 use std::rc::{Rc, Weak};
 use std::str::Lines;
+use std::time::{Duration, Instant};
+use storage::Storage;
+use termion::color;
 use termion::event::Key;
-use todo::{Priority, ToDo};
+use todo::{Defaults, Priority, Stamp, ToDo};
 use tui::Window;
 use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
 
-/// Check if save file exists.
-pub fn look_for_save(mut args: Args) -> Result<PathBuf, ()> {
-    args.next();
-
-    match args.next() {
-        Some(arg) => {
-            let filename = PathBuf::from(&arg);
-            match metadata(&filename) {
-                Ok(_) => Ok(filename),
-                Err(err) => {
-                    warn!("Provided save file does not exist: {}", err);
-                    Err(())
-                }
+/// Check if save file exists, either as given on the command line or at the
+/// configured location.
+pub fn look_for_save(
+    save_file: Option<PathBuf>,
+    config: &config::Config,
+) -> Result<PathBuf, Error> {
+    match save_file {
+        Some(filename) => match metadata(&filename) {
+            Ok(_) => Ok(filename),
+            Err(err) => {
+                warn!("Provided save file does not exist: {}", err);
+                Err(Error::NoSaveFile(filename))
             }
-        }
+        },
         None => {
-            let mut filename = match home_dir() {
-                Some(dir) => dir,
+            let filename = match config.save_file_path() {
+                Some(filename) => filename,
                 None => {
                     warn!("Unable to find home directory.");
-                    return Err(());
+                    return Err(Error::NoHome);
                 }
             };
-            filename.push(".todo");
+
+            if let Some(dir) = filename.parent() {
+                if metadata(dir).is_err() {
+                    create_dir_all(dir).unwrap_or_else(|err| {
+                        warn!("Unable to create directory {}: {}", dir.display(), err);
+                    });
+                    info!("Created {} directory.", dir.display());
+                    return Err(Error::NoSaveFile(filename));
+                }
+            }
 
             match metadata(&filename) {
                 Ok(_) => {
-                    filename.push("save.txt");
-                    match metadata(&filename) {
-                        Ok(_) => {
-                            info!("Found save file.");
-                            Ok(filename)
-                        }
-                        Err(err) => {
-                            warn!("$HOME/.todo/save.txt does not exist: {}", err);
-                            Err(())
-                        }
-                    }
+                    info!("Found save file.");
+                    Ok(filename)
                 }
-                Err(_) => {
-                    create_dir(filename).unwrap_or_else(|err| {
-                        warn!("Unable to create directory ~/.todo: {}", err);
-                    });
-                    info!("Created $HOME/.todo directory.");
-                    Err(())
+                Err(err) => {
+                    warn!("{} does not exist: {}", filename.display(), err);
+                    Err(Error::NoSaveFile(filename))
                 }
             }
         }
     }
 }
 
+/// Load a todo list without starting the interface, returning its root
+/// task and the name of the list.
+fn load_list(
+    save_file: Option<PathBuf>,
+    config: &config::Config,
+) -> Result<(Rc<RefCell<ToDo>>, String), Error> {
+    let (buf, list) = match remote_save(&save_file, config) {
+        Some(mut storage) => match storage.load()? {
+            Some(buf) => (buf, storage.describe()),
+            None => (String::new(), storage.describe()),
+        },
+        None => {
+            let filename = look_for_save(save_file, config)?;
+            let list = filename
+                .file_name()
+                .map_or(String::new(), |name| name.to_string_lossy().into_owned());
+            let buf = View::load(filename.clone())?;
+            match config.journal {
+                true => (journal::replay(&filename, &buf), list),
+                false => (buf, list),
+            }
+        }
+    };
+    let root = parse_list(&buf)?;
+    Ok((root, list))
+}
+
+/// Print the agenda of a todo list to stdout, for the day or week ahead.
+pub fn print_agenda(
+    save_file: Option<PathBuf>,
+    config: &config::Config,
+    span: agenda::Span,
+) -> Result<(), Error> {
+    let (root, list) = load_list(save_file, config)?;
+    let today = Local::now().date_naive();
+    let locale = Locale::new(config);
+    let week_numbers = config.week_numbers;
+    let text = agenda::agenda(&root, span, today, &list, &locale, week_numbers);
+    io::stdout().write_all(text.as_bytes())?;
+    Ok(())
+}
+
+/// Compose a digest of the tasks due today and overdue, and print it or
+/// send it on. Nothing is sent when nothing is due.
+pub fn send_digest(
+    save_file: Option<PathBuf>,
+    config: &config::Config,
+    delivery: digest::Delivery,
+) -> Result<(), Error> {
+    let (root, list) = load_list(save_file, config)?;
+    let locale = Locale::new(config);
+    let digest = digest::digest(&root, Local::now().date_naive(), &locale);
+    if digest.empty && delivery != digest::Delivery::Print {
+        info!("Nothing due today in {}, so no digest sent.", list);
+        return Ok(());
+    }
+
+    match delivery {
+        digest::Delivery::Print => {
+            let mut text = format!("{}\n", digest.summary);
+            if !digest.empty {
+                text = format!("{}\n{}\n", text, digest.body);
+            }
+            io::stdout().write_all(text.as_bytes())?;
+            Ok(())
+        }
+        digest::Delivery::Notify => {
+            let status = Command::new("notify-send")
+                .args(["--app-name", "yat", &digest.summary, &digest.body])
+                .status();
+            match status {
+                Ok(status) if status.success() => Ok(()),
+                Ok(status) => Err(Error::Delivery(format!("notify-send failed: {}", status))),
+                Err(err) => Err(Error::Delivery(format!(
+                    "Unable to run notify-send: {}",
+                    err
+                ))),
+            }
+        }
+        digest::Delivery::Email => {
+            let email = config.email.ok_or(Error::Config(
+                "No [email] section in config.toml to send the digest with",
+            ))?;
+            let subject = format!("{} in {}", digest.summary, list);
+            let message = email::compose(email, &email.to, &subject, &digest.body);
+            email::send(email, &email.to, &message)
+                .map_err(|err| Error::Delivery(format!("Unable to send digest: {}", err)))
+        }
+    }
+}
+
+/// Check the save file for problems, print them, and offer to repair those
+/// which can be safely fixed, keeping a backup of the original. Returns
+/// whether the save file is healthy afterwards.
+pub fn check_save(save_file: Option<PathBuf>, config: &config::Config) -> Result<bool, Error> {
+    let filename = look_for_save(save_file, config)?;
+    let bytes = match fs::read(&filename) {
+        Ok(bytes) => bytes,
+        Err(err) => return Err(Error::Read(filename, err)),
+    };
+    let report = doctor::check(&bytes);
+    println!("Checked {}", filename.display());
+    if report.problems.is_empty() {
+        println!("No problems found");
+        return Ok(true);
+    }
+
+    for problem in report.problems.iter() {
+        let place = match problem.line {
+            Some(line) => format!("line {}: ", line),
+            None => String::new(),
+        };
+        let note = match problem.fixable {
+            true => "",
+            false => " (needs fixing by hand)",
+        };
+        println!("  {}{}{}", place, problem.description, note);
+    }
+    if let Err(err) = parse_list(&report.repaired) {
+        let reason = match err {
+            Error::Parse(reason) => reason.to_string(),
+            err => err.to_string(),
+        };
+        println!("The list still can't be loaded once repaired: {}", reason);
+        return Ok(false);
+    }
+
+    let fixable = report.fixable();
+    if fixable == 0 {
+        return Ok(false);
+    }
+    print!(
+        "Repair {} of {} problems? [y/N] ",
+        fixable,
+        report.problems.len()
+    );
+    io::stdout().flush()?;
+    let mut answer = String::new();
+    if io::stdin().read_line(&mut answer)? == 0 {
+        println!();
+    }
+    if !answer.trim().eq_ignore_ascii_case("y") {
+        return Ok(false);
+    }
+
+    let mut backup = filename.clone().into_os_string();
+    backup.push(".bak");
+    let backup = PathBuf::from(backup);
+    if let Err(err) = fs::write(&backup, &bytes) {
+        return Err(Error::Write(backup, err));
+    }
+    if let Err(err) = fs::write(&filename, &report.repaired) {
+        return Err(Error::Write(filename, err));
+    }
+    println!("Repaired, with the original kept as {}", backup.display());
+    Ok(fixable == report.problems.len())
+}
+
+/// Import the tasks in a file exported from Apple Reminders or Things 3
+/// into the todo list, with each list or project as a new top-level task.
+/// Returns the number of tasks imported.
+pub fn import_tasks(
+    export: &Path,
+    save_file: Option<PathBuf>,
+    config: &config::Config,
+) -> Result<usize, Error> {
+    let text = fs::read(export).map_err(|err| Error::Read(export.to_path_buf(), err))?;
+    let imported = import::import(&String::from_utf8_lossy(&text))
+        .map_err(|err| Error::Import(export.to_path_buf(), err))?;
+    nesting::check_depth(imported.height(), config.max_depth)
+        .map_err(|err| Error::Import(export.to_path_buf(), err))?;
+    let count = imported.count();
+    update_save(save_file, config, |root| imported.add_to(root))?;
+    Ok(count)
+}
+
+/// Add a task given on the command line, e.g. from a desktop hotkey, to the
+/// save file, or with to_daemon, to the yat running as a daemon with it so
+/// it shows up straight away.
+pub fn quick_add(
+    task: &str,
+    save_file: Option<PathBuf>,
+    config: &config::Config,
+    to_daemon: bool,
+) -> Result<(), Error> {
+    if to_daemon {
+        let socket = match remote_save(&save_file, config) {
+            Some(storage) => storage.cache_path(),
+            None => save_file.or_else(|| config.save_file_path()),
+        };
+        return daemon::send(&socket.ok_or(Error::NoHome)?, task);
+    }
+    update_save(save_file, config, |root| {
+        for line in task.lines().map(str::trim).filter(|line| !line.is_empty()) {
+            let todo = new_task(line, root);
+            root.borrow_mut()
+                .sub_tasks
+                .push(Rc::new(RefCell::new(todo)));
+        }
+    })
+}
+
+/// Load the todo list, either as given on the command line or at the
+/// configured location, change it and save it again, without starting the
+/// interface.
+fn update_save(
+    save_file: Option<PathBuf>,
+    config: &config::Config,
+    update: impl FnOnce(&Rc<RefCell<ToDo>>),
+) -> Result<(), Error> {
+    let mut remote = remote_save(&save_file, config);
+    let existing = match (&mut remote, &save_file) {
+        (Some(storage), _) => storage.load()?,
+        (None, Some(filename)) => fs::read_to_string(filename).ok(),
+        (None, None) => config
+            .save_file_path()
+            .and_then(|filename| fs::read_to_string(filename).ok()),
+    };
+    let root = parse_list(&existing.unwrap_or_default())?;
+    update(&root);
+
+    let buffer = root.borrow().save_string();
+    match remote {
+        Some(mut storage) => storage.save(&buffer, &worker::Progress::new())?,
+        None => {
+            let filename = save_file
+                .or_else(|| config.save_file_path())
+                .ok_or(Error::NoHome)?;
+            if let Err(err) = fs::write(&filename, buffer) {
+                return Err(Error::Write(filename, err));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Check if the save file, either as given on the command line or at the
+/// configured location, is kept on a remote server.
+pub fn remote_save(
+    save_file: &Option<PathBuf>,
+    config: &config::Config,
+) -> Option<Box<dyn Storage>> {
+    let location = match (save_file, config.storage) {
+        (Some(filename), _) => filename.to_str()?,
+        (None, Some(storage)) => return storage::from_config(storage),
+        (None, None) => config.save_file?,
+    };
+    storage::remote(location)
+}
+
+/// Number of occurrences of a recurring task coming up which can be chosen
+/// to skip.
+const SKIP_CHOICES: usize = 6;
+
+/// Number of pasted lines turned into tasks between updates of the
+/// progress shown.
+const PASTE_CHUNK: usize = 50;
+
+/// How often the progress of an operation on a worker thread is redrawn.
+const WORKER_REDRAW: Duration = Duration::from_millis(100);
+
+/// Frames of the spinner shown while an operation on a worker thread runs.
+const SPINNER: [char; 4] = ['|', '/', '-', '\\'];
+
 /// Wrapper around the terminal user interface (Window) and the todo list
 /// tree structure (ToDo).
 pub struct View<'a> {
@@ -78,157 +394,374 @@ pub struct View<'a> {
     root: bool,
     quit: bool,
     save_file: Option<PathBuf>,
+    read_only: bool,
+    /// Remote location of the todo list, if not a local file.
+    remote: Option<Box<dyn Storage>>,
+    /// Whether the remote todo list couldn't be downloaded, so saves only
+    /// go to the local copy.
+    offline: bool,
+    status: Option<String>,
+    /// Whether a countdown is displayed, which needs updating every second.
+    countdown: bool,
+    /// Ids of the unfinished tasks as of the last redraw, which block any
+    /// tasks waiting on them.
+    unfinished: HashSet<u32>,
+    /// Whether details of the selected task are shown in place of its
+    /// sub-tasks.
+    details: bool,
+    /// Active context (e.g. "@phone"), if only tasks in it are shown.
+    context: Option<String>,
+    /// Whether tasks which haven't started yet are shown (dimmed).
+    show_upcoming: bool,
+    /// What the shown tasks are grouped under headers by, if anything.
+    group: Option<command::GroupKey>,
+    /// Whether task ages are shown in place of due dates.
+    show_age: bool,
+    /// Whether the status bar is flashing, after a task falls due.
+    flash: bool,
+    /// When due tasks were last checked for.
+    last_checked: NaiveDateTime,
+    /// Position in the tree of a task to jump to, with the message to
+    /// announce on arrival.
+    jump: Option<(Vec<usize>, String)>,
+    /// Number of times to repeat the next command, as typed so far.
+    count: usize,
+    /// Tasks marked for changing several at once.
+    marked: Vec<Rc<RefCell<ToDo>>>,
+    /// Deleted tasks which can still be restored.
+    trash: Vec<trash::Trashed>,
+    /// Words in the tasks, for finding them as a search is typed.
+    index: index::Index,
+    /// Task picked up to move under another task.
+    moving: Option<Rc<RefCell<ToDo>>>,
+    /// Snippet of the task cut or copied last, for pasting.
+    clipboard: Option<String>,
+    /// Task cut last, until pasted back into the list as it was, keeping
+    /// its ids and blockers.
+    cut: Option<Rc<RefCell<ToDo>>>,
+    /// Word list for spell checking tasks as they are typed.
+    dictionary: Option<Dictionary>,
+    /// Language of the interface.
+    locale: Locale,
+    /// Hash of the list as last loaded or saved, to tell whether there are
+    /// unsaved changes.
+    saved: u64,
+    /// Thread writing the list to the save file in the background.
+    saver: Saver,
+    /// Journal recording each change, if kept.
+    journal: Option<Journal>,
+    /// Changes to the list which can be undone and redone.
+    history: History,
+    /// Socket tasks are sent to with yat add --daemon, if running as a
+    /// daemon.
+    listener: Option<daemon::Listener>,
 }
 
 impl<'a> View<'a> {
     /// Create view of a new todo list.
-    pub fn new(config: config::Config<'a>) -> Result<View<'a>, ()> {
-        let root = ToDo::new("", Weak::new());
-        let stdin = io::stdin();
+    pub fn new(config: config::Config<'a>) -> Result<View<'a>, Error> {
         let stdout = io::stdout();
-        let mut window = Window::new(stdin, stdout, config)?;
-        window.colour_off();
+        let window = Window::new(stdout, config)?;
+        Ok(Self::with_window(window))
+    }
 
-        info!("Created new View.");
-        Ok(View {
-            window,
-            current_task: Rc::new(RefCell::new(root)),
-            selection: None,
-            root: true,
-            quit: false,
-            save_file: None,
-        })
+    /// Create view of a todo list, given in save file format, drawn in
+    /// memory at a fixed size (rows, columns) rather than on the terminal.
+    pub fn new_headless(
+        config: config::Config<'a>,
+        dimensions: (usize, usize),
+        buf: &str,
+    ) -> View<'a> {
+        let mut view = Self::with_window(Window::headless(config, dimensions));
+        view.fill_from_buffer(buf);
+
+        info!("Created new headless View.");
+        view
     }
 
-    /// Create view of a todo list loaded from save file.
-    pub fn new_from_save(filename: PathBuf, config: config::Config<'a>) -> Result<View<'a>, ()> {
+    /// Draw the todo list, returning the text displayed if the view is
+    /// headless.
+    pub fn render(&mut self) -> Option<String> {
+        self.list_tasks();
+        self.window.contents()
+    }
+
+    /// Create view of a new todo list in a window.
+    fn with_window(mut window: Window<'a>) -> View<'a> {
         let root = ToDo::new("", Weak::new());
-        let stdin = io::stdin();
-        let stdout = io::stdout();
-        let mut window = Window::new(stdin, stdout, config)?;
         window.colour_off();
+        let show_upcoming = window.config.dim_upcoming;
+        let dictionary = window
+            .config
+            .spell_check_path()
+            .and_then(|path| Dictionary::load(&path));
+        let locale = Locale::new(&window.config);
 
-        let mut view = View {
+        info!("Created new View.");
+        View {
             window,
             current_task: Rc::new(RefCell::new(root)),
             selection: None,
             root: true,
             quit: false,
-            save_file: Some(filename.clone()),
-        };
+            save_file: None,
+            read_only: false,
+            remote: None,
+            offline: false,
+            status: None,
+            countdown: false,
+            unfinished: HashSet::new(),
+            details: false,
+            context: None,
+            show_upcoming,
+            group: None,
+            show_age: false,
+            flash: false,
+            last_checked: Local::now().naive_local(),
+            jump: None,
+            count: 0,
+            marked: Vec::new(),
+            trash: Vec::new(),
+            index: index::Index::default(),
+            moving: None,
+            clipboard: None,
+            cut: None,
+            dictionary,
+            locale,
+            saved: hash(""),
+            saver: Saver::new(),
+            journal: None,
+            history: History::default(),
+            listener: None,
+        }
+    }
 
-        let proot = Rc::clone(&view.current_task);
-        if let Ok(buf) = Self::load(filename) {
-            match view.fill_children(&mut buf.lines(), 0) {
-                Ok(()) => {
-                    view.current_task = proot;
-                }
-                Err(err) => {
-                    warn!("Unable to parse save file: {}", err);
-                    let new_root = ToDo::new("", Weak::new());
-                    view.current_task = Rc::new(RefCell::new(new_root));
+    /// Create view of a todo list loaded from save file.
+    pub fn new_from_save(filename: PathBuf, config: config::Config<'a>) -> Result<View<'a>, Error> {
+        let mut view = Self::new(config)?;
+        view.save_file = Some(filename.clone());
+        match view.window.config.journal {
+            true => view.open_journal(&filename),
+            false => {
+                if let Ok(buf) = Self::load(filename) {
+                    view.fill_from_buffer(&buf);
                 }
             }
-        };
+        }
+        view.auto_archive();
+        view.load_trash();
 
         info!("Created new View from save file.");
         Ok(view)
     }
 
-    /// Load save file into string buffer.
-    fn load(filename: PathBuf) -> Result<String, ()> {
-        let mut file = match File::open(filename) {
-            Ok(f) => f,
-            Err(_) => {
-                warn!("Unable to load file.");
-                return Err(());
+    /// Create view of a todo list kept on a remote server, falling back to
+    /// the local copy if it can't be downloaded.
+    pub fn new_from_remote(
+        mut storage: Box<dyn Storage>,
+        config: config::Config<'a>,
+    ) -> Result<View<'a>, Error> {
+        let mut view = Self::new(config)?;
+        let location = storage.describe();
+        match storage.load() {
+            Ok(Some(buf)) => {
+                storage::write_cache(storage.as_ref(), &buf);
+                view.fill_from_buffer(&buf);
+                view.announce(&format!("Loaded {}", location));
             }
-        };
-
-        let mut buffer = String::new();
-        match file.read_to_string(&mut buffer) {
-            Ok(_) => Ok(buffer),
+            Ok(None) => view.announce(&format!("New todo list at {}", location)),
             Err(_) => {
-                warn!("Unable to read from save file.");
-                Err(())
+                view.offline = true;
+                match storage::read_cache(storage.as_ref()) {
+                    Some(buf) => {
+                        view.fill_from_buffer(&buf);
+                        view.announce(&format!("Unable to reach {}: using local copy", location));
+                    }
+                    None => view.announce(&format!("Unable to reach {}", location)),
+                }
             }
         }
+        view.remote = Some(storage);
+
+        info!("Created new View from remote save file.");
+        Ok(view)
+    }
+
+    /// Create view of the demo todo list, which is never saved.
+    pub fn new_demo(config: config::Config<'a>) -> Result<View<'a>, Error> {
+        let mut view = Self::new(config)?;
+        view.read_only = true;
+        view.fill_from_buffer(include_str!("demo.txt"));
+
+        info!("Created new View of demo list.");
+        Ok(view)
+    }
+
+    /// Load a save file with the changes recorded in its journal since it
+    /// was last compacted, compacting them into it straight away if the
+    /// journal was cut short, so new records aren't lost after the damage.
+    fn open_journal(&mut self, filename: &Path) {
+        let base = Self::load(filename.to_path_buf()).unwrap_or_default();
+        let (journal, buf) = Journal::open(filename, &base);
+        let damaged = journal.damaged();
+        self.journal = Some(journal);
+        self.fill_from_buffer(&buf);
+        if damaged {
+            self.write_save(filename.to_path_buf());
+        }
     }
 
-    /// Parse save file and load into todo list tree structure.
-    fn fill_children(&mut self, buf: &mut Lines, tabs: usize) -> Result<(), &'static str> {
-        // Parse save file line by line
-        if let Some(line) = buf.next() {
-            // Use indentation to determine where to insert each task. If
-            // indentation is the same as the previous line then we continue
-            // adding sub-tasks to the current line.
-            let num_tabs = tab_num(&line);
-            let current = Rc::clone(&self.current_task);
-            if num_tabs == tabs + 1 {
-                // If indentation is increased compared to the previous line,
-                // then the previously added sub-task is the new current task
-                let n = self.current_task.borrow().sub_tasks.len();
-                if n == 0 {
-                    return Err("Can't have child without parent.");
+    /// Record any change to the list in the journal, if kept, compacting
+    /// the journal into the save file once it has grown.
+    fn record_change(&mut self) {
+        let buffer = match &self.journal {
+            Some(_) => self.root_task().borrow().save_string(),
+            None => return,
+        };
+        let (result, full) = match &mut self.journal {
+            Some(journal) => (journal.record(&buffer), journal.full()),
+            None => return,
+        };
+        match result {
+            Ok(true) => {
+                self.saved = hash(&buffer);
+                if full {
+                    if let Some(filename) = self.save_file.clone() {
+                        self.write_save(filename);
+                    }
                 }
-                let new_current = &current.borrow().sub_tasks[n - 1];
-                self.current_task = Rc::clone(&new_current);
-            } else if num_tabs < tabs {
-                // If indentation is decreased compared to the previous line,
-                // then the parent (or an even earlier ancestor) of the
-                // previous task is the new current task
-                self.ancestor(tabs - num_tabs);
-            } else if num_tabs > tabs + 1 {
-                return Err("Too much indentation.");
             }
+            Ok(false) => (),
+            Err(err) => {
+                warn!("Unable to write to journal: {}", err);
+                self.announce(&format!("Unable to write to journal: {}", err));
+            }
+        }
+    }
 
-            self.add_task_from_string(line.trim_start());
-
-            // Continue onto next line
-            self.fill_children(buf, num_tabs)?;
+    /// Load save file into string buffer.
+    fn load(filename: PathBuf) -> Result<String, Error> {
+        match fs::read_to_string(&filename) {
+            Ok(buffer) => Ok(buffer),
+            Err(err) => {
+                warn!("Unable to read from save file: {}", err);
+                Err(Error::Read(filename, err))
+            }
         }
-        Ok(())
     }
 
-    /// Move current task to parent task, if it exists.
-    fn ancestor(&mut self, level: usize) {
-        let current = Rc::clone(&self.current_task);
-        let pparent = &current.borrow().parent;
-        if level > 0 {
-            if let Some(parent) = pparent.upgrade() {
-                self.current_task = Rc::clone(&parent);
-                self.ancestor(level - 1);
+    /// Parse buffer in save file format, replacing the todo list tree. The
+    /// selected task stays selected if it is still in the list, found by
+    /// its id or otherwise its text.
+    fn fill_from_buffer(&mut self, buf: &str) {
+        let previous = self.selected_task().map(|task| {
+            let task = task.borrow();
+            (task.id, task.task.clone())
+        });
+        match parse_list_to_depth(buf, self.unread_depth()) {
+            Ok(root) => {
+                let found = previous.and_then(|(id, text)| {
+                    root.borrow().sub_tasks.iter().position(|task| {
+                        let task = task.borrow();
+                        match id {
+                            Some(id) => task.id == Some(id),
+                            None => task.task == text,
+                        }
+                    })
+                });
+                self.selection = found.or(root.borrow().sub_tasks.len().checked_sub(1));
+                self.current_task = root;
+                self.saved = self.list_hash();
+            }
+            Err(err) => {
+                warn!("Unable to parse save file: {}", err);
+                let new_root = ToDo::new("", Weak::new());
+                self.current_task = Rc::new(RefCell::new(new_root));
             }
         }
+        if self.window.config.auto_rollover {
+            self.rollover();
+        }
+        let raised = self.escalate();
+        if !raised.is_empty() {
+            self.announce(&format!("Priority raised: {}", raised.join(", ")));
+        }
+    }
+
+    /// Depth of tasks left unread until focused on, at least two so that
+    /// the tasks and sub-tasks panels are always filled.
+    fn unread_depth(&self) -> usize {
+        match self.window.config.lazy_depth {
+            0 => usize::MAX,
+            depth => depth.max(2),
+        }
+    }
+
+    /// Read the tasks left unread under a task and under each of its
+    /// sub-tasks, before focusing on it, returning false if they can't be.
+    fn read_subtree(&mut self, task: &Rc<RefCell<ToDo>>) -> bool {
+        let depth = self.unread_depth();
+        let mut result = read_unread(task, depth);
+        if result.is_ok() {
+            let sub_tasks = task.borrow().sub_tasks.clone();
+            result = sub_tasks
+                .iter()
+                .try_for_each(|sub_task| read_unread(sub_task, depth));
+        }
+        if let Err(err) = result {
+            warn!("Unable to read tasks under {}: {}", task.borrow().task, err);
+            self.announce(&format!(
+                "Unable to read tasks under {}: {}",
+                task.borrow().task,
+                err
+            ));
+            return false;
+        }
+        true
     }
 
     /// Game loop for user interaction and display.
     pub fn run(&mut self) {
         loop {
+            if self.jump.is_some() && !self.follow_jump() {
+                break;
+            }
+            self.record_change();
             self.list_tasks();
-            match self.window.getch() {
-                Some(key) if key == self.window.config.quit => {
-                    self.quit = true;
+            let key = match (self.tick(), &self.listener) {
+                (timeout, Some(listener)) => self.window.getch_or_wake(timeout, listener.fd()),
+                (Some(timeout), None) => self.window.getch_timeout(timeout),
+                (None, None) => self.window.getch(),
+            };
+            self.flash = false;
+
+            // No key means the display just needs updating
+            if key.is_some() {
+                self.status = None;
+            }
+            self.check_due();
+            self.check_saves();
+            self.receive_tasks();
+
+            // Digits build up a count of times to repeat the next command
+            if let Some(Key::Char(ch)) = key {
+                if self.add_to_count(ch) {
+                    continue;
                 }
-                Some(key) if key == self.window.config.back => match self.root {
-                    true => (),
-                    false => break,
-                },
-                Some(key) if key == self.window.config.save => self.save(),
-                Some(key) if key == self.window.config.add => self.add_task_from_input(),
-                Some(key) if key == self.window.config.edit => self.edit_task(),
-                Some(key) if key == self.window.config.delete => self.remove_task(),
-                Some(key) if key == self.window.config.task_up => self.move_task(true),
-                Some(key) if key == self.window.config.task_down => self.move_task(false),
-                Some(key) if key == self.window.config.focus => self.new_focus(),
-                Some(key) if key == self.window.config.complete => self.complete_task(),
-                Some(key) if key == self.window.config.up => self.move_selection(true),
-                Some(key) if key == self.window.config.down => self.move_selection(false),
-                Some(key) if key == self.window.config.increase => self.increase_priority(),
-                Some(key) if key == self.window.config.decrease => self.decrease_priority(),
-                Some(key) if key == self.window.config.sort => self.sort_by_priority(),
-                Some(_) => (),
-                None => (),
+            }
+            let count = match key {
+                Some(_) => std::mem::take(&mut self.count).max(1),
+                None => 1,
+            };
+            if !self.press(key, count) {
+                break;
+            }
+            if let (Some(task), None) = (&self.moving, &self.status) {
+                self.status = Some(format!(
+                    "Moving {}: {} to put it here, Esc to stop",
+                    task.borrow().task,
+                    key_name(self.keymap().move_to)
+                ));
             }
             if self.quit {
                 self.window.endwin();
@@ -237,476 +770,4617 @@ impl<'a> View<'a> {
         }
     }
 
-    /// Create a diaglogue for user input with specified prompt.
-    fn input_dialogue(&mut self, prompt: &str) -> String {
-        self.dialogue(prompt, "")
+    /// Act on a key pressed, repeating it count times where it can be, and
+    /// keep the list as it was to undo back to if the key changes it.
+    /// Returns false if the key leaves this view.
+    fn press(&mut self, key: Option<Key>, count: usize) -> bool {
+        let keys = *self.keymap();
+        let before = key
+            .and_then(|key| self.undoable(key))
+            .map(|change| self.step(change));
+        match key {
+            Some(key) if key == keys.quit => {
+                self.quit = self.confirm_quit();
+            }
+            Some(key) if key == keys.back => match self.root {
+                true => (),
+                false => return false,
+            },
+            Some(key) if key == keys.save => self.save(),
+            Some(key) if key == keys.add => self.add_task_from_input(),
+            Some(key) if key == keys.edit => self.edit_task(),
+            Some(key) if key == keys.notes => self.edit_notes(),
+            Some(key) if key == keys.comment => self.add_comment(),
+            Some(key) if key == keys.details => {
+                self.details = !self.details;
+            }
+            Some(key) if key == keys.context => self.switch_context(),
+            Some(key) if key == keys.tag_filter => self.filter_by_tag(),
+            Some(key) if key == keys.share => self.share_task(),
+            Some(key) if key == keys.block => self.block_task(),
+            Some(key) if key == keys.upcoming => {
+                self.show_upcoming = !self.show_upcoming;
+                self.announce(match self.show_upcoming {
+                    true => "Showing tasks not started yet",
+                    false => "Hiding tasks not started yet",
+                });
+            }
+            Some(key) if key == keys.age => {
+                self.show_age = !self.show_age;
+                self.announce(match self.show_age {
+                    true => "Showing task ages",
+                    false => "Showing due dates",
+                });
+            }
+            Some(key) if key == keys.skip => self.skip_occurrence(),
+            Some(key) if key == keys.defaults => self.set_defaults(),
+            Some(key) if key == keys.timer => self.toggle_timer(),
+            Some(key) if key == keys.due => self.set_due(),
+            Some(key) if key == keys.start => self.set_start(),
+            Some(key) if key == keys.target => self.set_target(),
+            Some(key) if key == keys.estimate => self.set_estimate(),
+            Some(key) if key == keys.url => self.set_url(),
+            Some(key) if key == keys.remind => self.set_reminder(),
+            Some(key) if key == keys.open_url => self.open_url(),
+            Some(key) if key == keys.today => self.flag_today(),
+            Some(key) if key == keys.delete => self.remove_task(),
+            Some(key) if key == keys.task_up => self.move_task(true),
+            Some(key) if key == keys.task_down => self.move_task(false),
+            Some(key) if key == keys.focus => self.new_focus(),
+            Some(key) if key == keys.focus_mode => self.focus_mode(),
+            Some(key) if key == keys.complete => self.complete_task(),
+            Some(key) if key == keys.up || key == keys.down => {
+                // A burst of the same key, e.g. held down over a slow
+                // connection, moves the selection once, stopping at the
+                // first or last task rather than going round and round
+                let up = key == keys.up;
+                match self.window.take_repeats(key) {
+                    0 => self.move_selection(up, count),
+                    repeats => self.move_selection_clamped(up, count + repeats),
+                }
+            }
+            Some(key) if key == keys.increase => (0..count).for_each(|_| self.increase_priority()),
+            Some(key) if key == keys.decrease => (0..count).for_each(|_| self.decrease_priority()),
+            Some(key) if key == keys.sort => self.sort_by_priority(),
+            Some(key) if key == keys.rollover => self.rollover(),
+            Some(key) if key == keys.export => self.export_html(),
+            Some(key) if key == keys.review => self.weekly_review(),
+            Some(key) if key == keys.report => self.time_report(),
+            Some(key) if key == keys.graph => self.dependency_graph(),
+            Some(key) if key == keys.heatmap => self.completion_heatmap(),
+            Some(key) if key == keys.theme => self.pick_theme(),
+            Some(key) if key == keys.next_action => self.next_action(),
+            Some(key) if key == keys.mark => self.toggle_mark(),
+            Some(key) if key == keys.command => self.enter_command(),
+            Some(key) if key == keys.jump => self.quick_jump(),
+            Some(key) if key == keys.email_task => self.email_task(),
+            Some(key) if key == keys.archive => self.open_archive(),
+            Some(key) if key == keys.trash => self.open_trash(),
+            Some(key) if key == keys.duplicate => self.duplicate_task(),
+            Some(key) if key == keys.find => self.find_task(),
+            Some(key) if key == keys.move_to => self.move_to(),
+            Some(key) if key == keys.cut => self.cut_task(),
+            Some(key) if key == keys.paste => self.paste_clipboard(),
+            Some(tui::PASTE) => self.paste_tasks(),
+            Some(Key::Esc) if self.moving.is_some() => {
+                self.moving = None;
+                self.announce("Stopped moving task");
+            }
+            Some(Key::Esc) if !self.marked.is_empty() => {
+                self.marked.clear();
+                self.announce("Cleared marks");
+            }
+            Some(key) if key == keys.undo => self.undo(),
+            Some(key) if key == keys.redo => self.redo(),
+            Some(key) if key == keys.link => self.copy_link(),
+            Some(key) => self.run_shell_command(key),
+            None => (),
+        }
+        if let Some(before) = before {
+            let after = self.root_task().borrow().save_string();
+            self.history.record(before, &after);
+        }
+        true
     }
 
-    /// Create an editing dialogue.
-    fn edit_dialogue(&mut self, prompt: &str, index: usize) -> String {
-        let mut original = String::new();
-        {
-            let sub_tasks = &self.current_task.borrow().sub_tasks;
-            original.push_str(&sub_tasks[index].borrow().task);
+    /// Check before quitting with unsaved changes: saving them if saving on
+    /// exit, otherwise asking if configured to. Returns whether to quit.
+    fn confirm_quit(&mut self) -> bool {
+        if self.read_only || self.list_hash() == self.saved {
+            return true;
+        }
+        if self.window.config.save_on_exit {
+            self.save();
+            return true;
         }
-        self.dialogue(prompt, &original)
+        !self.window.config.confirm.quit_unsaved
+            || self.popup("There are unsaved changes. Quit without saving? y/n")
     }
 
-    /// A dialogue box for user interaction.
-    fn dialogue(&mut self, prompt: &str, text: &str) -> String {
-        let mut entry = String::from(text);
-        let mut index = entry.len(); // byte position
-        let mut nchars = UnicodeWidthStr::width(entry.as_str()); // total displayed width
-        let mut chars = nchars; // displayed character position
-        let plen = UnicodeWidthStr::width(prompt);
-        let mut prev_lines: usize = 0;
-        loop {
-            let (ymax, xmax) = self.window.get_max_yx();
-            let max_width = if (xmax - 5 - plen) % 2 == 0 {
-                xmax - 4
-            } else {
-                xmax - 3
-            };
-            let nlines = 1 + (nchars + plen + 1) / max_width;
-            self.window.hide_cursor();
-            self.window
-                .rectangle(' ', (ymax - 2, 1), (nlines, xmax - 2));
+    /// Keys of the task list in the current mode: visual mode while tasks
+    /// are marked, otherwise normal mode.
+    fn keymap(&self) -> &config::KeyMap {
+        match self.marked.is_empty() {
+            true => &self.window.config.keys,
+            false => &self.window.config.visual_keys,
+        }
+    }
 
-            // Print dialogue box
-            if nlines != prev_lines {
-                self.window.border((ymax - 1, 0), (2 + nlines, xmax));
-                prev_lines = nlines;
+    /// What a key changes, if it makes a change which can be undone.
+    fn undoable(&self, key: Key) -> Option<&'static str> {
+        let keys = self.keymap();
+        match key {
+            key if key == keys.add => Some("add"),
+            key if key == keys.edit => Some("edit"),
+            key if key == keys.delete => Some("delete"),
+            key if key == keys.complete => Some("complete"),
+            key if key == keys.task_up || key == keys.task_down => Some("move"),
+            key if key == keys.sort => Some("sort"),
+            key if key == keys.increase || key == keys.decrease => Some("priority change"),
+            tui::PASTE => Some("paste"),
+            key if key == keys.duplicate => Some("duplicate"),
+            key if key == keys.move_to => Some("move"),
+            key if key == keys.cut => Some("cut"),
+            key if key == keys.paste => Some("paste"),
+            _ => None,
+        }
+    }
+
+    /// Current state of the list, to undo a change back to.
+    fn step(&self, change: &'static str) -> Step {
+        Step {
+            list: self.root_task().borrow().save_string(),
+            trash: trash::format(&self.trash),
+            selection: self.selection,
+            change,
+        }
+    }
+
+    /// Undo the latest change to the list.
+    fn undo(&mut self) {
+        let step = match self.history.undo(self.step("")) {
+            Some(step) => step,
+            None => return self.announce("Nothing to undo"),
+        };
+        match self.restore(&step) {
+            Ok(()) => self.announce(&format!("Undid {}", step.change)),
+            Err(err) => {
+                self.announce(&format!("Unable to undo {}: {}", step.change, err));
+                self.history.cancel_undo(step);
             }
+        }
+    }
 
-            // Print prompt
-            self.window.colour_on(0, 7);
-            self.window.mvprintw(ymax - 1 - nlines, 2, prompt);
-            self.window.colour_off();
+    /// Redo the latest change undone.
+    fn redo(&mut self) {
+        let step = match self.history.redo(self.step("")) {
+            Some(step) => step,
+            None => return self.announce("Nothing to redo"),
+        };
+        match self.restore(&step) {
+            Ok(()) => self.announce(&format!("Redid {}", step.change)),
+            Err(err) => {
+                self.announce(&format!("Unable to redo {}: {}", step.change, err));
+                self.history.cancel_redo(step);
+            }
+        }
+    }
 
-            // Print entry
-            let mut ypos = ymax - 1 - nlines;
-            let mut xpos = plen + 3;
-            for (i, ch) in entry.char_indices() {
-                if let Some(w) = UnicodeWidthChar::width(ch) {
-                    if xpos + w > max_width + 2 {
-                        ypos += 1;
-                        xpos = 2;
-                    }
+    /// Put the list and the trash back to an earlier state. The tasks from
+    /// the top level down to the one in view are kept and refilled in
+    /// place, as views of them are open, so each has to be in the earlier
+    /// list too, found by its id or otherwise its text.
+    fn restore(&mut self, step: &Step) -> Result<(), String> {
+        let mut path = vec![Rc::clone(&self.current_task)];
+        loop {
+            let parent = path[0].borrow().parent.upgrade();
+            match parent {
+                Some(parent) => path.insert(0, parent),
+                None => break,
+            }
+        }
 
-                    let mut j = i + 1;
-                    while !entry.is_char_boundary(j) && j < entry.len() {
-                        j += 1;
-                    }
+        let mut found = vec![parse_list(&step.list).map_err(|err| err.to_string())?];
+        for task in path.iter().skip(1) {
+            let task = task.borrow();
+            let parent = Rc::clone(&found[found.len() - 1]);
+            let position = parent.borrow().sub_tasks.iter().position(|other| {
+                let other = other.borrow();
+                match task.id {
+                    Some(id) => other.id == Some(id),
+                    None => other.task == task.task,
+                }
+            });
+            match position {
+                Some(position) => found.push(Rc::clone(&parent.borrow().sub_tasks[position])),
+                None => return Err(format!("{} is gone", task.task)),
+            }
+        }
 
-                    self.window.mvprintw(ypos, xpos, &entry[i..j]);
-                    xpos += w;
+        for (i, (task, earlier)) in path.iter().zip(found.iter()).enumerate() {
+            let mut earlier = earlier.replace(ToDo::new("", Weak::new()));
+            earlier.parent = task.borrow().parent.clone();
+            for sub_task in earlier.sub_tasks.iter_mut() {
+                if let Some(next) = path
+                    .get(i + 1)
+                    .filter(|_| Rc::ptr_eq(sub_task, &found[i + 1]))
+                {
+                    *sub_task = Rc::clone(next);
                 }
+                sub_task.borrow_mut().parent = Rc::downgrade(task);
             }
+            *task.borrow_mut() = earlier;
+        }
 
-            // Print cursor position
-            let y = (chars + plen + 1) / max_width;
-            let x = (chars + plen + 1) % max_width;
-            self.window.show_cursor();
-            self.window.mv(ymax - 1 - nlines + y, 2 + x);
-            self.window.refresh();
+        let len = self.current_task.borrow().sub_tasks.len();
+        self.selection = step
+            .selection
+            .filter(|&index| index < len)
+            .or(len.checked_sub(1));
+        self.marked.clear();
+        if trash::format(&self.trash) != step.trash {
+            self.trash = trash::parse(&step.trash);
+            self.save_trash();
+        }
+        Ok(())
+    }
 
-            // User input
-            match self.window.getch() {
-                Some(Key::Char('\n')) => break,
-                Some(Key::Char(ch)) => {
-                    if index >= entry.len() {
-                        entry.push(ch);
-                    } else {
-                        entry.insert(index, ch);
-                    }
-                    index += ch.len_utf8();
-                    let chwidth = UnicodeWidthChar::width(ch).unwrap();
-                    chars += chwidth;
-                    nchars += chwidth;
-                }
-                Some(Key::Backspace) => {
-                    if !entry.is_empty() {
-                        let end = index;
-                        while index > 0 {
-                            index -= 1;
-                            if entry.is_char_boundary(index) {
-                                break;
-                            }
-                        }
-                        let chwidth = UnicodeWidthStr::width(&entry[index..end]);
-                        chars -= chwidth;
-                        nchars -= chwidth;
-                        entry.remove(index);
-                    }
-                }
-                Some(Key::Delete) => {
-                    if !entry.is_empty() && index < entry.len() {
-                        let mut end = index;
-                        while end < entry.len() {
-                            end += 1;
-                            if entry.is_char_boundary(end) {
-                                break;
-                            }
+    /// Hash of the whole list in save file format.
+    fn list_hash(&self) -> u64 {
+        hash(&self.root_task().borrow().save_string())
+    }
+
+    /// Run a command entered by the user, such as "postpone overdue +1d".
+    fn enter_command(&mut self) {
+        let input = self.input_dialogue(":");
+        if input.trim().is_empty() {
+            return;
+        }
+        self.run_command(&input);
+    }
+
+    /// Run commands given on the command line, such as ":filter +work",
+    /// before showing the list.
+    pub fn run_commands(&mut self, commands: &[String]) {
+        for command in commands {
+            info!("Running command: {}", command);
+            self.run_command(command);
+        }
+    }
+
+    /// Run a command, such as "postpone overdue +1d".
+    fn run_command(&mut self, input: &str) {
+        match command::Command::parse(input, self.window.config.commands) {
+            Ok(command) => self.apply_command(command),
+            Err(err) => self.announce(err),
+        }
+    }
+
+    /// Carry out a parsed command, as one step to undo if it changes the
+    /// list.
+    fn apply_command(&mut self, command: command::Command) {
+        let before = command.change().map(|change| self.step(change));
+        self.carry_out(command);
+        if let Some(before) = before {
+            let after = self.root_task().borrow().save_string();
+            self.history.record(before, &after);
+        }
+    }
+
+    /// Carry out a parsed command, or each in a sequence of them.
+    fn carry_out(&mut self, command: command::Command) {
+        match command {
+            command::Command::PostponeOverdue(interval) => {
+                let prompt = format!(
+                    "Postpone all overdue tasks by {} days? y/n",
+                    interval.num_days()
+                );
+                if self.window.config.confirm.bulk && !self.popup(&prompt) {
+                    return;
+                }
+                let count = self.root_task().borrow().postpone_overdue(
+                    Local::now().naive_local(),
+                    interval,
+                    &|todo| self.in_context(todo),
+                );
+                info!("Postponed {} overdue tasks.", count);
+                self.announce(&format!(
+                    "Postponed {} overdue tasks by {} days",
+                    count,
+                    interval.num_days()
+                ));
+            }
+            command::Command::Filter(filter) => {
+                self.context = filter;
+                let message = match &self.context {
+                    Some(filter) => format!("Context: {}", filter),
+                    None => String::from("Showing all tasks"),
+                };
+                self.announce(&message);
+            }
+            command::Command::Sort(command::SortKey::Priority) => self.sort_by_priority(),
+            command::Command::Sort(command::SortKey::Due) => {
+                let selected = self.selected_task();
+                self.current_task.borrow_mut().sort_by_due();
+                if let Some(selected) = selected {
+                    self.select_task(&selected);
+                }
+                self.announce("Sorted by due date");
+            }
+            command::Command::Group(group) => {
+                self.group = group;
+                self.announce(match group {
+                    Some(command::GroupKey::Tag) => "Grouped by tag",
+                    Some(command::GroupKey::Priority) => "Grouped by priority",
+                    Some(command::GroupKey::Due) => "Grouped by due week",
+                    None => "Not grouped",
+                });
+            }
+            command::Command::ArchiveCompleted => self.archive_completed(),
+            command::Command::Sequence(commands) => {
+                commands
+                    .into_iter()
+                    .for_each(|command| self.carry_out(command));
+            }
+        }
+    }
+
+    /// Add a digit to the count of times to repeat the next command, unless
+    /// it is bound to a shell command, returning whether it was added.
+    fn add_to_count(&mut self, ch: char) -> bool {
+        let digit = match ch.to_digit(10) {
+            Some(0) if self.count == 0 => return false,
+            Some(digit) => digit as usize,
+            None => return false,
+        };
+        let key = Key::Char(ch);
+        if self
+            .window
+            .config
+            .shell_commands
+            .iter()
+            .any(|(k, _)| *k == key)
+        {
+            return false;
+        }
+        self.count = (self.count * 10 + digit).min(9999);
+        self.status = Some(self.count.to_string());
+        true
+    }
+
+    /// Take a step towards the task being jumped to, returning false if the
+    /// current task has to be left for its parent.
+    fn follow_jump(&mut self) -> bool {
+        let (target, message) = match self.jump.clone() {
+            Some(jump) => jump,
+            None => return true,
+        };
+        let here = self.current_task.borrow().index_path();
+        if target.len() > here.len() && target.starts_with(&here) {
+            self.selection = Some(target[here.len()]);
+            if target.len() == here.len() + 1 {
+                self.jump = None;
+                self.announce(&message);
+            } else {
+                self.new_focus();
+            }
+            true
+        } else if self.root {
+            self.jump = None;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Open the todo list at a task, given its id or part of its title
+    /// (ignoring case), preferring the task with the id, then unfinished
+    /// tasks.
+    pub fn goto(&mut self, target: &str) {
+        let root = self.root_task();
+        let root = root.borrow();
+        let id = target.trim_start_matches('#').parse::<u32>().ok();
+        let fragment = target.to_lowercase();
+        let matches = |todo: &ToDo| todo.task.to_lowercase().contains(&fragment);
+        let found = id
+            .and_then(|id| root.find_in_tree(&|todo| todo.id == Some(id)))
+            .or_else(|| root.find_in_tree(&|todo| !todo.complete && matches(todo)))
+            .or_else(|| root.find_in_tree(&matches));
+        match found {
+            Some(task) => {
+                let task = task.borrow();
+                info!("Opening at task: {}", task.task);
+                let message = format!("Go to: {}", task.task);
+                self.jump = Some((task.index_path(), message));
+            }
+            None => self.announce(&format!("No task matching \"{}\"", target)),
+        }
+    }
+
+    /// Find a task by the words in its title or notes, showing the tasks
+    /// found as the search is typed, and jump to the one chosen.
+    fn find_task(&mut self) {
+        self.index.update(&self.root_task());
+        let mut query = String::new();
+        let mut index = 0;
+        loop {
+            let found = self.index.search(&query);
+            index = index.min(found.len().saturating_sub(1));
+            self.list_tasks();
+            self.print_found(&query, &found, index);
+            let n = found.len().max(1);
+            let keys = self.window.config.search_keys;
+            match self.window.getch() {
+                Some(key) if key == keys.accept => {
+                    if let Some(task) = found.get(index) {
+                        let task = task.borrow();
+                        let message = format!("Found: {}", task.task);
+                        self.jump = Some((task.index_path(), message));
+                    }
+                    return;
+                }
+                Some(Key::Esc) | None => return,
+                Some(Key::Backspace) => {
+                    query.pop();
+                }
+                Some(key) if key == keys.previous => index = (index + n - 1) % n,
+                Some(key) if key == keys.next => index = (index + 1) % n,
+                Some(Key::Char(c)) if !c.is_control() => {
+                    query.push(c);
+                    index = 0;
+                }
+                _ => (),
+            }
+        }
+    }
+
+    /// Draw the search for a task over the list, with the tasks found and
+    /// where they are, and the one under the cursor highlighted.
+    fn print_found(&mut self, query: &str, found: &[Rc<RefCell<ToDo>>], index: usize) {
+        let (ymax, xmax) = self.window.get_max_yx();
+        let width = (xmax * 3 / 4).max(20).min(xmax);
+        let height = (found.len() + 4).max(5).min(ymax);
+        let top = (ymax - height) / 2;
+        let left = (xmax - width) / 2;
+        self.window
+            .rectangle(' ', (top + height - 1, left), (height, width));
+        self.window
+            .border((top + height - 1, left), (height, width));
+        self.window.colour_on(4, 8);
+        self.window.mvprintw(top, left + 2, self.locale.tr("Find"));
+        self.window.colour_off();
+        let prompt = format!("/{}", query);
+        let prompt = column::fit(&prompt, width - 4, column::Align::Left);
+        self.window.mvprintw(top + 1, left + 2, &prompt);
+
+        let rows = height.saturating_sub(4);
+        let offset = (index + 1).saturating_sub(rows);
+        if found.is_empty() && !query.trim().is_empty() {
+            self.window
+                .mvprintw(top + 3, left + 2, self.locale.tr("No tasks found"));
+        }
+        for (row, task) in found.iter().enumerate().skip(offset).take(rows) {
+            let task = task.borrow();
+            let mut path = String::new();
+            task.task_path(&mut path);
+            let text = match path.trim_start_matches(": ").trim_end_matches(": ") {
+                "" => task.task.clone(),
+                path => format!("{}  ({})", task.task, path),
+            };
+            let text = column::fit(&text, width - 4, column::Align::Left);
+            if row == index {
+                self.window.colour_on(0, 7);
+            } else if task.complete {
+                self.window.colour_on(8, 0);
+            }
+            self.window
+                .mvprintw(top + 3 + row - offset, left + 2, &text);
+            self.window.colour_off();
+        }
+        if self.window.config.screen_reader {
+            self.window.show_cursor();
+            let x = UnicodeWidthStr::width(prompt.trim_end());
+            self.window.mv(top + 1, left + 2 + x);
+        }
+        self.window.refresh();
+    }
+
+    /// Jump to the suggested next task: the highest ranked unfinished task
+    /// which can be done now, in the current context.
+    fn next_action(&mut self) {
+        let today = Local::now().date_naive();
+        let suggestion = next::suggest(
+            &self.root_task(),
+            &self.window.config.weights,
+            self.window.config.priority_levels,
+            Local::now().naive_local(),
+            &|todo| todo.started(today) && self.in_context(todo),
+        );
+        match suggestion {
+            Some(task) => {
+                let task = task.borrow();
+                let message = format!("Next action: {}", task.task);
+                self.jump = Some((task.index_path(), message));
+            }
+            None => self.announce("No next action to suggest"),
+        }
+    }
+
+    /// Time to wait for input before updating the display, if a countdown
+    /// is shown, the status bar is flashing or a task is yet to fall due.
+    fn tick(&self) -> Option<Duration> {
+        if self.saver.busy() {
+            return Some(Duration::from_millis(50));
+        }
+        if self.countdown || self.flash || self.running_timer().is_some() {
+            return Some(Duration::from_secs(1));
+        }
+        let now = Local::now().naive_local();
+        let next = self.root_task().borrow().next_deadline(now)?;
+        let wait = (next - now).to_std().unwrap_or_default();
+        Some(wait.min(Duration::from_secs(60)) + Duration::from_millis(10))
+    }
+
+    /// Alert the user to any tasks which have fallen due since last
+    /// checked, ringing the bell and flashing the status bar if configured.
+    fn check_due(&mut self) {
+        let now = Local::now().naive_local();
+        let mut found = Vec::new();
+        let mut reminders = Vec::new();
+        let root = self.root_task();
+        root.borrow()
+            .falling_due(self.last_checked, now, &mut found);
+        root.borrow()
+            .reminders_due(self.last_checked, now, &mut reminders);
+        self.last_checked = now;
+        let raised = self.escalate();
+
+        let mut messages = Vec::new();
+        if !found.is_empty() {
+            messages.push(format!("Due now: {}", found.join(", ")));
+        }
+        if !reminders.is_empty() {
+            messages.push(format!("Reminder: {}", reminders.join(", ")));
+        }
+        if !raised.is_empty() {
+            messages.push(format!("Priority raised: {}", raised.join(", ")));
+        }
+        if !messages.is_empty() {
+            self.announce(&messages.join("; "));
+        }
+        if !found.is_empty() || !reminders.is_empty() {
+            if self.window.config.bell {
+                self.window.bell();
+            }
+            self.flash = self.window.config.flash;
+        }
+    }
+
+    /// Raise the priority of tasks by the escalation rules in config.toml,
+    /// returning the titles of those raised.
+    fn escalate(&mut self) -> Vec<String> {
+        let mut raised = Vec::new();
+        let rules = self.window.config.escalate;
+        if !rules.is_empty() {
+            let now = Local::now().naive_local();
+            escalate::raise(rules, &self.root_task().borrow(), now, &mut raised);
+        }
+        for task in raised.iter() {
+            info!("Priority raised: {}", task);
+        }
+        raised
+    }
+
+    /// Create a diaglogue for user input with specified prompt.
+    fn input_dialogue(&mut self, prompt: &str) -> String {
+        self.dialogue(prompt, "")
+    }
+
+    /// Create an editing dialogue.
+    fn edit_dialogue(&mut self, prompt: &str, index: usize) -> String {
+        let mut original = String::new();
+        {
+            let sub_tasks = &self.current_task.borrow().sub_tasks;
+            let sub_task = sub_tasks[index].borrow();
+            original.push_str(&sub_task.task);
+            if let Some(repeat) = sub_task.repeat {
+                original.push_str(&format!(" repeat:{}", repeat));
+            }
+        }
+        self.task_dialogue(prompt, &original)
+    }
+
+    /// A dialogue box for entering a task, completing the tags and
+    /// contexts already in use, and the contexts set up in config.toml.
+    fn task_dialogue(&mut self, prompt: &str, text: &str) -> String {
+        let root = self.root_task();
+        let mut tags = BTreeSet::new();
+        let mut contexts: BTreeSet<String> = self.window.config.contexts.iter().cloned().collect();
+        root.borrow().all_tags(&mut tags);
+        root.borrow().all_contexts(&mut contexts);
+
+        let mut words: Vec<String> = Vec::new();
+        for prefix in ['+', '#'].iter() {
+            words.extend(tags.iter().map(|tag| format!("{}{}", prefix, tag)));
+        }
+        words.extend(contexts);
+        self.completing_dialogue(prompt, text, &words, true)
+    }
+
+    /// A dialogue box for user interaction.
+    fn dialogue(&mut self, prompt: &str, text: &str) -> String {
+        self.completing_dialogue(prompt, text, &[], false)
+    }
+
+    /// A dialogue box for user interaction, offering to complete a word
+    /// starting with '+', '#' or '@' from a list of words, chosen with the
+    /// up and down keys and accepted with tab (or return once chosen).
+    /// Misspelt words are underlined if spell checking is on.
+    fn completing_dialogue(
+        &mut self,
+        prompt: &str,
+        text: &str,
+        words: &[String],
+        spell_check: bool,
+    ) -> String {
+        let prompt = String::from(self.locale.tr(prompt));
+        let prompt = prompt.as_str();
+        let mut entry = String::from(text);
+        let mut index = entry.len(); // byte position
+        let mut nchars = UnicodeWidthStr::width(entry.as_str()); // total displayed width
+        let mut chars = nchars; // displayed character position
+        let plen = UnicodeWidthStr::width(prompt);
+        let mut prev_lines: usize = 0;
+        let mut matches: Vec<&String> = Vec::new();
+        let mut chosen: Option<usize> = None;
+        loop {
+            let (ymax, xmax) = self.window.get_max_yx();
+            let max_width = if (xmax - 5 - plen) % 2 == 0 {
+                xmax - 4
+            } else {
+                xmax - 3
+            };
+            let nlines = 1 + (nchars + plen + 1) / max_width;
+
+            // Completions of the word before the cursor, redrawing the
+            // screen behind them as they change
+            let start = entry[..index].rfind(' ').map_or(0, |i| i + 1);
+            let word = &entry[start..index];
+            let previous = matches.len();
+            matches = match word.starts_with(['+', '#', '@']) {
+                true => words
+                    .iter()
+                    .filter(|w| w.starts_with(word) && w.as_str() != word)
+                    .take(ymax.saturating_sub(nlines + 6))
+                    .collect(),
+                false => Vec::new(),
+            };
+            if matches.len() != previous || !matches.is_empty() {
+                self.list_tasks();
+                prev_lines = 0;
+            }
+            chosen = chosen.filter(|&i| i < matches.len());
+            if !matches.is_empty() {
+                self.print_completions(&matches, chosen, ymax - 3 - nlines);
+            }
+            self.window.hide_cursor();
+            self.window
+                .rectangle(' ', (ymax - 2, 1), (nlines, xmax - 2));
+
+            // Print dialogue box
+            if nlines != prev_lines {
+                self.window.colour_fg(self.window.config.border_edit);
+                self.window.border((ymax - 1, 0), (2 + nlines, xmax));
+                self.window.colour_off();
+                prev_lines = nlines;
+            }
+
+            // Print prompt
+            self.window.colour_on(0, 7);
+            self.window.mvprintw(ymax - 1 - nlines, 2, prompt);
+            self.window.colour_off();
+
+            // Print entry, underlining misspelt words other than the one
+            // being typed
+            let misspelt = match (&self.dictionary, spell_check) {
+                (Some(dictionary), true) => dictionary.misspelt(&entry),
+                _ => Vec::new(),
+            };
+            let misspelt: Vec<_> = misspelt
+                .into_iter()
+                .filter(|word| !(word.start..=word.end).contains(&index))
+                .collect();
+            let mut ypos = ymax - 1 - nlines;
+            let mut xpos = plen + 3;
+            for (i, ch) in entry.char_indices() {
+                if let Some(w) = UnicodeWidthChar::width(ch) {
+                    if xpos + w > max_width + 2 {
+                        ypos += 1;
+                        xpos = 2;
+                    }
+
+                    let mut j = i + 1;
+                    while !entry.is_char_boundary(j) && j < entry.len() {
+                        j += 1;
+                    }
+
+                    let underline = misspelt.iter().any(|word| word.contains(&i));
+                    self.window.underline(underline);
+                    self.window.mvprintw(ypos, xpos, &entry[i..j]);
+                    self.window.underline(false);
+                    xpos += w;
+                }
+            }
+
+            // Print cursor position
+            let y = (chars + plen + 1) / max_width;
+            let x = (chars + plen + 1) % max_width;
+            self.window.show_cursor();
+            self.window.mv(ymax - 1 - nlines + y, 2 + x);
+            self.window.refresh();
+
+            // User input
+            match self.window.getch() {
+                Some(Key::Up) if !matches.is_empty() => {
+                    chosen = Some(chosen.map_or(matches.len() - 1, |i| i.saturating_sub(1)));
+                }
+                Some(Key::Down) if !matches.is_empty() => {
+                    chosen = Some(chosen.map_or(0, |i| (i + 1).min(matches.len() - 1)));
+                }
+                Some(key)
+                    if key == self.window.config.edit_keys.complete && !matches.is_empty() =>
+                {
+                    let completion = matches[chosen.unwrap_or(0)].clone();
+                    self.complete_word(&mut entry, &mut index, start, &completion);
+                    chars = UnicodeWidthStr::width(&entry[..index]);
+                    nchars = UnicodeWidthStr::width(entry.as_str());
+                    chosen = None;
+                }
+                Some(key) if key == self.window.config.edit_keys.accept && chosen.is_some() => {
+                    let completion = matches[chosen.unwrap_or(0)].clone();
+                    self.complete_word(&mut entry, &mut index, start, &completion);
+                    chars = UnicodeWidthStr::width(&entry[..index]);
+                    nchars = UnicodeWidthStr::width(entry.as_str());
+                    chosen = None;
+                }
+                Some(key) if key == self.window.config.edit_keys.accept => break,
+                Some(tui::PASTE) => {
+                    // Pasted lines are joined into one
+                    if let Some(text) = self.window.take_paste() {
+                        let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+                        entry.insert_str(index, &text);
+                        index += text.len();
+                        chars = UnicodeWidthStr::width(&entry[..index]);
+                        nchars = UnicodeWidthStr::width(entry.as_str());
+                    }
+                }
+                // Control characters, e.g. return once accept is another
+                // key, take up no width and aren't entered
+                Some(Key::Char(ch)) if UnicodeWidthChar::width(ch).is_some() => {
+                    if index >= entry.len() {
+                        entry.push(ch);
+                    } else {
+                        entry.insert(index, ch);
+                    }
+                    index += ch.len_utf8();
+                    let chwidth = UnicodeWidthChar::width(ch).unwrap_or(0);
+                    chars += chwidth;
+                    nchars += chwidth;
+                }
+                Some(Key::Backspace) if !entry.is_empty() => {
+                    let end = index;
+                    while index > 0 {
+                        index -= 1;
+                        if entry.is_char_boundary(index) {
+                            break;
                         }
-                        nchars -= UnicodeWidthStr::width(&entry[index..end]);
-                        entry.remove(index);
                     }
+                    let chwidth = UnicodeWidthStr::width(&entry[index..end]);
+                    chars -= chwidth;
+                    nchars -= chwidth;
+                    entry.remove(index);
                 }
-                Some(Key::Left) => {
-                    if index > 0 {
-                        let end = index;
-                        while index > 0 {
-                            index -= 1;
-                            if entry.is_char_boundary(index) {
-                                break;
-                            }
+                Some(Key::Delete) if !entry.is_empty() && index < entry.len() => {
+                    let mut end = index;
+                    while end < entry.len() {
+                        end += 1;
+                        if entry.is_char_boundary(end) {
+                            break;
                         }
-                        chars -= UnicodeWidthStr::width(&entry[index..end]);
                     }
+                    nchars -= UnicodeWidthStr::width(&entry[index..end]);
+                    entry.remove(index);
                 }
-                Some(Key::Right) => {
-                    if index < entry.len() {
-                        let start = index;
-                        while index < entry.len() {
-                            index += 1;
-                            if entry.is_char_boundary(index) {
-                                break;
-                            }
+                Some(Key::Left) if index > 0 => {
+                    let end = index;
+                    while index > 0 {
+                        index -= 1;
+                        if entry.is_char_boundary(index) {
+                            break;
+                        }
+                    }
+                    chars -= UnicodeWidthStr::width(&entry[index..end]);
+                }
+                Some(Key::Right) if index < entry.len() => {
+                    let start = index;
+                    while index < entry.len() {
+                        index += 1;
+                        if entry.is_char_boundary(index) {
+                            break;
                         }
-                        chars += UnicodeWidthStr::width(&entry[start..index]);
+                    }
+                    chars += UnicodeWidthStr::width(&entry[start..index]);
+                }
+                _ => (),
+            }
+        }
+        entry
+    }
+
+    /// Colour of the borders around the task panels, showing at a glance
+    /// whether tasks are marked, only some tasks are shown or there are
+    /// unsaved changes.
+    fn border_colour(&self) -> &'a dyn color::Color {
+        let config = &self.window.config;
+        if !self.marked.is_empty() {
+            config.border_visual
+        } else if self.context.is_some() {
+            config.border_filter
+        } else if !self.read_only && self.list_hash() != self.saved {
+            config.border_unsaved
+        } else {
+            config.border_normal
+        }
+    }
+
+    /// Print a list of completions in a box above the dialogue box, ending
+    /// at row bottom, with the chosen one (if any) highlighted.
+    fn print_completions(&mut self, matches: &[&String], chosen: Option<usize>, bottom: usize) {
+        let width = matches
+            .iter()
+            .map(|word| UnicodeWidthStr::width(word.as_str()))
+            .max()
+            .unwrap_or(0)
+            + 4;
+        let height = matches.len() + 2;
+        self.window.rectangle(' ', (bottom, 1), (height, width));
+        self.window.border((bottom, 1), (height, width));
+        for (row, word) in matches.iter().enumerate() {
+            if chosen == Some(row) {
+                self.window.colour_on(0, 7);
+            }
+            self.window.mvprintw(bottom + 2 - height + row, 3, word);
+            self.window.colour_off();
+        }
+    }
+
+    /// Replace the word in an entry from byte position start up to the
+    /// cursor with a completion, moving the cursor past it.
+    fn complete_word(&self, entry: &mut String, index: &mut usize, start: usize, completion: &str) {
+        let mut completion = String::from(completion);
+        if !entry[*index..].starts_with(' ') {
+            completion.push(' ');
+        }
+        entry.replace_range(start..*index, &completion);
+        *index = start + completion.len();
+    }
+
+    /// Display a list of the sub-tasks of the current task.
+    fn list_tasks(&mut self) {
+        self.window.clear();
+        self.window.hide_cursor();
+        self.countdown = false;
+        self.unfinished = deps::unfinished(&self.root_task());
+
+        let (ymax, xmax) = self.window.get_max_yx();
+        let top = self.first_row();
+        let margin = self.window.config.margin;
+        let (left, right) = layout::task_panes(xmax, margin);
+        let line = layout::line(xmax, margin);
+        if self.window.config.title_bar {
+            self.print_title_bar(xmax);
+        }
+
+        // Panels
+        let mut path = self.current_task.borrow().task.clone();
+        self.current_task.borrow().task_path(&mut path);
+        self.window.mvprintw(top - 3, 1, &path);
+        if let Some(context) = &self.context {
+            let len = UnicodeWidthStr::width(context.as_str());
+            self.window.colour_on(3, 8);
+            self.window
+                .mvprintw(top - 3, xmax.saturating_sub(len + 2), context);
+            self.window.colour_off();
+        }
+        self.window.colour_fg(self.border_colour());
+        self.window.border((top - 2, 0), (3, xmax));
+        self.window
+            .border((ymax - 4, 0), (ymax - top - 2, xmax / 2));
+        self.window
+            .border((ymax - 4, xmax / 2), (ymax - top - 2, xmax / 2));
+        self.window.border((ymax - 1, 0), (3, xmax));
+        self.window.colour_off();
+
+        self.window.colour_on(4, 8);
+        self.window.mvprintw(top - 4, 2, self.locale.tr("Parent"));
+        self.window.mvprintw(top - 1, 2, self.locale.tr("Tasks"));
+        let right_panel = if self.details { "Details" } else { "Sub-tasks" };
+        let right_panel = self.locale.tr(right_panel);
+        self.window.mvprintw(top - 1, xmax / 2 + 2, right_panel);
+        self.window
+            .mvprintw(ymax - 3, 2, self.locale.tr("Selection"));
+        self.window.colour_off();
+
+        // Status line, at a fixed location for screen readers, with any
+        // timer running and the progress of the focused task at the end
+        let mut progress = match self.root {
+            true => String::new(),
+            false => self.progress(),
+        };
+        if let Some(timer) = self.timer_status(line.width / 2) {
+            progress = match progress.is_empty() {
+                true => timer,
+                false => format!("{}  {}", timer, progress),
+            };
+        }
+        let plen = UnicodeWidthStr::width(progress.as_str());
+        if let Some(status) = &self.status {
+            if self.flash {
+                self.window.colour_on(0, 7);
+                self.window.rectangle(' ', (ymax - 1, 1), (1, xmax - 2));
+            }
+            let width = line.width.saturating_sub(plen + 2).max(1);
+            self.window.wrap_print(ymax - 1, line.x, width, status);
+            self.window.colour_off();
+        }
+        if plen > 0 && plen + 1 < line.width {
+            let x = line.x + line.width - plen - 1;
+            self.window.mvprintw(ymax - 1, x, &progress);
+        }
+
+        // Only tasks in the active context are shown, so the selection
+        // might need to move to one which is
+        let current = Rc::clone(&self.current_task);
+        let rows = self.pipeline().rows(&current.borrow());
+        if let Some(index) = self.selection {
+            if !rows.contains(&pipeline::Row::Task(index)) {
+                self.selection = rows.iter().find_map(|row| match row {
+                    pipeline::Row::Task(i) => Some(*i),
+                    pipeline::Row::Header(..) => None,
+                });
+            }
+        }
+        let selected_row = self.selection.and_then(|index| {
+            rows.iter()
+                .position(|row| *row == pipeline::Row::Task(index))
+        });
+
+        self.window.colour_on(6, 8);
+        if let Some(index) = self.selection {
+            if index > self.current_task.borrow().sub_tasks.len() - 1 {
+                warn!("Index larger than it should be.");
+                self.selection = None;
+            } else {
+                self.window
+                    .mvprintw(top + selected_row.unwrap_or(0), layout::BORDER, ">");
+                self.window.wrap_print(
+                    ymax - 2,
+                    line.x,
+                    line.width,
+                    &self.current_task.borrow().sub_tasks[index].borrow().task,
+                );
+            }
+        };
+        self.window.colour_off();
+
+        let sub_tasks = &current.borrow().sub_tasks;
+        for (row, entry) in rows.iter().enumerate() {
+            let i = match entry {
+                pipeline::Row::Task(i) => *i,
+                pipeline::Row::Header(title, count) => {
+                    self.print_header(top + row, left.x, left.width, title, *count);
+                    continue;
+                }
+            };
+            let elem = &sub_tasks[i];
+            if self.marked.iter().any(|task| Rc::ptr_eq(task, elem)) {
+                self.window.colour_on(3, 8);
+                self.window.mvprintw(top + row, layout::BORDER + 1, "+");
+                self.window.colour_off();
+            }
+            self.print_task(top + row, left.x, left.width, &elem.borrow());
+
+            if let Some(index) = self.selection {
+                if index == i && self.details {
+                    self.print_details(&elem.borrow(), ymax - 5, right);
+                } else if index == i {
+                    let sub_rows = self.pipeline().rows(&elem.borrow());
+                    for (j, sub_row) in sub_rows.iter().enumerate() {
+                        match sub_row {
+                            pipeline::Row::Task(k) => {
+                                let sub_elem = &elem.borrow().sub_tasks[*k];
+                                self.print_task(top + j, right.x, right.width, &sub_elem.borrow());
+                            }
+                            pipeline::Row::Header(title, count) => {
+                                self.print_header(top + j, right.x, right.width, title, *count);
+                            }
+                        }
+                    }
+                }
+            };
+        }
+
+        // Keep the cursor on the selected task for screen readers
+        if self.window.config.screen_reader {
+            if let Some(row) = selected_row {
+                self.window.show_cursor();
+                self.window.mv(top + row, 1);
+            }
+        }
+        self.window.refresh();
+    }
+
+    /// Print the header of a group of tasks, with how many are in it.
+    fn print_header(&mut self, y: usize, x: usize, width: usize, title: &str, count: usize) {
+        self.window.colour_on(4, 8);
+        self.window.bold(true);
+        self.window
+            .wrap_print(y, x, width, &format!("{} ({})", title, count));
+        self.window.bold(false);
+        self.window.colour_off();
+    }
+
+    /// Row of the first task in the task panels, below the title bar if
+    /// shown.
+    fn first_row(&self) -> usize {
+        match self.window.config.title_bar {
+            true => 5,
+            false => 4,
+        }
+    }
+
+    /// Print the title bar along the top of the screen, with the name of
+    /// the list and counts of open, due today and overdue tasks (in the
+    /// active context).
+    fn print_title_bar(&mut self, xmax: usize) {
+        let list = self.list_name();
+
+        let now = Local::now().naive_local();
+        let today = now.date();
+        let root = self.root_task();
+        let root = root.borrow();
+        let open = |todo: &ToDo| !todo.complete && todo.started(today) && self.in_context(todo);
+        let count = |condition: &dyn Fn(&ToDo) -> bool| root.count_in_tree(condition);
+        let mut summary = format!(
+            "{} open  {} due today  {} overdue",
+            count(&|t| open(t)),
+            count(&|t| open(t)
+                && t.due
+                    .is_some_and(|due| due.day() == today && due.deadline() > now)),
+            count(&|t| open(t) && t.due.is_some_and(|due| due.deadline() <= now)),
+        );
+
+        if self.window.config.week_numbers {
+            let week = self.locale.week_number(today);
+            summary = format!("{} {}  {}", self.locale.tr("week"), week, summary);
+        }
+
+        self.window
+            .colour_pair(self.window.config.title_fg, self.window.config.title_bg);
+        self.window.bold(self.window.config.title_bold);
+        self.window.mvprintw(0, 0, &" ".repeat(xmax));
+        self.window
+            .wrap_print(0, 1, xmax - 2, &format!("yat: {}", list));
+        let len = UnicodeWidthStr::width(summary.as_str());
+        if len + UnicodeWidthStr::width(list.as_str()) + 10 < xmax {
+            self.window.mvprintw(0, xmax - len - 1, &summary);
+        }
+        self.window.bold(false);
+        self.window.colour_off();
+    }
+
+    /// Progress of the focused task, e.g. "3/5 done (60%), 2h left": how
+    /// many tasks under it are complete, and the time estimated for the
+    /// rest less the time spent on them.
+    fn progress(&self) -> String {
+        let task = self.current_task.borrow();
+        let total = task.count_in_tree(&|_| true);
+        if total == 0 {
+            return String::new();
+        }
+        let done = task.count_in_tree(&|t| t.complete);
+        let mut progress = format!("{}/{} done ({}%)", done, total, done * 100 / total);
+        let left = effort::remaining(&task);
+        if left > 0 {
+            progress = format!("{}, {} left", progress, effort::format(left));
+        }
+        format!(" {} ", progress)
+    }
+
+    /// Name of the todo list, for titles.
+    fn list_name(&self) -> String {
+        match (&self.remote, &self.save_file) {
+            (Some(storage), _) => storage.describe(),
+            (None, Some(path)) => path
+                .file_name()
+                .map_or(String::new(), |name| name.to_string_lossy().into_owned()),
+            (None, None) => String::from("new list"),
+        }
+    }
+
+    /// Stages choosing the tasks shown in the task panels: tasks which
+    /// have started (unless upcoming tasks are shown), then those in the
+    /// active context. Tasks with sub-tasks in the context are also shown,
+    /// so that they can still be reached. Last they are grouped, if chosen.
+    fn pipeline(&self) -> pipeline::Pipeline<'_> {
+        let mut pipeline = pipeline::Pipeline::default();
+        if !self.show_upcoming {
+            let today = Local::now().date_naive();
+            pipeline = pipeline.filter(move |todo| todo.started(today));
+        }
+        if self.context.is_some() {
+            pipeline = pipeline.filter(move |todo| todo.any_in_tree(&|t| self.in_context(t)));
+        }
+        match self.group {
+            Some(command::GroupKey::Tag) => pipeline.group(self.locale.tr("No tag"), |todo| {
+                todo.tags()
+                    .next()
+                    .map(|tag| (tag.to_string(), format!("+{}", tag)))
+            }),
+            Some(command::GroupKey::Priority) => {
+                pipeline.group(self.locale.tr("No priority"), move |todo| {
+                    todo.priority.map(|priority| {
+                        let name = self.priority_name(&Some(priority));
+                        (
+                            priority.letter().to_string(),
+                            self.locale.fill("Priority {}", &name),
+                        )
+                    })
+                })
+            }
+            Some(command::GroupKey::Due) => {
+                pipeline.group(self.locale.tr("No due date"), move |todo| {
+                    todo.due.map(|due| due.day()).or(todo.target).map(|day| {
+                        let week = self.locale.week_start(day);
+                        let start = week.format("%Y-%m-%d").to_string();
+                        (start.clone(), self.locale.fill("Week of {}", &start))
+                    })
+                })
+            }
+            None => pipeline,
+        }
+    }
+
+    /// Whether a task is shown in the task panels.
+    fn shown(&self, todo: &ToDo) -> bool {
+        self.pipeline().shows(todo)
+    }
+
+    /// Whether a task is in every context and has every tag of the active
+    /// filter (if any), e.g. "+work @phone".
+    fn in_context(&self, todo: &ToDo) -> bool {
+        match &self.context {
+            Some(filter) => filter.split_whitespace().all(|word| has_label(todo, word)),
+            None => true,
+        }
+    }
+
+    /// Indices of the sub-tasks of a task which are shown.
+    fn shown_indices(&self, todo: &ToDo) -> Vec<usize> {
+        self.pipeline().run(todo)
+    }
+
+    /// Choose a context to show only the tasks in, or show all tasks.
+    fn switch_context(&mut self) {
+        let mut contexts = BTreeSet::new();
+        self.root_task().borrow().all_contexts(&mut contexts);
+        let declared = self.window.config.contexts;
+        let mut options = vec![String::from("All tasks")];
+        options.extend(declared.iter().cloned());
+        options.extend(contexts.into_iter().filter(|c| !declared.contains(c)));
+        let current = self
+            .context
+            .as_ref()
+            .and_then(|context| options.iter().position(|o| o == context))
+            .unwrap_or(0);
+
+        if let Some(choice) = self.pick("Context", &options, current) {
+            self.context = match choice {
+                0 => None,
+                _ => Some(options[choice].clone()),
+            };
+            let message = match &self.context {
+                Some(context) => format!("Context: {}", context),
+                None => String::from("Showing all tasks"),
+            };
+            self.announce(&message);
+        }
+    }
+
+    /// Choose a tag to show only the tasks with, or show all tasks.
+    fn filter_by_tag(&mut self) {
+        let mut tags = BTreeSet::new();
+        self.root_task().borrow().all_tags(&mut tags);
+        let mut options = vec![String::from("All tasks")];
+        options.extend(tags.iter().map(|tag| format!("+{}", tag)));
+        let current = self
+            .context
+            .as_ref()
+            .and_then(|context| context.strip_prefix(['+', '#']))
+            .and_then(|tag| options.iter().position(|o| o[1..] == *tag))
+            .unwrap_or(0);
+
+        if let Some(choice) = self.pick("Tag", &options, current) {
+            self.context = match choice {
+                0 => None,
+                _ => Some(options[choice].clone()),
+            };
+            let message = match &self.context {
+                Some(tag) => format!("Tag: {}", tag),
+                None => String::from("Showing all tasks"),
+            };
+            self.announce(&message);
+        }
+    }
+
+    /// A pop-up list of options, returning the index of the chosen option,
+    /// or None if cancelled.
+    fn pick(&mut self, title: &str, options: &[String], index: usize) -> Option<usize> {
+        self.preview_pick(title, options, index, &|_, _| ())
+    }
+
+    /// A pop-up list of options over the task list, previewing the option
+    /// under the cursor before the list is redrawn.
+    fn preview_pick(
+        &mut self,
+        title: &str,
+        options: &[String],
+        mut index: usize,
+        preview: &dyn Fn(&mut View<'a>, usize),
+    ) -> Option<usize> {
+        loop {
+            preview(self, index);
+            self.list_tasks();
+            let (ymax, xmax) = self.window.get_max_yx();
+            let longest = options
+                .iter()
+                .map(|option| UnicodeWidthStr::width(option.as_str()))
+                .chain(Some(UnicodeWidthStr::width(title)))
+                .max()
+                .unwrap_or(0);
+            let width = (longest + 6).min(xmax);
+            let height = (options.len() + 2).min(ymax);
+            let top = (ymax - height) / 2;
+            let left = (xmax - width) / 2;
+            self.window
+                .rectangle(' ', (top + height - 1, left), (height, width));
+            self.window
+                .border((top + height - 1, left), (height, width));
+            self.window.colour_on(4, 8);
+            self.window.mvprintw(top, left + 2, title);
+            self.window.colour_off();
+
+            let rows = height - 2;
+            let offset = (index + 1).saturating_sub(rows);
+            for (row, option) in options.iter().enumerate().skip(offset).take(rows) {
+                if row == index {
+                    self.window.colour_on(0, 7);
+                }
+                let text = column::fit(option, width - 4, column::Align::Left);
+                self.window
+                    .mvprintw(top + 1 + row - offset, left + 2, &text);
+                self.window.colour_off();
+            }
+            if self.window.config.screen_reader {
+                self.window.show_cursor();
+                self.window.mv(top + 1 + index - offset, left + 1);
+            }
+            self.window.refresh();
+
+            let n = options.len();
+            match self.window.getch() {
+                Some(Key::Char('\n')) => return Some(index),
+                Some(Key::Esc) => return None,
+                Some(key)
+                    if key == self.window.config.keys.quit
+                        || key == self.window.config.keys.back =>
+                {
+                    return None
+                }
+                Some(key) if key == Key::Up || key == self.window.config.keys.up => {
+                    index = (index + n - 1) % n;
+                }
+                Some(key) if key == Key::Down || key == self.window.config.keys.down => {
+                    index = (index + 1) % n;
+                }
+                _ => (),
+            }
+        }
+    }
+
+    /// Print the notes and comment log of a task in the right-hand panel,
+    /// down to row bottom, keeping the notes and latest comments in view.
+    fn print_details(&mut self, todo: &ToDo, bottom: usize, pane: layout::Pane) {
+        let layout::Pane { x, width } = pane;
+        let top = self.first_row();
+        if todo.notes.is_empty() && todo.comments.is_empty() {
+            self.window.mvprintw(top, x, "No notes or comments");
+            return;
+        }
+
+        // Notes, keeping their line breaks, then a gap before the log
+        let mut notes = Vec::new();
+        for line in todo.notes.lines() {
+            match line.trim().is_empty() {
+                true => notes.push(String::new()),
+                false => notes.extend(wrap_words(line, width.saturating_sub(3))),
+            }
+        }
+        if !notes.is_empty() && !todo.comments.is_empty() {
+            notes.push(String::new());
+        }
+
+        // Lines of the log, as (is timestamp, text)
+        let mut lines = Vec::new();
+        for comment in todo.comments.iter() {
+            lines.push((true, comment.time.format("%Y-%m-%d %H:%M").to_string()));
+            for line in wrap_words(&comment.text, width.saturating_sub(3)) {
+                lines.push((false, line));
+            }
+        }
+
+        // The notes stay in view, with as much of the end of the log as
+        // fits below them
+        let height = (bottom + 1).saturating_sub(top);
+        let notes = notes.into_iter().map(|line| (false, line));
+        let skip = (notes.len() + lines.len()).saturating_sub(height);
+        let skip = skip.min(lines.len());
+        let lines = notes.chain(lines.into_iter().skip(skip)).take(height);
+        for (row, (timestamp, text)) in lines.enumerate() {
+            if timestamp {
+                self.window.colour_on(4, 8);
+            }
+            self.window.wrap_print(top + row, x, width, &text);
+            self.window.colour_off();
+        }
+    }
+
+    /// Print the configured columns for a task at row y, starting at
+    /// column x and filling width.
+    fn print_task(&mut self, y: usize, x: usize, width: usize, todo: &ToDo) {
+        // Ages are shown in place of due dates while toggled on
+        let columns: Vec<Column> = self
+            .window
+            .config
+            .columns
+            .iter()
+            .map(|column| match column.kind {
+                ColumnKind::Due if self.show_age => Column {
+                    kind: ColumnKind::Age,
+                    width: column.width,
+                },
+                _ => *column,
+            })
+            .collect();
+        let fixed: usize = columns
+            .iter()
+            .filter_map(|c| c.width().map(|w| w + 1))
+            .sum();
+        let upcoming = !todo.started(Local::now().date_naive());
+        let blocked = !todo.complete && deps::is_blocked(todo, &self.unfinished);
+        if upcoming || blocked {
+            self.window.dim(true);
+        }
+        let mut xpos = x;
+        for column in columns.iter() {
+            let w = match column.width() {
+                Some(w) => w,
+                None => width.saturating_sub(fixed),
+            };
+            if xpos + w > x + width {
+                break;
+            }
+
+            match column.kind {
+                ColumnKind::Checkbox => {
+                    let checkbox = if blocked { "[-]" } else { "[ ]" };
+                    let cell = column::fit(checkbox, w, column.align());
+                    self.window.mvprintw(y, xpos, &cell);
+                    if todo.complete && w >= 3 {
+                        self.window.colour_on(4, 8);
+                        self.window.mvprintw(y, xpos + 1, "X");
+                        self.window.colour_off();
+                    }
+                }
+                ColumnKind::Today => {
+                    if todo.today.is_some() && !todo.complete {
+                        self.window.colour_on(3, 8);
+                        self.window
+                            .mvprintw(y, xpos, &column::fit("*", w, column.align()));
+                        self.window.colour_off();
+                    }
+                }
+                ColumnKind::Priority => {
+                    let text = todo
+                        .priority
+                        .as_ref()
+                        .map_or(String::new(), Priority::label);
+                    self.colour_priority(&todo.priority);
+                    self.window
+                        .mvprintw(y, xpos, &column::fit(&text, w, column.align()));
+                    self.window.colour_off();
+                }
+                ColumnKind::Title => {
+                    if w > 3 {
+                        match self.tag_colour(todo) {
+                            Some(colour) => self.window.colour_choice(colour),
+                            None => self.colour_priority(&todo.priority),
+                        }
+                        // Without the priority column, only colour would show it
+                        let shown = columns.iter().any(|c| c.kind == ColumnKind::Priority);
+                        let title = match shown {
+                            true => todo.task.clone(),
+                            false => self.with_priority_cue(todo),
+                        };
+                        // Parents have how many tasks under them are done
+                        // after their title, e.g. "3/5"
+                        let progress = match todo.count_in_tree(&|_| true) {
+                            0 => String::new(),
+                            total => format!("{}/{}", todo.count_in_tree(&|t| t.complete), total),
+                        };
+                        let title_width = match progress.is_empty() {
+                            true => w,
+                            false => w.saturating_sub(progress.len() + 1).max(4),
+                        };
+                        self.window.wrap_print(y, xpos, title_width, &title);
+                        self.window.colour_off();
+                        self.print_tags(y, xpos, title_width, &title);
+                        if title_width + progress.len() < w {
+                            let config = &self.window.config;
+                            let printed = layout::truncate(
+                                &title,
+                                title_width,
+                                config.ellipsis,
+                                config.ellipsis_at,
+                            );
+                            let x = xpos + UnicodeWidthStr::width(printed.as_str()) + 1;
+                            self.window.colour_on(6, 8);
+                            self.window.mvprintw(y, x, &progress);
+                            self.window.colour_off();
+                        }
+                    }
+                }
+                ColumnKind::Due => {
+                    if let Some(due) = todo.due {
+                        let text = if todo.complete {
+                            due.date()
+                        } else {
+                            due.display()
+                        };
+                        let urgent =
+                            !todo.complete && due.remaining() <= chrono::Duration::hours(24);
+                        let cue = urgent && self.window.config.colour_blind;
+                        if urgent {
+                            self.countdown |= due.imminent();
+                            self.window.colour_fg(self.window.config.priority_high);
+                        }
+                        let text = match cue && due.remaining() > chrono::Duration::zero() {
+                            true => format!("!{}", text),
+                            false => text,
+                        };
+                        if cue {
+                            self.window.bold(true);
+                        }
+                        self.window
+                            .mvprintw(y, xpos, &column::fit(&text, w, column.align()));
+                        if cue {
+                            // Turning bold off turns dimming off too
+                            self.window.bold(false);
+                            self.window.dim(upcoming || blocked);
+                        }
+                        self.window.colour_off();
+                    } else if let Some(target) = todo.target {
+                        // Targets are shown more softly than deadlines and
+                        // never as overdue, only in another colour once passed
+                        let text = target.format("~%y-%m-%d").to_string();
+                        let passed = !todo.complete && target < Local::now().date_naive();
+                        if passed {
+                            self.window.colour_fg(self.window.config.priority_medium);
+                        }
+                        self.window.dim(true);
+                        self.window
+                            .mvprintw(y, xpos, &column::fit(&text, w, column.align()));
+                        self.window.dim(upcoming || blocked);
+                        self.window.colour_off();
+                    }
+                }
+                ColumnKind::Age => {
+                    if let Some(text) = todo.age(Local::now().naive_local()) {
+                        self.window
+                            .mvprintw(y, xpos, &column::fit(&text, w, column.align()));
+                    }
+                }
+                ColumnKind::Estimate => {
+                    let left = effort::left(todo) + effort::remaining(todo);
+                    if left > 0 {
+                        let text = effort::format(left);
+                        self.window.colour_on(6, 8);
+                        self.window
+                            .mvprintw(y, xpos, &column::fit(&text, w, column.align()));
+                        self.window.colour_off();
+                    }
+                }
+            }
+            xpos += w + 1;
+        }
+        if upcoming || blocked {
+            self.window.dim(false);
+        }
+    }
+
+    /// Print over the tags in a task's title, as printed by wrap_print, in
+    /// the tag colour.
+    fn print_tags(&mut self, y: usize, x: usize, width: usize, task: &str) {
+        let config = &self.window.config;
+        let shown = layout::truncate(task, width, config.ellipsis, config.ellipsis_at);
+        let mut xpos = x;
+        for word in shown.split(' ') {
+            // Only whole tags, not those cut off
+            let tag = word.len() > 1 && word.starts_with(['+', '#']);
+            if tag && task.split(' ').any(|w| w == word) {
+                self.window.colour_fg(self.window.config.tag_fg);
+                self.window.mvprintw(y, xpos, word);
+                self.window.colour_off();
+            }
+            xpos += UnicodeWidthStr::width(word) + 1;
+        }
+    }
+
+    /// Announce a change of state on the status line.
+    fn announce(&mut self, message: &str) {
+        let message = self.locale.tr(message);
+        info!("{}", message);
+        self.status = Some(String::from(message));
+    }
+
+    /// Title of a task, with its priority in front, e.g. "(A) Pay rent",
+    /// where colour isn't enough to show it.
+    fn with_priority_cue(&self, todo: &ToDo) -> String {
+        match &todo.priority {
+            Some(priority) if self.window.config.colour_blind => {
+                format!("{} {}", priority.label(), todo.task)
+            }
+            _ => todo.task.clone(),
+        }
+    }
+
+    /// Add priority colour to subsequent printed text: the colour for its
+    /// level if one is set, or else the high, medium or low colour.
+    fn colour_priority(&mut self, priority: &Option<Priority>) {
+        let Some(priority) = priority else {
+            return;
+        };
+        let levels = self.window.config.priority_colours;
+        if let Some(colour) = levels.get(usize::from(priority.rank())) {
+            self.window.colour_choice(colour);
+            return;
+        }
+        let colour = match priority.name(self.window.config.priority_levels) {
+            "high" => self.window.config.priority_high,
+            "medium" => self.window.config.priority_medium,
+            _ => self.window.config.priority_low,
+        };
+        self.window.colour_fg(colour);
+    }
+
+    /// Colour of a task given by the first of its tags with a colour rule,
+    /// which takes precedence over its priority colour.
+    fn tag_colour(&self, todo: &ToDo) -> Option<&'a ColourChoice> {
+        let rules = self.window.config.tag_colours;
+        todo.tags().find_map(|tag| {
+            rules
+                .iter()
+                .find(|(rule, _)| rule == tag)
+                .map(|(_, colour)| colour)
+        })
+    }
+
+    /// Describe the priority of a task in words, or by its letter on a scale
+    /// with more levels than high, medium and low.
+    fn priority_name(&self, priority: &Option<Priority>) -> String {
+        let levels = self.window.config.priority_levels;
+        match priority {
+            Some(priority) if levels > 3 => priority.letter().to_string(),
+            Some(priority) => String::from(priority.name(levels)),
+            None => String::from("none"),
+        }
+    }
+
+    /// Show a label next to each task in the tasks panel, and select the
+    /// task whose label is typed.
+    fn quick_jump(&mut self) {
+        let rows = self.pipeline().rows(&self.current_task.borrow());
+        // Rows of the shown tasks, below any headers, with their indices
+        let shown: Vec<(usize, usize)> = rows
+            .iter()
+            .enumerate()
+            .filter_map(|(row, entry)| match entry {
+                pipeline::Row::Task(i) => Some((row, *i)),
+                pipeline::Row::Header(..) => None,
+            })
+            .collect();
+        if shown.is_empty() {
+            return;
+        }
+        let labels = jump_labels(shown.len(), self.window.config.jump_labels);
+        let mut typed = String::new();
+        loop {
+            self.list_tasks();
+            self.window.colour_on(0, 3);
+            let top = self.first_row();
+            for (&(row, _), label) in shown.iter().zip(labels.iter()) {
+                if label.starts_with(&typed) {
+                    self.window.mvprintw(top + row, 1, label);
+                }
+            }
+            self.window.colour_off();
+            self.window.refresh();
+
+            match self.window.getch() {
+                Some(Key::Char(ch)) => typed.push(ch),
+                _ => return,
+            }
+            if let Some(row) = labels.iter().position(|label| *label == typed) {
+                self.selection = Some(shown[row].1);
+                return;
+            }
+            if !labels.iter().any(|label| label.starts_with(&typed)) {
+                return;
+            }
+        }
+    }
+
+    /// Mark the currently selected task, or unmark it if already marked.
+    fn toggle_mark(&mut self) {
+        if let Some(index) = self.selection {
+            let sub_task = Rc::clone(&self.current_task.borrow().sub_tasks[index]);
+            match self
+                .marked
+                .iter()
+                .position(|task| Rc::ptr_eq(task, &sub_task))
+            {
+                Some(position) => {
+                    self.marked.remove(position);
+                }
+                None => self.marked.push(sub_task),
+            }
+            self.announce(&format!("{} marked", self.marked.len()));
+        }
+    }
+
+    /// Change the priority of all marked tasks at once, returning false if
+    /// none are marked.
+    fn change_marked_priority(&mut self, increase: bool) -> bool {
+        if self.marked.is_empty() {
+            return false;
+        }
+        let prompt = format!(
+            "Change the priority of {} marked tasks? y/n",
+            self.marked.len()
+        );
+        if self.window.config.confirm.bulk && self.marked.len() > 1 && !self.popup(&prompt) {
+            return true;
+        }
+        let marked = self.marked.clone();
+        let levels = self.window.config.priority_levels;
+        for task in marked.iter() {
+            match increase {
+                true => task.borrow_mut().increase_priority(levels),
+                false => task.borrow_mut().decrease_priority(levels),
+            }
+            self.auto_sort(task);
+        }
+        self.announce(&format!(
+            "Priority {} for {} marked tasks",
+            if increase { "increased" } else { "decreased" },
+            marked.len()
+        ));
+        true
+    }
+
+    /// Increase the priority of the currently selected task, or of all
+    /// marked tasks.
+    fn increase_priority(&mut self) {
+        if self.change_marked_priority(true) {
+            return;
+        }
+        if let Some(index) = self.selection {
+            let priority = {
+                let current = self.current_task.borrow();
+                let mut sub_task = current.sub_tasks[index].borrow_mut();
+                sub_task.increase_priority(self.window.config.priority_levels);
+                sub_task.priority
+            };
+            let sub_task = Rc::clone(&self.current_task.borrow().sub_tasks[index]);
+            self.auto_sort(&sub_task);
+            self.announce(&format!("Priority: {}", self.priority_name(&priority)));
+        }
+    }
+
+    /// Decrease the priority of the currently selected task, or of all
+    /// marked tasks.
+    fn decrease_priority(&mut self) {
+        if self.change_marked_priority(false) {
+            return;
+        }
+        if let Some(index) = self.selection {
+            let priority = {
+                let current = self.current_task.borrow();
+                let mut sub_task = current.sub_tasks[index].borrow_mut();
+                sub_task.decrease_priority(self.window.config.priority_levels);
+                sub_task.priority
+            };
+            let sub_task = Rc::clone(&self.current_task.borrow().sub_tasks[index]);
+            self.auto_sort(&sub_task);
+            self.announce(&format!("Priority: {}", self.priority_name(&priority)));
+        }
+    }
+
+    /// Add new task from user input.
+    fn add_task_from_input(&mut self) {
+        self.add_task("");
+    }
+
+    /// Add a task entered in a dialogue, starting with some text.
+    fn add_task(&mut self, text: &str) {
+        let depth = nesting::depth(&self.current_task) + 1;
+        if let Err(err) = nesting::check_depth(depth, self.window.config.max_depth) {
+            return self.announce(&err);
+        }
+        let task = self.task_dialogue("New Task:", text);
+        let todo = match self.prepare_task(&task) {
+            Some(todo) => todo,
+            None => return,
+        };
+        self.call_webhooks(webhook::Event::Add, &todo);
+        let due = todo.due;
+        let todo = Rc::new(RefCell::new(todo));
+        {
+            let sub_tasks = &mut self.current_task.borrow_mut().sub_tasks;
+            sub_tasks.push(Rc::clone(&todo));
+            self.selection = Some(sub_tasks.len() - 1);
+        }
+        if due.is_some() {
+            self.auto_sort(&todo);
+        }
+        let added = self.locale.tr("Added task");
+        let task = todo.borrow().task.clone();
+        self.announce(&format!("{}: {}", added, task));
+    }
+
+    /// A new sub-task of the current task from the text entered for it,
+    /// with placeholders filled in and kept in the active context, or None
+    /// if it is similar to an open task and the user jumps to that instead.
+    fn prepare_task(&mut self, text: &str) -> Option<ToDo> {
+        let task = self.fill_placeholders(text);
+        let todo = self.new_task(&task);
+        if self.jump_to_duplicate(&todo.task) {
+            return None;
+        }
+        Some(self.keep_in_context(todo))
+    }
+
+    /// A new sub-task of the current task, taking any due date or rule
+    /// for recurring out of the text entered for it.
+    fn new_task(&self, task: &str) -> ToDo {
+        new_task(task, &self.current_task)
+    }
+
+    /// Keep a new task in the active context, so it doesn't disappear.
+    fn keep_in_context(&self, mut todo: ToDo) -> ToDo {
+        if let Some(filter) = &self.context {
+            for word in filter.split_whitespace() {
+                if !has_label(&todo, word) {
+                    todo.task = format!("{} {}", todo.task, word);
+                }
+            }
+        }
+        todo
+    }
+
+    /// Add each line of text pasted into the list as a new task, or open
+    /// the dialogue for adding a task with a single line. Progress is shown
+    /// while a long paste is read, which can be cancelled with escape, and
+    /// the tasks are added all at once at the end. Each is checked against
+    /// the open tasks like a task typed in, and those similar to one the
+    /// user jumps to are left out.
+    fn paste_tasks(&mut self) {
+        let text = match self.window.take_paste() {
+            Some(text) => text,
+            None => return,
+        };
+        if snippet::is_snippet(&text) {
+            return self.graft_snippet(&text);
+        }
+        let lines: Vec<&str> = text
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .collect();
+        match lines.len() {
+            0 => return,
+            1 => return self.add_task(lines[0]),
+            _ => (),
+        }
+
+        let mut tasks = Vec::with_capacity(lines.len());
+        for (i, chunk) in lines.chunks(PASTE_CHUNK).enumerate() {
+            let message = format!(
+                "Adding pasted tasks: {} of {} (Esc to cancel)",
+                i * PASTE_CHUNK,
+                lines.len()
+            );
+            self.print_overlay(&message);
+            if self.window.getch_timeout(Duration::ZERO) == Some(Key::Esc) {
+                self.announce("Cancelled adding pasted tasks");
+                return;
+            }
+            for line in chunk {
+                if let Some(todo) = self.prepare_task(line) {
+                    tasks.push(Rc::new(RefCell::new(todo)));
+                }
+            }
+        }
+        if tasks.is_empty() {
+            return;
+        }
+
+        if let Err(err) = nesting::check(&self.current_task, &tasks, self.window.config.max_depth) {
+            return self.announce(&err);
+        }
+        for task in tasks.iter() {
+            self.call_webhooks(webhook::Event::Add, &task.borrow());
+        }
+        let count = tasks.len();
+        {
+            let sub_tasks = &mut self.current_task.borrow_mut().sub_tasks;
+            self.selection = Some(sub_tasks.len());
+            sub_tasks.extend(tasks);
+        }
+        if self.window.config.auto_sort {
+            let selected = self.selected_task();
+            self.current_task.borrow_mut().sort_by_priority();
+            if let Some(selected) = selected {
+                self.select_task(&selected);
+            }
+        }
+        self.announce(&format!("Added {} pasted tasks", count));
+    }
+
+    /// Copy the selected task, with its sub-tasks, notes and comments, to
+    /// the clipboard as a snippet which can be pasted into another list,
+    /// or elsewhere in this one with the paste key.
+    fn share_task(&mut self) {
+        let task = match self.selected_task() {
+            Some(task) => task,
+            None => return,
+        };
+        match snippet::export(&task) {
+            Ok(text) => {
+                self.window.copy_to_clipboard(&text);
+                self.clipboard = Some(text);
+                self.cut = None;
+                let count = task.borrow().count_in_tree(&|_| true) + 1;
+                self.announce(&format!("Copied snippet of {} tasks", count));
+            }
+            Err(err) => self.announce(&format!("Unable to copy snippet: {}", err)),
+        }
+    }
+
+    /// Take the selected task, with its sub-tasks, out of the list, to
+    /// paste elsewhere in it, or as a snippet into another list.
+    fn cut_task(&mut self) {
+        let task = match self.selected_task() {
+            Some(task) => task,
+            None => return,
+        };
+        let text = match snippet::export(&task) {
+            Ok(text) => text,
+            Err(err) => return self.announce(&format!("Unable to cut task: {}", err)),
+        };
+        nesting::detach(&task);
+        {
+            let removed = task.borrow();
+            self.marked
+                .retain(|marked| !removed.any_in_tree(&|t| std::ptr::eq(t, marked.as_ptr())));
+        }
+        self.selection = None;
+        self.window.copy_to_clipboard(&text);
+        self.clipboard = Some(text);
+        let count = task.borrow().count_in_tree(&|_| true) + 1;
+        self.cut = Some(task);
+        self.announce(&format!("Cut {} tasks", count));
+    }
+
+    /// Paste the task cut or copied last under the current task. A task
+    /// cut goes back in as it was the first time, and as a copy with new
+    /// ids after that, as a copied one always does.
+    fn paste_clipboard(&mut self) {
+        let task = match self.cut.take() {
+            Some(task) => task,
+            None => match self.clipboard.clone() {
+                Some(text) => return self.graft_snippet(&text),
+                None => return self.announce("Nothing cut or copied to paste"),
+            },
+        };
+        if let Err(err) = nesting::check(
+            &self.current_task,
+            &[Rc::clone(&task)],
+            self.window.config.max_depth,
+        ) {
+            self.cut = Some(task);
+            return self.announce(&err);
+        }
+        deps::renumber_clashes(&task, &self.root_task());
+        let count = task.borrow().count_in_tree(&|_| true) + 1;
+        let end = self.current_task.borrow().sub_tasks.len();
+        self.selection = Some(nesting::attach(task, &self.current_task, end));
+        self.announce(&format!("Pasted {} tasks", count));
+    }
+
+    /// Add a copy of the selected task, with its sub-tasks, after it, all
+    /// not yet done and without their comments, time spent or ids, e.g.
+    /// to repeat the steps of a project.
+    fn duplicate_task(&mut self) {
+        let (index, task) = match (self.selection, self.selected_task()) {
+            (Some(index), Some(task)) => (index, task),
+            _ => return,
+        };
+        let now = Local::now().naive_local();
+        let copy = task
+            .borrow()
+            .fresh_copy(Rc::downgrade(&self.current_task), now);
+        let count = copy.borrow().count_in_tree(&|_| true) + 1;
+        self.current_task
+            .borrow_mut()
+            .sub_tasks
+            .insert(index + 1, copy);
+        self.selection = Some(index + 1);
+        self.announce(&match count {
+            1 => String::from("Duplicated task"),
+            count => format!("Duplicated {} tasks", count),
+        });
+    }
+
+    /// Copy a link to the selected task, which opens yat at it with
+    /// yat open, first giving the task an id if it has none.
+    fn copy_link(&mut self) {
+        let task = match self.selected_task() {
+            Some(task) => task,
+            None => return,
+        };
+        let link = link::to_task(deps::ensure_id(&task, &self.root_task()));
+        self.window.copy_to_clipboard(&link);
+        self.announce(&format!("Copied link {}", link));
+    }
+
+    /// Add the tasks in a pasted snippet under the current task, keeping
+    /// their sub-tasks and blockers between them.
+    fn graft_snippet(&mut self, text: &str) {
+        let mut tasks = Vec::new();
+        deps::collect(&self.root_task(), &mut tasks);
+        let used = tasks.iter().filter_map(|task| task.borrow().id).collect();
+        let snippet = match snippet::import(text, &used) {
+            Ok(snippet) => snippet,
+            Err(err) => return self.announce(&format!("Unable to read snippet: {}", err)),
+        };
+        let grafted = std::mem::take(&mut snippet.borrow_mut().sub_tasks);
+        if grafted.is_empty() {
+            return self.announce("No tasks in snippet");
+        }
+        if let Err(err) = nesting::check(&self.current_task, &grafted, self.window.config.max_depth)
+        {
+            return self.announce(&err);
+        }
+        for task in grafted.iter() {
+            task.borrow_mut().parent = Rc::downgrade(&self.current_task);
+        }
+        let count: usize = grafted
+            .iter()
+            .map(|task| task.borrow().count_in_tree(&|_| true) + 1)
+            .sum();
+        {
+            let sub_tasks = &mut self.current_task.borrow_mut().sub_tasks;
+            self.selection = Some(sub_tasks.len());
+            sub_tasks.extend(grafted);
+        }
+        self.announce(&format!("Added {} tasks from snippet", count));
+    }
+
+    /// Run a long operation on a worker thread, showing its progress on the
+    /// status bar until it finishes, so the screen is still redrawn, or
+    /// until escape cancels it. Other keys are ignored in the meantime.
+    fn run_worker<T: Send + 'static>(
+        &mut self,
+        doing: &str,
+        operation: impl FnOnce(&worker::Progress) -> T + Send + 'static,
+    ) -> Result<T, &'static str> {
+        let worker = worker::Worker::start(operation);
+        let mut tick = 0;
+        loop {
+            let frame = SPINNER[tick % SPINNER.len()];
+            tick += 1;
+            if let Some(result) = worker.wait(WORKER_REDRAW) {
+                return result.map_err(|_| {
+                    warn!("{} stopped unexpectedly.", doing);
+                    "stopped unexpectedly"
+                });
+            }
+            let progress = worker.progress().describe();
+            let escape = self.locale.tr("Esc to cancel");
+            self.status = Some(match progress.is_empty() {
+                true => format!("{} {} ({})", doing, frame, escape),
+                false => format!("{} {} {} ({})", doing, frame, progress, escape),
+            });
+            self.list_tasks();
+            if self.window.getch_timeout(WORKER_REDRAW) == Some(Key::Esc) {
+                info!("Cancelled: {}.", doing);
+                worker.cancel();
+                return Err("cancelled");
+            }
+        }
+    }
+
+    /// Show a message in a box over the middle of the screen, e.g. the
+    /// progress of something slow.
+    fn print_overlay(&mut self, message: &str) {
+        let (ymax, xmax) = self.window.get_max_yx();
+        let message: String = message.chars().take(xmax.saturating_sub(6)).collect();
+        let width = UnicodeWidthStr::width(message.as_str()) + 4;
+        let x = (xmax - width) / 2;
+        let y = ymax / 2 + 1;
+        self.window.colour_fg(self.window.config.border_edit);
+        self.window.border((y, x), (3, width));
+        self.window.colour_off();
+        self.window.rectangle(' ', (y - 1, x + 1), (1, width - 2));
+        self.window.mvprintw(y - 1, x + 2, &message);
+        self.window.refresh();
+    }
+
+    /// Fill in placeholders such as {date} or {week} in a new task.
+    fn fill_placeholders(&self, task: &str) -> String {
+        let today = Local::now().date_naive();
+        let name = self.list_name();
+        let list = Path::new(&name)
+            .file_stem()
+            .map_or(name.clone(), |stem| stem.to_string_lossy().into_owned());
+        let values = template::Values {
+            today,
+            week: self.locale.week_number(today),
+            list: &list,
+        };
+        template::expand(task, &values)
+    }
+
+    /// Warn if a new task closely matches an open task in the list, and
+    /// offer to jump to that task instead of adding the new one. Returns
+    /// whether the new task was dropped.
+    fn jump_to_duplicate(&mut self, task: &str) -> bool {
+        if task.trim().is_empty() {
+            return false;
+        }
+        let threshold = self.window.config.duplicate_threshold;
+        let existing = match duplicate::find(&self.root_task(), task, threshold) {
+            Some(existing) => existing,
+            None => return false,
+        };
+        let existing = existing.borrow();
+        let title: String = existing.task.chars().take(40).collect();
+        let prompt = format!(
+            "Similar to open task \"{}\". Jump to it instead? y/n",
+            title
+        );
+        if self.popup(&prompt) {
+            let message = format!("Existing task: {}", existing.task);
+            self.jump = Some((existing.index_path(), message));
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Mark task as completed.
+    fn complete_task(&mut self) {
+        if let Some(index) = self.selection {
+            let task = Rc::clone(&self.current_task.borrow().sub_tasks[index]);
+            self.toggle_complete(&task);
+        }
+    }
+
+    /// Mark a task complete (or not), adding its next occurrence after it
+    /// if it recurs, and completing the tasks above it which are finished
+    /// if configured to. Completing a task which is still blocked has to be
+    /// confirmed first.
+    fn toggle_complete(&mut self, task: &Rc<RefCell<ToDo>>) {
+        if !task.borrow().complete {
+            let blockers = self.unfinished_blockers(&task.borrow());
+            if !blockers.is_empty() {
+                let blocked_by = match blockers.as_slice() {
+                    [blocker] => format!("\"{}\"", blocker.chars().take(40).collect::<String>()),
+                    _ => format!("{} unfinished tasks", blockers.len()),
+                };
+                let prompt = format!("Blocked by {}. Complete anyway? y/n", blocked_by);
+                if !self.popup(&prompt) {
+                    return;
+                }
+            }
+        }
+        let complete = task.borrow_mut().toggle_complete();
+        let (next, timed) = match complete {
+            true => {
+                // Finishing a task stops its timer
+                let timed = task.borrow_mut().stop_timer(Local::now().naive_local());
+                (self.add_next_occurrence(task), timed)
+            }
+            false => (None, None),
+        };
+        let parents = match complete && self.window.config.auto_complete_parents {
+            true => self.complete_parents(task),
+            false => Vec::new(),
+        };
+        self.announce_complete(&task.borrow(), complete, next, timed, &parents);
+    }
+
+    /// Mark the tasks above a task complete in turn, for as long as all of
+    /// their sub-tasks are, returning their descriptions.
+    fn complete_parents(&mut self, task: &Rc<RefCell<ToDo>>) -> Vec<String> {
+        let mut completed = Vec::new();
+        let mut parent = task.borrow().parent.upgrade();
+        while let Some(task) = parent {
+            let finished = {
+                let todo = task.borrow();
+                // The root of the list isn't a task, and tasks not read yet
+                // may not be finished
+                todo.parent.upgrade().is_some()
+                    && !todo.complete
+                    && todo.unread.is_empty()
+                    && todo
+                        .sub_tasks
+                        .iter()
+                        .all(|sub_task| sub_task.borrow().complete)
+            };
+            if !finished {
+                break;
+            }
+            task.borrow_mut().toggle_complete();
+            task.borrow_mut().stop_timer(Local::now().naive_local());
+            self.add_next_occurrence(&task);
+            self.call_webhooks(webhook::Event::Complete, &task.borrow());
+            completed.push(task.borrow().task.clone());
+            parent = task.borrow().parent.upgrade();
+        }
+        completed
+    }
+
+    /// Descriptions of the unfinished tasks which a task is waiting on.
+    fn unfinished_blockers(&self, task: &ToDo) -> Vec<String> {
+        let mut tasks = Vec::new();
+        deps::collect(&self.root_task(), &mut tasks);
+        tasks
+            .iter()
+            .map(|t| t.borrow())
+            .filter(|t| !t.complete && t.id.is_some_and(|id| task.blocked_by.contains(&id)))
+            .map(|t| t.task.clone())
+            .collect()
+    }
+
+    /// Descriptions of the tasks waiting on a task which aren't waiting on
+    /// any others now it's complete.
+    fn unblocked_by(&self, task: &ToDo) -> Vec<String> {
+        let id = match task.id {
+            Some(id) => id,
+            None => return Vec::new(),
+        };
+        let root = self.root_task();
+        let unfinished = deps::unfinished(&root);
+        let mut tasks = Vec::new();
+        deps::collect(&root, &mut tasks);
+        tasks
+            .iter()
+            .map(|t| t.borrow())
+            .filter(|t| !t.complete && t.blocked_by.contains(&id))
+            .filter(|t| !deps::is_blocked(t, &unfinished))
+            .map(|t| t.task.clone())
+            .collect()
+    }
+
+    /// Make the selected task wait on the marked tasks, or if none are
+    /// marked, on a task chosen from the unfinished tasks in the list (or
+    /// on none).
+    fn block_task(&mut self) {
+        let task = match self.selected_task() {
+            Some(task) => task,
+            None => return,
+        };
+        let blockers: Vec<_> = match self.marked.is_empty() {
+            false => std::mem::take(&mut self.marked)
+                .into_iter()
+                .filter(|blocker| !Rc::ptr_eq(blocker, &task))
+                .collect(),
+            true => {
+                let mut tasks = Vec::new();
+                deps::collect(&self.root_task(), &mut tasks);
+                let candidates: Vec<_> = tasks
+                    .into_iter()
+                    .skip(1)
+                    .filter(|t| !Rc::ptr_eq(t, &task) && !t.borrow().complete)
+                    .collect();
+                let mut options = vec![String::from("Not blocked")];
+                options.extend(candidates.iter().map(|t| {
+                    let mut path = t.borrow().task.clone();
+                    t.borrow().task_path(&mut path);
+                    String::from(path.trim_start_matches(": "))
+                }));
+                match self.pick("Blocked by", &options, 0) {
+                    Some(0) => {
+                        task.borrow_mut().blocked_by.clear();
+                        return self.announce("No longer blocked");
+                    }
+                    Some(choice) => vec![Rc::clone(&candidates[choice - 1])],
+                    None => return,
+                }
+            }
+        };
+        if blockers.is_empty() {
+            return;
+        }
+        let root = self.root_task();
+        for blocker in blockers.iter() {
+            let id = deps::ensure_id(blocker, &root);
+            let mut task = task.borrow_mut();
+            if !task.blocked_by.contains(&id) {
+                task.blocked_by.push(id);
+            }
+        }
+        let names: Vec<String> = blockers.iter().map(|b| b.borrow().task.clone()).collect();
+        self.announce(&format!("Blocked by {}", names.join(", ")));
+    }
+
+    /// Add the next occurrence of a recurring task after it, returning when
+    /// it is due. The completed task no longer recurs, so marking it
+    /// complete again doesn't add another.
+    fn add_next_occurrence(&mut self, task: &Rc<RefCell<ToDo>>) -> Option<Due> {
+        let next = task.borrow().next_occurrence(Local::now().naive_local())?;
+        let parent = task.borrow().parent.upgrade()?;
+        let position = parent
+            .borrow()
+            .sub_tasks
+            .iter()
+            .position(|sub_task| Rc::ptr_eq(sub_task, task))?;
+        {
+            let mut task = task.borrow_mut();
+            task.repeat = None;
+            task.skip.clear();
+            task.moved = None;
+        }
+        parent
+            .borrow_mut()
+            .sub_tasks
+            .insert(position + 1, Rc::clone(&next));
+        if Rc::ptr_eq(&parent, &self.current_task) {
+            if let Some(index) = self.selection.filter(|&index| index > position) {
+                self.selection = Some(index + 1);
+            }
+        }
+        self.auto_sort(&next);
+        let due = next.borrow().due;
+        due
+    }
+
+    /// Announce a task being marked complete (or not), along with when its
+    /// next occurrence is due if it recurs, scoring any habits on Habitica
+    /// for its tags and calling any webhooks.
+    fn announce_complete(
+        &mut self,
+        task: &ToDo,
+        complete: bool,
+        next: Option<Due>,
+        timed: Option<u32>,
+        parents: &[String],
+    ) {
+        if complete {
+            self.call_webhooks(webhook::Event::Complete, task);
+        }
+        let mut message = String::from(self.locale.tr(match complete {
+            true => "Marked complete",
+            false => "Marked not complete",
+        }));
+        if let Some(next) = next {
+            message = format!("{}, next due {}", message, next.date());
+        }
+        if let Some(minutes) = timed {
+            message = format!("{}, timer stopped at {}", message, effort::format(minutes));
+        }
+        let unblocked = match complete {
+            true => self.unblocked_by(task),
+            false => Vec::new(),
+        };
+        if !unblocked.is_empty() {
+            message = format!("{}, unblocking {}", message, unblocked.join(", "));
+        }
+        if !parents.is_empty() {
+            message = format!("{}, completing {}", message, parents.join(", "));
+        }
+        if let (true, Some(config)) = (complete, self.window.config.habitica) {
+            let habits = habitica::habits(config, task);
+            if !habits.is_empty() {
+                habitica::score(config, &habits);
+                message = format!("{} (scored {} on Habitica)", message, habits.join(", "));
+            }
+        }
+        self.announce(&message);
+    }
+
+    /// Tell the webhooks configured for an event about a task.
+    fn call_webhooks(&self, event: webhook::Event, task: &ToDo) {
+        if let Some(config) = self.window.config.webhooks {
+            let urls = match event {
+                webhook::Event::Add => &config.add,
+                webhook::Event::Complete => &config.complete,
+            };
+            if !urls.is_empty() {
+                webhook::post(urls, webhook::payload(event, task, &self.list_name()));
+            }
+        }
+    }
+
+    /// Show only the selected task, with a timer, until the user leaves
+    /// focus mode.
+    fn focus_mode(&mut self) {
+        if let Some(index) = self.selection {
+            let task = Rc::clone(&self.current_task.borrow().sub_tasks[index]);
+            let start = Instant::now();
+            info!("Entered focus mode.");
+            loop {
+                self.print_focus(&task.borrow(), start.elapsed());
+                match self.window.getch_timeout(Duration::from_secs(1)) {
+                    Some(key) if key == self.window.config.keys.complete => self.complete_task(),
+                    Some(key)
+                        if key == self.window.config.keys.focus_mode
+                            || key == self.window.config.keys.back
+                            || key == self.window.config.keys.quit =>
+                    {
+                        break
+                    }
+                    _ => (),
+                }
+            }
+
+            // Round time spent to the nearest minute, unless the task's
+            // timer is already counting it
+            let minutes = (start.elapsed().as_secs() + 30) / 60;
+            if task.borrow().timer.is_none() {
+                task.borrow_mut().spent += minutes as u32;
+            }
+            self.announce(&format!(
+                "Left focus mode: {} spent",
+                effort::format(minutes as u32)
+            ));
+        }
+    }
+
+    /// Display a task on its own, centred on the screen, with time spent in
+    /// focus mode.
+    fn print_focus(&mut self, todo: &ToDo, elapsed: Duration) {
+        self.window.clear();
+        self.window.hide_cursor();
+
+        let (ymax, xmax) = self.window.get_max_yx();
+        let width = (xmax * 2 / 3).max(24).min(xmax);
+        let title = wrap_words(&self.with_priority_cue(todo), width - 4);
+        let sub_tasks: Vec<String> = match self.window.config.focus_subtasks {
+            true => todo
+                .sub_tasks
+                .iter()
+                .filter(|t| self.shown(&t.borrow()))
+                .map(|t| {
+                    let t = t.borrow();
+                    format!("[{}] {}", if t.complete { 'X' } else { ' ' }, t.task)
+                })
+                .collect(),
+            false => Vec::new(),
+        };
+        let mut height = title.len() + 5;
+        if !sub_tasks.is_empty() {
+            height += sub_tasks.len() + 1;
+        }
+        let height = height.min(ymax);
+        let top = (ymax - height) / 2;
+        let left = (xmax - width) / 2;
+        self.window
+            .border((top + height - 1, left), (height, width));
+
+        let mut y = top + 2;
+        self.colour_priority(&todo.priority);
+        for line in title.iter() {
+            let len = UnicodeWidthStr::width(line.as_str());
+            self.window
+                .mvprintw(y, left + width.saturating_sub(len) / 2, line);
+            y += 1;
+        }
+        self.window.colour_off();
+
+        if !sub_tasks.is_empty() {
+            y += 1;
+            for sub_task in sub_tasks.iter() {
+                if y + 3 >= top + height {
+                    break;
+                }
+                self.window.wrap_print(y, left + 4, width - 8, sub_task);
+                y += 1;
+            }
+        }
+
+        let secs = elapsed.as_secs();
+        let mut timer = format!("{:02}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60);
+        if todo.complete {
+            timer.push_str("  [X]");
+        }
+        if let Some(estimate) = todo.estimate {
+            timer.push_str(&format!("  of {}", effort::format(estimate)));
+        }
+        if let Some(due) = todo.due {
+            timer.push_str(&format!("  due {}", due.display()));
+        }
+        if let Some(target) = todo.target {
+            timer.push_str(&format!("  target {}", target.format("%Y-%m-%d")));
+        }
+        let len = UnicodeWidthStr::width(timer.as_str());
+        self.window.colour_on(4, 8);
+        self.window.wrap_print(
+            top + height - 2,
+            left + (width - len.min(width - 4)) / 2,
+            width - 4,
+            &timer,
+        );
+        self.window.colour_off();
+        self.window.refresh();
+    }
+
+    /// Walk through each project (top-level task), showing tasks added,
+    /// completed and stalled in the last week, with quick actions on them.
+    fn weekly_review(&mut self) {
+        let projects: Vec<Rc<RefCell<ToDo>>> = self
+            .root_task()
+            .borrow()
+            .sub_tasks
+            .iter()
+            .filter(|project| self.shown(&project.borrow()))
+            .cloned()
+            .collect();
+        if projects.is_empty() {
+            self.announce("Nothing to review");
+            return;
+        }
+
+        info!("Started weekly review.");
+        let mut project = 0;
+        let mut cursor = 0;
+        loop {
+            let mut items = review::items(&projects[project], Local::now().date_naive());
+            items.retain(|(_, task)| self.in_context(&task.borrow()));
+            cursor = cursor.min(items.len().saturating_sub(1));
+            let title = format!(
+                "Weekly review: {} ({}/{})",
+                projects[project].borrow().task,
+                project + 1,
+                projects.len()
+            );
+            self.print_review(&title, &items, cursor);
+
+            let key = match self.window.getch() {
+                Some(key) => key,
+                None => continue,
+            };
+            self.status = None;
+            let task = items.get(cursor).map(|(_, task)| Rc::clone(task));
+            match key {
+                key if key == self.window.config.keys.quit
+                    || key == self.window.config.keys.review =>
+                {
+                    break
+                }
+                key if key == Key::Right || key == self.window.config.keys.focus => {
+                    project = (project + 1) % projects.len();
+                    cursor = 0;
+                }
+                key if key == Key::Left || key == self.window.config.keys.back => {
+                    project = (project + projects.len() - 1) % projects.len();
+                    cursor = 0;
+                }
+                key if key == Key::Up || key == self.window.config.keys.up => {
+                    cursor = cursor.saturating_sub(1 + self.window.take_repeats(key));
+                }
+                key if key == Key::Down || key == self.window.config.keys.down => {
+                    cursor += 1 + self.window.take_repeats(key);
+                }
+                key if key == self.window.config.keys.complete => {
+                    if let Some(task) = task {
+                        self.toggle_complete(&task);
+                    }
+                }
+                key if key == self.window.config.keys.today => {
+                    if let Some(task) = task {
+                        let mut task = task.borrow_mut();
+                        task.today = match task.today {
+                            Some(_) => None,
+                            None => Some(Local::now().date_naive()),
+                        };
+                    }
+                }
+                key if key == self.window.config.keys.increase => {
+                    if let Some(task) = task {
+                        task.borrow_mut()
+                            .increase_priority(self.window.config.priority_levels);
+                        self.auto_sort(&task);
+                    }
+                }
+                key if key == self.window.config.keys.decrease => {
+                    if let Some(task) = task {
+                        task.borrow_mut()
+                            .decrease_priority(self.window.config.priority_levels);
+                        self.auto_sort(&task);
+                    }
+                }
+                _ => (),
+            }
+        }
+        self.announce("Finished weekly review");
+    }
+
+    /// Display the weekly review of a project, with the cursor on one of
+    /// the tasks.
+    fn print_review(
+        &mut self,
+        title: &str,
+        items: &[(review::Section, Rc<RefCell<ToDo>>)],
+        cursor: usize,
+    ) {
+        self.window.clear();
+        self.window.hide_cursor();
+
+        let (ymax, xmax) = self.window.get_max_yx();
+        self.window.border((ymax - 1, 0), (ymax, xmax));
+        self.window.colour_on(4, 8);
+        self.window.wrap_print(1, 2, xmax - 4, title);
+        self.window.colour_off();
+
+        // Lines of the review, as (heading, task index), so the cursor can
+        // be kept on screen
+        let mut lines: Vec<(Option<review::Section>, Option<usize>)> = Vec::new();
+        for section in review::SECTIONS.iter() {
+            lines.push((Some(*section), None));
+            let start = lines.len();
+            for (i, (item_section, _)) in items.iter().enumerate() {
+                if item_section == section {
+                    lines.push((None, Some(i)));
+                }
+            }
+            if lines.len() == start {
+                lines.push((None, None));
+            }
+        }
+
+        let height = ymax.saturating_sub(6);
+        let cursor_line = lines
+            .iter()
+            .position(|&(_, index)| index == Some(cursor))
+            .unwrap_or(0);
+        let offset = (cursor_line + 1).saturating_sub(height);
+        for (row, line) in lines.iter().skip(offset).take(height).enumerate() {
+            let y = 3 + row;
+            match line {
+                (Some(section), _) => {
+                    let count = items.iter().filter(|(s, _)| s == section).count();
+                    self.window.colour_on(6, 8);
+                    self.window.wrap_print(
+                        y,
+                        2,
+                        xmax - 4,
+                        &format!("{} ({})", section.heading(), count),
+                    );
+                    self.window.colour_off();
+                }
+                (None, Some(index)) => {
+                    if *index == cursor {
+                        self.window.mvprintw(y, 2, ">");
+                    }
+                    let task = Rc::clone(&items[*index].1);
+                    self.print_task(y, 4, xmax - 6, &task.borrow());
+                }
+                (None, None) => self.window.mvprintw(y, 4, "(none)"),
+            }
+        }
+
+        let help = match &self.status {
+            Some(status) => status.clone(),
+            None => format!(
+                "Left/Right project, Up/Down task, {} complete, {} today, {}/{} priority, {} finish",
+                key_name(self.window.config.keys.complete),
+                key_name(self.window.config.keys.today),
+                key_name(self.window.config.keys.increase),
+                key_name(self.window.config.keys.decrease),
+                key_name(self.window.config.keys.review),
+            ),
+        };
+        self.window.wrap_print(ymax - 2, 2, xmax - 4, &help);
+        self.window.refresh();
+    }
+
+    /// Show estimated against actual time spent for each project
+    /// (top-level task), until the user closes the report.
+    fn time_report(&mut self) {
+        let root = self.root_task();
+        let mut rows = Vec::new();
+        for project in root.borrow().sub_tasks.iter() {
+            let project = project.borrow();
+            if !self.shown(&project) {
+                continue;
+            }
+            let mut totals = effort::Totals::default();
+            totals.add_tree(&project, &|t| self.in_context(t));
+            rows.push((project.task.clone(), totals));
+        }
+        if rows
+            .iter()
+            .all(|(_, totals)| totals.estimate == 0 && totals.spent == 0)
+        {
+            self.announce("No estimates or time spent to report");
+            return;
+        }
+
+        info!("Opened time report.");
+        let mut offset = 0;
+        loop {
+            offset = offset.min(rows.len().saturating_sub(1));
+            self.print_report(&rows, offset);
+            match self.window.getch() {
+                Some(key)
+                    if key == self.window.config.keys.quit
+                        || key == self.window.config.keys.report =>
+                {
+                    break
+                }
+                Some(key) if key == Key::Up || key == self.window.config.keys.up => {
+                    offset = offset.saturating_sub(1 + self.window.take_repeats(key));
+                }
+                Some(key) if key == Key::Down || key == self.window.config.keys.down => {
+                    offset += 1 + self.window.take_repeats(key);
+                }
+                _ => (),
+            }
+        }
+    }
+
+    /// Display the whole todo list, read-only, reloading it whenever the
+    /// save file changes, until the quit key is pressed. Meant for leaving
+    /// on a shared screen.
+    pub fn watch(&mut self) {
+        info!("Watching save file.");
+        self.read_only = true;
+        // Changes may only be in the journal, if kept
+        let journal = self.window.config.journal;
+        let modified = |path: &Option<PathBuf>| {
+            let path = path.as_ref()?;
+            let save = metadata(path).and_then(|data| data.modified()).ok();
+            let changes = match journal {
+                true => metadata(journal::path(path))
+                    .and_then(|data| data.modified())
+                    .ok(),
+                false => None,
+            };
+            Some((save, changes))
+        };
+        let mut last_modified = modified(&self.save_file);
+        let mut updated = Local::now().naive_local();
+        loop {
+            self.print_watch(updated);
+            match self.window.getch_timeout(Duration::from_secs(1)) {
+                Some(key) if key == self.window.config.keys.quit => break,
+                _ => (),
+            }
+
+            let now_modified = modified(&self.save_file);
+            if now_modified != last_modified {
+                last_modified = now_modified;
+                if let Some(filename) = self.save_file.clone() {
+                    if let Ok(mut buf) = Self::load(filename.clone()) {
+                        if journal {
+                            buf = journal::replay(&filename, &buf);
+                        }
+                        let root = ToDo::new("", Weak::new());
+                        self.current_task = Rc::new(RefCell::new(root));
+                        self.fill_from_buffer(&buf);
+                        updated = Local::now().naive_local();
+                        info!("Reloaded save file.");
+                    }
+                }
+            }
+        }
+        self.window.endwin();
+    }
+
+    /// Display the open tasks of the whole todo list as an outline, with
+    /// the time it was last loaded.
+    fn print_watch(&mut self, updated: NaiveDateTime) {
+        self.window.clear();
+        self.window.hide_cursor();
+
+        let (ymax, xmax) = self.window.get_max_yx();
+        self.window.border((ymax - 1, 0), (ymax, xmax));
+        let title = format!(
+            "{} (updated {})",
+            self.list_name(),
+            updated.format("%Y-%m-%d %H:%M:%S")
+        );
+        self.window.colour_on(4, 8);
+        self.window.wrap_print(1, 2, xmax - 4, &title);
+        self.window.colour_off();
+
+        let mut rows = Vec::new();
+        self.outline(&self.root_task(), 0, &mut rows);
+        if rows.is_empty() {
+            self.window.wrap_print(3, 2, xmax - 4, "Nothing to do");
+        }
+        let height = ymax.saturating_sub(4);
+        for (row, (depth, task)) in rows.iter().take(height).enumerate() {
+            let todo = task.borrow();
+            let indent = (2 * depth).min(xmax / 2);
+            let width = (xmax - 4).saturating_sub(indent);
+            let mut text = format!("[ ] {}", self.with_priority_cue(&todo));
+            if let Some(due) = todo.due {
+                text = format!("{} ({})", text, due.display());
+            }
+            match self.tag_colour(&todo) {
+                Some(colour) => self.window.colour_choice(colour),
+                None => self.colour_priority(&todo.priority),
+            }
+            self.window.wrap_print(3 + row, 2 + indent, width, &text);
+            self.window.colour_off();
+        }
+        if rows.len() > height {
+            let more = format!("... and {} more", rows.len() - height + 1);
+            self.window.wrap_print(ymax - 2, 2, xmax - 4, &more);
+        }
+        self.window.refresh();
+    }
+
+    /// Collect the open sub-tasks of a task, depth first, with their depth
+    /// in the tree.
+    fn outline(
+        &self,
+        task: &Rc<RefCell<ToDo>>,
+        depth: usize,
+        rows: &mut Vec<(usize, Rc<RefCell<ToDo>>)>,
+    ) {
+        for sub_task in task.borrow().sub_tasks.iter() {
+            if !sub_task.borrow().complete && self.shown(&sub_task.borrow()) {
+                rows.push((depth, Rc::clone(sub_task)));
+                self.outline(sub_task, depth + 1, rows);
+            }
+        }
+    }
+
+    /// Display the time report, with rows of (project, totals), scrolled
+    /// down to a given row.
+    fn print_report(&mut self, rows: &[(String, effort::Totals)], offset: usize) {
+        self.window.clear();
+        self.window.hide_cursor();
+
+        let (ymax, xmax) = self.window.get_max_yx();
+        self.window.border((ymax - 1, 0), (ymax, xmax));
+        self.window.colour_on(4, 8);
+        self.window
+            .wrap_print(1, 2, xmax - 4, "Time report: estimated against actual");
+        self.window.colour_off();
+
+        let width = xmax.saturating_sub(4);
+        // Leave room for the columns, as wrap_print truncates long lines
+        let title_width = width.saturating_sub(33);
+        let line = |title: &str, estimate: &str, spent: &str, difference: &str| {
+            format!(
+                "{} {:>9} {:>9} {:>9}",
+                column::fit(title, title_width, column::Align::Left),
+                estimate,
+                spent,
+                difference
+            )
+        };
+        self.window.colour_on(6, 8);
+        self.window
+            .wrap_print(3, 2, width, &line("Project", "Estimate", "Actual", "Diff"));
+        self.window.colour_off();
+
+        let mut total = effort::Totals::default();
+        for (_, totals) in rows.iter() {
+            total.combine(totals);
+        }
+        let height = ymax.saturating_sub(10);
+        for (row, (title, totals)) in rows.iter().skip(offset).take(height).enumerate() {
+            let text = line(
+                title,
+                &effort::format(totals.estimate),
+                &effort::format(totals.spent),
+                &effort::format_difference(totals.estimate, totals.spent),
+            );
+            self.window.wrap_print(4 + row, 2, width, &text);
+        }
+
+        let y = 5 + height.min(rows.len());
+        let text = line(
+            "Total",
+            &effort::format(total.estimate),
+            &effort::format(total.spent),
+            &effort::format_difference(total.estimate, total.spent),
+        );
+        self.window.colour_on(6, 8);
+        self.window.wrap_print(y, 2, width, &text);
+        self.window.colour_off();
+
+        let accuracy = match total.accuracy() {
+            Some(percent) => format!(
+                "{} completed tasks with estimates took {}% of the time estimated",
+                total.done, percent
+            ),
+            None => String::from("No completed tasks with estimates yet"),
+        };
+        self.window.wrap_print(y + 2, 2, width, &accuracy);
+
+        let help = format!(
+            "Up/Down scroll, {} close",
+            key_name(self.window.config.keys.report)
+        );
+        self.window.wrap_print(ymax - 2, 2, xmax - 4, &help);
+        self.window.refresh();
+    }
+
+    /// Show the tasks of the selected sub-task in dependency order, marking
+    /// the critical path and any cycles, until the user closes the view.
+    fn dependency_graph(&mut self) {
+        let task = match self.selection {
+            Some(index) => Rc::clone(&self.current_task.borrow().sub_tasks[index]),
+            None => return,
+        };
+        let graph = deps::Graph::new(&task, &self.root_task());
+        if graph.is_empty() {
+            self.announce("No dependencies in this task");
+            return;
+        }
+
+        info!("Opened dependency graph.");
+        let title = format!("Dependencies: {}", task.borrow().task);
+        let mut offset = 0;
+        loop {
+            offset = offset.min(graph.tasks.len().saturating_sub(1));
+            self.print_graph(&title, &graph, offset);
+            match self.window.getch() {
+                Some(key)
+                    if key == self.window.config.keys.quit
+                        || key == self.window.config.keys.graph =>
+                {
+                    break
+                }
+                Some(key) if key == Key::Up || key == self.window.config.keys.up => {
+                    offset = offset.saturating_sub(1 + self.window.take_repeats(key));
+                }
+                Some(key) if key == Key::Down || key == self.window.config.keys.down => {
+                    offset += 1 + self.window.take_repeats(key);
+                }
+                _ => (),
+            }
+        }
+    }
+
+    /// Send the selected task by email, to the configured address or another
+    /// typed in.
+    fn email_task(&mut self) {
+        let task = match self.selection {
+            Some(index) => Rc::clone(&self.current_task.borrow().sub_tasks[index]),
+            None => return,
+        };
+        let config = match self.window.config.email {
+            Some(config) => config,
+            None => {
+                self.announce("No [email] section in config.toml");
+                return;
+            }
+        };
+        let to = self.dialogue("Send task to:", &config.to);
+        let to = to.trim();
+        if to.is_empty() {
+            return;
+        }
+
+        let message = email::message(config, to, &task.borrow());
+        match email::send(config, to, &message) {
+            Ok(()) => self.announce(&format!("Sent \"{}\" to {}", task.borrow().task, to)),
+            Err(err) => self.announce(&format!("Unable to send email: {}", err)),
+        }
+    }
+
+    /// Display a dependency graph as a list sorted so each task comes after
+    /// the tasks blocking it, indented by how many come before it, scrolled
+    /// down to a given row.
+    fn print_graph(&mut self, title: &str, graph: &deps::Graph, offset: usize) {
+        self.window.clear();
+        self.window.hide_cursor();
+
+        let (ymax, xmax) = self.window.get_max_yx();
+        self.window.border((ymax - 1, 0), (ymax, xmax));
+        self.window.colour_on(4, 8);
+        self.window.wrap_print(1, 2, xmax - 4, title);
+        self.window.colour_off();
+
+        let critical = graph.critical_path();
+        let height = ymax.saturating_sub(6);
+        for (row, &(index, level)) in graph.sorted().iter().skip(offset).take(height).enumerate() {
+            let y = 3 + row;
+            let cycle = graph.in_cycle(index);
+            if cycle {
+                self.window.colour_on(1, 8);
+                self.window.mvprintw(y, 2, "!");
+                self.window.colour_off();
+            } else if critical.contains(&index) {
+                self.window.colour_on(3, 8);
+                self.window.mvprintw(y, 2, "*");
+                self.window.colour_off();
+            }
+
+            let todo = graph.tasks[index].borrow();
+            let mut after: Vec<String> = graph
+                .blockers(index)
+                .iter()
+                .map(|&blocker| graph.tasks[blocker].borrow().task.clone())
+                .collect();
+            after.extend(graph.outside[index].iter().cloned());
+            let mut text = format!(
+                "{}[{}] {}",
+                "  ".repeat(level),
+                if todo.complete { 'X' } else { ' ' },
+                todo.task
+            );
+            if !after.is_empty() {
+                text.push_str(&format!("  (after {})", after.join(", ")));
+            }
+            if cycle {
+                text.push_str("  (cycle)");
+            }
+            self.window.wrap_print(y, 4, xmax - 6, &text);
+        }
+
+        let help = format!(
+            "* critical path, ! cycle, Up/Down scroll, {} close",
+            key_name(self.window.config.keys.graph)
+        );
+        self.window.wrap_print(ymax - 2, 2, xmax - 4, &help);
+        self.window.refresh();
+    }
+
+    /// Choose a theme, previewing each on the screen, and write the chosen
+    /// one to config.toml.
+    fn pick_theme(&mut self) {
+        let themes = theme::available();
+        let loaded: Vec<_> = themes.iter().map(|(_, text)| theme::load(text)).collect();
+        let current = self.window.config.theme();
+        let mut options = vec![String::from("Current theme")];
+        options.extend(themes.iter().map(|(name, _)| name.clone()));
+
+        let preview = |view: &mut View<'a>, index: usize| {
+            let theme = match index {
+                0 => Some(current),
+                _ => loaded[index - 1],
+            };
+            view.window.config.set_theme(&theme.unwrap_or(current));
+        };
+        let choice = self.preview_pick("Theme", &options, 0, &preview);
+        let (name, text) = match choice {
+            Some(choice) if choice > 0 && loaded[choice - 1].is_some() => &themes[choice - 1],
+            _ => {
+                self.window.config.set_theme(&current);
+                return;
+            }
+        };
+
+        let result = match self.window.config.config_file {
+            Some(filename) => theme::write(filename, text),
+            None => Err(String::from("no configuration file")),
+        };
+        match result {
+            Ok(()) => self.announce(&format!("Theme: {}", name)),
+            Err(err) => {
+                warn!("Unable to write theme: {}", err);
+                self.announce(&format!("Unable to write theme: {}", err));
+            }
+        }
+    }
+
+    /// Show how many tasks were completed each day over the last year,
+    /// counting those archived too, until the user closes the view.
+    fn completion_heatmap(&mut self) {
+        let today = Local::now().date_naive();
+        let mut heatmap = heatmap::Heatmap::new(self.locale.week_start(today), today);
+        heatmap.add_tree(&self.root_task().borrow());
+        if let Some(filename) = self.save_file.as_ref().map(|path| archive::path(path)) {
+            match archive::load(&filename).map(|buf| parse_list(&buf)) {
+                Ok(Ok(archived)) => heatmap.add_tree(&archived.borrow()),
+                Ok(Err(err)) => warn!("Unable to parse {}: {}", filename.display(), err),
+                Err(err) => warn!("Unable to read {}: {}", filename.display(), err),
+            }
+        }
+
+        info!("Opened completion heatmap.");
+        loop {
+            self.print_heatmap(&heatmap);
+            match self.window.getch() {
+                Some(key)
+                    if key == self.window.config.keys.quit
+                        || key == self.window.config.keys.heatmap =>
+                {
+                    break
+                }
+                _ => (),
+            }
+        }
+    }
+
+    /// Display a heatmap as a row for each day of the week and a column
+    /// for each week, as many of the latest weeks as fit, shaded and
+    /// coloured by how many tasks were completed.
+    fn print_heatmap(&mut self, heatmap: &heatmap::Heatmap) {
+        self.window.clear();
+        self.window.hide_cursor();
+
+        let (ymax, xmax) = self.window.get_max_yx();
+        self.window.border((ymax - 1, 0), (ymax, xmax));
+        self.window.colour_on(4, 8);
+        self.window
+            .wrap_print(1, 2, xmax - 4, self.locale.tr("Completed tasks"));
+        self.window.colour_off();
+
+        // Two characters a day if the whole year fits, after the day names
+        let width = xmax.saturating_sub(8);
+        let cell = if width >= 2 * heatmap::WEEKS { 2 } else { 1 };
+        let weeks = (width / cell).min(heatmap::WEEKS);
+        let first_week = heatmap::WEEKS - weeks;
+        let ascii = self.window.config.ascii;
+
+        let mut label_end = 0;
+        for week in first_week..heatmap::WEEKS {
+            let x = 6 + (week - first_week) * cell;
+            let start = heatmap.first + chrono::Duration::weeks(week as i64);
+            let previous = start - chrono::Duration::weeks(1);
+            if x >= label_end && (week == first_week || previous.month() != start.month()) {
+                let month = self.locale.format_date(start, "%b");
+                if x + month.width() <= xmax - 2 {
+                    self.window.mvprintw(3, x, &month);
+                    label_end = x + month.width() + 1;
+                }
+            }
+        }
+
+        for row in 0..7 {
+            let y = 4 + row;
+            let weekday = heatmap.first + chrono::Duration::days(row as i64);
+            self.window
+                .mvprintw(y, 2, &self.locale.format_date(weekday, "%a"));
+            for week in first_week..heatmap::WEEKS {
+                let day = match heatmap.day(week, row) {
+                    Some(day) => day,
+                    None => break,
+                };
+                let level = heatmap.level(day);
+                let x = 6 + (week - first_week) * cell;
+                self.heatmap_colour(level);
+                let shade = heatmap::shade(level, ascii).to_string();
+                self.window.mvprintw(y, x, &shade.repeat(cell));
+                self.window.colour_off();
+            }
+        }
+
+        let less = self.locale.tr("Less");
+        self.window.mvprintw(12, 2, less);
+        let mut x = 3 + less.width();
+        for level in 0..heatmap::LEVELS {
+            self.heatmap_colour(level);
+            self.window
+                .mvprintw(12, x, &heatmap::shade(level, ascii).to_string());
+            self.window.colour_off();
+            x += 2;
+        }
+        self.window.mvprintw(12, x, self.locale.tr("More"));
+
+        let summary = match heatmap.total() {
+            0 => String::from(self.locale.tr("No tasks completed in the last year")),
+            total => format!(
+                "{} {}",
+                self.locale
+                    .fill("{} tasks completed in the last year,", &total.to_string()),
+                self.locale
+                    .fill("at most {} in a day", &heatmap.most().to_string())
+            ),
+        };
+        self.window.wrap_print(14, 2, xmax - 4, &summary);
+
+        let help = format!("{} close", key_name(self.window.config.keys.heatmap));
+        self.window.wrap_print(ymax - 2, 2, xmax - 4, &help);
+        self.window.refresh();
+    }
+
+    /// Add the colour of a heatmap shade to subsequent printed text: the
+    /// configured colour of the level, if any, or else colour 2 for days
+    /// with completions.
+    fn heatmap_colour(&mut self, level: usize) {
+        match self.window.config.heatmap_colours.get(level) {
+            Some(colour) => self.window.colour_choice(colour),
+            None if level > 0 => self.window.colour_on(2, 8),
+            None => (),
+        }
+    }
+
+    /// Change ordering of sub-tasks for current task.
+    fn move_task(&mut self, up: bool) {
+        let current = Rc::clone(&self.current_task);
+        let sub_tasks = &mut current.borrow_mut().sub_tasks;
+        if let Some(index) = self.selection {
+            if up {
+                let new_index = if index == 0 {
+                    sub_tasks.len() - 1
+                } else {
+                    index - 1
+                };
+                sub_tasks.swap(new_index, index);
+                self.selection = Some(new_index);
+            } else {
+                let new_index = if index == sub_tasks.len() - 1 {
+                    0
+                } else {
+                    index + 1
+                };
+                sub_tasks.swap(new_index, index);
+                self.selection = Some(new_index);
+            }
+        }
+        if let Some(index) = self.selection {
+            self.announce(&format!("Moved task to position {}", index + 1));
+        }
+    }
+
+    /// Pick up the selected task to move, or if one has been picked up, put
+    /// it down under the current task, after the selected one.
+    fn move_to(&mut self) {
+        let task = match self.moving.take() {
+            Some(task) => task,
+            None => {
+                if let Some(task) = self.selected_task() {
+                    let title = task.borrow().task.clone();
+                    self.moving = Some(task);
+                    let key = key_name(self.window.config.keys.move_to);
+                    self.announce(&format!(
+                        "Moving {}: go to where it belongs and press {}",
+                        title, key
+                    ));
+                }
+                return;
+            }
+        };
+        if task.borrow().parent.upgrade().is_none() {
+            return self.announce("The task being moved is no longer in the list");
+        }
+        let parent = Rc::clone(&self.current_task);
+        if let Err(err) = nesting::check(&parent, &[Rc::clone(&task)], self.window.config.max_depth)
+        {
+            self.moving = Some(task);
+            return self.announce(&err);
+        }
+
+        let mut index = self
+            .selection
+            .map_or(parent.borrow().sub_tasks.len(), |index| index + 1);
+        if let Some((from, from_index)) = nesting::detach(&task) {
+            // Taking it out from above the selection moves the rest up
+            if Rc::ptr_eq(&from, &parent) && from_index < index {
+                index -= 1;
+            }
+        }
+        self.selection = Some(nesting::attach(Rc::clone(&task), &parent, index));
+        let place = match self.root {
+            true => String::from("the top level"),
+            false => parent.borrow().task.clone(),
+        };
+        self.announce(&format!("Moved {} under {}", task.borrow().task, place));
+    }
+
+    /// Focus on currently selected sub-task.
+    fn new_focus(&mut self) {
+        let previous_root = self.root;
+        let previous_selection = self.selection;
+        let psub_tasks = Rc::clone(&self.current_task);
+        let sub_tasks = &psub_tasks.borrow().sub_tasks;
+        if let Some(index) = self.selection {
+            // Focus on sub-task
+            let sub_task = &sub_tasks[index];
+            if !self.read_subtree(sub_task) {
+                return;
+            }
+            self.current_task = Rc::clone(sub_task);
+            self.root = false;
+            self.selection = if !self.current_task.borrow().sub_tasks.is_empty() {
+                Some(0)
+            } else {
+                None
+            };
+            self.announce(&format!("Focused on: {}", sub_task.borrow().task));
+            self.run();
+
+            // Return to parent task (unwrap cannot panic here)
+            self.current_task = sub_task.borrow().parent.upgrade().unwrap();
+            self.root = previous_root;
+            self.selection = previous_selection;
+            self.select_task(sub_task);
+            if !self.quit {
+                let message = match self.root {
+                    true => String::from("Returned to top level"),
+                    false => format!("Returned to: {}", self.current_task.borrow().task),
+                };
+                self.announce(&message);
+            }
+        }
+    }
+
+    /// Edited currently selected sub-task.
+    fn edit_task(&mut self) {
+        if let Some(index) = self.selection {
+            let task = self.edit_dialogue("Edit Task:", index);
+            let (task, due) = due::extract(&task, Local::now().naive_local());
+            let sub_task = Rc::clone(&self.current_task.borrow().sub_tasks[index]);
+            let day = due
+                .or(sub_task.borrow().due)
+                .map_or(Local::now().date_naive(), |due| due.day());
+            // The rule is edited along with the task, so leaving it out
+            // stops the task recurring
+            let (task, repeat) = recur::extract(&task, day);
+            sub_task.borrow_mut().task = task;
+            if sub_task.borrow().repeat != repeat {
+                let mut sub_task = sub_task.borrow_mut();
+                sub_task.repeat = repeat;
+                sub_task.skip.clear();
+                sub_task.moved = None;
+            }
+            match due {
+                Some(due) => {
+                    self.keep_series(&sub_task, due);
+                    sub_task.borrow_mut().due = Some(due);
+                    self.auto_sort(&sub_task);
+                    self.announce(&format!("Edited task, due {}", due.date()));
+                }
+                None => self.announce("Edited task"),
+            }
+        }
+    }
+
+    /// Add a comment to the log of the currently selected sub-task.
+    fn add_comment(&mut self) {
+        if let Some(index) = self.selection {
+            let text = self.input_dialogue("Comment:");
+            if text.trim().is_empty() {
+                return;
+            }
+            self.current_task.borrow().sub_tasks[index]
+                .borrow_mut()
+                .add_comment(text.trim());
+            self.details = true;
+            self.announce("Added comment");
+        }
+    }
+
+    /// Edit notes of currently selected sub-task in external editor.
+    fn edit_notes(&mut self) {
+        if let Some(index) = self.selection {
+            let sub_task = Rc::clone(&self.current_task.borrow().sub_tasks[index]);
+            let notes = sub_task.borrow().notes.clone();
+
+            let editor = env::var("EDITOR").unwrap_or_else(|_| String::from("vi"));
+            self.window.suspend();
+            let edited = external_editor(&editor, &notes);
+            self.window.resume();
+
+            match edited {
+                Ok(text) => {
+                    sub_task.borrow_mut().notes = String::from(text.trim_end());
+                    self.announce("Edited notes");
+                }
+                Err(err) => self.announce(&err.to_string()),
+            }
+        }
+    }
+
+    /// Set due date of currently selected sub-task, or clear it if left
+    /// empty.
+    fn set_due(&mut self) {
+        if let Some(index) = self.selection {
+            let sub_task = Rc::clone(&self.current_task.borrow().sub_tasks[index]);
+            let original = sub_task
+                .borrow()
+                .due
+                .map_or(String::new(), |due| due.to_string());
+            let input = self.dialogue("Due (YYYY-MM-DD [HH:MM], or e.g. next fri):", &original);
+
+            if input.trim().is_empty() {
+                sub_task.borrow_mut().due = None;
+                sub_task.borrow_mut().moved = None;
+                self.auto_sort(&sub_task);
+                self.announce("Cleared due date");
+            } else if let Some(due) = Due::parse_natural(&input, Local::now().naive_local()) {
+                self.keep_series(&sub_task, due);
+                sub_task.borrow_mut().due = Some(due);
+                self.auto_sort(&sub_task);
+                self.announce(&format!("Due {}", due.date()));
+            } else {
+                self.announce("Invalid due date");
+            }
+        }
+    }
+
+    /// Before a recurring task is given a new due date, ask whether only
+    /// this occurrence is moving, leaving the days of the ones after it as
+    /// they were, or the whole series.
+    fn keep_series(&mut self, task: &Rc<RefCell<ToDo>>, due: Due) {
+        let scheduled = match task.borrow().scheduled() {
+            Some(day) if task.borrow().repeat.is_some() => day,
+            _ => return,
+        };
+        let moved = match due.day() == scheduled {
+            true => None,
+            false if self.popup("Move only this occurrence? y/n") => Some(scheduled),
+            false => None,
+        };
+        task.borrow_mut().moved = moved;
+    }
+
+    /// Skip an occurrence of the selected recurring task: this one, making
+    /// it the next one instead, or one of those coming up, which can be
+    /// picked again to stop skipping it.
+    fn skip_occurrence(&mut self) {
+        let task = match self.selected_task() {
+            Some(task) => task,
+            None => return,
+        };
+        let (repeat, scheduled) = {
+            let todo = task.borrow();
+            match (todo.repeat, todo.scheduled()) {
+                (Some(repeat), Some(scheduled)) => (repeat, scheduled),
+                _ => return self.announce("Only recurring tasks with a due date can be skipped"),
+            }
+        };
+        let upcoming = repeat.upcoming(scheduled, SKIP_CHOICES);
+        let mut options = vec![format!(
+            "This one ({})",
+            task.borrow().due.map_or(String::new(), |due| due.date())
+        )];
+        for day in upcoming.iter() {
+            let skipped = task.borrow().skip.contains(day);
+            options.push(format!(
+                "{}{}",
+                day.format("%Y-%m-%d"),
+                if skipped { " (skipped)" } else { "" }
+            ));
+        }
+        let choice = match self.pick("Skip occurrence", &options, 0) {
+            Some(choice) => choice,
+            None => return,
+        };
+        if choice == 0 {
+            let next = task.borrow_mut().skip_occurrence(Local::now().date_naive());
+            self.auto_sort(&task);
+            if let Some(due) = next {
+                self.announce(&format!("Skipped, next due {}", due.date()));
+            }
+            return;
+        }
+        let day = upcoming[choice - 1];
+        let skip = &mut task.borrow_mut().skip;
+        let message = match skip.remove(&day) {
+            true => format!("No longer skipping {}", day.format("%Y-%m-%d")),
+            false => {
+                skip.insert(day);
+                format!("Skipping {}", day.format("%Y-%m-%d"))
+            }
+        };
+        self.announce(&message);
+    }
+
+    /// The task whose timer is running, if any.
+    fn running_timer(&self) -> Option<Rc<RefCell<ToDo>>> {
+        self.root_task()
+            .borrow()
+            .find_in_tree(&|task| task.timer.is_some())
+    }
+
+    /// The running timer for the status line, e.g. "Timer 0:25:03 Write
+    /// report", in up to width columns.
+    fn timer_status(&self, width: usize) -> Option<String> {
+        let task = self.running_timer()?;
+        let task = task.borrow();
+        let seconds = (Local::now().naive_local() - task.timer?)
+            .num_seconds()
+            .max(0);
+        let clock = format!(
+            "{} {}:{:02}:{:02}",
+            self.locale.tr("Timer"),
+            seconds / 3600,
+            seconds / 60 % 60,
+            seconds % 60
+        );
+        let room = width.saturating_sub(UnicodeWidthStr::width(clock.as_str()) + 1);
+        let config = &self.window.config;
+        let title = layout::truncate(&task.task, room, config.ellipsis, config.ellipsis_at);
+        Some(format!("{} {}", clock, title.trim_end()))
+    }
+
+    /// Start the timer for time spent on the selected task, stopping any
+    /// other running, or stop it if it's running.
+    fn toggle_timer(&mut self) {
+        let task = match self.selected_task() {
+            Some(task) => task,
+            None => return,
+        };
+        let now = Local::now().naive_local();
+        let mut message = String::new();
+        if let Some(running) = self.running_timer() {
+            let minutes = running.borrow_mut().stop_timer(now).unwrap_or(0);
+            message = format!(
+                "Stopped timer on {}: {} (total {})",
+                running.borrow().task,
+                effort::format(minutes),
+                effort::format(running.borrow().spent)
+            );
+            if Rc::ptr_eq(&running, &task) {
+                return self.announce(&message);
+            }
+        }
+        task.borrow_mut().timer = now.with_nanosecond(0);
+        let started = format!("Started timer on {}", task.borrow().task);
+        message = match message.is_empty() {
+            true => started,
+            false => format!("{}; {}", message, started),
+        };
+        self.announce(&message);
+    }
+
+    /// Set the tags, contexts and priority given to tasks added under the
+    /// selected task, or clear them if left empty.
+    fn set_defaults(&mut self) {
+        let task = match self.selected_task() {
+            Some(task) => task,
+            None => return,
+        };
+        let original = task.borrow().defaults.join(" ");
+        let input = self.dialogue("Defaults for sub-tasks (e.g. +tag @context B):", &original);
+        match Defaults::parse(&input) {
+            Some(defaults) if defaults.is_empty() => {
+                task.borrow_mut().defaults = defaults;
+                self.announce("Cleared defaults");
+            }
+            Some(defaults) => {
+                let message = format!("New sub-tasks get {}", defaults.join(" "));
+                task.borrow_mut().defaults = defaults;
+                self.announce(&message);
+            }
+            None => self.announce("Defaults can only be tags, contexts and a priority letter"),
+        }
+    }
+
+    /// Set start date of currently selected sub-task, before which it is
+    /// hidden, or clear it if left empty.
+    fn set_start(&mut self) {
+        if let Some(index) = self.selection {
+            let sub_task = Rc::clone(&self.current_task.borrow().sub_tasks[index]);
+            let original = sub_task
+                .borrow()
+                .start
+                .map_or(String::new(), |start| start.format("%Y-%m-%d").to_string());
+            let input = self.dialogue("Start (YYYY-MM-DD):", &original);
+
+            if input.trim().is_empty() {
+                sub_task.borrow_mut().start = None;
+                self.announce("Cleared start date");
+            } else if let Ok(start) = NaiveDate::parse_from_str(input.trim(), "%Y-%m-%d") {
+                sub_task.borrow_mut().start = Some(start);
+                let hidden = !self.show_upcoming && start > Local::now().date_naive();
+                self.announce(&format!(
+                    "Starts {}{}",
+                    start.format("%Y-%m-%d"),
+                    if hidden { ": hidden until then" } else { "" }
+                ));
+            } else {
+                self.announce("Invalid start date");
+            }
+        }
+    }
+
+    /// Set target date of currently selected sub-task, a day it is aimed
+    /// to be done by without becoming overdue, or clear it if left empty.
+    fn set_target(&mut self) {
+        if let Some(index) = self.selection {
+            let sub_task = Rc::clone(&self.current_task.borrow().sub_tasks[index]);
+            let original = sub_task.borrow().target.map_or(String::new(), |target| {
+                target.format("%Y-%m-%d").to_string()
+            });
+            let input = self.dialogue("Target (YYYY-MM-DD, or e.g. next fri):", &original);
+
+            if input.trim().is_empty() {
+                sub_task.borrow_mut().target = None;
+                self.auto_sort(&sub_task);
+                self.announce("Cleared target date");
+            } else if let Some(due) = Due::parse_natural(&input, Local::now().naive_local()) {
+                sub_task.borrow_mut().target = Some(due.day());
+                self.auto_sort(&sub_task);
+                self.announce(&format!("Aiming for {}", due.day().format("%Y-%m-%d")));
+            } else {
+                self.announce("Invalid target date");
+            }
+        }
+    }
+
+    /// Set effort estimate of currently selected sub-task, or clear it if
+    /// left empty.
+    fn set_estimate(&mut self) {
+        if let Some(index) = self.selection {
+            let sub_task = Rc::clone(&self.current_task.borrow().sub_tasks[index]);
+            let original = sub_task
+                .borrow()
+                .estimate
+                .map_or(String::new(), effort::format);
+            let input = self.dialogue("Estimate (e.g. 1h30m):", &original);
+
+            if input.trim().is_empty() {
+                sub_task.borrow_mut().estimate = None;
+                self.announce("Cleared estimate");
+            } else if let Some(estimate) = effort::parse(&input) {
+                sub_task.borrow_mut().estimate = Some(estimate);
+                self.announce(&format!("Estimated {}", effort::format(estimate)));
+            } else {
+                self.announce("Invalid estimate");
+            }
+        }
+    }
+
+    /// Set how long before its deadline to be reminded of the selected
+    /// sub-task, or remove the reminder.
+    fn set_reminder(&mut self) {
+        if let Some(index) = self.selection {
+            let sub_task = Rc::clone(&self.current_task.borrow().sub_tasks[index]);
+            if sub_task.borrow().due.is_none() {
+                return self.announce("No due date to be reminded of");
+            }
+            let original = sub_task
+                .borrow()
+                .remind
+                .map_or(String::new(), due::format_lead);
+            let input = self.dialogue("Remind before due (e.g. 1d, 2h, 30m):", &original);
+
+            if input.trim().is_empty() {
+                sub_task.borrow_mut().remind = None;
+                self.announce("Removed reminder");
+            } else if let Some(lead) = due::parse_lead(&input) {
+                sub_task.borrow_mut().remind = Some(lead);
+                self.announce(&format!("Reminder {} before due", due::format_lead(lead)));
+            } else {
+                self.announce("Invalid reminder");
+            }
+        }
+    }
+
+    /// Attach a URL to the selected sub-task, or change or remove it.
+    fn set_url(&mut self) {
+        if let Some(index) = self.selection {
+            let sub_task = Rc::clone(&self.current_task.borrow().sub_tasks[index]);
+            let original = sub_task.borrow().url.clone().unwrap_or_default();
+            let input = self.dialogue("URL:", &original);
+
+            match input.trim() {
+                "" => {
+                    sub_task.borrow_mut().url = None;
+                    self.announce("Removed URL");
+                }
+                url if url.contains(char::is_whitespace) => self.announce("Invalid URL"),
+                url => {
+                    sub_task.borrow_mut().url = Some(String::from(url));
+                    self.announce(&format!("URL: {}", url));
+                }
+            }
+        }
+    }
+
+    /// Open the URL of the selected sub-task with the desktop's opener,
+    /// handing over the terminal in case it opens there.
+    fn open_url(&mut self) {
+        let url = match self.selected_task() {
+            Some(task) => task.borrow().url.clone(),
+            None => return,
+        };
+        let url = match url {
+            Some(url) => url,
+            None => return self.announce("No URL: attach one first"),
+        };
+        let opener = match cfg!(target_os = "macos") {
+            true => "open",
+            false => "xdg-open",
+        };
+
+        info!("Opening {} with {}.", url, opener);
+        self.window.suspend();
+        let status = Command::new(opener).arg(&url).status();
+        self.window.resume();
+
+        match status {
+            Ok(status) if status.success() => self.announce(&format!("Opened {}", url)),
+            Ok(status) => self.announce(&format!("Unable to open {}: {}", url, status)),
+            Err(err) => self.announce(&format!("Unable to run {}: {}", opener, err)),
+        }
+    }
+
+    /// Flag currently selected sub-task to be done today, or remove the
+    /// flag.
+    fn flag_today(&mut self) {
+        if let Some(index) = self.selection {
+            let flagged = {
+                let current_task = self.current_task.borrow();
+                let mut sub_task = current_task.sub_tasks[index].borrow_mut();
+                sub_task.today = match sub_task.today {
+                    Some(_) => None,
+                    None => Some(Local::now().date_naive()),
+                };
+                sub_task.today.is_some()
+            };
+            self.announce(match flagged {
+                true => "Flagged for today",
+                false => "No longer flagged for today",
+            });
+        }
+    }
+
+    /// Move unfinished tasks flagged for previous days on to today, logging
+    /// how many times each has been carried over.
+    fn rollover(&mut self) {
+        let mut carried = Vec::new();
+        self.root_task()
+            .borrow_mut()
+            .rollover(Local::now().date_naive(), &mut carried);
+        for (task, times) in carried.iter() {
+            info!(
+                "Carried over: {} ({} time{})",
+                task,
+                times,
+                if *times == 1 { "" } else { "s" }
+            );
+        }
+        self.announce(&match carried.len() {
+            0 => String::from("Nothing to carry over"),
+            1 => String::from("Carried 1 task over to today"),
+            n => format!("Carried {} tasks over to today", n),
+        });
+    }
+
+    /// Run the shell command configured for a key, with fields of the
+    /// currently selected sub-task substituted.
+    fn run_shell_command(&mut self, key: Key) {
+        let template = match self
+            .window
+            .config
+            .shell_commands
+            .iter()
+            .find(|(k, _)| *k == key)
+        {
+            Some((_, template)) => template.clone(),
+            None => return,
+        };
+        let index = match self.selection {
+            Some(index) => index,
+            None => return,
+        };
+
+        // Fields are passed as environment variables, so that task text
+        // can't be interpreted by the shell
+        let fields = {
+            let current = self.current_task.borrow();
+            let todo = current.sub_tasks[index].borrow();
+            let mut path = String::new();
+            todo.task_path(&mut path);
+            vec![
+                ("title", todo.task.clone()),
+                ("notes", todo.notes.clone()),
+                (
+                    "path",
+                    format!("{}{}", path.trim_start_matches(": "), todo.task),
+                ),
+                (
+                    "priority",
+                    todo.priority
+                        .map_or(String::new(), |priority| priority.letter().to_string()),
+                ),
+                ("due", todo.due.map_or(String::new(), |due| due.date())),
+                ("complete", todo.complete.to_string()),
+            ]
+        };
+        let mut command = template;
+        let mut shell = Command::new("sh");
+        for (name, value) in fields.iter() {
+            let var = format!("YAT_{}", name.to_uppercase());
+            command = command.replace(&format!("{{{}}}", name), &format!("${{{}}}", var));
+            shell.env(var, value);
+        }
+
+        info!("Running shell command: {}", command);
+        self.window.suspend();
+        let status = shell.arg("-c").arg(&command).status();
+        self.window.resume();
+
+        match status {
+            Ok(status) if status.success() => self.announce("Command finished"),
+            Ok(status) => self.announce(&format!("Command failed: {}", status)),
+            Err(err) => self.announce(&format!("Unable to run command: {}", err)),
+        }
+    }
+
+    /// Move selection cursor by a number of tasks.
+    fn move_selection(&mut self, ifup: bool, count: usize) {
+        // Move between shown tasks only (wrapping around)
+        let shown = self.shown_indices(&self.current_task.borrow());
+        let n = shown.len();
+        let position = self
+            .selection
+            .and_then(|index| shown.iter().position(|&i| i == index));
+        let position = match position {
+            Some(pos) if ifup => Some((pos + n - count % n) % n),
+            Some(pos) => Some((pos + count) % n),
+            None if n > 0 => Some(0),
+            None => None,
+        };
+        self.selection = position.map(|pos| shown[pos]);
+
+        if self.window.config.screen_reader {
+            if let (Some(index), Some(pos)) = (self.selection, position) {
+                let message = {
+                    let sub_tasks = &self.current_task.borrow().sub_tasks;
+                    let sub_task = sub_tasks[index].borrow();
+                    format!(
+                        "{} of {}: {}{}",
+                        pos + 1,
+                        n,
+                        sub_task.task,
+                        if sub_task.complete { ", complete" } else { "" }
+                    )
+                };
+                self.announce(&message);
+            }
+        }
+    }
+
+    /// Move the selection by a number of shown tasks, stopping at the first
+    /// or last rather than wrapping around.
+    fn move_selection_clamped(&mut self, ifup: bool, count: usize) {
+        let shown = self.shown_indices(&self.current_task.borrow());
+        let position = self
+            .selection
+            .and_then(|index| shown.iter().position(|&i| i == index));
+        let steps = match position {
+            Some(pos) if ifup => count.min(pos),
+            Some(pos) => count.min(shown.len() - 1 - pos),
+            None => 1,
+        };
+        if steps > 0 {
+            self.move_selection(ifup, steps);
+        }
+    }
+
+    /// Create a pop-up diaglogue with user choice.
+    fn popup(&mut self, prompt: &str) -> bool {
+        let (ymax, xmax) = self.window.get_max_yx();
+        self.window.border((ymax - 1, 0), (3, xmax));
+        self.window.rectangle(' ', (ymax - 2, 1), (1, xmax - 2));
+        self.window.colour_on(1, 7);
+        self.window.mvprintw(ymax - 2, 2, self.locale.tr(prompt));
+        self.window.colour_off();
+        self.window.refresh();
+
+        let mut choice = false;
+        loop {
+            match self.window.getch() {
+                Some(Key::Char('y')) => {
+                    choice = true;
+                    break;
+                }
+                Some(Key::Char('n')) => break,
+                Some(Key::Char('q')) => break,
+                Some(Key::Char('b')) => break,
+                _ => (),
+            }
+        }
+        choice
+    }
+
+    /// Remove selected sub-task.
+    fn remove_task(&mut self) {
+        if let Some(index) = self.selection {
+            let prompt = "Are you sure you want to delete this task? y/n";
+            if !self.window.config.confirm.delete || self.popup(prompt) {
+                let removed = self.current_task.borrow_mut().sub_tasks.remove(index);
+                {
+                    let removed = removed.borrow();
+                    self.marked
+                        .retain(|task| !removed.any_in_tree(&|t| std::ptr::eq(t, task.as_ptr())));
+                }
+                self.selection = None;
+                match self.window.config.trash_days {
+                    0 => self.announce("Deleted task"),
+                    _ => {
+                        let now = Local::now().naive_local();
+                        let trashed = trash::Trashed::new(removed, &self.current_task, index, now);
+                        self.trash.push(trashed);
+                        self.save_trash();
+                        let trash = key_name(self.window.config.keys.trash);
+                        self.announce(&format!("Moved task to the trash: {} to restore", trash));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Find the location of the save file.
+    fn save_path(&self) -> Option<PathBuf> {
+        if let Some(storage) = &self.remote {
+            return storage.cache_path();
+        }
+        match self.save_file.clone() {
+            Some(f) => Some(f),
+            None => {
+                let filename = self.window.config.save_file_path();
+                if filename.is_none() {
+                    warn!("Unable to locate home directory.");
+                }
+                filename
+            }
+        }
+    }
+
+    /// Find the root of the todo list tree.
+    fn root_task(&self) -> Rc<RefCell<ToDo>> {
+        let mut root = Rc::clone(&self.current_task);
+        loop {
+            let parent = root.borrow().parent.upgrade();
+            match parent {
+                Some(parent) => root = parent,
+                None => return root,
+            }
+        }
+    }
+
+    /// Save todo list to file.
+    fn save(&mut self) {
+        if self.read_only {
+            self.announce("Demo list is read-only: nothing was saved");
+            return;
+        }
+        if let Some(storage) = self.remote.take() {
+            let buffer = self.root_task().borrow().save_string();
+            storage::write_cache(storage.as_ref(), &buffer);
+            let location = storage.describe();
+            let message = if self.offline {
+                self.remote = Some(storage);
+                format!("Offline: saved local copy of {} only", location)
+            } else {
+                // Upload from a copy, keeping this one if it's cancelled
+                let mut copy = storage.duplicate();
+                self.remote = Some(storage);
+                let upload = buffer.clone();
+                let doing = format!("Uploading to {}", location);
+                let outcome = self.run_worker(&doing, move |progress| {
+                    copy.save(&upload, progress)
+                        .map(|_| copy)
+                        .map_err(|err| match err {
+                            Error::Remote(_, reason) => reason,
+                            err => err.to_string(),
+                        })
+                });
+                match outcome.map_err(String::from).and_then(|result| result) {
+                    Ok(copy) => {
+                        self.remote = Some(copy);
+                        format!("Saved to {}", location)
+                    }
+                    Err(reason) => format!("{}: {}; saved local copy only", location, reason),
+                }
+            };
+            self.saved = hash(&buffer);
+            self.announce(&message);
+            return;
+        }
+        if let Some(filename) = self.save_path() {
+            self.write_save(filename);
+        }
+    }
+
+    /// Write the list to a file in the background, announcing when it has
+    /// been saved (or why it couldn't be).
+    fn write_save(&mut self, filename: PathBuf) {
+        let buffer = self.root_task().borrow().save_string();
+        self.saved = hash(&buffer);
+        // Changes are already safe in the journal, which is only compacted
+        // into the save file once that is written
+        if let Some(journal) = &mut self.journal {
+            match journal.compact(&filename, &buffer) {
+                Ok(_) => self.announce(&format!("Saved to {}", filename.display())),
+                Err(err) => {
+                    warn!("Unable to compact journal: {}", err);
+                    self.announce(&format!(
+                        "Unable to save to {}: {}",
+                        filename.display(),
+                        err
+                    ));
+                }
+            }
+            return;
+        }
+        self.saver.save(filename, buffer);
+    }
+
+    /// Announce the outcomes of saves finished in the background.
+    fn check_saves(&mut self) {
+        for outcome in self.saver.outcomes() {
+            match outcome {
+                Ok(filename) => self.announce(&format!("Saved to {}", filename.display())),
+                Err(err) => {
+                    self.announce(&err);
+                    if self.window.config.bell {
+                        self.window.bell();
                     }
                 }
-                _ => (),
             }
         }
-        entry
     }
 
-    /// Display a list of the sub-tasks of the current task.
-    fn list_tasks(&mut self) {
-        self.window.clear();
-        self.window.hide_cursor();
+    /// Listen for tasks sent with yat add --daemon, adding them to the list
+    /// as they arrive.
+    pub fn listen(&mut self) -> Result<(), Error> {
+        let filename = self.save_path().ok_or(Error::NoHome)?;
+        self.listener = Some(daemon::Listener::bind(&filename)?);
+        Ok(())
+    }
 
-        let (ymax, xmax) = self.window.get_max_yx();
+    /// Add any tasks sent with yat add --daemon at the end of the top level
+    /// of the list.
+    fn receive_tasks(&mut self) {
+        let tasks = match &self.listener {
+            Some(listener) => listener.receive(),
+            None => return,
+        };
+        if tasks.is_empty() {
+            return;
+        }
+        let root = self.root_task();
+        for task in tasks.iter() {
+            let todo = new_task(task, &root);
+            self.call_webhooks(webhook::Event::Add, &todo);
+            root.borrow_mut()
+                .sub_tasks
+                .push(Rc::new(RefCell::new(todo)));
+        }
+        match tasks.len() {
+            1 => {
+                let added = self.locale.tr("Added task");
+                let task = root
+                    .borrow()
+                    .sub_tasks
+                    .last()
+                    .map(|t| t.borrow().task.clone());
+                self.announce(&format!("{}: {}", added, task.unwrap_or_default()));
+            }
+            count => self.announce(&format!("Added {} tasks", count)),
+        }
+    }
 
-        // Panels
-        let mut path = self.current_task.borrow().task.clone();
-        self.current_task.borrow().task_path(&mut path);
-        self.window.mvprintw(1, 1, &path);
-        self.window.border((2, 0), (3, xmax));
-        self.window.border((ymax - 4, 0), (ymax - 6, xmax / 2));
-        self.window
-            .border((ymax - 4, xmax / 2), (ymax - 6, xmax / 2));
-        self.window.border((ymax - 1, 0), (3, xmax));
+    /// Move all completed tasks to the archive file beside the save file,
+    /// saving the smaller list.
+    fn archive_completed(&mut self) {
+        let filename = match &self.save_file {
+            Some(filename) if !self.read_only => filename.clone(),
+            _ => return self.announce("Only lists saved on this computer can be archived"),
+        };
+        let completed = archive::find_completed(&self.root_task().borrow());
+        if completed.is_empty() {
+            return self.announce("No completed tasks to archive");
+        }
+        let prompt = format!("Archive {} completed tasks? y/n", completed.len());
+        if self.window.config.confirm.archive && !self.popup(&prompt) {
+            return;
+        }
+        if self.move_to_archive(&filename, &completed) {
+            let count = completed.len();
+            self.announce(&format!(
+                "Archived {} completed task{}",
+                count,
+                if count == 1 { "" } else { "s" }
+            ));
+        }
+    }
 
-        self.window.colour_on(4, 8);
-        self.window.mvprintw(0, 2, "Parent");
-        self.window.mvprintw(3, 2, "Tasks");
-        self.window.mvprintw(3, xmax / 2 + 2, "Sub-tasks");
-        self.window.mvprintw(ymax - 3, 2, "Selection");
-        self.window.colour_off();
+    /// Append tasks to the archive of a save file and remove them from the
+    /// list, saving it straight away so no task is ever in both. Returns
+    /// whether they were archived.
+    fn move_to_archive(&mut self, filename: &Path, tasks: &[Rc<RefCell<ToDo>>]) -> bool {
+        let archive = archive::path(filename);
+        if let Err(err) = archive::append(&archive, tasks) {
+            warn!("Unable to write to {}: {}", archive.display(), err);
+            self.announce(&format!("Unable to write to {}", archive.display()));
+            return false;
+        }
+        let selected = self.selected_task();
+        archive::remove(tasks);
+        // Undoing back past this would put the tasks in both
+        self.history = History::default();
+        self.write_save(filename.to_path_buf());
+        if !selected.is_some_and(|selected| self.select_task(&selected)) {
+            let len = self.current_task.borrow().sub_tasks.len();
+            self.selection = len.checked_sub(1);
+        }
+        true
+    }
 
-        self.window.colour_on(6, 8);
-        if let Some(index) = self.selection {
-            if index > self.current_task.borrow().sub_tasks.len() - 1 {
-                warn!("Index larger than it should be.");
-                self.selection = None;
-            } else {
-                self.window.mvprintw(4 + index, 1, ">");
-                self.window.wrap_print(
-                    ymax - 2,
-                    2,
-                    xmax - 3,
-                    &self.current_task.borrow().sub_tasks[index].borrow().task,
-                );
+    /// Show the archive of completed tasks, read from the archive file only
+    /// now, so that long-lived lists start quickly.
+    fn open_archive(&mut self) {
+        let filename = match &self.save_file {
+            Some(filename) => archive::path(filename),
+            None => return self.announce("Only lists saved on this computer have an archive"),
+        };
+        let buf = match archive::load(&filename) {
+            Ok(buf) => buf,
+            Err(err) => {
+                warn!("Unable to read {}: {}", filename.display(), err);
+                return self.announce(&format!("Unable to read {}", filename.display()));
             }
         };
-        self.window.colour_off();
-
-        let sub_tasks = &self.current_task.borrow().sub_tasks;
-        let mut y = 4;
-        for (i, elem) in sub_tasks.iter().enumerate() {
-            if elem.borrow().complete {
-                self.window.mvprintw(y, 3, "[");
-                self.window.colour_on(4, 8);
-                self.window.mvprintw(y, 4, "X");
-                self.window.colour_off();
-                self.window.mvprintw(y, 5, "]");
-            } else {
-                self.window.mvprintw(y, 3, "[ ]");
+        let root = match parse_list(&buf) {
+            Ok(root) => root,
+            Err(err) => {
+                warn!("Unable to parse {}: {}", filename.display(), err);
+                return self.announce(&format!("Unable to read {}", filename.display()));
             }
-            match elem.borrow().priority {
-                Some(Priority::Low) => {
-                    self.window.colour_on(2, 8);
+        };
+        let mut rows = Vec::new();
+        archive::outline(&root.borrow(), 0, &mut rows);
+        if rows.is_empty() {
+            return self.announce("Nothing archived yet");
+        }
+
+        info!("Opened archive {}.", filename.display());
+        let mut offset = 0;
+        loop {
+            offset = offset.min(rows.len().saturating_sub(1));
+            self.print_archive(&rows, offset);
+            match self.window.getch() {
+                Some(key)
+                    if key == self.window.config.keys.quit
+                        || key == self.window.config.keys.archive =>
+                {
+                    break
                 }
-                Some(Priority::Medium) => {
-                    self.window.colour_on(3, 8);
+                Some(key) if key == Key::Up || key == self.window.config.keys.up => {
+                    offset = offset.saturating_sub(1 + self.window.take_repeats(key));
                 }
-                Some(Priority::High) => {
-                    self.window.colour_on(1, 8);
+                Some(key) if key == Key::Down || key == self.window.config.keys.down => {
+                    offset += 1 + self.window.take_repeats(key);
                 }
                 _ => (),
-            };
-            self.window
-                .wrap_print(y, 7, xmax / 2 - 8, &elem.borrow().task.to_string());
-            self.window.colour_off();
-            y += 1;
+            }
+        }
+    }
 
-            if let Some(index) = self.selection {
-                if index == i {
-                    let mut yy = 4;
-                    for sub_elem in elem.borrow().sub_tasks.iter() {
-                        if sub_elem.borrow().complete {
-                            self.window.mvprintw(yy, xmax / 2 + 3, "[");
-                            self.window.colour_on(4, 8);
-                            self.window.mvprintw(yy, xmax / 2 + 4, "X");
-                            self.window.colour_off();
-                            self.window.mvprintw(yy, xmax / 2 + 5, "]");
-                        } else {
-                            self.window.mvprintw(yy, xmax / 2 + 3, "[ ]");
-                        }
-                        match sub_elem.borrow().priority {
-                            Some(Priority::Low) => {
-                                self.window.colour_on(2, 8);
-                            }
-                            Some(Priority::Medium) => {
-                                self.window.colour_on(3, 8);
-                            }
-                            Some(Priority::High) => {
-                                self.window.colour_on(1, 8);
-                            }
-                            _ => (),
-                        };
-                        self.window.wrap_print(
-                            yy,
-                            xmax / 2 + 7,
-                            xmax / 2 - 8,
-                            &sub_elem.borrow().task.to_string(),
-                        );
-                        self.window.colour_off();
-                        yy += 1;
-                    }
-                }
-            };
+    /// Read the trash beside the save file, emptying it of tasks deleted
+    /// more than the configured number of days ago.
+    fn load_trash(&mut self) {
+        let filename = match &self.save_file {
+            Some(filename) => trash::path(filename),
+            None => return,
+        };
+        self.trash = match trash::load(&filename) {
+            Ok(trash) => trash,
+            Err(err) => {
+                warn!("Unable to read {}: {}", filename.display(), err);
+                return;
+            }
+        };
+        let days = self.window.config.trash_days;
+        let before = Local::now()
+            .naive_local()
+            .checked_sub_days(chrono::Days::new(days.into()));
+        let expired = match before {
+            Some(before) => trash::expire(&mut self.trash, before),
+            None => 0,
+        };
+        if expired > 0 {
+            info!("Emptied {} tasks from the trash.", expired);
+            self.save_trash();
         }
-        self.window.refresh();
     }
 
-    /// Increase the priority of the currently selected task.
-    fn increase_priority(&mut self) {
-        if let Some(index) = self.selection {
-            let current = self.current_task.borrow();
-            let mut sub_task = current.sub_tasks[index].borrow_mut();
-            sub_task.priority = match sub_task.priority {
-                None => Some(Priority::Low),
-                Some(Priority::Low) => Some(Priority::Medium),
-                Some(Priority::Medium) => Some(Priority::High),
-                Some(Priority::High) => Some(Priority::High),
-            };
+    /// Write the trash beside the save file, if the list has one; otherwise
+    /// the trash only lasts until yat is closed.
+    fn save_trash(&mut self) {
+        let filename = match &self.save_file {
+            Some(filename) if !self.read_only => trash::path(filename),
+            _ => return,
+        };
+        if let Err(err) = trash::save(&filename, &self.trash) {
+            warn!("Unable to write {}: {}", filename.display(), err);
+            self.announce(&format!("Unable to write {}", filename.display()));
         }
     }
 
-    /// Decrease the priority of the currently selected task.
-    fn decrease_priority(&mut self) {
-        if let Some(index) = self.selection {
-            let current = self.current_task.borrow();
-            let mut sub_task = current.sub_tasks[index].borrow_mut();
-            sub_task.priority = match sub_task.priority {
-                None => None,
-                Some(Priority::Low) => None,
-                Some(Priority::Medium) => Some(Priority::Low),
-                Some(Priority::High) => Some(Priority::Medium),
+    /// Browse the deleted tasks, newest first, restoring the one chosen to
+    /// where it was.
+    fn open_trash(&mut self) {
+        if self.trash.is_empty() {
+            return self.announce("The trash is empty");
+        }
+        let options: Vec<String> = self
+            .trash
+            .iter()
+            .rev()
+            .map(|trashed| {
+                format!(
+                    "{} (deleted {} from {})",
+                    trashed.task.borrow().task,
+                    self.locale.format_date(trashed.deleted.date(), "%-d %b"),
+                    trashed.place()
+                )
+            })
+            .collect();
+        let title = format!("Restore from the trash ({} tasks)", options.len());
+        let choice = match self.pick(&title, &options, 0) {
+            Some(choice) => self.trash.len() - 1 - choice,
+            None => return,
+        };
+
+        let root = self.root_task();
+        let parent = trash::parent(&self.trash[choice], &root);
+        let task = Rc::clone(&self.trash[choice].task);
+        if let Err(err) = nesting::check(&parent, &[Rc::clone(&task)], self.window.config.max_depth)
+        {
+            return self.announce(&err);
+        }
+        let trashed = self.trash.remove(choice);
+        deps::renumber_clashes(&task, &root);
+        let index = nesting::attach(Rc::clone(&task), &parent, trashed.index);
+        self.save_trash();
+
+        let title = task.borrow().task.clone();
+        if Rc::ptr_eq(&parent, &self.current_task) {
+            self.selection = Some(index);
+            self.announce(&format!("Restored {}", title));
+        } else {
+            let place = match Rc::ptr_eq(&parent, &root) {
+                true => String::from("the top level"),
+                false => parent.borrow().task.clone(),
             };
+            self.announce(&format!("Restored {} under {}", title, place));
         }
     }
 
-    /// Add new task from user input.
-    fn add_task_from_input(&mut self) {
-        let task = self.input_dialogue("New Task:");
-        let parent = Rc::downgrade(&self.current_task);
-        let todo = ToDo::new(&task, parent);
-        let sub_tasks = &mut self.current_task.borrow_mut().sub_tasks;
-        sub_tasks.push(Rc::new(RefCell::new(todo)));
-        self.selection = Some(sub_tasks.len() - 1);
-    }
+    /// Display the archived tasks, as rows of (depth, text), scrolled down
+    /// to a given row.
+    fn print_archive(&mut self, rows: &[(usize, String)], offset: usize) {
+        self.window.clear();
+        self.window.hide_cursor();
+
+        let (ymax, xmax) = self.window.get_max_yx();
+        self.window.border((ymax - 1, 0), (ymax, xmax));
+        let title = format!("Archive: {} tasks", rows.len());
+        self.window.colour_on(4, 8);
+        self.window.wrap_print(1, 2, xmax - 4, &title);
+        self.window.colour_off();
 
-    /// Add new task from string buffer.
-    fn add_task_from_string(&mut self, input: &str) {
-        let parent = Rc::downgrade(&self.current_task);
-        let todo = ToDo::from_string(input, parent);
-        let sub_tasks = &mut self.current_task.borrow_mut().sub_tasks;
-        sub_tasks.push(Rc::new(RefCell::new(todo)));
-        self.selection = Some(sub_tasks.len() - 1);
+        let height = ymax.saturating_sub(6);
+        for (row, (depth, text)) in rows.iter().skip(offset).take(height).enumerate() {
+            let indent = (2 * depth).min(xmax / 2);
+            let width = (xmax - 4).saturating_sub(indent);
+            self.window.wrap_print(3 + row, 2 + indent, width, text);
+        }
+
+        let help = format!(
+            "Up/Down scroll, {} close",
+            key_name(self.window.config.keys.archive)
+        );
+        self.window.wrap_print(ymax - 2, 2, xmax - 4, &help);
+        self.window.refresh();
     }
 
-    /// Mark task as completed.
-    fn complete_task(&mut self) {
-        let sub_tasks = &mut self.current_task.borrow_mut().sub_tasks;
-        if let Some(index) = self.selection {
-            let mut sub_task = sub_tasks[index].borrow_mut();
-            sub_task.complete = !sub_task.complete;
+    /// Move tasks completed more than the configured number of days ago to
+    /// the archive file beside the save file, saving the smaller list.
+    fn auto_archive(&mut self) {
+        let days = self.window.config.archive_after;
+        let filename = match (&self.save_file, days) {
+            (Some(filename), 1..) => filename.clone(),
+            _ => return,
+        };
+        // A limit before the earliest date there is leaves nothing to archive
+        let before = match Local::now()
+            .date_naive()
+            .checked_sub_days(chrono::Days::new(days.into()))
+        {
+            Some(before) => before,
+            None => return,
+        };
+        let old = archive::find_old(&self.root_task().borrow(), before);
+        if old.is_empty() {
+            return;
+        }
+
+        let archive = archive::path(&filename);
+        let prompt = format!(
+            "Archive {} tasks completed over {} days ago? y/n",
+            old.len(),
+            days
+        );
+        if self.window.config.confirm.archive {
+            self.list_tasks();
+            if !self.popup(&prompt) {
+                return;
+            }
+        }
+        if !self.move_to_archive(&filename, &old) {
+            return;
         }
+        let count = old.len();
+        self.announce(&format!(
+            "Archived {} task{} completed over {} days ago to {}",
+            count,
+            if count == 1 { "" } else { "s" },
+            days,
+            archive.display()
+        ));
     }
 
-    /// Change ordering of sub-tasks for current task.
-    fn move_task(&mut self, up: bool) {
-        let sub_tasks = &mut self.current_task.borrow_mut().sub_tasks;
-        if let Some(index) = self.selection {
-            if up {
-                let new_index = if index == 0 {
-                    sub_tasks.len() - 1
-                } else {
-                    index - 1
-                };
-                sub_tasks.swap(new_index, index);
-                self.selection = Some(new_index);
-            } else {
-                let new_index = if index == sub_tasks.len() - 1 {
-                    0
-                } else {
-                    index + 1
-                };
-                sub_tasks.swap(new_index, index);
-                self.selection = Some(new_index);
+    /// Export todo list as HTML page alongside the save file.
+    fn export_html(&mut self) {
+        if self.read_only {
+            self.announce("Demo list is read-only: nothing was exported");
+            return;
+        }
+        if let Some(mut filename) = self.save_path() {
+            filename.set_extension("html");
+            // The list is copied to the worker in save file format, as the
+            // tree itself can't be shared between threads
+            let buffer = self.root_task().borrow().save_string();
+            let levels = self.window.config.priority_levels;
+            let path = filename.clone();
+            let doing = format!("Exporting to {}", filename.display());
+            let outcome = self.run_worker(&doing, move |progress| -> Result<(), String> {
+                let root = parse_list(&buffer).map_err(|err| err.to_string())?;
+                let html = export::html(&root.borrow(), levels);
+                if progress.cancelled() {
+                    return Err(String::from("cancelled"));
+                }
+                export::write(&path, &html).map_err(|err| err.to_string())
+            });
+            match outcome.map_err(String::from).and_then(|result| result) {
+                Ok(()) => self.announce(&format!("Exported to {}", filename.display())),
+                Err(reason) => self.announce(&format!("{}: {}", doing, reason)),
             }
         }
     }
 
-    /// Focus on currently selected sub-task.
-    fn new_focus(&mut self) {
-        let previous_root = self.root;
-        let previous_selection = self.selection;
-        let psub_tasks = Rc::clone(&self.current_task);
-        let sub_tasks = &psub_tasks.borrow().sub_tasks;
-        if let Some(index) = self.selection {
-            // Focus on sub-task
-            let sub_task = &sub_tasks[index];
-            self.current_task = Rc::clone(sub_task);
-            self.root = false;
-            self.selection = if !self.current_task.borrow().sub_tasks.is_empty() {
-                Some(0)
-            } else {
-                None
-            };
-            self.run();
+    /// Sort sub-tasks by priority, keeping the same task selected.
+    fn sort_by_priority(&mut self) {
+        let selected = self.selected_task();
+        self.current_task.borrow_mut().sort_by_priority();
+        if let Some(selected) = selected {
+            self.select_task(&selected);
+        }
+        self.announce("Sorted by priority");
+    }
 
-            // Return to parent task (unwrap cannot panic here)
-            self.current_task = sub_task.borrow().parent.upgrade().unwrap();
-            self.root = previous_root;
-            self.selection = previous_selection;
+    /// The selected task, if any.
+    fn selected_task(&self) -> Option<Rc<RefCell<ToDo>>> {
+        let index = self.selection?;
+        self.current_task.borrow().sub_tasks.get(index).cloned()
+    }
+
+    /// Select a task, if it is one of the sub-tasks of the current task,
+    /// so the selection follows the task when the tasks are reordered.
+    /// Returns whether it was found.
+    fn select_task(&mut self, task: &Rc<RefCell<ToDo>>) -> bool {
+        let position = self
+            .current_task
+            .borrow()
+            .sub_tasks
+            .iter()
+            .position(|t| Rc::ptr_eq(t, task));
+        if position.is_some() {
+            self.selection = position;
         }
+        position.is_some()
     }
 
-    /// Edited currently selected sub-task.
-    fn edit_task(&mut self) {
-        if let Some(index) = self.selection {
-            let task = self.edit_dialogue("Edit Task:", index);
-            let current_task = self.current_task.borrow_mut();
-            let mut sub_task = current_task.sub_tasks[index].borrow_mut();
-            sub_task.task = task;
+    /// Sort a task among its siblings again after its priority or due date
+    /// changed, if configured, keeping the same task selected.
+    fn auto_sort(&mut self, task: &Rc<RefCell<ToDo>>) {
+        if !self.window.config.auto_sort {
+            return;
+        }
+        let parent = match task.borrow().parent.upgrade() {
+            Some(parent) => parent,
+            None => return,
+        };
+        let selected = self.selected_task();
+        parent.borrow_mut().sort_by_priority();
+        if let Some(selected) = selected {
+            self.select_task(&selected);
         }
     }
+}
 
-    /// Move selection cursor.
-    fn move_selection(&mut self, ifup: bool) {
-        self.selection = if let Some(index) = self.selection {
-            if ifup {
-                self.up(index)
-            } else {
-                self.down(index)
-            }
-        } else {
-            match self.current_task.borrow().sub_tasks.len() {
-                0 => None,
-                _ => Some(0),
+/// Hash of some text, for telling whether it has changed.
+fn hash(text: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    text.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Labels for jumping to a number of tasks, all of the same length, made
+/// up of the given characters or numbered if "numbers".
+fn jump_labels(count: usize, characters: &str) -> Vec<String> {
+    if characters == "numbers" {
+        let width = count.to_string().len();
+        return (1..=count)
+            .map(|n| format!("{:0width$}", n, width = width))
+            .collect();
+    }
+
+    let characters: Vec<char> = characters.chars().collect();
+    if characters.len() < 2 {
+        return jump_labels(count, "numbers");
+    }
+    let mut length = 1;
+    while characters.len().pow(length) < count {
+        length += 1;
+    }
+    (0..count)
+        .map(|mut n| {
+            let mut label = Vec::new();
+            for _ in 0..length {
+                label.push(characters[n % characters.len()]);
+                n /= characters.len();
             }
-        };
+            label.iter().rev().collect()
+        })
+        .collect()
+}
+
+/// Whether a task has a tag (e.g. "+work") or is in a context (e.g.
+/// "@phone").
+fn has_label(todo: &ToDo, label: &str) -> bool {
+    match label.strip_prefix(['+', '#']) {
+        Some(tag) => todo.tags().any(|t| t == tag),
+        None => todo.contexts().any(|c| c == label),
     }
+}
 
-    /// Change index (wrapping below).
-    fn up(&self, index: usize) -> Option<usize> {
-        let ntasks = self.current_task.borrow().sub_tasks.len();
-        if index as isize - 1 < 0 {
-            Some(index + ntasks - 1)
-        } else {
-            Some(index - 1)
+/// Open text in an editor, given as a command such as the user's $EDITOR
+/// (using a temporary file), and return the edited result.
+fn external_editor(editor: &str, text: &str) -> Result<String, Error> {
+    let mut words = editor.split_whitespace();
+    let program = match words.next() {
+        Some(program) => program,
+        None => {
+            warn!("No editor specified in $EDITOR.");
+            return Err(Error::Editor(String::from(
+                "no editor specified in $EDITOR",
+            )));
         }
-    }
+    };
 
-    /// Change index (wrapping above).
-    fn down(&self, index: usize) -> Option<usize> {
-        let ntasks = self.current_task.borrow().sub_tasks.len();
-        if index + 1 >= ntasks {
-            Some(index + 1 - ntasks)
-        } else {
-            Some(index + 1)
+    let file = tempfile::Builder::new()
+        .prefix("yat-notes-")
+        .suffix(".txt")
+        .tempfile()
+        .and_then(|mut file| file.write_all(text.as_bytes()).map(|_| file));
+    let file = match file {
+        Ok(file) => file,
+        Err(err) => {
+            warn!("Unable to write temporary notes file: {}", err);
+            let reason = format!("unable to write temporary file: {}", err);
+            return Err(Error::Editor(reason));
+        }
+    };
+
+    let status = Command::new(program).args(words).arg(file.path()).status();
+    let result = match status {
+        Ok(status) if status.success() => match fs::read_to_string(file.path()) {
+            Ok(buffer) => Ok(buffer),
+            Err(err) => {
+                warn!("Unable to read temporary notes file: {}", err);
+                let reason = format!("unable to read temporary file: {}", err);
+                Err(Error::Editor(reason))
+            }
+        },
+        Ok(status) => {
+            warn!("Editor exited unsuccessfully: {}", status);
+            let reason = format!("{} exited unsuccessfully", program);
+            Err(Error::Editor(reason))
         }
+        Err(err) => {
+            warn!("Unable to run editor {}: {}", program, err);
+            let reason = format!("unable to run {}: {}", program, err);
+            Err(Error::Editor(reason))
+        }
+    };
+
+    file.close().unwrap_or_else(|err| {
+        warn!("Unable to remove temporary notes file: {}", err);
+    });
+    result
+}
+
+/// Parse a buffer in save file format into a todo list tree, returning
+/// its root.
+pub fn parse_list(buf: &str) -> Result<Rc<RefCell<ToDo>>, Error> {
+    parse_list_to_depth(buf, usize::MAX)
+}
+
+/// Parse a buffer in save file format into a todo list tree, leaving the
+/// tasks nested a number of levels deep or more unread, kept as lines under
+/// the task above them.
+fn parse_list_to_depth(buf: &str, depth: usize) -> Result<Rc<RefCell<ToDo>>, Error> {
+    let root = Rc::new(RefCell::new(ToDo::new("", Weak::new())));
+    let mut current = Rc::clone(&root);
+    fill_children(&mut current, &mut buf.lines(), 0, depth)?;
+    Ok(root)
+}
+
+/// Read the sub-tasks of a task which were left unread, down to a number of
+/// levels further. If they can't be read, they are left unread.
+fn read_unread(task: &Rc<RefCell<ToDo>>, depth: usize) -> Result<(), Error> {
+    if task.borrow().unread.is_empty() {
+        return Ok(());
     }
+    let holder = parse_list_to_depth(&task.borrow().unread, depth)?;
+    let sub_tasks = std::mem::take(&mut holder.borrow_mut().sub_tasks);
+    for sub_task in sub_tasks.iter() {
+        sub_task.borrow_mut().parent = Rc::downgrade(task);
+    }
+    let mut task = task.borrow_mut();
+    task.sub_tasks = sub_tasks;
+    task.unread.clear();
+    Ok(())
+}
 
-    /// Create a pop-up diaglogue with user choice.
-    fn popup(&mut self, prompt: &str) -> bool {
-        let (ymax, xmax) = self.window.get_max_yx();
-        self.window.border((ymax - 1, 0), (3, xmax));
-        self.window.rectangle(' ', (ymax - 2, 1), (1, xmax - 2));
-        self.window.colour_on(1, 7);
-        self.window.mvprintw(ymax - 2, 2, prompt);
-        self.window.colour_off();
-        self.window.refresh();
+/// Deepest nesting of tasks read from a save file, well within what the
+/// recursive drawing and saving of the list can handle.
+const MAX_DEPTH: usize = 1000;
 
-        let mut choice = false;
-        loop {
-            match self.window.getch() {
-                Some(Key::Char('y')) => {
-                    choice = true;
-                    break;
-                }
-                Some(Key::Char('n')) => break,
-                Some(Key::Char('q')) => break,
-                Some(Key::Char('b')) => break,
-                _ => (),
+/// Parse save file line by line and load into todo list tree structure,
+/// adding tasks to the current task. Tasks nested a number of levels deep
+/// or more are kept unread by the task above them.
+fn fill_children(
+    current: &mut Rc<RefCell<ToDo>>,
+    buf: &mut Lines,
+    mut tabs: usize,
+    depth: usize,
+) -> Result<(), Error> {
+    // Whether the last task was kept unread, along with its notes and
+    // comments
+    let mut unread = false;
+    // Lines are read in a loop rather than by recursion, so that long
+    // lists can't overflow the stack
+    for line in buf {
+        let note = line.trim_start().starts_with(['|', '>']);
+        if (note && unread) || (!note && tab_num(line) >= depth && tabs + 1 >= depth) {
+            // Kept indented from the sub-tasks of the task above
+            let indent = line.len() - line.trim_start_matches(' ').len();
+            match current.borrow().sub_tasks.last() {
+                Some(todo) => todo.borrow_mut().add_unread(&line[indent.min(4 * depth)..]),
+                None => return Err(Error::Parse("Can't have child without parent.")),
             }
+            unread = true;
+            continue;
         }
-        choice
-    }
+        unread = false;
 
-    /// Remove selected sub-task.
-    fn remove_task(&mut self) {
-        if let Some(index) = self.selection {
-            if self.popup("Are you sure you want to delete this task? y/n") {
-                let mut current_task = self.current_task.borrow_mut();
-                current_task.sub_tasks.remove(index);
-                self.selection = None;
+        // Lines starting with '|' hold the notes of the previously added
+        // task, so they leave the current indentation unchanged.
+        if line.trim_start().starts_with('|') {
+            match current.borrow().sub_tasks.last() {
+                Some(todo) => todo.borrow_mut().add_note_from_string(line.trim_start()),
+                None => return Err(Error::Parse("Can't have notes without task.")),
             }
+            continue;
         }
-    }
 
-    /// Save todo list to file.
-    fn save(&self) {
-        let current = self.current_task.borrow();
-        let filename = match self.save_file.clone() {
-            Some(f) => f,
-            None => {
-                let mut buffer = match home_dir() {
-                    Some(dir) => dir,
-                    None => {
-                        warn!("Unable to locate home directory.");
-                        return;
-                    }
-                };
-                buffer.push(".todo/save.txt");
-                buffer
+        // Likewise, lines starting with '>' hold comments
+        if line.trim_start().starts_with('>') {
+            match current.borrow().sub_tasks.last() {
+                Some(todo) => todo
+                    .borrow_mut()
+                    .add_comment_from_string(line.trim_start())?,
+                None => return Err(Error::Parse("Can't have comments without task.")),
             }
-        };
+            continue;
+        }
+
+        // Use indentation to determine where to insert each task. If
+        // indentation is the same as the previous line then we continue
+        // adding sub-tasks to the current line.
+        let num_tabs = tab_num(line);
+        if num_tabs == tabs + 1 {
+            // If indentation is increased compared to the previous line,
+            // then the previously added sub-task is the new current task
+            let last = current.borrow().sub_tasks.last().map(Rc::clone);
+            match last {
+                Some(new_current) => *current = new_current,
+                None => return Err(Error::Parse("Can't have child without parent.")),
+            }
+        } else if num_tabs < tabs {
+            // If indentation is decreased compared to the previous line,
+            // then the parent (or an even earlier ancestor) of the
+            // previous task is the new current task
+            ancestor(current, tabs - num_tabs);
+        } else if num_tabs > tabs + 1 {
+            return Err(Error::Parse("Too much indentation."));
+        }
+        if num_tabs >= MAX_DEPTH {
+            return Err(Error::Parse("Tasks nested too deeply."));
+        }
 
-        current.save(filename.as_path())
+        let todo = ToDo::from_string(line.trim_start(), Rc::downgrade(current));
+        current
+            .borrow_mut()
+            .sub_tasks
+            .push(Rc::new(RefCell::new(todo)));
+
+        // Continue onto next line
+        tabs = num_tabs;
     }
+    Ok(())
+}
 
-    /// Sort sub-tasks by priority.
-    fn sort_by_priority(&mut self) {
-        self.current_task.borrow_mut().sort_by_priority()
+/// Move current task to parent task, if it exists.
+fn ancestor(current: &mut Rc<RefCell<ToDo>>, level: usize) {
+    if level > 0 {
+        let parent = current.borrow().parent.upgrade();
+        if let Some(parent) = parent {
+            *current = parent;
+            ancestor(current, level - 1);
+        }
     }
 }
 
+/// A new sub-task of a task, taking any due date or rule for recurring out
+/// of the text entered for it, with the defaults of the tasks above it.
+fn new_task(task: &str, parent: &Rc<RefCell<ToDo>>) -> ToDo {
+    let (task, due) = due::extract(task, Local::now().naive_local());
+    let day = due.map_or(Local::now().date_naive(), |due| due.day());
+    let (task, repeat) = recur::extract(&task, day);
+    let mut todo = ToDo::new(&task, Rc::downgrade(parent));
+    if !todo.task.trim().is_empty() {
+        todo.inherit_defaults(parent);
+    }
+    todo.created = Some(Stamp::now());
+    todo.due = due;
+    todo.repeat = repeat;
+    todo
+}
+
 /// Determine number of tabs at start of string line.
 fn tab_num(line: &str) -> usize {
     let mut num = 0;
@@ -715,3 +5389,42 @@ fn tab_num(line: &str) -> usize {
     }
     num / 4
 }
+
+/// Name of a key, for help text.
+fn key_name(key: Key) -> String {
+    match key {
+        Key::Char(' ') => String::from("Space"),
+        Key::Char('\n') => String::from("Enter"),
+        Key::Char('\t') => String::from("Tab"),
+        Key::Char(ch) => ch.to_string(),
+        Key::Ctrl(ch) => format!("Ctrl-{}", ch),
+        Key::Alt(ch) => format!("Alt-{}", ch),
+        Key::F(n) => format!("F{}", n),
+        Key::Esc => String::from("Esc"),
+        Key::Backspace => String::from("Backspace"),
+        other => format!("{:?}", other),
+    }
+}
+
+/// Split text into lines of at most width (displayed) characters, breaking
+/// at spaces where possible.
+fn wrap_words(text: &str, width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    for word in text.split_whitespace() {
+        let len = UnicodeWidthStr::width(line.as_str());
+        if !line.is_empty() && len + 1 + UnicodeWidthStr::width(word) > width {
+            lines.push(line);
+            line = String::new();
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(word);
+    }
+    lines.push(line);
+    lines
+}
+
+#[cfg(test)]
+mod tests;
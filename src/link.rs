@@ -0,0 +1,18 @@
+/// Links to tasks, such as yat://task/3, which open yat at the task with
+/// that id, so that notes apps and exported lists can link back into yat.
+/// Every task link starts with the same prefix, followed by the task's id.
+const PREFIX: &str = "yat://task/";
+
+/// Link to the task with an id.
+pub fn to_task(id: u32) -> String {
+    format!("{}{}", PREFIX, id)
+}
+
+/// Id of the task a link is to, if it is a task link.
+pub fn parse(link: &str) -> Option<u32> {
+    link.trim()
+        .strip_prefix(PREFIX)?
+        .trim_end_matches('/')
+        .parse()
+        .ok()
+}
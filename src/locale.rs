@@ -0,0 +1,477 @@
+/// Translation of the interface and of the names of days and months, from a
+/// built-in message catalog or one in ~/.todo/locale/<language>.toml.
+use crate::config::Config;
+use chrono::{Datelike, Duration, NaiveDate, Weekday};
+use dirs::home_dir;
+use log::{info, warn};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+
+/// Names of the days of the week (from Monday) and months in English.
+const WEEKDAYS: [&str; 7] = [
+    "Monday",
+    "Tuesday",
+    "Wednesday",
+    "Thursday",
+    "Friday",
+    "Saturday",
+    "Sunday",
+];
+const MONTHS: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+
+/// Regions (by ISO 3166 code) where weeks usually start on Sunday or
+/// Saturday, rather than Monday.
+const SUNDAY_REGIONS: [&str; 16] = [
+    "US", "CA", "MX", "BR", "JP", "KR", "TW", "HK", "IL", "PH", "IN", "ZA", "CO", "PE", "VE", "PR",
+];
+const SATURDAY_REGIONS: [&str; 11] = [
+    "AE", "AF", "BH", "DZ", "EG", "IQ", "JO", "KW", "OM", "QA", "SY",
+];
+
+/// A built-in catalog: names of days and months, and translated messages
+/// keyed by their English text.
+struct Catalog {
+    language: &'static str,
+    weekdays: [&'static str; 7],
+    months: [&'static str; 12],
+    messages: &'static [(&'static str, &'static str)],
+}
+
+const CATALOGS: [Catalog; 3] = [
+    Catalog {
+        language: "de",
+        weekdays: [
+            "Montag",
+            "Dienstag",
+            "Mittwoch",
+            "Donnerstag",
+            "Freitag",
+            "Samstag",
+            "Sonntag",
+        ],
+        months: [
+            "Januar",
+            "Februar",
+            "März",
+            "April",
+            "Mai",
+            "Juni",
+            "Juli",
+            "August",
+            "September",
+            "Oktober",
+            "November",
+            "Dezember",
+        ],
+        messages: &[
+            ("Parent", "Übergeordnet"),
+            ("Tasks", "Aufgaben"),
+            ("Details", "Details"),
+            ("Sub-tasks", "Unteraufgaben"),
+            ("Selection", "Auswahl"),
+            ("New Task:", "Neue Aufgabe:"),
+            ("Edit Task:", "Aufgabe bearbeiten:"),
+            ("Comment:", "Kommentar:"),
+            ("Due (YYYY-MM-DD [HH:MM]):", "Fällig (JJJJ-MM-TT [HH:MM]):"),
+            ("Start (YYYY-MM-DD):", "Beginn (JJJJ-MM-TT):"),
+            ("Estimate (e.g. 1h30m):", "Schätzung (z. B. 1h30m):"),
+            ("Send task to:", "Aufgabe senden an:"),
+            (
+                "Are you sure you want to delete this task? y/n",
+                "Diese Aufgabe wirklich löschen? y/n",
+            ),
+            ("Added task", "Aufgabe hinzugefügt"),
+            ("Marked complete", "Als erledigt markiert"),
+            ("Marked not complete", "Als nicht erledigt markiert"),
+            ("Edited task", "Aufgabe bearbeitet"),
+            ("Added comment", "Kommentar hinzugefügt"),
+            ("Edited notes", "Notizen bearbeitet"),
+            ("Cleared due date", "Fälligkeitsdatum entfernt"),
+            ("Invalid due date", "Ungültiges Fälligkeitsdatum"),
+            ("Cleared start date", "Startdatum entfernt"),
+            ("Invalid start date", "Ungültiges Startdatum"),
+            ("Cleared estimate", "Schätzung entfernt"),
+            ("Invalid estimate", "Ungültige Schätzung"),
+            ("Deleted task", "Aufgabe gelöscht"),
+            ("Sorted by priority", "Nach Priorität sortiert"),
+            ("Cleared marks", "Markierungen aufgehoben"),
+            ("Nothing to review", "Nichts zu überprüfen"),
+            ("Finished weekly review", "Wochenrückblick beendet"),
+            (
+                "No next action to suggest",
+                "Keine nächste Aufgabe vorzuschlagen",
+            ),
+            ("Command finished", "Befehl beendet"),
+            ("Overdue", "Überfällig"),
+            ("Due today", "Heute fällig"),
+            ("Nothing due", "Nichts fällig"),
+            ("Nothing due today", "Heute nichts fällig"),
+            ("continued", "Fortsetzung"),
+            ("Agenda for", "Agenda für"),
+            ("Agenda for the week of", "Wochenagenda ab"),
+            ("Week", "Woche"),
+            ("week", "Woche"),
+            ("Next week", "Nächste Woche"),
+            ("1 task", "1 Aufgabe"),
+            ("{} tasks", "{} Aufgaben"),
+            ("{} due today", "{} heute fällig"),
+            ("{} overdue", "{} überfällig"),
+            ("due {}", "fällig {}"),
+            ("in {}", "in {}"),
+            ("%A %-d %B", "%A, %-d. %B"),
+            ("%A %-d %B %Y", "%A, %-d. %B %Y"),
+        ],
+    },
+    Catalog {
+        language: "fr",
+        weekdays: [
+            "lundi", "mardi", "mercredi", "jeudi", "vendredi", "samedi", "dimanche",
+        ],
+        months: [
+            "janvier",
+            "février",
+            "mars",
+            "avril",
+            "mai",
+            "juin",
+            "juillet",
+            "août",
+            "septembre",
+            "octobre",
+            "novembre",
+            "décembre",
+        ],
+        messages: &[
+            ("Parent", "Parent"),
+            ("Tasks", "Tâches"),
+            ("Details", "Détails"),
+            ("Sub-tasks", "Sous-tâches"),
+            ("Selection", "Sélection"),
+            ("New Task:", "Nouvelle tâche :"),
+            ("Edit Task:", "Modifier la tâche :"),
+            ("Comment:", "Commentaire :"),
+            (
+                "Due (YYYY-MM-DD [HH:MM]):",
+                "Échéance (AAAA-MM-JJ [HH:MM]) :",
+            ),
+            ("Start (YYYY-MM-DD):", "Début (AAAA-MM-JJ) :"),
+            ("Estimate (e.g. 1h30m):", "Estimation (p. ex. 1h30m) :"),
+            ("Send task to:", "Envoyer la tâche à :"),
+            (
+                "Are you sure you want to delete this task? y/n",
+                "Supprimer vraiment cette tâche ? y/n",
+            ),
+            ("Added task", "Tâche ajoutée"),
+            ("Marked complete", "Marquée comme terminée"),
+            ("Marked not complete", "Marquée comme non terminée"),
+            ("Edited task", "Tâche modifiée"),
+            ("Added comment", "Commentaire ajouté"),
+            ("Edited notes", "Notes modifiées"),
+            ("Cleared due date", "Échéance supprimée"),
+            ("Invalid due date", "Échéance invalide"),
+            ("Cleared start date", "Date de début supprimée"),
+            ("Invalid start date", "Date de début invalide"),
+            ("Cleared estimate", "Estimation supprimée"),
+            ("Invalid estimate", "Estimation invalide"),
+            ("Deleted task", "Tâche supprimée"),
+            ("Sorted by priority", "Triées par priorité"),
+            ("Cleared marks", "Marques effacées"),
+            ("Nothing to review", "Rien à passer en revue"),
+            ("Finished weekly review", "Revue hebdomadaire terminée"),
+            (
+                "No next action to suggest",
+                "Aucune prochaine action à suggérer",
+            ),
+            ("Command finished", "Commande terminée"),
+            ("Overdue", "En retard"),
+            ("Due today", "À faire aujourd'hui"),
+            ("Nothing due", "Rien à faire"),
+            ("Nothing due today", "Rien à faire aujourd'hui"),
+            ("continued", "suite"),
+            ("Agenda for", "Agenda du"),
+            ("Agenda for the week of", "Agenda de la semaine du"),
+            ("Week", "Semaine"),
+            ("week", "semaine"),
+            ("Next week", "Semaine prochaine"),
+            ("1 task", "1 tâche"),
+            ("{} tasks", "{} tâches"),
+            ("{} due today", "{} pour aujourd'hui"),
+            ("{} overdue", "{} en retard"),
+            ("due {}", "échéance {}"),
+            ("in {}", "dans {}"),
+        ],
+    },
+    Catalog {
+        language: "es",
+        weekdays: [
+            "lunes",
+            "martes",
+            "miércoles",
+            "jueves",
+            "viernes",
+            "sábado",
+            "domingo",
+        ],
+        months: [
+            "enero",
+            "febrero",
+            "marzo",
+            "abril",
+            "mayo",
+            "junio",
+            "julio",
+            "agosto",
+            "septiembre",
+            "octubre",
+            "noviembre",
+            "diciembre",
+        ],
+        messages: &[
+            ("Parent", "Superior"),
+            ("Tasks", "Tareas"),
+            ("Details", "Detalles"),
+            ("Sub-tasks", "Subtareas"),
+            ("Selection", "Selección"),
+            ("New Task:", "Nueva tarea:"),
+            ("Edit Task:", "Editar tarea:"),
+            ("Comment:", "Comentario:"),
+            ("Due (YYYY-MM-DD [HH:MM]):", "Vence (AAAA-MM-DD [HH:MM]):"),
+            ("Start (YYYY-MM-DD):", "Inicio (AAAA-MM-DD):"),
+            ("Estimate (e.g. 1h30m):", "Estimación (p. ej. 1h30m):"),
+            ("Send task to:", "Enviar tarea a:"),
+            (
+                "Are you sure you want to delete this task? y/n",
+                "¿Seguro que quieres borrar esta tarea? y/n",
+            ),
+            ("Added task", "Tarea añadida"),
+            ("Marked complete", "Marcada como completada"),
+            ("Marked not complete", "Marcada como no completada"),
+            ("Edited task", "Tarea editada"),
+            ("Added comment", "Comentario añadido"),
+            ("Edited notes", "Notas editadas"),
+            ("Cleared due date", "Fecha de vencimiento borrada"),
+            ("Invalid due date", "Fecha de vencimiento no válida"),
+            ("Cleared start date", "Fecha de inicio borrada"),
+            ("Invalid start date", "Fecha de inicio no válida"),
+            ("Cleared estimate", "Estimación borrada"),
+            ("Invalid estimate", "Estimación no válida"),
+            ("Deleted task", "Tarea borrada"),
+            ("Sorted by priority", "Ordenadas por prioridad"),
+            ("Cleared marks", "Marcas borradas"),
+            ("Nothing to review", "Nada que revisar"),
+            ("Finished weekly review", "Revisión semanal terminada"),
+            (
+                "No next action to suggest",
+                "Ninguna próxima acción que sugerir",
+            ),
+            ("Command finished", "Orden terminada"),
+            ("Overdue", "Vencidas"),
+            ("Due today", "Para hoy"),
+            ("Nothing due", "Nada pendiente"),
+            ("Nothing due today", "Nada pendiente para hoy"),
+            ("continued", "continuación"),
+            ("Agenda for", "Agenda del"),
+            ("Agenda for the week of", "Agenda de la semana del"),
+            ("Week", "Semana"),
+            ("week", "semana"),
+            ("Next week", "Próxima semana"),
+            ("1 task", "1 tarea"),
+            ("{} tasks", "{} tareas"),
+            ("{} due today", "{} para hoy"),
+            ("{} overdue", "{} con retraso"),
+            ("due {}", "vence {}"),
+            ("in {}", "en {}"),
+            ("%A %-d %B", "%A %-d de %B"),
+            ("%A %-d %B %Y", "%A %-d de %B de %Y"),
+        ],
+    },
+];
+
+/// Layout of a message catalog file, ~/.todo/locale/<language>.toml.
+#[derive(Deserialize, Debug)]
+struct CatalogFile {
+    /// Names of the days of the week, from Monday.
+    weekdays: Option<Vec<String>>,
+    months: Option<Vec<String>>,
+    /// Translations, keyed by the English text.
+    messages: Option<HashMap<String, String>>,
+}
+
+/// The language of the interface.
+pub struct Locale {
+    weekdays: Vec<String>,
+    months: Vec<String>,
+    messages: HashMap<String, String>,
+    /// First day of the week.
+    first_weekday: Weekday,
+}
+
+impl Locale {
+    /// Load the locale given in the configuration, or else from the
+    /// environment (LC_ALL, LC_MESSAGES or LANG, in order of precedence).
+    pub fn new(config: &Config) -> Locale {
+        let name = match config.locale {
+            Some(name) => String::from(name),
+            None => ["LC_ALL", "LC_MESSAGES", "LANG"]
+                .iter()
+                .filter_map(|var| env::var(var).ok())
+                .find(|name| !name.is_empty())
+                .unwrap_or_default(),
+        };
+        // e.g. "de" and "DE" from "de_DE.UTF-8"
+        let mut parts = name
+            .split(['.', '@'])
+            .next()
+            .unwrap_or_default()
+            .split(['_', '-']);
+        let language = parts.next().unwrap_or_default().to_lowercase();
+        let region = parts.next().unwrap_or_default().to_uppercase();
+
+        let first_weekday = match config.first_day_of_week.map(|day| day.parse::<Weekday>()) {
+            Some(Ok(day)) => day,
+            Some(Err(_)) => {
+                warn!("Unknown first_day_of_week, using the one for the locale.");
+                usual_first_weekday(&region)
+            }
+            None => usual_first_weekday(&region),
+        };
+        let mut locale = Locale {
+            weekdays: WEEKDAYS.iter().map(|&name| String::from(name)).collect(),
+            months: MONTHS.iter().map(|&name| String::from(name)).collect(),
+            messages: HashMap::new(),
+            first_weekday,
+        };
+        if language.is_empty() || language == "en" || language == "c" || language == "posix" {
+            return locale;
+        }
+
+        if let Some(catalog) = CATALOGS.iter().find(|c| c.language == language) {
+            locale.weekdays = catalog.weekdays.iter().map(|&n| String::from(n)).collect();
+            locale.months = catalog.months.iter().map(|&n| String::from(n)).collect();
+            locale.messages = catalog
+                .messages
+                .iter()
+                .map(|&(english, text)| (String::from(english), String::from(text)))
+                .collect();
+            info!("Using built-in {} messages.", language);
+        }
+        if let Some(file) = load_catalog(&language) {
+            if let Some(weekdays) = file.weekdays.filter(|names| names.len() == 7) {
+                locale.weekdays = weekdays;
+            }
+            if let Some(months) = file.months.filter(|names| names.len() == 12) {
+                locale.months = months;
+            }
+            locale.messages.extend(file.messages.unwrap_or_default());
+        }
+        locale
+    }
+
+    /// First day of the week containing a day.
+    pub fn week_start(&self, day: NaiveDate) -> NaiveDate {
+        let since = (day.weekday().num_days_from_monday() + 7
+            - self.first_weekday.num_days_from_monday())
+            % 7;
+        day - Duration::days(since.into())
+    }
+
+    /// ISO number of the week containing a day: that of the Monday in the
+    /// week, so weeks starting on another day are numbered sensibly too.
+    pub fn week_number(&self, day: NaiveDate) -> u32 {
+        let start = self.week_start(day);
+        let to_monday = (7 - start.weekday().num_days_from_monday()) % 7;
+        (start + Duration::days(to_monday.into())).iso_week().week()
+    }
+
+    /// Translate a message, leaving it in English if there is no
+    /// translation.
+    pub fn tr<'a>(&'a self, message: &'a str) -> &'a str {
+        self.messages.get(message).map_or(message, String::as_str)
+    }
+
+    /// Translate a message with a placeholder, "{}", filled in.
+    pub fn fill(&self, message: &str, value: &str) -> String {
+        self.tr(message).replace("{}", value)
+    }
+
+    /// Format a date, with the names of days (%A, or %a abbreviated) and
+    /// months (%B, or %b) in the language of the locale. The format string
+    /// is translated first, so it can be given in the usual local order.
+    pub fn format_date(&self, date: NaiveDate, format: &str) -> String {
+        let weekday = &self.weekdays[date.weekday().num_days_from_monday() as usize];
+        let month = &self.months[date.month0() as usize];
+        let mut expanded = String::new();
+        let mut chars = self.tr(format).chars();
+        while let Some(c) = chars.next() {
+            if c != '%' {
+                expanded.push(c);
+                continue;
+            }
+            match chars.next() {
+                Some('A') => expanded.push_str(&escape(weekday)),
+                Some('a') => expanded.push_str(&escape(&abbreviate(weekday))),
+                Some('B') => expanded.push_str(&escape(month)),
+                Some('b') => expanded.push_str(&escape(&abbreviate(month))),
+                Some(c) => {
+                    expanded.push('%');
+                    expanded.push(c);
+                }
+                None => expanded.push('%'),
+            }
+        }
+        date.format(&expanded).to_string()
+    }
+}
+
+/// First day of the week usual in a region.
+fn usual_first_weekday(region: &str) -> Weekday {
+    if SUNDAY_REGIONS.contains(&region) {
+        Weekday::Sun
+    } else if SATURDAY_REGIONS.contains(&region) {
+        Weekday::Sat
+    } else {
+        Weekday::Mon
+    }
+}
+
+/// Load the user's message catalog for a language, if there is one.
+fn load_catalog(language: &str) -> Option<CatalogFile> {
+    let mut filename = home_dir()?;
+    filename.push(format!(".todo/locale/{}.toml", language));
+    let text = fs::read_to_string(&filename).ok()?;
+    match toml::from_str(&text) {
+        Ok(file) => {
+            info!("Loaded messages from {}.", filename.display());
+            Some(file)
+        }
+        Err(err) => {
+            warn!("Unable to parse {}: {}", filename.display(), err);
+            None
+        }
+    }
+}
+
+/// Shorten the name of a day or month to its first three letters.
+fn abbreviate(name: &str) -> String {
+    name.chars().take(3).collect()
+}
+
+/// Escape text for use in a chrono format string.
+fn escape(text: &str) -> String {
+    text.replace('%', "%%")
+}
@@ -1,4 +1,4 @@
-/// Logging functionality.
+//! Logging functionality.
 
 /// Dispatch logger to report errors and other information.
 pub fn setup_logger() {
@@ -1,36 +1,141 @@
 use std::env;
 use std::process;
 use yat::{
-    config::{check_for_config, Config},
+    check_save,
+    cli::{Options, USAGE},
+    config::{check_for_config, config_path, load_profile, Config},
+    error::Error,
+    import_tasks,
     logger::setup_logger,
-    look_for_save, View,
+    look_for_save, print_agenda, quick_add, remote_save, send_digest, wizard, View,
 };
 
 fn main() {
+    // Command line options
+    let options = Options::parse(env::args().skip(1)).unwrap_or_else(|err| {
+        eprintln!("{}\n\n{}", err, USAGE);
+        process::exit(2);
+    });
+    if options.help {
+        println!("{}", USAGE);
+        return;
+    }
+
     // Set up loggin to stderr
     setup_logger();
 
+    // First-run setup
+    if wizard::first_run(&options) {
+        wizard::run();
+    }
+
+    // Profile
+    let profile = options.profile.as_ref().map(|name| {
+        let profile = load_profile(name).unwrap_or_else(|| {
+            eprintln!("Unable to load profile {} from ~/.todo/profiles.toml", name);
+            process::exit(2);
+        });
+        (profile.config_path(name), profile.save_file(name))
+    });
+
     // Configuration
     let mut config = Config::default();
-    let found_config = check_for_config();
-    match &found_config {
-        Some(configbuf) => {
-            config = configbuf.config(config);
+    let filename = match &profile {
+        Some((filename, _)) => filename.clone(),
+        None => config_path(),
+    };
+    let found_config = filename.as_deref().and_then(check_for_config);
+    if let Some(configbuf) = &found_config {
+        config = configbuf.config(config);
+    }
+    config.config_file = filename.as_deref();
+    if let Some((_, save_file)) = &profile {
+        config.save_file = Some(save_file);
+    }
+
+    // Print the agenda without starting the interface
+    if let Some(span) = options.agenda {
+        if let Err(err) = print_agenda(options.save_file, &config, span) {
+            fail(err);
+        }
+        return;
+    }
+
+    // Send a digest of the tasks due today without starting the interface
+    if let Some(delivery) = options.digest {
+        if let Err(err) = send_digest(options.save_file, &config, delivery) {
+            fail(err);
+        }
+        return;
+    }
+
+    // Check the save file for problems without starting the interface
+    if options.doctor {
+        match check_save(options.save_file, &config) {
+            Ok(true) => return,
+            Ok(false) => process::exit(1),
+            Err(err) => fail(err),
         }
-        None => (),
+    }
+
+    // Import tasks from another app without starting the interface
+    if let Some(export) = &options.import {
+        match import_tasks(export, options.save_file, &config) {
+            Ok(1) => println!("Imported 1 task from {}", export.display()),
+            Ok(count) => println!("Imported {} tasks from {}", count, export.display()),
+            Err(err) => fail(err),
+        }
+        return;
+    }
+
+    // Add a task, e.g. from a desktop hotkey, without starting the interface
+    if let Some(task) = &options.add {
+        if let Err(err) = quick_add(task, options.save_file, &config, options.to_daemon) {
+            fail(err);
+        }
+        return;
     }
 
     // Check for existence of valid save file
-    let view_result = match look_for_save(env::args()) {
-        Ok(filename) => View::new_from_save(filename, config),
-        Err(_) => View::new(config),
+    let view_result = if options.demo {
+        View::new_demo(config)
+    } else if let Some(storage) = remote_save(&options.save_file, &config) {
+        View::new_from_remote(storage, config)
+    } else {
+        match look_for_save(options.save_file, &config) {
+            Ok(filename) => View::new_from_save(filename, config),
+            Err(_) => View::new(config),
+        }
     };
 
     // Create UI
-    let mut view = view_result.unwrap_or_else(|_| {
-        process::exit(1);
-    });
+    let mut view = view_result.unwrap_or_else(|err| fail(err));
+
+    // Listen for tasks sent from outside the terminal
+    if options.daemon {
+        if let Err(err) = view.listen() {
+            drop(view);
+            fail(err);
+        }
+    }
+
+    // Run commands given on the command line, e.g. to open a filtered view
+    view.run_commands(&options.commands);
+
+    // Open at a task given on the command line
+    if let Some(target) = &options.goto {
+        view.goto(target);
+    }
 
     // Run todo list manager
-    view.run();
+    match options.watch {
+        true => view.watch(),
+        false => view.run(),
+    }
+}
+
+/// Print an error to stderr and exit with its exit code.
+fn fail(err: Error) -> ! {
+    eprintln!("{}", err);
+    process::exit(err.exit_code());
 }
@@ -0,0 +1,79 @@
+/// Moving tasks from one parent to another, with the checks made before
+/// putting tasks under another, so that the tree never ends up inside
+/// itself, or nested deeper than the configured limit.
+use crate::todo::ToDo;
+use std::cell::RefCell;
+use std::rc::{Rc, Weak};
+
+/// Check that tasks can be put under a parent: that none of them is the
+/// parent or above it, and that with their sub-tasks they would be nested
+/// no deeper than max_depth (0 for no limit).
+pub fn check(
+    parent: &Rc<RefCell<ToDo>>,
+    tasks: &[Rc<RefCell<ToDo>>],
+    max_depth: usize,
+) -> Result<(), String> {
+    let mut above = Some(Rc::clone(parent));
+    while let Some(task) = above {
+        if let Some(moved) = tasks.iter().find(|moved| Rc::ptr_eq(moved, &task)) {
+            return Err(format!("{} can't go under itself", moved.borrow().task));
+        }
+        above = task.borrow().parent.upgrade();
+    }
+
+    let height = tasks
+        .iter()
+        .map(|task| 1 + task.borrow().height())
+        .max()
+        .unwrap_or(0);
+    check_depth(depth(parent) + height, max_depth)
+}
+
+/// Check that tasks nested to a depth are within max_depth (0 for no
+/// limit).
+pub fn check_depth(depth: usize, max_depth: usize) -> Result<(), String> {
+    match max_depth {
+        0 => Ok(()),
+        max_depth if depth <= max_depth => Ok(()),
+        max_depth => Err(format!(
+            "Tasks can only be nested {} deep (max_depth)",
+            max_depth
+        )),
+    }
+}
+
+/// Depth of a task: 0 for the top of the list, 1 for the tasks in it and
+/// so on.
+pub fn depth(task: &Rc<RefCell<ToDo>>) -> usize {
+    let mut depth = 0;
+    let mut above = task.borrow().parent.upgrade();
+    while let Some(task) = above {
+        depth += 1;
+        above = task.borrow().parent.upgrade();
+    }
+    depth
+}
+
+/// Take a task out from under its parent, returning the parent and where
+/// it was among its sub-tasks, or None if it isn't in a tree.
+pub fn detach(task: &Rc<RefCell<ToDo>>) -> Option<(Rc<RefCell<ToDo>>, usize)> {
+    let parent = task.borrow().parent.upgrade()?;
+    let index = parent
+        .borrow()
+        .sub_tasks
+        .iter()
+        .position(|sub_task| Rc::ptr_eq(sub_task, task))?;
+    parent.borrow_mut().sub_tasks.remove(index);
+    task.borrow_mut().parent = Weak::new();
+    Some((parent, index))
+}
+
+/// Put a task under a parent, at a position among its sub-tasks, or last
+/// if there are fewer, returning the position.
+pub fn attach(task: Rc<RefCell<ToDo>>, parent: &Rc<RefCell<ToDo>>, index: usize) -> usize {
+    task.borrow_mut().parent = Rc::downgrade(parent);
+    let sub_tasks = &mut parent.borrow_mut().sub_tasks;
+    let index = index.min(sub_tasks.len());
+    sub_tasks.insert(index, task);
+    index
+}
@@ -0,0 +1,68 @@
+/// Suggesting the next task to work on, by ranking the tasks which can be
+/// done now.
+use crate::config::Weights;
+use crate::deps;
+use crate::todo::ToDo;
+use chrono::NaiveDateTime;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+/// Rank a task by its priority on a scale with a number of levels, how
+/// soon it is due and whether it is flagged for today, each between 0 and 1,
+/// combined by weight.
+pub fn score(todo: &ToDo, weights: &Weights, levels: u8, now: NaiveDateTime) -> f64 {
+    let priority = match todo.priority {
+        Some(priority) => f64::from(levels.saturating_sub(priority.rank())) / f64::from(levels),
+        None => 0.0,
+    };
+    let due = match todo.due {
+        Some(due) => {
+            let days = (due.deadline() - now).num_minutes() as f64 / 1440.0;
+            match days > 0.0 {
+                true => 1.0 / (1.0 + days),
+                false => 1.0,
+            }
+        }
+        None => 0.0,
+    };
+    let today = match todo.today {
+        Some(_) => 1.0,
+        None => 0.0,
+    };
+    weights.priority * priority + weights.due * due + weights.today * today
+}
+
+/// Suggest the next task under root, out of unfinished tasks without
+/// unfinished sub-tasks, which aren't blocked and satisfy a condition
+/// (such as being in the current context). The earliest of equally ranked
+/// tasks is suggested.
+pub fn suggest(
+    root: &Rc<RefCell<ToDo>>,
+    weights: &Weights,
+    levels: u8,
+    now: NaiveDateTime,
+    condition: &dyn Fn(&ToDo) -> bool,
+) -> Option<Rc<RefCell<ToDo>>> {
+    let unfinished = deps::unfinished(root);
+    let mut best: Option<(f64, Rc<RefCell<ToDo>>)> = None;
+    let mut stack: Vec<Rc<RefCell<ToDo>>> = root.borrow().sub_tasks.iter().rev().cloned().collect();
+    while let Some(task) = stack.pop() {
+        {
+            let todo = task.borrow();
+            stack.extend(todo.sub_tasks.iter().rev().cloned());
+            let actionable = !todo.complete
+                && todo.sub_tasks.iter().all(|t| t.borrow().complete)
+                && !deps::is_blocked(&todo, &unfinished)
+                && condition(&todo);
+            if !actionable {
+                continue;
+            }
+            let score = score(&todo, weights, levels, now);
+            if best.as_ref().is_some_and(|(best, _)| *best >= score) {
+                continue;
+            }
+            best = Some((score, Rc::clone(&task)));
+        }
+    }
+    best.map(|(_, task)| task)
+}
@@ -0,0 +1,95 @@
+/// The stages the sub-tasks of a task pass through on their way to the
+/// task panels. Each filter in turn narrows down the tasks shown, then an
+/// optional grouping stage buckets them under headers, so new views plug
+/// in as extra stages rather than changes to the drawing.
+use crate::todo::ToDo;
+use std::collections::BTreeMap;
+
+/// Condition a task has to meet to be shown.
+type Filter<'p> = Box<dyn Fn(&ToDo) -> bool + 'p>;
+
+/// Group of a task, as a key to order the groups by and the title of its
+/// header, or None for tasks left over.
+type Grouping<'p> = Box<dyn Fn(&ToDo) -> Option<(String, String)> + 'p>;
+
+/// Row of the task panels.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Row {
+    /// Header of a group, with the number of tasks in it.
+    Header(String, usize),
+    /// Sub-task, by its index.
+    Task(usize),
+}
+
+/// Stages which choose the sub-tasks shown, in the order they are added.
+#[derive(Default)]
+pub struct Pipeline<'p> {
+    filters: Vec<Filter<'p>>,
+    group: Option<(Grouping<'p>, &'p str)>,
+}
+
+impl<'p> Pipeline<'p> {
+    /// Add a filter stage, after those already in the pipeline.
+    pub fn filter(mut self, filter: impl Fn(&ToDo) -> bool + 'p) -> Self {
+        self.filters.push(Box::new(filter));
+        self
+    }
+
+    /// Group the tasks shown under headers, in the order of their keys,
+    /// with any tasks not in a group last under a header of their own.
+    pub fn group(
+        mut self,
+        other: &'p str,
+        group: impl Fn(&ToDo) -> Option<(String, String)> + 'p,
+    ) -> Self {
+        self.group = Some((Box::new(group), other));
+        self
+    }
+
+    /// Whether a task gets through every stage.
+    pub fn shows(&self, todo: &ToDo) -> bool {
+        self.filters.iter().all(|filter| filter(todo))
+    }
+
+    /// Indices of the sub-tasks of a task which get through, in the order
+    /// they are shown.
+    pub fn run(&self, todo: &ToDo) -> Vec<usize> {
+        self.rows(todo)
+            .into_iter()
+            .filter_map(|row| match row {
+                Row::Task(i) => Some(i),
+                Row::Header(..) => None,
+            })
+            .collect()
+    }
+
+    /// Rows showing the sub-tasks of a task which get through: under the
+    /// header of each group if grouped, keeping their order within it.
+    pub fn rows(&self, todo: &ToDo) -> Vec<Row> {
+        let shown = todo
+            .sub_tasks
+            .iter()
+            .enumerate()
+            .filter(|(_, sub_task)| self.shows(&sub_task.borrow()));
+        let (grouping, other) = match &self.group {
+            Some(group) => group,
+            None => return shown.map(|(i, _)| Row::Task(i)).collect(),
+        };
+
+        // Keyed by whether tasks are left over, so those go last
+        let mut groups: BTreeMap<(bool, String), (String, Vec<usize>)> = BTreeMap::new();
+        for (i, sub_task) in shown {
+            let (key, title) = match grouping(&sub_task.borrow()) {
+                Some((key, title)) => ((false, key), title),
+                None => ((true, String::new()), other.to_string()),
+            };
+            groups.entry(key).or_insert((title, Vec::new())).1.push(i);
+        }
+        let mut rows = Vec::new();
+        for (title, tasks) in groups.into_values() {
+            rows.push(Row::Header(title, tasks.len()));
+            rows.extend(tasks.into_iter().map(Row::Task));
+        }
+        rows
+    }
+}
@@ -0,0 +1,155 @@
+/// Rules for tasks which recur, so that completing one adds the next
+/// occurrence, due when the rule says.
+use chrono::{Datelike, Days, Months, NaiveDate};
+use std::collections::BTreeSet;
+use std::fmt;
+
+/// How often a task recurs, stored after it as e.g. "repeat:weekly".
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Recurrence {
+    Daily,
+    Weekly,
+    /// Every number of days, e.g. "repeat:3d", other than every day or
+    /// every week.
+    Days(u32),
+    /// Monthly on a day of the month, e.g. "repeat:monthly-15", or on the
+    /// last day for months without it.
+    Monthly(u32),
+}
+
+impl Recurrence {
+    /// Parse a rule: "daily", "weekly", a number of days or weeks such as
+    /// "3d" or "2w", or "monthly-N" for monthly on day N. "monthly" on its
+    /// own is monthly on the day a task is due, or today.
+    pub fn parse(text: &str, day: NaiveDate) -> Option<Recurrence> {
+        let text = text.trim().to_lowercase();
+        let number = |text: &str| text.parse::<u32>().ok().filter(|&n| n > 0);
+        let days = match text.as_str() {
+            "daily" => 1,
+            "weekly" => 7,
+            "monthly" => return Some(Recurrence::Monthly(day.day())),
+            _ => {
+                if let Some(day) = text.strip_prefix("monthly-") {
+                    return number(day)
+                        .filter(|&day| day <= 31)
+                        .map(Recurrence::Monthly);
+                }
+                let (count, unit) = text.split_at(text.char_indices().last()?.0);
+                match unit {
+                    "d" => number(count)?,
+                    "w" => number(count)?.checked_mul(7)?,
+                    _ => return None,
+                }
+            }
+        };
+        match days {
+            1 => Some(Recurrence::Daily),
+            7 => Some(Recurrence::Weekly),
+            _ => Some(Recurrence::Days(days)),
+        }
+    }
+
+    /// Day of the occurrence after one on a given day.
+    fn after(&self, day: NaiveDate) -> Option<NaiveDate> {
+        match *self {
+            Recurrence::Daily => day.checked_add_days(Days::new(1)),
+            Recurrence::Weekly => day.checked_add_days(Days::new(7)),
+            Recurrence::Days(count) => day.checked_add_days(Days::new(count.into())),
+            Recurrence::Monthly(dom) => {
+                // Later this month if the day hasn't come yet, otherwise
+                // next month
+                let on = |month: NaiveDate| {
+                    let last = month.checked_add_months(Months::new(1))?.pred_opt()?.day();
+                    month.with_day(dom.min(last))
+                };
+                let month = day.with_day(1)?;
+                match on(month)? {
+                    date if date > day => Some(date),
+                    _ => on(month.checked_add_months(Months::new(1))?),
+                }
+            }
+        }
+    }
+
+    /// Day the next occurrence is due, after one due on a given day: the
+    /// first day the rule gives which is after today, so that a task done
+    /// late doesn't leave a trail of overdue occurrences behind it, and
+    /// which isn't skipped.
+    pub fn next(
+        &self,
+        due: NaiveDate,
+        today: NaiveDate,
+        skip: &BTreeSet<NaiveDate>,
+    ) -> Option<NaiveDate> {
+        let mut day = self.after(due)?;
+        if day <= today {
+            // Skip straight to about today, for long-overdue tasks
+            let behind = (today - day).num_days() as u64;
+            if let Recurrence::Days(_) | Recurrence::Daily | Recurrence::Weekly = self {
+                let step = (self.after(day)? - day).num_days() as u64;
+                day = day.checked_add_days(Days::new(behind / step * step))?;
+            }
+            while day <= today {
+                day = self.after(day)?;
+            }
+        }
+        while skip.contains(&day) {
+            day = self.after(day)?;
+        }
+        Some(day)
+    }
+
+    /// Days of the occurrences after one on a given day, skipped or not.
+    pub fn upcoming(&self, day: NaiveDate, count: usize) -> Vec<NaiveDate> {
+        let mut days = Vec::with_capacity(count);
+        let mut day = day;
+        while days.len() < count {
+            match self.after(day) {
+                Some(next) => day = next,
+                None => break,
+            }
+            days.push(day);
+        }
+        days
+    }
+}
+
+impl fmt::Display for Recurrence {
+    /// Save file format, as a single word.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Recurrence::Daily => write!(f, "daily"),
+            Recurrence::Weekly => write!(f, "weekly"),
+            Recurrence::Days(count) if count % 7 == 0 => write!(f, "{}w", count / 7),
+            Recurrence::Days(count) => write!(f, "{}d", count),
+            Recurrence::Monthly(day) => write!(f, "monthly-{}", day),
+        }
+    }
+}
+
+/// Take a rule typed as part of a task, such as "water plants
+/// repeat:weekly", out of it, returning the rest of the task along with the
+/// rule, if there is one which can be read.
+pub fn extract(task: &str, day: NaiveDate) -> (String, Option<Recurrence>) {
+    let mut repeat = None;
+    let words: Vec<&str> = task
+        .split(' ')
+        .filter(|word| {
+            let rule = word.strip_prefix("repeat:");
+            match rule.and_then(|rule| Recurrence::parse(rule, day)) {
+                Some(rule) if repeat.is_none() => {
+                    repeat = Some(rule);
+                    false
+                }
+                _ => true,
+            }
+        })
+        .collect();
+    match repeat {
+        Some(_) => (words.join(" ").trim().to_string(), repeat),
+        None => (String::from(task), None),
+    }
+}
+
+#[cfg(test)]
+mod tests;
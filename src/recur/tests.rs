@@ -0,0 +1,150 @@
+/// Tests of recurrence rules, around the ends of months and leap days.
+use super::{extract, Recurrence};
+use crate::tests::day;
+use chrono::NaiveDate;
+use std::collections::BTreeSet;
+
+/// Days, from "YYYY-MM-DD".
+fn days(texts: &[&str]) -> Vec<NaiveDate> {
+    texts.iter().map(|text| day(text)).collect()
+}
+
+#[test]
+fn rules() {
+    let today = day("2024-02-29");
+    assert_eq!(Recurrence::parse("Daily", today), Some(Recurrence::Daily));
+    assert_eq!(Recurrence::parse("1d", today), Some(Recurrence::Daily));
+    assert_eq!(Recurrence::parse("7d", today), Some(Recurrence::Weekly));
+    assert_eq!(Recurrence::parse("1w", today), Some(Recurrence::Weekly));
+    assert_eq!(Recurrence::parse("2w", today), Some(Recurrence::Days(14)));
+    assert_eq!(
+        Recurrence::parse("monthly", today),
+        Some(Recurrence::Monthly(29))
+    );
+    assert_eq!(
+        Recurrence::parse("monthly-31", today),
+        Some(Recurrence::Monthly(31))
+    );
+    for rule in [
+        Recurrence::Days(3),
+        Recurrence::Days(21),
+        Recurrence::Monthly(31),
+    ] {
+        assert_eq!(Recurrence::parse(&rule.to_string(), today), Some(rule));
+    }
+}
+
+#[test]
+fn invalid_rules() {
+    let today = day("2024-02-29");
+    for text in [
+        "",
+        "d",
+        "0d",
+        "-1d",
+        "3x",
+        "3é",
+        "é",
+        "yearly",
+        "monthly-0",
+        "monthly-32",
+        "monthly-",
+        "monthly-x",
+        "4294967295w",
+    ] {
+        assert_eq!(Recurrence::parse(text, today), None, "{:?}", text);
+    }
+}
+
+#[test]
+fn monthly_on_days_short_months_lack() {
+    assert_eq!(
+        Recurrence::Monthly(31).upcoming(day("2024-01-31"), 4),
+        days(&["2024-02-29", "2024-03-31", "2024-04-30", "2024-05-31"])
+    );
+    assert_eq!(
+        Recurrence::Monthly(31).upcoming(day("2023-01-31"), 2),
+        days(&["2023-02-28", "2023-03-31"])
+    );
+    assert_eq!(
+        Recurrence::Monthly(30).upcoming(day("2023-02-28"), 2),
+        days(&["2023-03-30", "2023-04-30"])
+    );
+    assert_eq!(
+        Recurrence::Monthly(29).upcoming(day("2023-12-29"), 3),
+        days(&["2024-01-29", "2024-02-29", "2024-03-29"])
+    );
+    // Earlier in the month than the rule's day
+    assert_eq!(
+        Recurrence::Monthly(31).upcoming(day("2024-02-10"), 1),
+        days(&["2024-02-29"])
+    );
+    assert_eq!(
+        Recurrence::Monthly(15).upcoming(day("2024-12-15"), 1),
+        days(&["2025-01-15"])
+    );
+}
+
+#[test]
+fn every_number_of_days_over_leap_days() {
+    assert_eq!(
+        Recurrence::Days(3).upcoming(day("2024-02-26"), 3),
+        days(&["2024-02-29", "2024-03-03", "2024-03-06"])
+    );
+    assert_eq!(
+        Recurrence::Weekly.upcoming(day("2023-02-22"), 2),
+        days(&["2023-03-01", "2023-03-08"])
+    );
+}
+
+#[test]
+fn next_after_today_and_skipped_days() {
+    let none = BTreeSet::new();
+    let daily = Recurrence::Daily;
+    assert_eq!(
+        daily.next(day("2024-02-28"), day("2024-02-28"), &none),
+        Some(day("2024-02-29"))
+    );
+    assert_eq!(
+        daily.next(day("2024-01-01"), day("2024-03-01"), &none),
+        Some(day("2024-03-02"))
+    );
+    // Mondays, done long after
+    assert_eq!(
+        Recurrence::Weekly.next(day("2024-01-01"), day("2024-02-29"), &none),
+        Some(day("2024-03-04"))
+    );
+    assert_eq!(
+        Recurrence::Monthly(31).next(day("2023-12-31"), day("2024-03-15"), &none),
+        Some(day("2024-03-31"))
+    );
+
+    let skip: BTreeSet<NaiveDate> = days(&["2024-02-29", "2024-03-01"]).into_iter().collect();
+    assert_eq!(
+        daily.next(day("2024-02-28"), day("2024-02-28"), &skip),
+        Some(day("2024-03-02"))
+    );
+    assert_eq!(
+        Recurrence::Monthly(31).next(day("2024-01-31"), day("2024-01-31"), &skip),
+        Some(day("2024-03-31"))
+    );
+}
+
+#[test]
+fn rules_taken_out_of_tasks() {
+    let today = day("2024-02-29");
+    assert_eq!(
+        extract("water plants repeat:monthly repeat:weekly", today),
+        (
+            String::from("water plants repeat:weekly"),
+            Some(Recurrence::Monthly(29))
+        )
+    );
+    for task in [
+        "water plants repeat:monthly-40",
+        "water plants repeat:",
+        "repeat",
+    ] {
+        assert_eq!(extract(task, today), (String::from(task), None));
+    }
+}
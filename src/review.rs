@@ -0,0 +1,69 @@
+/// Weekly review of projects (top-level tasks), in the style of GTD.
+use crate::todo::ToDo;
+use chrono::{Duration, NaiveDate};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+/// Section of the weekly review.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Section {
+    /// Tasks created during the week.
+    Added,
+    /// Tasks completed during the week.
+    Completed,
+    /// Unfinished tasks created before the week.
+    Stalled,
+}
+
+/// Sections in the order they are reviewed.
+pub const SECTIONS: [Section; 3] = [Section::Added, Section::Completed, Section::Stalled];
+
+impl Section {
+    /// Heading of section.
+    pub fn heading(&self) -> &'static str {
+        match self {
+            Section::Added => "Added this week",
+            Section::Completed => "Completed this week",
+            Section::Stalled => "Stalled (open for over a week)",
+        }
+    }
+
+    /// Whether a task belongs in the section, for the week up to today.
+    fn contains(&self, todo: &ToDo, today: NaiveDate) -> bool {
+        let week_ago = today - Duration::days(7);
+        match self {
+            Section::Added => todo.created.is_some_and(|stamp| stamp.day() > week_ago),
+            Section::Completed => {
+                todo.complete && todo.completed.is_some_and(|stamp| stamp.day() > week_ago)
+            }
+            Section::Stalled => {
+                !todo.complete && todo.created.is_none_or(|stamp| stamp.day() <= week_ago)
+            }
+        }
+    }
+}
+
+/// Tasks of a project (including the project itself) to review, by
+/// section, for the week up to today.
+pub fn items(project: &Rc<RefCell<ToDo>>, today: NaiveDate) -> Vec<(Section, Rc<RefCell<ToDo>>)> {
+    let mut tasks = Vec::new();
+    all_tasks(project, &mut tasks);
+
+    let mut items = Vec::new();
+    for section in SECTIONS.iter() {
+        for task in tasks.iter() {
+            if section.contains(&task.borrow(), today) {
+                items.push((*section, Rc::clone(task)));
+            }
+        }
+    }
+    items
+}
+
+/// Collect a task and all of its sub-tasks.
+fn all_tasks(task: &Rc<RefCell<ToDo>>, tasks: &mut Vec<Rc<RefCell<ToDo>>>) {
+    tasks.push(Rc::clone(task));
+    for sub_task in task.borrow().sub_tasks.iter() {
+        all_tasks(sub_task, tasks);
+    }
+}
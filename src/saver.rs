@@ -0,0 +1,142 @@
+/// Saving the todo list in a background thread, so that writing a large
+/// list never holds up input or drawing.
+use log::{info, warn};
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread::{self, JoinHandle};
+
+/// Snapshot of the list in save file format, with where to write it.
+type Snapshot = (PathBuf, String);
+
+/// Outcome of writing a snapshot: where it was saved, or why it wasn't.
+pub type Outcome = Result<PathBuf, String>;
+
+/// Handle on the thread writing snapshots of the list.
+pub struct Saver {
+    /// Snapshots waiting to be written, closed when the saver is dropped.
+    snapshots: Option<Sender<Snapshot>>,
+    /// Outcomes of snapshots written, with how many snapshots each covers.
+    outcomes: Receiver<(usize, Outcome)>,
+    /// Number of snapshots sent without an outcome collected yet.
+    pending: usize,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Saver {
+    /// Start the thread writing snapshots.
+    pub fn new() -> Saver {
+        let (snapshots, received) = mpsc::channel::<Snapshot>();
+        let (sender, outcomes) = mpsc::channel();
+        let thread = thread::spawn(move || {
+            while let Ok(first) = received.recv() {
+                // Only the latest of the snapshots waiting for each file
+                // needs writing, when saving faster than the disk keeps up
+                let mut waiting = vec![first];
+                waiting.extend(received.try_iter());
+                for (covered, (path, buffer)) in latest(&waiting) {
+                    let outcome = match write(path, buffer) {
+                        Ok(_) => {
+                            info!("Todo list saved to {}.", path.display());
+                            Ok(path.clone())
+                        }
+                        Err(err) => {
+                            warn!("Unable to save to {}: {}", path.display(), err);
+                            Err(format!("Unable to save to {}: {}", path.display(), err))
+                        }
+                    };
+                    if sender.send((covered, outcome)).is_err() {
+                        return;
+                    }
+                }
+            }
+        });
+        Saver {
+            snapshots: Some(snapshots),
+            outcomes,
+            pending: 0,
+            thread: Some(thread),
+        }
+    }
+
+    /// Write a snapshot of the list to a file in the background.
+    pub fn save(&mut self, path: PathBuf, buffer: String) {
+        if let Some(snapshots) = &self.snapshots {
+            match snapshots.send((path, buffer)) {
+                Ok(_) => self.pending += 1,
+                Err(_) => warn!("Unable to save: the saving thread has stopped."),
+            }
+        }
+    }
+
+    /// Whether any snapshots are still being written.
+    pub fn busy(&self) -> bool {
+        self.pending > 0
+    }
+
+    /// Collect the outcomes of the snapshots written since last checked.
+    pub fn outcomes(&mut self) -> Vec<Outcome> {
+        let mut outcomes = Vec::new();
+        for (covered, outcome) in self.outcomes.try_iter() {
+            self.pending = self.pending.saturating_sub(covered);
+            outcomes.push(outcome);
+        }
+        outcomes
+    }
+}
+
+impl Drop for Saver {
+    /// Finish writing any snapshots still waiting, so quitting never loses
+    /// a save.
+    fn drop(&mut self) {
+        self.snapshots.take();
+        if let Some(thread) = self.thread.take() {
+            if thread.join().is_err() {
+                warn!("The saving thread panicked.");
+            }
+        }
+    }
+}
+
+/// The snapshots to write out of those waiting, the latest for each file,
+/// with how many of the waiting snapshots each covers.
+fn latest(waiting: &[Snapshot]) -> Vec<(usize, &Snapshot)> {
+    waiting
+        .iter()
+        .enumerate()
+        .filter(|(i, (path, _))| !waiting[i + 1..].iter().any(|(later, _)| later == path))
+        .map(|(i, (path, _))| {
+            let covered = waiting[..=i].iter().filter(|(p, _)| p == path).count();
+            (covered, &waiting[i])
+        })
+        .collect()
+}
+
+/// Write a file in full or not at all, by writing a temporary file beside
+/// it and moving that into place, so a reader never sees half a list. A
+/// symbolic link is followed, and the file keeps its permissions (a new
+/// one is only readable by its owner).
+pub fn write(path: &Path, buffer: &str) -> io::Result<()> {
+    let path = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    let name = path.file_name().map_or(String::from("save"), |name| {
+        name.to_string_lossy().into_owned()
+    });
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    // A name of its own, so saves from two processes can't mix
+    let mut temporary = tempfile::Builder::new()
+        .prefix(&format!(".{}.", name))
+        .suffix(".saving")
+        .tempfile_in(dir)?;
+    temporary.write_all(buffer.as_bytes())?;
+    if let Ok(metadata) = fs::metadata(&path) {
+        fs::set_permissions(temporary.path(), metadata.permissions())?;
+    }
+    temporary.persist(&path).map(drop).map_err(|err| err.error)
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,104 @@
+/// Tests of saving in the background: snapshots written faster than the
+/// disk keeps up are coalesced, and each is accounted for once written.
+use super::{latest, write, Saver};
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// A snapshot of a list for a file.
+fn snapshot(path: &str, list: &str) -> (PathBuf, String) {
+    (PathBuf::from(path), String::from(list))
+}
+
+/// Collect outcomes until every snapshot saved has been written.
+fn wait(saver: &mut Saver) -> Vec<Result<PathBuf, String>> {
+    let start = Instant::now();
+    let mut outcomes = Vec::new();
+    while saver.busy() {
+        assert!(
+            start.elapsed() < Duration::from_secs(10),
+            "saver never finished"
+        );
+        outcomes.extend(saver.outcomes());
+        thread::sleep(Duration::from_millis(5));
+    }
+    outcomes
+}
+
+/// Files in a directory, other than those given.
+fn others(dir: &Path, known: &[&str]) -> Vec<String> {
+    fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+        .filter(|name| !known.contains(&name.as_str()))
+        .collect()
+}
+
+#[test]
+fn latest_snapshot_of_each_file_covers_the_others() {
+    let waiting = [
+        snapshot("a", "1"),
+        snapshot("b", "1"),
+        snapshot("a", "2"),
+        snapshot("a", "3"),
+        snapshot("b", "2"),
+    ];
+    assert_eq!(latest(&waiting), [(3, &waiting[3]), (2, &waiting[4])]);
+    let waiting = [snapshot("a", "1"), snapshot("b", "1")];
+    assert_eq!(latest(&waiting), [(1, &waiting[0]), (1, &waiting[1])]);
+    assert_eq!(latest(&[]), []);
+}
+
+#[test]
+fn every_snapshot_is_accounted_for() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("todo.txt");
+    let mut saver = Saver::new();
+    for i in 0..50 {
+        saver.save(path.clone(), format!("[ ] ( ) Task {}\n", i));
+    }
+    assert!(saver.busy());
+    let outcomes = wait(&mut saver);
+    assert!(!outcomes.is_empty() && outcomes.len() <= 50);
+    assert!(outcomes.iter().all(|outcome| outcome.as_ref() == Ok(&path)));
+    assert_eq!(fs::read_to_string(&path).unwrap(), "[ ] ( ) Task 49\n");
+
+    let missing = dir.path().join("missing").join("todo.txt");
+    saver.save(missing.clone(), String::new());
+    saver.save(path.clone(), String::from("[ ] ( ) Last\n"));
+    let outcomes = wait(&mut saver);
+    assert_eq!(outcomes.len(), 2);
+    assert!(outcomes.iter().any(|outcome| outcome.is_err()));
+    assert!(outcomes.contains(&Ok(path.clone())));
+    assert!(saver.outcomes().is_empty());
+    assert_eq!(others(dir.path(), &["todo.txt"]), Vec::<String>::new());
+}
+
+#[test]
+fn written_in_place_of_the_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("todo.txt");
+    write(&path, "[ ] ( ) New\n").unwrap();
+    assert_eq!(fs::read_to_string(&path).unwrap(), "[ ] ( ) New\n");
+
+    fs::set_permissions(&path, fs::Permissions::from_mode(0o640)).unwrap();
+    let link = dir.path().join("link.txt");
+    std::os::unix::fs::symlink(&path, &link).unwrap();
+    write(&link, "[ ] ( ) Through the link\n").unwrap();
+    assert_eq!(
+        fs::read_to_string(&path).unwrap(),
+        "[ ] ( ) Through the link\n"
+    );
+    assert!(fs::symlink_metadata(&link)
+        .unwrap()
+        .file_type()
+        .is_symlink());
+    let mode = fs::metadata(&path).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o640);
+    assert_eq!(
+        others(dir.path(), &["todo.txt", "link.txt"]),
+        Vec::<String>::new()
+    );
+}
@@ -0,0 +1,77 @@
+/// Snippets of a todo list for sharing: a task with its sub-tasks, notes
+/// and comments in the save file format, under a header which marks it as
+/// a snippet when pasted back in:
+///
+/// ```text
+/// # yat snippet
+/// [ ] (A) Plan the garden +home id:1
+/// | Seeds from the market
+///     [ ] ( ) Order compost blocked-by:1
+/// ```
+use crate::deps;
+use crate::error::Error;
+use crate::todo::ToDo;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::rc::{Rc, Weak};
+
+/// First line of a snippet.
+const HEADER: &str = "# yat snippet";
+
+/// A snippet of a task and everything under it, without any blockers from
+/// outside it, so that it stands on its own.
+pub fn export(task: &Rc<RefCell<ToDo>>) -> Result<String, Error> {
+    let mut wrapper = ToDo::new("", Weak::new());
+    wrapper.sub_tasks.push(Rc::clone(task));
+    let copy = crate::parse_list(&wrapper.save_string())?;
+
+    let mut tasks = Vec::new();
+    deps::collect(&copy, &mut tasks);
+    let ids: HashSet<u32> = tasks.iter().filter_map(|task| task.borrow().id).collect();
+    for task in tasks.iter() {
+        task.borrow_mut().blocked_by.retain(|id| ids.contains(id));
+    }
+    let text = copy.borrow().save_string();
+    Ok(format!("{}\n{}", HEADER, text))
+}
+
+/// Whether some text is a snippet.
+pub fn is_snippet(text: &str) -> bool {
+    text.trim_start().lines().next().map(str::trim_end) == Some(HEADER)
+}
+
+/// Read the tasks in a snippet, under a new root, giving any with ids new
+/// ones which aren't among those already used.
+pub fn import(text: &str, used: &HashSet<u32>) -> Result<Rc<RefCell<ToDo>>, Error> {
+    let text = text.trim_start();
+    let list = text
+        .strip_prefix(HEADER)
+        .unwrap_or(text)
+        .trim_start_matches(['\r', '\n']);
+    let root = crate::parse_list(list)?;
+
+    let mut tasks = Vec::new();
+    deps::collect(&root, &mut tasks);
+    let mut next = used.iter().max().map_or(1, |max| max.saturating_add(1));
+    let mut renumbered = HashMap::new();
+    for task in tasks.iter() {
+        let mut task = task.borrow_mut();
+        if let Some(id) = task.id {
+            let new = *renumbered.entry(id).or_insert_with(|| {
+                next = next.saturating_add(1);
+                next - 1
+            });
+            task.id = Some(new);
+        }
+    }
+    for task in tasks.iter() {
+        let mut task = task.borrow_mut();
+        let blocked_by = task
+            .blocked_by
+            .iter()
+            .filter_map(|id| renumbered.get(id).copied())
+            .collect();
+        task.blocked_by = blocked_by;
+    }
+    Ok(root)
+}
@@ -0,0 +1,71 @@
+/// Spell checking of tasks against a word list, such as a hunspell
+/// dictionary or /usr/share/dict/words.
+use log::{info, warn};
+use std::collections::HashSet;
+use std::fs;
+use std::ops::Range;
+use std::path::Path;
+
+/// Words known to be spelt correctly.
+pub struct Dictionary {
+    words: HashSet<String>,
+}
+
+impl Dictionary {
+    /// Load a word list with one word per line. Hunspell .dic files are
+    /// understood too: the word count on the first line and the affix flags
+    /// after each word are skipped.
+    pub fn load(path: &Path) -> Option<Dictionary> {
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(err) => {
+                warn!("Unable to read word list {}: {}", path.display(), err);
+                return None;
+            }
+        };
+        let words: HashSet<String> = text
+            .lines()
+            .filter_map(|line| line.split('/').next())
+            .map(str::trim)
+            .filter(|word| !word.is_empty() && !word.chars().all(|c| c.is_ascii_digit()))
+            .map(String::from)
+            .collect();
+        info!("Loaded {} words for spell checking.", words.len());
+        Some(Dictionary { words })
+    }
+
+    /// Whether a word is in the list, as it is or in lower case (so words
+    /// at the start of a sentence are found).
+    fn contains(&self, word: &str) -> bool {
+        self.words.contains(word) || self.words.contains(&word.to_lowercase())
+    }
+
+    /// Byte ranges of the misspelt words in some text. Tags, contexts,
+    /// fields (e.g. "due:2024-01-01"), links and words with digits in them
+    /// aren't checked.
+    pub fn misspelt(&self, text: &str) -> Vec<Range<usize>> {
+        let mut ranges = Vec::new();
+        let mut start = 0;
+        for word in text.split(' ') {
+            let end = start + word.len();
+            if checked(word) {
+                let trimmed = word.trim_matches(|c: char| !c.is_alphabetic());
+                let offset = word.find(trimmed).unwrap_or(0);
+                let bare = trimmed.trim_end_matches("'s");
+                if !bare.is_empty() && !self.contains(bare) {
+                    ranges.push(start + offset..start + offset + trimmed.len());
+                }
+            }
+            start = end + 1;
+        }
+        ranges
+    }
+}
+
+/// Whether a word of a task is ordinary text which should be spell checked.
+fn checked(word: &str) -> bool {
+    !word.starts_with(['+', '#', '@'])
+        && !word.contains(':')
+        && !word.contains('/')
+        && !word.chars().any(|c| c.is_ascii_digit())
+}
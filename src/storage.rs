@@ -0,0 +1,554 @@
+/// Storage of todo lists away from the local filesystem.
+use crate::config::StorageConfig;
+use crate::error::Error;
+use crate::worker::Progress;
+use log::{info, warn};
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::PathBuf;
+use std::process::{Child, Command, Output, Stdio};
+use std::thread;
+use std::time::Duration;
+
+/// How often a running command is checked on, to see whether it has
+/// finished or been cancelled.
+const POLL: Duration = Duration::from_millis(50);
+
+/// Size of the pieces a list is uploaded in, with progress shown after
+/// each.
+const CHUNK: usize = 16 * 1024;
+
+/// Somewhere a todo list can be downloaded from and uploaded to, which
+/// can be uploaded to from a worker thread.
+pub trait Storage: Send {
+    /// Name of the location, for status messages.
+    fn describe(&self) -> String;
+
+    /// Download the todo list in save file format, or None if there is no
+    /// todo list there yet.
+    fn load(&mut self) -> Result<Option<String>, Error>;
+
+    /// Upload the todo list in save file format, or give the reason it
+    /// couldn't be uploaded, stopping if the upload is cancelled.
+    fn save(&mut self, buffer: &str, progress: &Progress) -> Result<(), Error>;
+
+    /// Copy of the storage, to upload to from a worker thread while this
+    /// one is kept in case the upload is cancelled.
+    fn duplicate(&self) -> Box<dyn Storage>;
+
+    /// Local copy of the todo list, kept for when the location can't be
+    /// reached.
+    fn cache_path(&self) -> Option<PathBuf> {
+        let name: String = self
+            .describe()
+            .chars()
+            .map(|c| {
+                if c.is_alphanumeric() || c == '.' {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        dirs::home_dir().map(|home| home.join(".todo").join("remote").join(name))
+    }
+}
+
+/// Find storage for a remote location, e.g. "user@host:path", or None if
+/// the location is a local file.
+pub fn remote(location: &str) -> Option<Box<dyn Storage>> {
+    Ssh::parse(location).map(|ssh| Box::new(ssh) as Box<dyn Storage>)
+}
+
+/// Find storage from the [storage] section of config.toml.
+pub fn from_config(config: &StorageConfig) -> Option<Box<dyn Storage>> {
+    match config.kind.as_str() {
+        "ssh" => remote(&config.url),
+        "webdav" => Some(Box::new(WebDav::new(config))),
+        kind => {
+            warn!("Unknown storage type: {}", kind);
+            None
+        }
+    }
+}
+
+/// Read local copy of a remote todo list.
+pub fn read_cache(storage: &dyn Storage) -> Option<String> {
+    let path = storage.cache_path()?;
+    fs::read_to_string(&path).ok()
+}
+
+/// Update local copy of a remote todo list.
+pub fn write_cache(storage: &dyn Storage, buffer: &str) {
+    let path = match storage.cache_path() {
+        Some(path) => path,
+        None => return,
+    };
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).unwrap_or_else(|err| {
+            warn!("Unable to create directory {}: {}", dir.display(), err);
+        });
+    }
+    fs::write(&path, buffer).unwrap_or_else(|err| {
+        warn!("Unable to write local copy {}: {}", path.display(), err);
+    });
+}
+
+/// Todo list on a server, accessed with the ssh command (so that keys and
+/// settings from ~/.ssh/config are used).
+#[derive(Clone)]
+pub struct Ssh {
+    /// Host, including user name, e.g. "user@host".
+    host: String,
+    port: Option<u16>,
+    /// Path on the server, where relative paths are from the home directory.
+    path: String,
+}
+
+impl Ssh {
+    /// Parse location of the form "user@host:path", "ssh://user@host/path"
+    /// or "sftp://user@host:port/path".
+    pub fn parse(location: &str) -> Option<Ssh> {
+        let url = location
+            .strip_prefix("ssh://")
+            .or_else(|| location.strip_prefix("sftp://"));
+        let (host, port, path) = match url {
+            Some(url) => {
+                let slash = url.find('/')?;
+                let (authority, path) = url.split_at(slash);
+                let path = path.strip_prefix("/~/").unwrap_or(path);
+                let (host, port) = match authority.rfind(':') {
+                    Some(colon) => (
+                        &authority[..colon],
+                        Some(authority[colon + 1..].parse().ok()?),
+                    ),
+                    None => (authority, None),
+                };
+                (host, port, path)
+            }
+            None => {
+                let colon = location.find(':')?;
+                let host = &location[..colon];
+                if !host.contains('@') || host.contains('/') {
+                    return None;
+                }
+                (host, None, &location[colon + 1..])
+            }
+        };
+        let path = path.strip_prefix("~/").unwrap_or(path);
+        if host.is_empty() || path.is_empty() {
+            return None;
+        }
+
+        Some(Ssh {
+            host: String::from(host),
+            port,
+            path: String::from(path),
+        })
+    }
+
+    /// Command to run a shell command on the server, without prompting for
+    /// a password.
+    fn command(&self, remote: &str) -> Command {
+        let mut cmd = Command::new("ssh");
+        cmd.args(["-o", "BatchMode=yes", "-o", "ConnectTimeout=10"]);
+        if let Some(port) = self.port {
+            cmd.arg("-p").arg(port.to_string());
+        }
+        cmd.arg(&self.host).arg(remote);
+        cmd
+    }
+}
+
+impl Storage for Ssh {
+    fn describe(&self) -> String {
+        format!("{}:{}", self.host, self.path)
+    }
+
+    fn load(&mut self) -> Result<Option<String>, Error> {
+        let path = shell_quote(&self.path);
+        let remote = format!("if [ -e {0} ]; then cat -- {0}; else exit 3; fi", path);
+        let output = match self.command(&remote).stdin(Stdio::null()).output() {
+            Ok(output) => output,
+            Err(err) => {
+                warn!("Unable to run ssh: {}", err);
+                let reason = format!("unable to run ssh: {}", err);
+                return Err(Error::Remote(self.describe(), reason));
+            }
+        };
+
+        match output.status.code() {
+            Some(0) => {
+                info!("Downloaded {}.", self.describe());
+                Ok(Some(String::from_utf8_lossy(&output.stdout).into_owned()))
+            }
+            Some(3) => {
+                info!("{} does not exist yet.", self.describe());
+                Ok(None)
+            }
+            _ => {
+                let reason = String::from_utf8_lossy(&output.stderr).trim().to_string();
+                warn!("Unable to download {}: {}", self.describe(), reason);
+                Err(Error::Remote(self.describe(), reason))
+            }
+        }
+    }
+
+    fn save(&mut self, buffer: &str, progress: &Progress) -> Result<(), Error> {
+        // Write to a temporary file first, so a dropped connection doesn't
+        // leave a partial save file
+        let remote = format!(
+            "cat > {0} && mv -- {0} {1}",
+            shell_quote(&format!("{}.yat-tmp", self.path)),
+            shell_quote(&self.path)
+        );
+        let mut child = match self
+            .command(&remote)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn()
+        {
+            Ok(child) => child,
+            Err(err) => {
+                warn!("Unable to run ssh: {}", err);
+                let reason = format!("unable to run ssh: {}", err);
+                return Err(Error::Remote(self.describe(), reason));
+            }
+        };
+
+        let mut stdin = child.stdin.take();
+        if let Some(stdin) = &mut stdin {
+            let bytes = buffer.as_bytes();
+            for (i, chunk) in bytes.chunks(CHUNK).enumerate() {
+                if progress.cancelled() {
+                    break;
+                }
+                if let Err(err) = stdin.write_all(chunk) {
+                    warn!("Unable to send save file to ssh: {}", err);
+                    break;
+                }
+                progress.set(i * CHUNK + chunk.len(), bytes.len());
+            }
+        }
+        // Closing the input moves the file into place, so a cancelled
+        // upload keeps it open until ssh has been stopped
+        if !progress.cancelled() {
+            stdin.take();
+        }
+        let output = finish(child, progress);
+        drop(stdin);
+        match output {
+            Ok(output) if output.status.success() => {
+                info!("Uploaded {}.", self.describe());
+                Ok(())
+            }
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => {
+                info!("Cancelled upload to {}.", self.describe());
+                Err(Error::Remote(self.describe(), String::from("cancelled")))
+            }
+            Ok(output) => {
+                let reason = String::from_utf8_lossy(&output.stderr).trim().to_string();
+                warn!("Unable to upload {}: {}", self.describe(), reason);
+                Err(Error::Remote(self.describe(), reason))
+            }
+            Err(err) => {
+                warn!("Unable to upload {}: {}", self.describe(), err);
+                let reason = format!("unable to upload: {}", err);
+                Err(Error::Remote(self.describe(), reason))
+            }
+        }
+    }
+
+    fn duplicate(&self) -> Box<dyn Storage> {
+        Box::new(self.clone())
+    }
+}
+
+/// Todo list on a WebDAV server (e.g. Nextcloud), accessed with the curl
+/// command. The ETag of the file is remembered so that changes made
+/// elsewhere are not overwritten.
+#[derive(Clone)]
+pub struct WebDav {
+    url: String,
+    user: Option<String>,
+    password: Option<String>,
+    /// ETag of the file when last downloaded or uploaded.
+    etag: Option<String>,
+}
+
+/// Response to a HTTP request.
+struct Response {
+    status: u32,
+    etag: Option<String>,
+    body: Vec<u8>,
+}
+
+impl WebDav {
+    /// Create WebDAV storage from configuration, running the password
+    /// command if given.
+    pub fn new(config: &StorageConfig) -> WebDav {
+        let mut password = config.password.clone();
+        if let Some(command) = &config.password_command {
+            password = password_from_command(command).or(password);
+        }
+
+        WebDav {
+            url: config.url.clone(),
+            user: config.user.clone(),
+            password,
+            etag: None,
+        }
+    }
+
+    /// Make a request with curl, passing the URL and credentials on stdin
+    /// so they don't appear in the process list, and stopping curl if the
+    /// request is cancelled.
+    fn request(&self, args: &[&str], progress: &Progress) -> Result<Response, Error> {
+        let mut curl_config = format!("url = \"{}\"\n", curl_escape(&self.url));
+        if let Some(user) = &self.user {
+            let password = self.password.as_deref().unwrap_or("");
+            curl_config.push_str(&format!(
+                "user = \"{}:{}\"\n",
+                curl_escape(user),
+                curl_escape(password)
+            ));
+        }
+
+        let mut child = match Command::new("curl")
+            .args(["--silent", "--show-error", "--connect-timeout", "10"])
+            .args(["--dump-header", "-", "--config", "-"])
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+        {
+            Ok(child) => child,
+            Err(err) => {
+                warn!("Unable to run curl: {}", err);
+                let reason = format!("unable to run curl: {}", err);
+                return Err(Error::Remote(self.url.clone(), reason));
+            }
+        };
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin
+                .write_all(curl_config.as_bytes())
+                .unwrap_or_else(|err| warn!("Unable to configure curl: {}", err));
+        }
+        let output = match finish(child, progress) {
+            Ok(output) if output.status.success() => output,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => {
+                info!("Cancelled request to {}.", self.url);
+                return Err(Error::Remote(self.url.clone(), String::from("cancelled")));
+            }
+            Ok(output) => {
+                let reason = String::from_utf8_lossy(&output.stderr).trim().to_string();
+                warn!("Unable to reach {}: {}", self.url, reason);
+                return Err(Error::Remote(self.url.clone(), reason));
+            }
+            Err(err) => {
+                warn!("Unable to run curl: {}", err);
+                let reason = format!("unable to run curl: {}", err);
+                return Err(Error::Remote(self.url.clone(), reason));
+            }
+        };
+
+        parse_response(&output.stdout).ok_or_else(|| {
+            warn!("Invalid response from {}", self.url);
+            Error::Remote(self.url.clone(), String::from("invalid response"))
+        })
+    }
+
+    /// Find the current ETag of the file, e.g. when the server doesn't
+    /// return one after an upload.
+    fn head(&mut self) {
+        if let Ok(response) = self.request(&["--head"], &Progress::new()) {
+            self.etag = response.etag;
+        }
+    }
+}
+
+impl Storage for WebDav {
+    fn describe(&self) -> String {
+        self.url.clone()
+    }
+
+    fn load(&mut self) -> Result<Option<String>, Error> {
+        let response = self.request(&[], &Progress::new())?;
+        match response.status {
+            200 => {
+                info!("Downloaded {}.", self.url);
+                self.etag = response.etag;
+                Ok(Some(String::from_utf8_lossy(&response.body).into_owned()))
+            }
+            404 => {
+                info!("{} does not exist yet.", self.url);
+                self.etag = None;
+                Ok(None)
+            }
+            status => {
+                warn!("Unable to download {}: HTTP status {}", self.url, status);
+                let reason = format!("HTTP status {}", status);
+                Err(Error::Remote(self.url.clone(), reason))
+            }
+        }
+    }
+
+    fn save(&mut self, buffer: &str, progress: &Progress) -> Result<(), Error> {
+        let upload = tempfile::Builder::new()
+            .prefix("yat-upload-")
+            .suffix(".txt")
+            .tempfile()
+            .and_then(|mut file| file.write_all(buffer.as_bytes()).map(|_| file));
+        let upload = match upload {
+            Ok(upload) => upload,
+            Err(err) => {
+                warn!("Unable to write temporary file: {}", err);
+                let reason = format!("unable to write temporary file: {}", err);
+                return Err(Error::Remote(self.url.clone(), reason));
+            }
+        };
+
+        // Only replace the version last seen, or create a new file
+        let condition = match &self.etag {
+            Some(etag) => format!("If-Match: {}", etag),
+            None => String::from("If-None-Match: *"),
+        };
+        let upload_path = upload.path().to_string_lossy().into_owned();
+        let response = self.request(
+            &["--upload-file", &upload_path, "--header", &condition],
+            progress,
+        );
+        upload.close().unwrap_or_else(|err| {
+            warn!("Unable to remove temporary file: {}", err);
+        });
+
+        match response {
+            Ok(response) if (200..300).contains(&response.status) => {
+                info!("Uploaded {}.", self.url);
+                match response.etag {
+                    Some(etag) => self.etag = Some(etag),
+                    None => self.head(),
+                }
+                Ok(())
+            }
+            Ok(response) if response.status == 412 => {
+                warn!(
+                    "{} has changed on the server since it was loaded.",
+                    self.url
+                );
+                let reason = String::from("changed on the server since loaded");
+                Err(Error::Remote(self.url.clone(), reason))
+            }
+            Ok(response) => {
+                warn!(
+                    "Unable to upload {}: HTTP status {}",
+                    self.url, response.status
+                );
+                let reason = format!("HTTP status {}", response.status);
+                Err(Error::Remote(self.url.clone(), reason))
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    fn duplicate(&self) -> Box<dyn Storage> {
+        Box::new(self.clone())
+    }
+}
+
+/// Wait for a command to finish and collect its output, as
+/// wait_with_output does, but killing it if the operation it is part of is
+/// cancelled, which gives an Interrupted error.
+fn finish(mut child: Child, progress: &Progress) -> io::Result<Output> {
+    let stdout = drain(child.stdout.take());
+    let stderr = drain(child.stderr.take());
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if progress.cancelled() {
+            child.kill()?;
+            child.wait()?;
+            return Err(io::Error::new(io::ErrorKind::Interrupted, "cancelled"));
+        }
+        thread::sleep(POLL);
+    };
+    Ok(Output {
+        status,
+        stdout: stdout.join().unwrap_or_default(),
+        stderr: stderr.join().unwrap_or_default(),
+    })
+}
+
+/// Read all of the output of a command on another thread, so it never
+/// stops waiting for its output to be read.
+fn drain(pipe: Option<impl Read + Send + 'static>) -> thread::JoinHandle<Vec<u8>> {
+    thread::spawn(move || {
+        let mut buffer = Vec::new();
+        if let Some(mut pipe) = pipe {
+            pipe.read_to_end(&mut buffer).unwrap_or_else(|err| {
+                warn!("Unable to read output of command: {}", err);
+                0
+            });
+        }
+        buffer
+    })
+}
+
+/// Split curl output into the final status, ETag and body, skipping any
+/// interim (1xx) responses.
+fn parse_response(output: &[u8]) -> Option<Response> {
+    let mut rest = output;
+    loop {
+        let end = rest.windows(4).position(|w| w == b"\r\n\r\n")?;
+        let head = String::from_utf8_lossy(&rest[..end]).into_owned();
+        rest = &rest[end + 4..];
+
+        let mut lines = head.lines();
+        let status: u32 = lines.next()?.split_whitespace().nth(1)?.parse().ok()?;
+        if (100..200).contains(&status) {
+            continue;
+        }
+        let etag = lines.find_map(|line| {
+            let (name, value) = line.split_once(':')?;
+            match name.trim().eq_ignore_ascii_case("etag") {
+                true => Some(String::from(value.trim())),
+                false => None,
+            }
+        });
+        return Some(Response {
+            status,
+            etag,
+            body: rest.to_vec(),
+        });
+    }
+}
+
+/// Run a command which prints a password, e.g. from a password manager.
+pub fn password_from_command(command: &str) -> Option<String> {
+    match Command::new("sh").arg("-c").arg(command).output() {
+        Ok(output) if output.status.success() => {
+            let text = String::from_utf8_lossy(&output.stdout);
+            Some(String::from(text.trim_end_matches(&['\r', '\n'][..])))
+        }
+        _ => {
+            warn!("Unable to run password command: {}", command);
+            None
+        }
+    }
+}
+
+/// Escape text for a quoted value in a curl config file.
+pub fn curl_escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// Quote text for use as a single word in a POSIX shell command.
+fn shell_quote(text: &str) -> String {
+    format!("'{}'", text.replace('\'', "'\\''"))
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,109 @@
+/// Tests of the quoting of paths and credentials passed to ssh and curl,
+/// and of reading remote locations and responses.
+use super::{curl_escape, parse_response, shell_quote, Ssh, Storage};
+use std::process::Command;
+
+/// Text as printed by a POSIX shell given it quoted.
+fn through_shell(text: &str) -> String {
+    let output = Command::new("sh")
+        .arg("-c")
+        .arg(format!("printf %s {}", shell_quote(text)))
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn shell_words_quoted() {
+    assert_eq!(shell_quote("todo.txt"), "'todo.txt'");
+    assert_eq!(shell_quote(""), "''");
+    assert_eq!(shell_quote("it's"), "'it'\\''s'");
+    for text in [
+        "todo.txt",
+        "",
+        "my todo.txt",
+        "it's",
+        "''",
+        "$HOME/`id`/$(id)",
+        "-n; rm -rf ~",
+        "a\\b\"c",
+        "two\nlines",
+        "caf\u{e9}",
+    ] {
+        assert_eq!(through_shell(text), text, "{:?}", text);
+    }
+}
+
+#[test]
+fn curl_values_escaped() {
+    assert_eq!(
+        curl_escape("https://example.com/todo.txt"),
+        "https://example.com/todo.txt"
+    );
+    assert_eq!(curl_escape("pa\"ss"), "pa\\\"ss");
+    assert_eq!(curl_escape("back\\slash"), "back\\\\slash");
+    // A backslash before a quote can't end the quoted value early
+    assert_eq!(curl_escape("\\\""), "\\\\\\\"");
+    assert_eq!(curl_escape("caf\u{e9}"), "caf\u{e9}");
+}
+
+#[test]
+fn ssh_locations() {
+    let location = |text| Ssh::parse(text).map(|ssh| (ssh.describe(), ssh.port));
+    assert_eq!(
+        location("me@host:todo.txt"),
+        Some((String::from("me@host:todo.txt"), None))
+    );
+    assert_eq!(
+        location("me@host:~/lists/todo.txt"),
+        Some((String::from("me@host:lists/todo.txt"), None))
+    );
+    assert_eq!(
+        location("ssh://me@host/~/todo.txt"),
+        Some((String::from("me@host:todo.txt"), None))
+    );
+    assert_eq!(
+        location("sftp://me@host:2222/srv/todo.txt"),
+        Some((String::from("me@host:/srv/todo.txt"), Some(2222)))
+    );
+    for text in [
+        "todo.txt",
+        "/home/me/todo.txt",
+        "host:todo.txt",
+        "./me@host:todo.txt",
+        "me@host:",
+        "ssh://me@host",
+        "ssh://me@host:port/todo.txt",
+        "sftp://:22/todo.txt",
+    ] {
+        assert_eq!(location(text), None, "{:?}", text);
+    }
+}
+
+#[test]
+fn http_responses() {
+    let response = parse_response(
+        b"HTTP/1.1 100 Continue\r\n\r\n\
+          HTTP/1.1 200 OK\r\nContent-Length: 13\r\nETag: \"abc\"\r\n\r\n\
+          [ ] ( ) Task\n",
+    )
+    .unwrap();
+    assert_eq!(response.status, 200);
+    assert_eq!(response.etag.as_deref(), Some("\"abc\""));
+    assert_eq!(response.body, b"[ ] ( ) Task\n");
+
+    let response = parse_response(b"HTTP/2 404\r\netag:  W/\"x\" \r\n\r\n").unwrap();
+    assert_eq!(response.status, 404);
+    assert_eq!(response.etag.as_deref(), Some("W/\"x\""));
+    assert!(response.body.is_empty());
+
+    for output in [
+        &b""[..],
+        b"HTTP/1.1 200 OK\r\n",
+        b"garbage\r\n\r\n",
+        b"HTTP/1.1 100 Continue\r\n\r\n",
+    ] {
+        assert!(parse_response(output).is_none(), "{:?}", output);
+    }
+}
@@ -0,0 +1,33 @@
+/// Placeholders in the text of added tasks, such as "Weekly report {week}",
+/// filled in when the task is added.
+use chrono::{Datelike, NaiveDate};
+
+/// Values which placeholders are filled in with.
+pub struct Values<'a> {
+    /// Today's date.
+    pub today: NaiveDate,
+    /// Number of the current week.
+    pub week: u32,
+    /// Name of the todo list.
+    pub list: &'a str,
+}
+
+/// Fill in the placeholders in a task: {date}, {week}, {month}, {year} and
+/// {list}. Anything else in braces is left alone.
+pub fn expand(text: &str, values: &Values) -> String {
+    if !text.contains('{') {
+        return String::from(text);
+    }
+    let placeholders = [
+        ("{date}", values.today.format("%Y-%m-%d").to_string()),
+        ("{week}", values.week.to_string()),
+        ("{month}", values.today.format("%Y-%m").to_string()),
+        ("{year}", values.today.year().to_string()),
+        ("{list}", String::from(values.list)),
+    ];
+    let mut expanded = String::from(text);
+    for (placeholder, value) in placeholders.iter() {
+        expanded = expanded.replace(placeholder, value);
+    }
+    expanded
+}
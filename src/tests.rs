@@ -0,0 +1,641 @@
+/// Snapshot tests of the screen: known todo lists are drawn on a headless
+/// window of a fixed size and compared with the screens stored in
+/// src/snapshots, so that any change to the layout shows up.
+///
+/// After a deliberate change to the layout, run the tests with
+/// YAT_UPDATE_SNAPSHOTS=1 to store the new screens, and check the diff.
+use crate::column::Column;
+use crate::config::{parse_config, Config, Confirm};
+use crate::escalate::Rule;
+use crate::export;
+use crate::heatmap::Heatmap;
+use crate::layout::Placement;
+use crate::locale::Locale;
+use crate::todo::ToDo;
+use crate::trash;
+use crate::tui;
+use crate::View;
+use chrono::NaiveDate;
+use std::cell::RefCell;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::rc::Rc;
+use termion::event::Key;
+
+/// A list with nesting, priorities, completed and blocked tasks, notes,
+/// comments and due dates which always display the same, whatever the day.
+const LIST: &str = "\
+[ ] (A) Plan the garden +home @outside due:2099-04-01
+| Seeds from the market
+|
+| Beans, peas and a row of sunflowers along the fence at the back
+> 2001-03-01T09:00 Dug over the beds
+    [X] (B) Measure the beds +home
+    [ ] ( ) Order compost @phone estimate:1h
+    [ ] (C) Build the trellis +home
+        [ ] ( ) Buy timber @town
+[X] ( ) File the tax return +work completed:2001-01-31
+[ ] (B) Write the quarterly report +work due:2001-02-01 estimate:3h spent:1h
+    [ ] ( ) Gather the figures +work id:1
+    [ ] ( ) Draft the summary +work blocked-by:1
+[ ] ( ) Call Bob about the weekend @phone
+[ ] ( ) Read \u{300c}\u{767d}\u{9be8}\u{300d} and a very long title which is much too wide for its panel
+";
+
+/// Configuration which doesn't depend on the environment.
+fn config() -> Config<'static> {
+    Config {
+        locale: Some("en_GB"),
+        ..Config::default()
+    }
+}
+
+/// A day, from "YYYY-MM-DD", for the tests of each module.
+pub fn day(text: &str) -> NaiveDate {
+    NaiveDate::parse_from_str(text, "%Y-%m-%d").unwrap()
+}
+
+/// The English locale, whatever the environment, for the tests of each
+/// module.
+pub fn english() -> Locale {
+    Locale::new(&config())
+}
+
+/// Compare a screen with the one stored under a name, or store it when
+/// updating snapshots.
+fn check(name: &str, screen: Option<String>) {
+    let screen = screen.expect("headless view has no screen");
+    let path: PathBuf = [env!("CARGO_MANIFEST_DIR"), "src", "snapshots"]
+        .iter()
+        .collect::<PathBuf>()
+        .join(format!("{}.txt", name));
+    if env::var_os("YAT_UPDATE_SNAPSHOTS").is_some() {
+        fs::write(&path, &screen).expect("unable to store snapshot");
+        return;
+    }
+    let expected = fs::read_to_string(&path).unwrap_or_else(|err| {
+        panic!(
+            "unable to read {}: {}\nRun with YAT_UPDATE_SNAPSHOTS=1 to store it.",
+            path.display(),
+            err
+        )
+    });
+    assert!(
+        screen == expected,
+        "screen differs from snapshot {}\n\nExpected:\n{}\nFound:\n{}",
+        name,
+        expected,
+        screen
+    );
+}
+
+#[test]
+fn list_at_standard_size() {
+    let mut view = View::new_headless(config(), (24, 80), LIST);
+    view.selection = Some(0);
+    check("list_80x24", view.render());
+}
+
+#[test]
+fn list_at_small_size() {
+    let mut view = View::new_headless(config(), (14, 60), LIST);
+    view.selection = Some(2);
+    check("list_60x14", view.render());
+}
+
+#[test]
+fn list_at_wide_size() {
+    let mut view = View::new_headless(config(), (30, 132), LIST);
+    view.selection = Some(4);
+    check("list_132x30", view.render());
+}
+
+#[test]
+fn details_panel() {
+    let mut view = View::new_headless(config(), (24, 80), LIST);
+    view.selection = Some(0);
+    view.details = true;
+    check("details_80x24", view.render());
+}
+
+#[test]
+fn focused_sub_task() {
+    let mut view = View::new_headless(config(), (24, 80), LIST);
+    let garden = Rc::clone(&view.current_task.borrow().sub_tasks[0]);
+    view.current_task = garden;
+    view.root = false;
+    view.selection = Some(2);
+    check("focused_80x24", view.render());
+}
+
+#[test]
+fn filtered_with_marks_and_title_bar() {
+    let mut config = config();
+    config.title_bar = true;
+    let mut view = View::new_headless(config, (24, 80), LIST);
+    view.run_commands(&[String::from(":filter +work")]);
+    let report = Rc::clone(&view.current_task.borrow().sub_tasks[2]);
+    view.marked.push(report);
+    check("filtered_80x24", view.render());
+}
+
+#[test]
+fn custom_layout() {
+    let config = Config {
+        ellipsis: "\u{2026}",
+        ellipsis_at: Placement::Middle,
+        margin: 1,
+        ..config()
+    };
+    let mut view = View::new_headless(config, (24, 80), LIST);
+    view.selection = Some(4);
+    check("layout_80x24", view.render());
+}
+
+#[test]
+fn colour_blind_cues() {
+    let config = Config {
+        colour_blind: true,
+        ..config()
+    };
+    let mut view = View::new_headless(config, (24, 80), LIST);
+    view.selection = Some(0);
+    check("colour_blind_80x24", view.render());
+}
+
+#[test]
+fn empty_list() {
+    let mut view = View::new_headless(config(), (24, 80), "");
+    check("empty_80x24", view.render());
+}
+
+#[test]
+fn completion_heatmap() {
+    let list = "\
+[X] ( ) Sow the beans completed:2001-03-05T10:00
+[X] ( ) Water the beans completed:2001-03-05
+[X] ( ) Pick the beans completed:2001-08-01T18:30
+[ ] ( ) Eat the beans
+";
+    // Shaded blocks whatever the locale
+    let config = Config {
+        ascii: false,
+        ..config()
+    };
+    let mut view = View::new_headless(config, (24, 120), list);
+    let today = NaiveDate::from_ymd_opt(2001, 10, 16).unwrap();
+    let mut heatmap = Heatmap::new(view.locale.week_start(today), today);
+    heatmap.add_tree(&view.root_task().borrow());
+    view.print_heatmap(&heatmap);
+    check("heatmap_120x24", view.window.contents());
+}
+
+#[test]
+fn undo_and_redo_while_focused() {
+    let mut view = View::new_headless(config(), (24, 80), LIST);
+    // The view of the top level holds on to it while focused on a task
+    let root = view.root_task();
+    let original = root.borrow().save_string();
+    let garden = Rc::clone(&root.borrow().sub_tasks[0]);
+    view.current_task = Rc::clone(&garden);
+    view.root = false;
+    view.selection = Some(2);
+    let before = view.step("move");
+    view.move_task(true);
+    let moved = view.root_task().borrow().save_string();
+    view.history.record(before, &moved);
+
+    view.undo();
+    assert!(Rc::ptr_eq(&view.current_task, &garden));
+    assert!(Rc::ptr_eq(&view.root_task(), &root));
+    assert_eq!(root.borrow().save_string(), original);
+    view.redo();
+    assert_eq!(root.borrow().save_string(), moved);
+    view.undo();
+    check("undo_80x24", view.render());
+}
+
+#[test]
+fn chained_filters() {
+    let mut view = View::new_headless(config(), (24, 80), LIST);
+    view.run_commands(&[String::from(":filter +home @outside")]);
+    check("chained_filters_80x24", view.render());
+}
+
+#[test]
+fn grouped_by_priority() {
+    let mut view = View::new_headless(config(), (24, 80), LIST);
+    view.run_commands(&[String::from(":group priority")]);
+    check("grouped_80x24", view.render());
+}
+
+#[test]
+fn escalated_priority() {
+    let rules = [Rule::parse(Some("1d"), None, "A", 3).unwrap()];
+    let config = Config {
+        escalate: &rules,
+        ..config()
+    };
+    let mut view = View::new_headless(config, (24, 80), LIST);
+    check("escalated_80x24", view.render());
+}
+
+#[test]
+fn escalation_within_priority_levels() {
+    let rules = |toml: &str| {
+        let buffer = parse_config(toml).unwrap();
+        let config = buffer.config(config());
+        config.escalate.to_vec()
+    };
+    let rule = |priority| {
+        format!(
+            "[[escalate]]\ndue_within = \"2d\"\npriority = \"{}\"\n",
+            priority
+        )
+    };
+    assert_eq!(
+        rules(&rule("C")),
+        [Rule::parse(Some("2d"), None, "C", 3).unwrap()]
+    );
+    assert_eq!(rules(&rule("D")), []);
+    assert_eq!(
+        rules(&format!("priority_levels = 4\n{}", rule("D"))).len(),
+        1
+    );
+    assert_eq!(rules(&format!("priority_levels = 2\n{}", rule("C"))), []);
+    assert_eq!(
+        Rule::parse(Some("2d"), None, "D", 3),
+        Err(String::from("Invalid priority D: use a letter A-C"))
+    );
+}
+
+#[test]
+fn keys_per_mode() {
+    let buffer = parse_config(
+        r#"
+[keys]
+complete = 'c'
+
+[keys.normal]
+add = 'n'
+
+[keys.visual]
+complete = 'x'
+
+[keys.edit]
+accept = "\t"
+
+[keys.search]
+next = "\u000e"
+"#,
+    )
+    .unwrap();
+    let mut view = View::new_headless(buffer.config(config()), (24, 80), LIST);
+    assert_eq!(view.keymap().complete, Key::Char('c'));
+    assert_eq!(view.keymap().add, Key::Char('n'));
+    let report = Rc::clone(&view.current_task.borrow().sub_tasks[2]);
+    view.marked.push(report);
+    assert_eq!(view.keymap().complete, Key::Char('x'));
+    assert_eq!(view.keymap().add, Key::Char('n'));
+    assert_eq!(view.window.config.edit_keys.accept, Key::Char('\t'));
+    assert_eq!(view.window.config.edit_keys.complete, Key::Char('\t'));
+    assert_eq!(view.window.config.search_keys.next, Key::Char('\u{e}'));
+    assert_eq!(view.window.config.search_keys.previous, Key::Up);
+}
+
+#[test]
+fn return_typed_with_accept_remapped() {
+    let buffer = parse_config("[keys.edit]\naccept = \"\\t\"\n").unwrap();
+    let mut view = View::new_headless(buffer.config(config()), (24, 80), LIST);
+    view.window.feed(b"Call\nBob\t");
+    assert_eq!(view.dialogue("Task:", ""), "CallBob");
+}
+
+#[test]
+fn trash_keeps_deleted_tasks() {
+    let config = Config {
+        confirm: Confirm {
+            delete: false,
+            ..Confirm::default()
+        },
+        ..config()
+    };
+    let mut view = View::new_headless(config, (24, 80), LIST);
+    let root = view.root_task();
+    let garden = Rc::clone(&root.borrow().sub_tasks[0]);
+    view.current_task = Rc::clone(&garden);
+    view.root = false;
+    view.selection = Some(2);
+    view.remove_task();
+    assert_eq!(garden.borrow().sub_tasks.len(), 2);
+    assert_eq!(view.trash.len(), 1);
+    assert_eq!(view.trash[0].path, ["Plan the garden +home @outside"]);
+
+    let filename = env::temp_dir().join(format!("yat-test-{}.trash.txt", std::process::id()));
+    trash::save(&filename, &view.trash).unwrap();
+    let loaded = trash::load(&filename).unwrap();
+    fs::remove_file(&filename).unwrap();
+    assert_eq!(loaded.len(), 1);
+    assert_eq!(loaded[0].index, 2);
+    assert_eq!(loaded[0].place(), "Plan the garden +home @outside");
+    assert_eq!(
+        loaded[0].task.borrow().save_string(),
+        view.trash[0].task.borrow().save_string()
+    );
+    assert!(Rc::ptr_eq(&trash::parent(&loaded[0], &root), &garden));
+}
+
+#[test]
+fn undone_delete_leaves_the_trash() {
+    let config = Config {
+        confirm: Confirm {
+            delete: false,
+            ..Confirm::default()
+        },
+        ..config()
+    };
+    let mut view = View::new_headless(config, (24, 80), LIST);
+    let root = view.root_task();
+    let original = root.borrow().save_string();
+    view.selection = Some(1);
+    view.press(Some(Key::Char('d')), 1);
+    assert_eq!(view.trash.len(), 1);
+    view.press(Some(Key::Char('z')), 1);
+    assert!(view.trash.is_empty());
+    view.press(Some(Key::Char('X')), 1);
+    assert_eq!(root.borrow().save_string(), original);
+
+    // Deleting it again by redoing puts it back in the trash to restore
+    view.press(Some(Key::Char('Z')), 1);
+    assert_eq!(view.trash.len(), 1);
+    view.window.feed(b"\n");
+    view.press(Some(Key::Char('X')), 1);
+    assert!(view.trash.is_empty());
+    assert_eq!(root.borrow().save_string(), original);
+}
+
+#[test]
+fn duplicated_subtree() {
+    let mut view = View::new_headless(config(), (24, 80), LIST);
+    view.selection = Some(0);
+    view.duplicate_task();
+    assert_eq!(view.selection, Some(1));
+    let root = view.root_task();
+    let copy = Rc::clone(&root.borrow().sub_tasks[1]);
+    let copy = copy.borrow();
+    assert_eq!(copy.task, "Plan the garden +home @outside");
+    assert!(copy.comments.is_empty());
+    assert_eq!(copy.notes, root.borrow().sub_tasks[0].borrow().notes);
+    assert_eq!(copy.count_in_tree(&|_| true), 4);
+    assert_eq!(copy.count_in_tree(&|t| t.complete), 0);
+    assert_eq!(
+        root.borrow().sub_tasks[0]
+            .borrow()
+            .count_in_tree(&|t| t.complete),
+        1
+    );
+}
+
+#[test]
+fn found_by_words() {
+    let mut view = View::new_headless(config(), (24, 80), LIST);
+    let root = view.root_task();
+    view.index.update(&root);
+    let titles = |found: Vec<Rc<RefCell<ToDo>>>| -> Vec<String> {
+        found
+            .iter()
+            .map(|task| task.borrow().task.clone())
+            .collect()
+    };
+    assert_eq!(
+        titles(view.index.search("work dra")),
+        ["Draft the summary +work"]
+    );
+    assert_eq!(
+        titles(view.index.search("seeds")),
+        ["Plan the garden +home @outside"]
+    );
+    assert_eq!(
+        titles(view.index.search("gardn")),
+        ["Plan the garden +home @outside"]
+    );
+    assert_eq!(view.index.search("work").len(), 4);
+    assert!(view.index.search("").is_empty());
+
+    root.borrow().sub_tasks[3].borrow_mut().task = String::from("Call Alice");
+    root.borrow_mut().sub_tasks.remove(0);
+    view.index.update(&root);
+    assert!(view.index.search("bob").is_empty());
+    assert!(view.index.search("seeds").is_empty());
+    assert_eq!(titles(view.index.search("alice")), ["Call Alice"]);
+
+    let found = view.index.search("the");
+    view.list_tasks();
+    view.print_found("the", &found, 1);
+    check("find_80x24", view.window.contents());
+}
+
+#[test]
+fn moved_to_another_parent() {
+    let mut view = View::new_headless(config(), (24, 80), LIST);
+    let root = view.root_task();
+    let garden = Rc::clone(&root.borrow().sub_tasks[0]);
+    view.selection = Some(0);
+    view.move_to();
+    view.current_task = Rc::clone(&garden);
+    view.root = false;
+    view.selection = Some(0);
+    view.move_to();
+    assert!(view.moving.is_some(), "a task can't go under itself");
+    assert_eq!(root.borrow().sub_tasks.len(), 5);
+
+    view.moving = None;
+    view.current_task = Rc::clone(&root);
+    view.root = true;
+    view.selection = Some(3);
+    view.move_to();
+    view.current_task = Rc::clone(&garden);
+    view.root = false;
+    view.selection = Some(0);
+    view.move_to();
+    assert!(view.moving.is_none());
+    assert_eq!(view.selection, Some(1));
+    assert_eq!(root.borrow().sub_tasks.len(), 4);
+    let bob = Rc::clone(&garden.borrow().sub_tasks[1]);
+    assert_eq!(bob.borrow().task, "Call Bob about the weekend @phone");
+    assert!(Rc::ptr_eq(&bob.borrow().parent.upgrade().unwrap(), &garden));
+    check("moved_80x24", view.render());
+}
+
+#[test]
+fn cut_and_pasted_twice() {
+    let mut view = View::new_headless(config(), (24, 80), LIST);
+    let root = view.root_task();
+    let garden = Rc::clone(&root.borrow().sub_tasks[0]);
+    let report = Rc::clone(&root.borrow().sub_tasks[2]);
+    view.selection = Some(2);
+    view.cut_task();
+    assert_eq!(root.borrow().sub_tasks.len(), 4);
+
+    view.current_task = Rc::clone(&garden);
+    view.root = false;
+    view.selection = Some(0);
+    view.paste_clipboard();
+    view.paste_clipboard();
+    let garden = garden.borrow();
+    assert_eq!(garden.sub_tasks.len(), 5);
+    assert!(Rc::ptr_eq(&garden.sub_tasks[3], &report));
+    assert_eq!(report.borrow().sub_tasks[0].borrow().id, Some(1));
+    let copy = garden.sub_tasks[4].borrow();
+    assert_eq!(copy.task, report.borrow().task);
+    let figures = copy.sub_tasks[0].borrow().id;
+    assert!(figures.is_some() && figures != Some(1));
+    assert_eq!(copy.sub_tasks[1].borrow().blocked_by, [figures.unwrap()]);
+}
+
+#[test]
+fn estimate_column() {
+    let columns: Vec<Column> = ["checkbox", "title", "estimate", "due"]
+        .iter()
+        .filter_map(|spec| Column::parse(spec))
+        .collect();
+    let config = Config {
+        columns: &columns,
+        ..config()
+    };
+    let mut view = View::new_headless(config, (24, 80), LIST);
+    check("estimate_80x24", view.render());
+}
+
+#[test]
+fn targets_sorted_with_deadlines() {
+    let list = "\
+[ ] ( ) Paint the shed target:2099-05-02
+[ ] ( ) Renew the passport due:2099-05-02
+[ ] ( ) Clear the gutters
+[ ] ( ) Sweep the chimney target:2001-03-01
+[ ] ( ) Book the boiler service due:2099-05-01
+";
+    let mut view = View::new_headless(config(), (24, 80), list);
+    let root = view.root_task();
+    root.borrow_mut().sort_by_due();
+    let titles: Vec<String> = root
+        .borrow()
+        .sub_tasks
+        .iter()
+        .map(|task| task.borrow().task.clone())
+        .collect();
+    assert_eq!(
+        titles,
+        [
+            "Sweep the chimney",
+            "Book the boiler service",
+            "Renew the passport",
+            "Paint the shed",
+            "Clear the gutters",
+        ]
+    );
+    check("targets_80x24", view.render());
+}
+
+#[test]
+fn pasted_tasks_checked_for_duplicates() {
+    let mut view = View::new_headless(config(), (24, 80), LIST);
+    view.window
+        .feed(b"\x1b[200~Order compost @phone\nWater the beds\n\x1b[201~");
+    assert_eq!(view.window.getch(), Some(tui::PASTE));
+    // A key pressed while the paste is added is dropped, then y jumps to
+    // the open task like the one pasted instead of adding it
+    view.window.feed(b" y");
+    view.paste_tasks();
+    let root = view.root_task();
+    let root = root.borrow();
+    assert_eq!(root.sub_tasks.len(), 6);
+    assert_eq!(root.sub_tasks[5].borrow().task, "Water the beds");
+    assert!(view.jump.is_some());
+}
+
+#[test]
+fn undo_after_paste() {
+    let mut view = View::new_headless(config(), (24, 80), LIST);
+    let original = view.root_task().borrow().save_string();
+    view.window
+        .feed(b"\x1b[200~Water the beds\nSweep the path\n\x1b[201~");
+    let key = view.window.getch();
+    assert_eq!(key, Some(tui::PASTE));
+    view.press(key, 1);
+    assert_eq!(view.root_task().borrow().sub_tasks.len(), 7);
+    view.press(Some(Key::Char('z')), 1);
+    assert_eq!(view.root_task().borrow().save_string(), original);
+    check("undo_paste_80x24", view.render());
+}
+
+#[test]
+fn undo_after_priority_change() {
+    let mut view = View::new_headless(config(), (24, 80), LIST);
+    let root = view.root_task();
+    let original = root.borrow().save_string();
+    view.selection = Some(0);
+    view.press(Some(Key::Char('<')), 2);
+    let garden = Rc::clone(&root.borrow().sub_tasks[0]);
+    let report = Rc::clone(&root.borrow().sub_tasks[2]);
+    view.marked = vec![Rc::clone(&garden), Rc::clone(&report)];
+    view.press(Some(Key::Char('>')), 1);
+    view.marked.clear();
+
+    view.press(Some(Key::Char('z')), 1);
+    view.press(Some(Key::Char('z')), 1);
+    assert_eq!(root.borrow().save_string(), original);
+    check("undo_priority_80x24", view.render());
+}
+
+#[test]
+fn sort_command_undone() {
+    let mut view = View::new_headless(config(), (24, 80), LIST);
+    let root = view.root_task();
+    let original = root.borrow().save_string();
+    view.run_commands(&[String::from(":sort due")]);
+    assert_ne!(root.borrow().save_string(), original);
+    view.undo();
+    assert_eq!(root.borrow().save_string(), original);
+}
+
+#[test]
+fn export_reports_unwritable_file() {
+    let missing = env::temp_dir().join(format!("yat-missing-{}", std::process::id()));
+    assert!(export::write(&missing.join("save.html"), "<html></html>").is_err());
+}
+
+#[test]
+fn notes_edited_in_temporary_file() {
+    let edited = crate::external_editor("sed -i s/draft/final/", "draft notes\n");
+    assert_eq!(edited.unwrap(), "final notes\n");
+    let err = crate::external_editor("false", "draft notes\n").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Unable to edit notes: false exited unsuccessfully"
+    );
+    assert_eq!(err.exit_code(), 69);
+    assert!(crate::external_editor(" ", "draft notes\n").is_err());
+}
+
+#[test]
+fn error_messages_and_sources() {
+    use std::error::Error as _;
+    let err = crate::error::Error::Read(
+        PathBuf::from("/tmp/save.txt"),
+        std::io::Error::new(std::io::ErrorKind::NotFound, "missing"),
+    );
+    assert_eq!(err.to_string(), "Unable to read /tmp/save.txt: missing");
+    assert_eq!(err.exit_code(), 66);
+    assert!(err.source().is_some());
+    assert!(crate::error::Error::NoHome.source().is_none());
+
+    let err = crate::parse_list("    [ ] ( ) orphan\n").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Unable to parse save file (try yat doctor): Can't have child without parent."
+    );
+    assert_eq!(err.exit_code(), 65);
+}
@@ -0,0 +1,114 @@
+/// Themes, sets of borders and colours, which can be previewed on the
+/// screen and chosen from inside yat.
+use crate::config::{expand_home, parse_config, Config, ConfigBuffer, Theme};
+use log::{info, warn};
+use std::fs::{self, read_dir};
+use std::path::Path;
+
+/// Built-in themes, as name and config.toml contents.
+pub const BUILT_IN: [(&str, &str); 4] = [
+    ("terminal default", ""),
+    (
+        "solarized dark",
+        include_str!("../configs/config-solarized_dark.toml"),
+    ),
+    ("nord", include_str!("../configs/config-nord.toml")),
+    (
+        "funky borders",
+        include_str!("../configs/config-funky_borders.toml"),
+    ),
+];
+
+/// Tables of config.toml which a theme sets.
+const TABLES: [&str; 3] = ["borders", "colours", "light_colours"];
+
+/// The built-in themes followed by the user's, from ~/.todo/themes/*.toml,
+/// as name and config.toml contents.
+pub fn available() -> Vec<(String, String)> {
+    let mut themes: Vec<(String, String)> = BUILT_IN
+        .iter()
+        .map(|&(name, text)| (String::from(name), String::from(text)))
+        .collect();
+    let dir = match expand_home("~/.todo/themes") {
+        Some(dir) => dir,
+        None => return themes,
+    };
+    let mut paths: Vec<_> = match read_dir(&dir) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "toml"))
+            .collect(),
+        Err(_) => return themes,
+    };
+    paths.sort();
+    for path in paths {
+        let name = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned());
+        match (name, fs::read_to_string(&path)) {
+            (Some(name), Ok(text)) => themes.push((name, text)),
+            (_, Err(err)) => warn!("Unable to read theme {}: {}", path.display(), err),
+            (None, _) => (),
+        }
+    }
+    themes
+}
+
+/// Borders and colours of a theme in config.toml format, over the defaults.
+/// The parsed theme lives as long as yat does, as the configuration only
+/// refers to its colours.
+pub fn load(text: &str) -> Option<Theme<'static>> {
+    let buffer = match parse_config(text) {
+        Ok(buffer) => buffer,
+        Err(err) => {
+            warn!("Unable to parse theme: {}", err);
+            return None;
+        }
+    };
+    let buffer: &'static ConfigBuffer = Box::leak(Box::new(buffer));
+    Some(buffer.config(Config::default()).theme())
+}
+
+/// Replace the theme in config.toml contents with another, keeping all the
+/// other settings. The theme's tables go at the end, after any top-level
+/// settings and other tables.
+pub fn replace(config: &str, theme: &str) -> String {
+    let mut buffer = String::new();
+    let mut skipping = false;
+    for line in config.lines() {
+        if let Some(header) = line.trim_start().strip_prefix('[') {
+            let name = header.split(']').next().unwrap_or_default().trim();
+            skipping = TABLES.contains(&name);
+        }
+        if !skipping {
+            buffer.push_str(line);
+            buffer.push('\n');
+        }
+    }
+    let theme = theme.trim();
+    if !theme.is_empty() {
+        let kept = buffer.trim_end().len();
+        buffer.truncate(kept);
+        if !buffer.is_empty() {
+            buffer.push_str("\n\n");
+        }
+        buffer.push_str(theme);
+        buffer.push('\n');
+    }
+    buffer
+}
+
+/// Write a theme into a configuration file, in place of the one there.
+pub fn write(filename: &Path, theme: &str) -> Result<(), String> {
+    let config = match fs::read_to_string(filename) {
+        Ok(config) => config,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(err) => return Err(err.to_string()),
+    };
+    if let Some(dir) = filename.parent() {
+        fs::create_dir_all(dir).map_err(|err| err.to_string())?;
+    }
+    fs::write(filename, replace(&config, theme)).map_err(|err| err.to_string())?;
+    info!("Theme written to {}.", filename.display());
+    Ok(())
+}
@@ -1,18 +1,225 @@
 /// Functionality for storing todo lists in a tree data structure.
-use log::{info, warn};
+use crate::due::{self, Due};
+use crate::effort;
+use crate::error::Error;
+use crate::recur::Recurrence;
+use chrono::{Duration, Local, NaiveDate, NaiveDateTime, Timelike};
 use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::collections::BTreeSet;
 use std::fmt;
-use std::fs::File;
-use std::io::Write;
-use std::path::Path;
 use std::rc::{Rc, Weak};
 
-/// Task priority.
-#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
-pub enum Priority {
-    Low,
-    Medium,
-    High,
+/// Most priority levels there can be, lettered A to Z.
+pub const MAX_PRIORITY_LEVELS: u8 = 26;
+
+/// Keys of the fields stored as "key:value" words after a task.
+pub const FIELDS: [&str; 18] = [
+    "due",
+    "repeat",
+    "skip",
+    "moved",
+    "defaults",
+    "start",
+    "target",
+    "today",
+    "carried",
+    "estimate",
+    "spent",
+    "timer",
+    "url",
+    "remind",
+    "id",
+    "blocked-by",
+    "created",
+    "completed",
+];
+
+/// Put before a word of a task's description in the save file which would
+/// otherwise be read back as a field, e.g. "read \\url:spec".
+const ESCAPE: char = '\\';
+
+/// Task priority, a level from A, the highest, downwards. Higher priorities
+/// compare greater.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Priority(u8);
+
+impl Priority {
+    pub const HIGH: Priority = Priority(0);
+    pub const MEDIUM: Priority = Priority(1);
+    pub const LOW: Priority = Priority(2);
+
+    /// Priority for a letter, from A to Z.
+    pub fn from_letter(letter: char) -> Option<Priority> {
+        match letter {
+            'A'..='Z' => Some(Priority(letter as u8 - b'A')),
+            _ => None,
+        }
+    }
+
+    /// Lowest priority on a scale with a number of levels.
+    pub fn lowest(levels: u8) -> Priority {
+        Priority(levels.clamp(1, MAX_PRIORITY_LEVELS) - 1)
+    }
+
+    /// Position from the top of the scale, 0 for A.
+    pub fn rank(&self) -> u8 {
+        self.0
+    }
+
+    /// Marker for the priority, e.g. (A) for the highest.
+    pub fn label(&self) -> String {
+        format!("({})", self.letter())
+    }
+
+    /// Letter for the priority, e.g. A for the highest.
+    pub fn letter(&self) -> char {
+        (b'A' + self.0) as char
+    }
+
+    /// Where the priority falls on a scale with a number of levels: high
+    /// for the top level, low for the bottom one (or below) and medium for
+    /// any between.
+    pub fn name(&self, levels: u8) -> &'static str {
+        if self.0 == 0 {
+            "high"
+        } else if self.0 + 1 >= levels {
+            "low"
+        } else {
+            "medium"
+        }
+    }
+}
+
+impl Ord for Priority {
+    fn cmp(&self, other: &Priority) -> Ordering {
+        other.0.cmp(&self.0)
+    }
+}
+
+impl PartialOrd for Priority {
+    fn partial_cmp(&self, other: &Priority) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Tags, contexts and priority given to tasks added under a task, stored
+/// after it as e.g. "defaults:+home,@outside,B".
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Defaults {
+    /// Tags and contexts, with their prefixes, e.g. "+home".
+    pub labels: Vec<String>,
+    pub priority: Option<Priority>,
+}
+
+impl Defaults {
+    /// Parse from tags, contexts and a priority letter, separated by commas
+    /// or spaces, e.g. "+home @outside B", or None if anything else is
+    /// among them.
+    pub fn parse(text: &str) -> Option<Defaults> {
+        let mut defaults = Defaults::default();
+        for item in text.split([',', ' ']).filter(|item| !item.is_empty()) {
+            let mut chars = item.chars();
+            let priority = match (chars.next(), chars.next()) {
+                (Some(letter), None) => Priority::from_letter(letter),
+                _ => None,
+            };
+            match item {
+                _ if priority.is_some() => defaults.priority = priority,
+                _ if item.len() > 1 && item.starts_with(['+', '#', '@']) && !item.contains(':') => {
+                    if !defaults.labels.iter().any(|label| label == item) {
+                        defaults.labels.push(String::from(item));
+                    }
+                }
+                _ => return None,
+            }
+        }
+        Some(defaults)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.labels.is_empty() && self.priority.is_none()
+    }
+
+    /// The defaults separated by sep, e.g. "+home,@outside,B".
+    pub fn join(&self, sep: &str) -> String {
+        let mut items = self.labels.clone();
+        items.extend(self.priority.as_ref().map(|p| p.letter().to_string()));
+        items.join(sep)
+    }
+}
+
+/// Timestamped entry in the comment log of a task.
+#[derive(Debug, Clone)]
+pub struct Comment {
+    pub time: NaiveDateTime,
+    pub text: String,
+}
+
+/// When a task was created or completed: a time, or only a day for those
+/// recorded before times were kept.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stamp {
+    Date(NaiveDate),
+    DateTime(NaiveDateTime),
+}
+
+impl Stamp {
+    /// The time now, to the minute.
+    pub fn now() -> Stamp {
+        let now = Local::now().naive_local();
+        Stamp::DateTime(
+            now.date()
+                .and_hms_opt(now.hour(), now.minute(), 0)
+                .unwrap_or(now),
+        )
+    }
+
+    /// Parse from "YYYY-MM-DD" or "YYYY-MM-DDTHH:MM".
+    pub fn parse(text: &str) -> Option<Stamp> {
+        if let Ok(date) = NaiveDate::parse_from_str(text, "%Y-%m-%d") {
+            return Some(Stamp::Date(date));
+        }
+        NaiveDateTime::parse_from_str(text, "%Y-%m-%dT%H:%M")
+            .ok()
+            .map(Stamp::DateTime)
+    }
+
+    /// The day of the stamp.
+    pub fn day(&self) -> NaiveDate {
+        match self {
+            Stamp::Date(date) => *date,
+            Stamp::DateTime(datetime) => datetime.date(),
+        }
+    }
+
+    /// How long before now the stamp is, briefly, e.g. "5m", "2h", "3d" or
+    /// "6w", or None if it's only known to be today.
+    pub fn age(&self, now: NaiveDateTime) -> Option<String> {
+        let days = (now.date() - self.day()).num_days().max(0);
+        let minutes = match self {
+            Stamp::DateTime(datetime) => (now - *datetime).num_minutes().max(0),
+            Stamp::Date(_) if days == 0 => return None,
+            Stamp::Date(_) => days * 24 * 60,
+        };
+        Some(match minutes {
+            m if m < 60 => format!("{}m", m),
+            m if m < 24 * 60 => format!("{}h", m / 60),
+            _ if days < 14 => format!("{}d", days.max(1)),
+            _ if days < 365 => format!("{}w", days / 7),
+            _ => format!("{}y", days / 365),
+        })
+    }
+}
+
+impl fmt::Display for Stamp {
+    /// Save file format.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Stamp::Date(date) => write!(f, "{}", date.format("%Y-%m-%d")),
+            Stamp::DateTime(datetime) => write!(f, "{}", datetime.format("%Y-%m-%dT%H:%M")),
+        }
+    }
 }
 
 /// Node in the todo list tree structure.
@@ -21,8 +228,43 @@ pub struct ToDo {
     pub task: String,
     pub complete: bool,
     pub priority: Option<Priority>,
+    pub notes: String,
+    pub comments: Vec<Comment>,
+    pub due: Option<Due>,
+    /// How often the task recurs, if it does.
+    pub repeat: Option<Recurrence>,
+    /// Days the rule for a recurring task gives which are skipped.
+    pub skip: BTreeSet<NaiveDate>,
+    /// Day the rule gave this occurrence of a recurring task, if it has been
+    /// moved to another day, so that the ones after it aren't.
+    pub moved: Option<NaiveDate>,
+    /// Labels and priority given to sub-tasks added under this task.
+    pub defaults: Defaults,
+    pub start: Option<NaiveDate>,
+    /// Day the task is aimed to be done by, which unlike a due date can
+    /// pass without the task becoming overdue.
+    pub target: Option<NaiveDate>,
+    pub today: Option<NaiveDate>,
+    pub carried: u32,
+    pub estimate: Option<u32>,
+    pub spent: u32,
+    /// When the timer for time spent on the task was started, if running.
+    pub timer: Option<NaiveDateTime>,
+    /// Web page or other resource the task is about, opened from yat.
+    pub url: Option<String>,
+    /// Minutes before the deadline to be reminded of the task, if at all.
+    pub remind: Option<u32>,
+    /// Identifier used by other tasks to refer to this one.
+    pub id: Option<u32>,
+    /// Ids of the tasks which have to be done before this one.
+    pub blocked_by: Vec<u32>,
+    pub created: Option<Stamp>,
+    pub completed: Option<Stamp>,
     pub parent: Weak<RefCell<ToDo>>,
     pub sub_tasks: Vec<Rc<RefCell<ToDo>>>,
+    /// Save file lines of sub-tasks nested too deep to read at startup,
+    /// indented from this task's sub-tasks, until it is focused on.
+    pub unread: String,
 }
 
 impl ToDo {
@@ -33,8 +275,29 @@ impl ToDo {
             task: String::from(task),
             complete: false,
             priority: None,
+            notes: String::new(),
+            comments: Vec::new(),
+            due: None,
+            repeat: None,
+            skip: BTreeSet::new(),
+            moved: None,
+            defaults: Defaults::default(),
+            start: None,
+            target: None,
+            today: None,
+            carried: 0,
+            estimate: None,
+            spent: 0,
+            timer: None,
+            url: None,
+            remind: None,
+            id: None,
+            blocked_by: Vec::new(),
+            created: None,
+            completed: None,
             parent,
             sub_tasks,
+            unread: String::new(),
         }
     }
 
@@ -47,6 +310,26 @@ impl ToDo {
         }
     }
 
+    /// Find the position of the task in the tree, as the index of each
+    /// task leading to it from the root.
+    pub fn index_path(&self) -> Vec<usize> {
+        match self.parent.upgrade() {
+            Some(parent_todo) => {
+                let parent_todo = parent_todo.borrow();
+                let mut path = parent_todo.index_path();
+                if let Some(index) = parent_todo
+                    .sub_tasks
+                    .iter()
+                    .position(|sub_task| std::ptr::eq(sub_task.as_ptr(), self))
+                {
+                    path.push(index);
+                }
+                path
+            }
+            None => Vec::new(),
+        }
+    }
+
     /// Convert all sub-tasks to string format.
     fn all_to_string(&self, tabs: usize, buf: &mut String) {
         for sub_task_rc in self.sub_tasks.iter() {
@@ -55,72 +338,579 @@ impl ToDo {
             let tab_pad = "    ".repeat(tabs);
             sub_task_str.insert_str(0, &tab_pad);
             buf.push_str(&sub_task_str);
+            // Split on newlines rather than using lines(), so that notes
+            // ending in a blank line keep it
+            if !sub_task.notes.is_empty() {
+                for line in sub_task.notes.split('\n') {
+                    buf.push_str(&format!("{}| {}\n", tab_pad, line));
+                }
+            }
+            for comment in sub_task.comments.iter() {
+                buf.push_str(&format!(
+                    "{}> {} {}\n",
+                    tab_pad,
+                    comment.time.format("%Y-%m-%dT%H:%M"),
+                    comment.text
+                ));
+            }
             sub_task.all_to_string(tabs + 1, buf);
+            for line in sub_task.unread.lines() {
+                buf.push_str(&format!("{}    {}\n", tab_pad, line));
+            }
         }
     }
 
-    /// Save todo list tree in string format to text file.
-    fn save_current(&self, filename: &Path) {
+    /// Convert todo list tree into save file format.
+    pub fn save_string(&self) -> String {
         let mut buffer = String::new();
         self.all_to_string(0, &mut buffer);
+        buffer
+    }
 
-        let mut file = match File::create(filename) {
-            Ok(f) => f,
-            Err(err) => {
-                warn!("Unable to open file to save: {}.", err);
-                return;
-            }
+    /// Convert from string format into ToDo node.
+    pub fn from_string(text: &str, parent: Weak<RefCell<ToDo>>) -> ToDo {
+        let complete = match text.chars().nth(1) {
+            Some(ch) => ch == 'X',
+            None => false,
         };
 
-        match file.write_all(buffer.as_bytes()) {
-            Ok(_) => (),
-            Err(err) => {
-                warn!("Unable to write to save file: {}", err);
-                return;
+        let priority = text.chars().nth(5).and_then(Priority::from_letter);
+
+        // The task follows the completion box and priority, e.g. "[ ] (A) "
+        let rest = text.char_indices().nth(8).map_or("", |(i, _)| &text[i..]);
+        let mut todo = Self::new("", parent);
+        let words: Vec<&str> = rest
+            .split(' ')
+            .filter_map(|word| match word.strip_prefix(ESCAPE) {
+                Some(escaped) if is_field_like(escaped) => Some(escaped),
+                _ if todo.parse_field(word) => None,
+                _ => Some(word),
+            })
+            .collect();
+        todo.task = words.join(" ");
+        todo.complete = complete;
+        todo.priority = priority;
+        todo
+    }
+
+    /// Set a field stored as a "key:value" word after the task description,
+    /// returning false if the word is part of the description.
+    fn parse_field(&mut self, word: &str) -> bool {
+        let (key, value) = match word.split_once(':') {
+            Some(field) => field,
+            None => return false,
+        };
+        match key {
+            "due" if self.due.is_none() => {
+                self.due = Due::parse(value);
+                self.due.is_some()
+            }
+            "repeat" if self.repeat.is_none() => {
+                let day = self.due.map_or(Local::now().date_naive(), |due| due.day());
+                self.repeat = Recurrence::parse(value, day);
+                self.repeat.is_some()
+            }
+            "skip" if self.skip.is_empty() => {
+                let days: Result<BTreeSet<NaiveDate>, _> = value
+                    .split(',')
+                    .map(|day| NaiveDate::parse_from_str(day, "%Y-%m-%d"))
+                    .collect();
+                self.skip = days.unwrap_or_default();
+                !self.skip.is_empty()
+            }
+            "moved" if self.moved.is_none() => {
+                self.moved = NaiveDate::parse_from_str(value, "%Y-%m-%d").ok();
+                self.moved.is_some()
+            }
+            "defaults" if self.defaults.is_empty() => {
+                self.defaults = Defaults::parse(value).unwrap_or_default();
+                !self.defaults.is_empty()
+            }
+            "start" if self.start.is_none() => {
+                self.start = NaiveDate::parse_from_str(value, "%Y-%m-%d").ok();
+                self.start.is_some()
+            }
+            "target" if self.target.is_none() => {
+                self.target = NaiveDate::parse_from_str(value, "%Y-%m-%d").ok();
+                self.target.is_some()
+            }
+            "today" if self.today.is_none() => {
+                self.today = NaiveDate::parse_from_str(value, "%Y-%m-%d").ok();
+                self.today.is_some()
+            }
+            "estimate" if self.estimate.is_none() => {
+                self.estimate = effort::parse(value);
+                self.estimate.is_some()
+            }
+            "timer" if self.timer.is_none() => {
+                self.timer = NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S").ok();
+                self.timer.is_some()
+            }
+            "spent" if self.spent == 0 => {
+                self.spent = effort::parse(value).unwrap_or(0);
+                self.spent > 0
+            }
+            "url" if self.url.is_none() && !value.is_empty() => {
+                self.url = Some(String::from(value));
+                true
+            }
+            "remind" if self.remind.is_none() => {
+                self.remind = due::parse_lead(value);
+                self.remind.is_some()
+            }
+            "id" if self.id.is_none() => {
+                self.id = value.parse().ok();
+                self.id.is_some()
+            }
+            "blocked-by" if self.blocked_by.is_empty() => {
+                let ids: Result<Vec<u32>, _> = value.split(',').map(str::parse).collect();
+                self.blocked_by = ids.unwrap_or_default();
+                !self.blocked_by.is_empty()
+            }
+            "created" if self.created.is_none() => {
+                self.created = Stamp::parse(value);
+                self.created.is_some()
+            }
+            "completed" if self.completed.is_none() => {
+                self.completed = Stamp::parse(value);
+                self.completed.is_some()
+            }
+            "carried" if self.carried == 0 => {
+                self.carried = value.parse().unwrap_or(0);
+                self.carried > 0
+            }
+            _ => false,
+        }
+    }
+
+    /// Append a line of notes from string format.
+    pub fn add_note_from_string(&mut self, text: &str) {
+        let line = text.strip_prefix('|').unwrap_or(text);
+        let line = line.strip_prefix(' ').unwrap_or(line);
+        if !self.notes.is_empty() {
+            self.notes.push('\n');
+        }
+        self.notes.push_str(line);
+    }
+
+    /// Contexts of the task, given by words starting with '@', e.g. @phone.
+    pub fn contexts(&self) -> impl Iterator<Item = &str> {
+        self.task
+            .split_whitespace()
+            .filter(|word| word.len() > 1 && word.starts_with('@'))
+    }
+
+    /// Tags of the task, given by words starting with '+' or '#', e.g.
+    /// +urgent, without the prefix.
+    pub fn tags(&self) -> impl Iterator<Item = &str> {
+        self.task.split_whitespace().filter_map(|word| {
+            word.strip_prefix(['+', '#'])
+                .filter(|tag| !tag.is_empty() && !tag.starts_with(['+', '#']))
+        })
+    }
+
+    /// Give a new task the defaults of the task it's added under and those
+    /// above that, the nearest first: the tags and contexts it doesn't
+    /// have, and a priority if it has none.
+    pub fn inherit_defaults(&mut self, parent: &Rc<RefCell<ToDo>>) {
+        let mut ancestor = Some(Rc::clone(parent));
+        while let Some(task) = ancestor {
+            let task = task.borrow();
+            for label in task.defaults.labels.iter() {
+                if !self.task.split_whitespace().any(|word| word == label) {
+                    self.task = format!("{} {}", self.task, label);
+                }
+            }
+            if self.priority.is_none() {
+                self.priority = task.defaults.priority;
             }
+            ancestor = task.parent.upgrade();
+        }
+    }
+
+    /// Stop the timer on the task, if running, adding the time since it was
+    /// started to the time spent, to the nearest minute. Returns the
+    /// minutes added.
+    pub fn stop_timer(&mut self, now: NaiveDateTime) -> Option<u32> {
+        let started = self.timer.take()?;
+        let seconds = (now - started).num_seconds().max(0);
+        let minutes = ((seconds + 30) / 60) as u32;
+        self.spent += minutes;
+        Some(minutes)
+    }
+
+    /// Whether the task has started by the given day, if it has a start
+    /// date.
+    pub fn started(&self, day: NaiveDate) -> bool {
+        self.start.is_none_or(|start| start <= day)
+    }
+
+    /// How long ago the task was added, e.g. "3d old", or if it's complete,
+    /// when it was completed, e.g. "done 2h ago".
+    pub fn age(&self, now: NaiveDateTime) -> Option<String> {
+        if self.complete {
+            let completed = self.completed?;
+            return Some(match completed.age(now) {
+                Some(age) => format!("done {} ago", age),
+                None => String::from("done today"),
+            });
+        }
+        let created = self.created?;
+        Some(match created.age(now) {
+            Some(age) => format!("{} old", age),
+            None => String::from("added today"),
+        })
+    }
+
+    /// Whether the task, or any of its sub-tasks, satisfies a condition.
+    pub fn any_in_tree(&self, condition: &dyn Fn(&ToDo) -> bool) -> bool {
+        condition(self)
+            || self
+                .sub_tasks
+                .iter()
+                .any(|sub_task| sub_task.borrow().any_in_tree(condition))
+    }
+
+    /// Find the first sub-task in the tree (depth first) which satisfies a
+    /// condition.
+    pub fn find_in_tree(&self, condition: &dyn Fn(&ToDo) -> bool) -> Option<Rc<RefCell<ToDo>>> {
+        self.sub_tasks
+            .iter()
+            .find_map(|sub_task| match condition(&sub_task.borrow()) {
+                true => Some(Rc::clone(sub_task)),
+                false => sub_task.borrow().find_in_tree(condition),
+            })
+    }
+
+    /// Count the sub-tasks in the tree which satisfy a condition.
+    pub fn count_in_tree(&self, condition: &dyn Fn(&ToDo) -> bool) -> usize {
+        self.sub_tasks
+            .iter()
+            .map(|sub_task| {
+                let sub_task = sub_task.borrow();
+                condition(&sub_task) as usize + sub_task.count_in_tree(condition)
+            })
+            .sum()
+    }
+
+    /// Number of levels of sub-tasks under the task, 0 if it has none.
+    pub fn height(&self) -> usize {
+        self.sub_tasks
+            .iter()
+            .map(|sub_task| 1 + sub_task.borrow().height())
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Collect the contexts used by sub-tasks in the tree.
+    pub fn all_contexts(&self, contexts: &mut BTreeSet<String>) {
+        for sub_task_rc in self.sub_tasks.iter() {
+            let sub_task = sub_task_rc.borrow();
+            contexts.extend(sub_task.contexts().map(String::from));
+            sub_task.all_contexts(contexts);
+        }
+    }
+
+    /// Collect the tags used by sub-tasks in the tree.
+    pub fn all_tags(&self, tags: &mut BTreeSet<String>) {
+        for sub_task_rc in self.sub_tasks.iter() {
+            let sub_task = sub_task_rc.borrow();
+            tags.extend(sub_task.tags().map(String::from));
+            sub_task.all_tags(tags);
+        }
+    }
+
+    /// Mark task as complete (or not), recording when it was completed.
+    pub fn toggle_complete(&mut self) -> bool {
+        self.complete = !self.complete;
+        self.completed = match self.complete {
+            true => Some(Stamp::now()),
+            false => None,
         };
-        info!("Todo list saved to file.");
+        self.complete
     }
 
-    /// Traverse tree back to root node and save.
-    pub fn save(&self, filename: &Path) {
-        if let Some(parent_todo) = self.parent.upgrade() {
-            parent_todo.borrow().save(filename)
-        } else {
-            self.save_current(filename)
+    /// The next occurrence of a recurring task, with its sub-tasks, due when
+    /// its rule says after this one, or None if it doesn't recur.
+    pub fn next_occurrence(&self, now: NaiveDateTime) -> Option<Rc<RefCell<ToDo>>> {
+        let due = self.next_due(now.date())?;
+        let todo = self.fresh_copy(self.parent.clone(), now);
+        todo.borrow_mut().move_to(due);
+        Some(todo)
+    }
+
+    /// Skip this occurrence of a recurring task, making it the next one
+    /// instead, returning when that's due.
+    pub fn skip_occurrence(&mut self, today: NaiveDate) -> Option<Due> {
+        let due = self.next_due(today)?;
+        self.move_to(due);
+        Some(due)
+    }
+
+    /// Day the rule for a recurring task gave this occurrence, which is
+    /// when it's due unless it was moved.
+    pub fn scheduled(&self) -> Option<NaiveDate> {
+        self.moved.or_else(|| self.due.map(|due| due.day()))
+    }
+
+    /// When the occurrence after this one is due, counting from when it
+    /// was scheduled, or None if it doesn't recur.
+    fn next_due(&self, today: NaiveDate) -> Option<Due> {
+        let repeat = self.repeat?;
+        let day = repeat.next(self.scheduled().unwrap_or(today), today, &self.skip)?;
+        Some(match self.due {
+            Some(Due::DateTime(datetime)) => Due::DateTime(day.and_time(datetime.time())),
+            _ => Due::Date(day),
+        })
+    }
+
+    /// Make this the occurrence due on a later day, moving the start date
+    /// along with it and forgetting days skipped before it.
+    fn move_to(&mut self, due: Due) {
+        if let (Some(start), Some(previous)) = (self.start, self.scheduled()) {
+            self.start = start.checked_add_signed(due.day() - previous);
         }
+        self.due = Some(due);
+        self.moved = None;
+        self.skip.retain(|&day| day > due.day());
     }
 
-    /// Convert from string format into ToDo node.
-    pub fn from_string(text: &str, parent: Weak<RefCell<ToDo>>) -> ToDo {
-        let complete = match text.chars().nth(1) {
-            Some(ch) => ch == 'X',
-            None => false,
+    /// Copy of a task and its sub-tasks as if newly added, without their
+    /// progress, comments or ids.
+    pub fn fresh_copy(&self, parent: Weak<RefCell<ToDo>>, now: NaiveDateTime) -> Rc<RefCell<ToDo>> {
+        let mut todo = ToDo::new(&self.task, parent);
+        todo.priority = self.priority;
+        todo.notes = self.notes.clone();
+        todo.due = self.due;
+        todo.repeat = self.repeat;
+        todo.skip = self.skip.clone();
+        todo.moved = self.moved;
+        todo.defaults = self.defaults.clone();
+        todo.start = self.start;
+        todo.target = self.target;
+        todo.estimate = self.estimate;
+        todo.url = self.url.clone();
+        todo.remind = self.remind;
+        todo.created = Some(Stamp::DateTime(now));
+        let todo = Rc::new(RefCell::new(todo));
+        // Sub-tasks not read yet are copied too, read just for the copy
+        let unread = match self.unread.is_empty() {
+            true => Vec::new(),
+            false => crate::parse_list(&self.unread)
+                .map(|root| root.borrow().sub_tasks.clone())
+                .unwrap_or_default(),
         };
+        let sub_tasks = self
+            .sub_tasks
+            .iter()
+            .chain(unread.iter())
+            .map(|sub_task| sub_task.borrow().fresh_copy(Rc::downgrade(&todo), now))
+            .collect();
+        todo.borrow_mut().sub_tasks = sub_tasks;
+        todo
+    }
 
-        let priority = match text.chars().nth(5) {
-            Some(ch) => match ch {
-                'A' => Some(Priority::High),
-                'B' => Some(Priority::Medium),
-                'C' => Some(Priority::Low),
-                _ => None,
-            },
-            None => None,
+    /// Increase task priority, up to the highest on a scale with a number
+    /// of levels. A priority below the scale goes to its lowest level.
+    pub fn increase_priority(&mut self, levels: u8) {
+        let lowest = Priority::lowest(levels);
+        self.priority = match self.priority {
+            None => Some(lowest),
+            Some(priority) if priority < lowest => Some(lowest),
+            Some(Priority(rank)) => Some(Priority(rank.saturating_sub(1))),
         };
+    }
 
-        let mut todo = Self::new(&text[8..], parent);
-        todo.complete = complete;
-        todo.priority = priority;
-        todo
+    /// Decrease task priority on a scale with a number of levels, down to
+    /// no priority.
+    pub fn decrease_priority(&mut self, levels: u8) {
+        self.priority = match self.priority {
+            Some(priority) if priority > Priority::lowest(levels) => Some(Priority(priority.0 + 1)),
+            _ => None,
+        };
+    }
+
+    /// Find incomplete tasks in the tree which fall due after one time, up
+    /// to and including another.
+    pub fn falling_due(&self, after: NaiveDateTime, until: NaiveDateTime, found: &mut Vec<String>) {
+        for sub_task_rc in self.sub_tasks.iter() {
+            let sub_task = sub_task_rc.borrow();
+            if let Some(due) = sub_task.due {
+                let deadline = due.deadline();
+                if !sub_task.complete && deadline > after && deadline <= until {
+                    found.push(sub_task.task.clone());
+                }
+            }
+            sub_task.falling_due(after, until, found);
+        }
+    }
+
+    /// When to be reminded of the task, its lead time before the deadline,
+    /// if it has a reminder and a due date.
+    pub fn reminder(&self) -> Option<NaiveDateTime> {
+        let lead = Duration::minutes(self.remind?.into());
+        Some(self.due?.deadline() - lead)
+    }
+
+    /// Find incomplete tasks in the tree whose reminders go off after one
+    /// time, up to and including another.
+    pub fn reminders_due(
+        &self,
+        after: NaiveDateTime,
+        until: NaiveDateTime,
+        found: &mut Vec<String>,
+    ) {
+        for sub_task_rc in self.sub_tasks.iter() {
+            let sub_task = sub_task_rc.borrow();
+            if let Some(reminder) = sub_task.reminder() {
+                if !sub_task.complete && reminder > after && reminder <= until {
+                    found.push(sub_task.task.clone());
+                }
+            }
+            sub_task.reminders_due(after, until, found);
+        }
     }
 
-    /// Reorder subtasks based on priority
+    /// Earliest deadline or reminder of incomplete tasks in the tree after
+    /// given time.
+    pub fn next_deadline(&self, after: NaiveDateTime) -> Option<NaiveDateTime> {
+        let mut next: Option<NaiveDateTime> = None;
+        for sub_task_rc in self.sub_tasks.iter() {
+            let sub_task = sub_task_rc.borrow();
+            let deadlines = sub_task
+                .due
+                .map(|due| due.deadline())
+                .into_iter()
+                .chain(sub_task.reminder())
+                .filter(|&deadline| !sub_task.complete && deadline > after);
+            for candidate in deadlines.chain(sub_task.next_deadline(after)) {
+                next = Some(next.map_or(candidate, |n| n.min(candidate)));
+            }
+        }
+        next
+    }
+
+    /// Shift the due dates of unfinished tasks in the tree which are
+    /// overdue at the given time and satisfy a condition, returning how
+    /// many were postponed.
+    pub fn postpone_overdue(
+        &self,
+        now: NaiveDateTime,
+        interval: Duration,
+        condition: &dyn Fn(&ToDo) -> bool,
+    ) -> usize {
+        let mut count = 0;
+        for sub_task_rc in self.sub_tasks.iter() {
+            let mut sub_task = sub_task_rc.borrow_mut();
+            match sub_task.due {
+                Some(due)
+                    if !sub_task.complete && due.deadline() <= now && condition(&sub_task) =>
+                {
+                    sub_task.due = Some(due.postpone(interval));
+                    count += 1;
+                }
+                _ => (),
+            }
+            count += sub_task.postpone_overdue(now, interval, condition);
+        }
+        count
+    }
+
+    /// Move unfinished tasks flagged for an earlier day on to the given day,
+    /// collecting the tasks carried over and how many times they have been.
+    pub fn rollover(&mut self, day: NaiveDate, carried: &mut Vec<(String, u32)>) {
+        for sub_task_rc in self.sub_tasks.iter() {
+            let mut sub_task = sub_task_rc.borrow_mut();
+            match sub_task.today {
+                Some(flagged) if flagged < day && !sub_task.complete => {
+                    sub_task.today = Some(day);
+                    sub_task.carried += 1;
+                    carried.push((sub_task.task.clone(), sub_task.carried));
+                }
+                _ => (),
+            }
+            sub_task.rollover(day, carried);
+        }
+    }
+
+    /// Keep a line of the save file under this task, to be read later.
+    pub fn add_unread(&mut self, line: &str) {
+        self.unread.push_str(line);
+        self.unread.push('\n');
+    }
+
+    /// Append a comment from string format, "> YYYY-MM-DDTHH:MM text".
+    pub fn add_comment_from_string(&mut self, text: &str) -> Result<(), Error> {
+        let entry = text.strip_prefix('>').unwrap_or(text).trim_start();
+        let (time, text) = entry.split_once(' ').unwrap_or((entry, ""));
+        match NaiveDateTime::parse_from_str(time, "%Y-%m-%dT%H:%M") {
+            Ok(time) => {
+                self.comments.push(Comment {
+                    time,
+                    text: String::from(text),
+                });
+                Ok(())
+            }
+            Err(_) => Err(Error::Parse("Invalid comment timestamp.")),
+        }
+    }
+
+    /// Add a comment at the current time.
+    pub fn add_comment(&mut self, text: &str) {
+        self.comments.push(Comment {
+            time: Local::now().naive_local(),
+            text: String::from(text),
+        });
+    }
+
+    /// Reorder subtasks based on priority, then due or target date,
+    /// otherwise keeping their order
     pub fn sort_by_priority(&mut self) {
         self.sub_tasks.sort_by(|a, b| {
+            let (a, b) = (a.borrow(), b.borrow());
             // Reverse order so we treat None properly
-            b.borrow().priority.cmp(&a.borrow().priority)
+            b.priority.cmp(&a.priority).then_with(|| by_date(&a, &b))
+        });
+    }
+
+    /// Sort sub-tasks by due or target date, soonest first, then by
+    /// priority.
+    pub fn sort_by_due(&mut self) {
+        self.sub_tasks.sort_by(|a, b| {
+            let (a, b) = (a.borrow(), b.borrow());
+            by_date(&a, &b).then_with(|| b.priority.cmp(&a.priority))
         });
     }
+
+    /// Date the task is sorted by: when it is due, or else its target
+    /// date, with whether it is only a target.
+    fn sort_date(&self) -> Option<(NaiveDateTime, bool)> {
+        match (self.due, self.target) {
+            (Some(due), _) => Some((due.deadline(), false)),
+            (None, Some(target)) => Some((Due::Date(target).deadline(), true)),
+            (None, None) => None,
+        }
+    }
+}
+
+/// Whether a word of a task's description has the key of a field, as in
+/// "url:spec", after any escapes before it, so it has to be escaped to be
+/// read back as part of the description.
+fn is_field_like(word: &str) -> bool {
+    word.trim_start_matches(ESCAPE)
+        .split_once(':')
+        .is_some_and(|(key, _)| FIELDS.contains(&key))
+}
+
+/// Order of two tasks by date, soonest first, where a deadline comes before
+/// a target on the same day and tasks with neither come last.
+fn by_date(a: &ToDo, b: &ToDo) -> Ordering {
+    match (a.sort_date(), b.sort_date()) {
+        (Some(a), Some(b)) => a.cmp(&b),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    }
 }
 
 impl fmt::Display for ToDo {
@@ -131,12 +921,80 @@ impl fmt::Display for ToDo {
         }
 
         match self.priority {
-            Some(Priority::Low) => write!(f, "(C) ")?,
-            Some(Priority::Medium) => write!(f, "(B) ")?,
-            Some(Priority::High) => write!(f, "(A) ")?,
+            Some(priority) => write!(f, "{} ", priority.label())?,
             None => write!(f, "( ) ")?,
         }
 
-        writeln!(f, "{}", &self.task)
+        for (i, word) in self.task.split(' ').enumerate() {
+            let space = if i > 0 { " " } else { "" };
+            match is_field_like(word) {
+                true => write!(f, "{}{}{}", space, ESCAPE, word)?,
+                false => write!(f, "{}{}", space, word)?,
+            }
+        }
+        if let Some(due) = self.due {
+            write!(f, " due:{}", due)?;
+        }
+        if let Some(repeat) = self.repeat {
+            write!(f, " repeat:{}", repeat)?;
+        }
+        if !self.skip.is_empty() {
+            let days: Vec<String> = self
+                .skip
+                .iter()
+                .map(|day| day.format("%Y-%m-%d").to_string())
+                .collect();
+            write!(f, " skip:{}", days.join(","))?;
+        }
+        if let Some(moved) = self.moved {
+            write!(f, " moved:{}", moved.format("%Y-%m-%d"))?;
+        }
+        if !self.defaults.is_empty() {
+            write!(f, " defaults:{}", self.defaults.join(","))?;
+        }
+        if let Some(start) = self.start {
+            write!(f, " start:{}", start.format("%Y-%m-%d"))?;
+        }
+        if let Some(target) = self.target {
+            write!(f, " target:{}", target.format("%Y-%m-%d"))?;
+        }
+        if let Some(today) = self.today {
+            write!(f, " today:{}", today.format("%Y-%m-%d"))?;
+        }
+        if self.carried > 0 {
+            write!(f, " carried:{}", self.carried)?;
+        }
+        if let Some(estimate) = self.estimate {
+            write!(f, " estimate:{}", effort::format(estimate))?;
+        }
+        if self.spent > 0 {
+            write!(f, " spent:{}", effort::format(self.spent))?;
+        }
+        if let Some(timer) = self.timer {
+            write!(f, " timer:{}", timer.format("%Y-%m-%dT%H:%M:%S"))?;
+        }
+        if let Some(url) = &self.url {
+            write!(f, " url:{}", url)?;
+        }
+        if let Some(remind) = self.remind {
+            write!(f, " remind:{}", due::format_lead(remind))?;
+        }
+        if let Some(id) = self.id {
+            write!(f, " id:{}", id)?;
+        }
+        if !self.blocked_by.is_empty() {
+            let ids: Vec<String> = self.blocked_by.iter().map(u32::to_string).collect();
+            write!(f, " blocked-by:{}", ids.join(","))?;
+        }
+        if let Some(created) = self.created {
+            write!(f, " created:{}", created)?;
+        }
+        if let Some(completed) = self.completed {
+            write!(f, " completed:{}", completed)?;
+        }
+        writeln!(f)
     }
 }
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,395 @@
+/// Property-based tests of the save file format: arbitrary task trees, with
+/// unicode text, deep nesting and every field, must load back exactly as
+/// they were saved.
+///
+/// Trees are generated with proptest, which shrinks a tree that fails to a
+/// smaller one failing the same way and keeps its seed in
+/// proptest-regressions to try first next time. PROPTEST_CASES sets how
+/// many trees are tried.
+use super::{Comment, Defaults, Priority, Stamp, ToDo, FIELDS, MAX_PRIORITY_LEVELS};
+use crate::due::Due;
+use crate::recur::Recurrence;
+use crate::{parse_list, parse_list_to_depth, read_unread};
+use chrono::{NaiveDate, NaiveDateTime};
+use proptest::collection::vec;
+use proptest::option;
+use proptest::prelude::*;
+use proptest::sample::select;
+use proptest::strategy::ValueTree;
+use proptest::test_runner::TestRunner;
+use std::cell::RefCell;
+use std::rc::{Rc, Weak};
+
+/// Number of trees checked for what they cover.
+const CASES: usize = 256;
+
+/// Most tasks in a generated list.
+const MAX_TASKS: usize = 60;
+
+/// Pieces of text which words are made from, including unicode and the
+/// characters with a meaning in the save file.
+const PIECES: [&str; 24] = [
+    "a", "task", "Bob", "42", "é", "ß", "日本", "🎉", "ü", "Ω", "+work", "@phone", "#tag", ":",
+    "|", ">", "[X]", "(A)", "-", "'", "\"", "\\", "e\u{301}", "x",
+];
+
+/// A word, sometimes one which looks like a field, e.g. "url:a", with or
+/// without backslashes before it.
+fn word() -> impl Strategy<Value = String> {
+    let plain = vec(select(&PIECES[..]), 1..=4).prop_map(|pieces| pieces.concat());
+    let field = (
+        select(&["", "\\", "\\\\"][..]),
+        select(&FIELDS[..]),
+        plain.clone(),
+    )
+        .prop_map(|(escapes, key, value)| format!("{}{}:{}", escapes, key, value));
+    prop_oneof![4 => plain, 1 => field]
+}
+
+/// Words separated by single spaces, or nothing.
+fn text(max_words: usize) -> impl Strategy<Value = String> {
+    vec(word(), 0..=max_words).prop_map(|words| words.join(" "))
+}
+
+/// Any priority from A to Z.
+fn priority() -> impl Strategy<Value = Priority> {
+    (0..MAX_PRIORITY_LEVELS)
+        .prop_map(|level| Priority::from_letter((b'A' + level) as char).unwrap())
+}
+
+fn number() -> impl Strategy<Value = u32> {
+    prop_oneof![0..100u32, 0..10_000u32, any::<u32>()]
+}
+
+fn date() -> impl Strategy<Value = NaiveDate> {
+    let start = NaiveDate::from_ymd_opt(1970, 1, 1).unwrap();
+    (0..365 * 130i64).prop_map(move |days| start + chrono::Duration::days(days))
+}
+
+fn datetime() -> impl Strategy<Value = NaiveDateTime> {
+    (date(), 0..24 * 60u32)
+        .prop_map(|(date, minutes)| date.and_hms_opt(minutes / 60, minutes % 60, 0).unwrap())
+}
+
+fn stamp() -> impl Strategy<Value = Stamp> {
+    prop_oneof![
+        date().prop_map(Stamp::Date),
+        datetime().prop_map(Stamp::DateTime)
+    ]
+}
+
+fn due() -> impl Strategy<Value = Due> {
+    prop_oneof![
+        date().prop_map(Due::Date),
+        datetime().prop_map(Due::DateTime)
+    ]
+}
+
+fn recurrence() -> impl Strategy<Value = Recurrence> {
+    prop_oneof![
+        Just(Recurrence::Daily),
+        Just(Recurrence::Weekly),
+        (2..402u32).prop_map(|days| match days {
+            7 => Recurrence::Weekly,
+            days => Recurrence::Days(days),
+        }),
+        (1..=31u32).prop_map(Recurrence::Monthly),
+    ]
+}
+
+/// Notes of a few lines, or none. The first line is never empty, as
+/// there'd be no telling it apart from no notes.
+fn notes() -> impl Strategy<Value = String> {
+    let lines =
+        (word(), text(4), vec((0..3usize, text(5)), 0..4)).prop_map(|(first, rest, more)| {
+            let mut lines = vec![format!("{}{}", first, rest)];
+            lines.extend(
+                more.into_iter()
+                    .map(|(indent, line)| format!("{}{}", " ".repeat(indent), line)),
+            );
+            lines.join("\n")
+        });
+    option::of(lines).prop_map(Option::unwrap_or_default)
+}
+
+fn defaults() -> impl Strategy<Value = Defaults> {
+    let label = (select(&["+", "#", "@"][..]), word())
+        .prop_map(|(prefix, word)| format!("{}{}", prefix, word.replace([',', ':'], "")));
+    (vec(label, 0..=2), option::of(priority())).prop_map(|(labels, priority)| {
+        let mut defaults = Defaults::default();
+        for label in labels {
+            if label.len() > 1 && !defaults.labels.contains(&label) {
+                defaults.labels.push(label);
+            }
+        }
+        defaults.priority = priority;
+        defaults
+    })
+}
+
+/// A task with every field chosen at random.
+fn task() -> impl Strategy<Value = ToDo> {
+    let text = (
+        text(6),
+        any::<bool>(),
+        option::of(priority()),
+        notes(),
+        vec((datetime(), text(5)), 0..3),
+    );
+    let dates = (
+        option::of(due()),
+        option::of(recurrence()),
+        vec(date(), 0..=3),
+        option::of(date()),
+        defaults(),
+        option::of(date()),
+        option::of(date()),
+        option::of(date()),
+    );
+    let effort = (
+        option::of(number()),
+        option::of(number()),
+        option::of(number()),
+        option::of((datetime(), 0..60i64)),
+    );
+    let links = (
+        option::of(word()),
+        option::of(1..10_001u32),
+        option::of(number()),
+        vec(number(), 0..=3),
+        option::of(stamp()),
+        option::of(stamp()),
+    );
+    (text, dates, effort, links).prop_map(|(text, dates, effort, links)| {
+        let (title, complete, priority, notes, comments) = text;
+        let (due, repeat, skip, moved, defaults, start, target, today) = dates;
+        let (carried, estimate, spent, timer) = effort;
+        let (url, remind, id, blocked_by, created, completed) = links;
+        let mut todo = ToDo::new(&title, Weak::new());
+        todo.complete = complete;
+        todo.priority = priority;
+        todo.notes = notes;
+        todo.comments = comments
+            .into_iter()
+            .map(|(time, text)| Comment { time, text })
+            .collect();
+        todo.due = due;
+        todo.repeat = repeat;
+        todo.skip = skip.into_iter().collect();
+        todo.moved = moved;
+        todo.defaults = defaults;
+        todo.start = start;
+        todo.target = target;
+        todo.today = today;
+        todo.carried = carried.unwrap_or(0);
+        todo.estimate = estimate;
+        todo.spent = spent.unwrap_or(0);
+        todo.timer = timer.map(|(time, seconds)| time + chrono::Duration::seconds(seconds));
+        todo.url = url.map(|word| format!("https://example.com/{}", word));
+        todo.remind = remind;
+        todo.id = id;
+        todo.blocked_by = blocked_by;
+        todo.created = created;
+        todo.completed = completed;
+        todo
+    })
+}
+
+/// A todo list of tasks in order, each one level under the task before it,
+/// beside it or a few levels back up, more often deeper so that lists nest
+/// deeply.
+fn tree() -> impl Strategy<Value = Rc<RefCell<ToDo>>> {
+    let step = prop_oneof![3 => Just(1i32), 1 => Just(0i32), 1 => -3..0i32];
+    vec((step, task()), 0..MAX_TASKS).prop_map(|tasks| {
+        let root = Rc::new(RefCell::new(ToDo::new("", Weak::new())));
+        // Tasks from the top level down to the one added last
+        let mut path = vec![Rc::clone(&root)];
+        for (step, mut todo) in tasks {
+            let parent = (path.len() as i32 - 2 + step).clamp(0, path.len() as i32 - 1);
+            path.truncate(parent as usize + 1);
+            let parent = &path[parent as usize];
+            todo.parent = Rc::downgrade(parent);
+            let todo = Rc::new(RefCell::new(todo));
+            parent.borrow_mut().sub_tasks.push(Rc::clone(&todo));
+            path.push(todo);
+        }
+        root
+    })
+}
+
+/// Compare two tasks and their sub-tasks, field by field, describing the
+/// first difference found.
+fn compare(saved: &ToDo, loaded: &ToDo, path: &str) -> Result<(), String> {
+    macro_rules! same {
+        ($field:ident) => {
+            if saved.$field != loaded.$field {
+                return Err(format!(
+                    "{} at {}: saved {:?}, loaded {:?}",
+                    stringify!($field),
+                    path,
+                    saved.$field,
+                    loaded.$field
+                ));
+            }
+        };
+    }
+    same!(task);
+    same!(complete);
+    same!(priority);
+    same!(notes);
+    same!(due);
+    same!(repeat);
+    same!(skip);
+    same!(moved);
+    same!(defaults);
+    same!(start);
+    same!(target);
+    same!(today);
+    same!(carried);
+    same!(estimate);
+    same!(spent);
+    same!(timer);
+    same!(url);
+    same!(remind);
+    same!(id);
+    same!(blocked_by);
+    same!(created);
+    same!(completed);
+    let comments = |todo: &ToDo| -> Vec<(NaiveDateTime, String)> {
+        let comments = todo.comments.iter();
+        comments.map(|c| (c.time, c.text.clone())).collect()
+    };
+    if comments(saved) != comments(loaded) {
+        return Err(format!(
+            "comments at {}: saved {:?}, loaded {:?}",
+            path,
+            comments(saved),
+            comments(loaded)
+        ));
+    }
+
+    if saved.sub_tasks.len() != loaded.sub_tasks.len() {
+        return Err(format!(
+            "number of sub-tasks at {}: saved {}, loaded {}",
+            path,
+            saved.sub_tasks.len(),
+            loaded.sub_tasks.len()
+        ));
+    }
+    for (i, (a, b)) in saved
+        .sub_tasks
+        .iter()
+        .zip(loaded.sub_tasks.iter())
+        .enumerate()
+    {
+        let parent = b.borrow().parent.upgrade();
+        if !parent.is_some_and(|parent| std::ptr::eq(parent.as_ptr(), loaded)) {
+            return Err(format!("parent of {}/{} not set", path, i));
+        }
+        compare(&a.borrow(), &b.borrow(), &format!("{}/{}", path, i))?;
+    }
+    Ok(())
+}
+
+/// Save a tree, load it back and compare, and save it again, which must
+/// give the same text.
+fn round_trip(root: &Rc<RefCell<ToDo>>) -> Result<(), String> {
+    let saved = root.borrow().save_string();
+    let loaded = parse_list(&saved).map_err(|err| format!("unable to load: {}", err))?;
+    compare(&root.borrow(), &loaded.borrow(), "")?;
+    let resaved = loaded.borrow().save_string();
+    if resaved != saved {
+        return Err(String::from("saving again changed the save file"));
+    }
+    Ok(())
+}
+
+/// Read all the tasks left unread under a task, a few levels at a time.
+fn read_all(task: &Rc<RefCell<ToDo>>, depth: usize) -> Result<(), String> {
+    read_unread(task, depth).map_err(|err| format!("unable to read: {}", err))?;
+    let sub_tasks = task.borrow().sub_tasks.clone();
+    sub_tasks
+        .iter()
+        .try_for_each(|sub_task| read_all(sub_task, depth))
+}
+
+/// Load a tree only to a depth, which must save unchanged, and compare it
+/// with the whole tree once the rest has been read.
+fn lazy_round_trip(root: &Rc<RefCell<ToDo>>, depth: usize) -> Result<(), String> {
+    let saved = root.borrow().save_string();
+    let loaded =
+        parse_list_to_depth(&saved, depth).map_err(|err| format!("unable to load: {}", err))?;
+    if loaded.borrow().save_string() != saved {
+        return Err(String::from("saving before reading changed the save file"));
+    }
+    read_all(&loaded, depth)?;
+    compare(&root.borrow(), &loaded.borrow(), "")?;
+    Ok(())
+}
+
+proptest! {
+    #[test]
+    fn save_then_load_is_identity(root in tree()) {
+        let result = round_trip(&root);
+        prop_assert!(
+            result.is_ok(),
+            "{}\n\nSave file:\n{}",
+            result.unwrap_err(),
+            root.borrow().save_string()
+        );
+    }
+
+    #[test]
+    fn lazy_load_then_read_is_identity(root in tree(), depth in 2..5usize) {
+        let result = lazy_round_trip(&root, depth);
+        prop_assert!(
+            result.is_ok(),
+            "{}\n\nSave file:\n{}",
+            result.unwrap_err(),
+            root.borrow().save_string()
+        );
+    }
+}
+
+#[test]
+fn generated_trees_cover_the_format() {
+    // Make sure the generator isn't quietly leaving anything out
+    let mut runner = TestRunner::deterministic();
+    let strategy = tree();
+    let mut depth = 0;
+    let mut fields = [false; 4];
+    for _ in 0..CASES {
+        let root = strategy.new_tree(&mut runner).unwrap().current();
+        let saved = root.borrow().save_string();
+        for line in saved.lines() {
+            let text = line.trim_start();
+            depth = depth.max((line.len() - text.len()) / 4);
+            fields[0] |= text.starts_with("| ");
+            fields[1] |= text.starts_with("> ");
+            fields[2] |= text.contains(" blocked-by:") && text.contains(" repeat:");
+            fields[3] |= text.contains(" \\url:");
+        }
+    }
+    assert!(depth >= 10, "deepest nesting was only {}", depth);
+    assert!(fields.iter().all(|&found| found));
+}
+
+#[test]
+fn field_words_stay_in_the_description() {
+    let titles = [
+        "read url:spec",
+        "ask id:3 about due:2024-03-01 and blocked-by:1",
+        "\\url:x and \\\\estimate:1h",
+        "no:field here\\ \\",
+    ];
+    for title in titles {
+        let mut todo = ToDo::new(title, Weak::new());
+        todo.id = Some(7);
+        let line = todo.to_string();
+        let loaded = ToDo::from_string(line.trim_end(), Weak::new());
+        assert_eq!(loaded.task, title, "saved as {}", line);
+        assert_eq!(loaded.id, Some(7));
+        assert_eq!(loaded.due, None);
+        assert_eq!(loaded.to_string(), line);
+    }
+}
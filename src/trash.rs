@@ -0,0 +1,187 @@
+/// Trash of deleted tasks, kept in a file beside the save file for a number
+/// of days so that they can be put back where they were. Each task is saved
+/// in the save file format under a line of tab-separated fields: "deleted",
+/// when it was deleted, its position and the titles of the tasks above it.
+use crate::todo::ToDo;
+use chrono::NaiveDateTime;
+use log::warn;
+use std::cell::RefCell;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::rc::{Rc, Weak};
+
+/// Start of the line before each task in the trash file.
+const HEADER: &str = "deleted";
+
+/// Format of the time a task was deleted.
+const TIME_FORMAT: &str = "%Y-%m-%dT%H:%M";
+
+/// Task deleted from the list, with where it was.
+pub struct Trashed {
+    pub task: Rc<RefCell<ToDo>>,
+    pub deleted: NaiveDateTime,
+    /// Position among the sub-tasks of the task it was under.
+    pub index: usize,
+    /// Titles of the tasks above it, from the top level down.
+    pub path: Vec<String>,
+}
+
+impl Trashed {
+    /// Trash a task removed from under a parent, at a position.
+    pub fn new(
+        task: Rc<RefCell<ToDo>>,
+        parent: &Rc<RefCell<ToDo>>,
+        index: usize,
+        deleted: NaiveDateTime,
+    ) -> Trashed {
+        let mut path = Vec::new();
+        let mut above = Some(Rc::clone(parent));
+        while let Some(todo) = above {
+            let parent = todo.borrow().parent.upgrade();
+            if parent.is_some() {
+                path.insert(0, todo.borrow().task.clone());
+            }
+            above = parent;
+        }
+        task.borrow_mut().parent = Weak::new();
+        Trashed {
+            task,
+            deleted,
+            index,
+            path,
+        }
+    }
+
+    /// Where the task was, e.g. "Plan the garden: Build the trellis", or
+    /// "top level".
+    pub fn place(&self) -> String {
+        match self.path.is_empty() {
+            true => String::from("top level"),
+            false => self.path.join(": "),
+        }
+    }
+}
+
+/// Location of the trash of a save file, e.g. save.trash.txt beside
+/// save.txt.
+pub fn path(save_file: &Path) -> PathBuf {
+    let stem = save_file.file_stem().map_or(String::from("save"), |stem| {
+        stem.to_string_lossy().into_owned()
+    });
+    let name = match save_file.extension() {
+        Some(extension) => format!("{}.trash.{}", stem, extension.to_string_lossy()),
+        None => format!("{}.trash", stem),
+    };
+    save_file.with_file_name(name)
+}
+
+/// Read a trash file, which is empty if nothing has been deleted yet.
+/// Tasks which can't be read are left out.
+pub fn load(path: &Path) -> io::Result<Vec<Trashed>> {
+    match fs::read_to_string(path) {
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Vec::new()),
+        result => Ok(parse(&result?)),
+    }
+}
+
+/// Read the tasks in the trash from text in the trash file format, leaving
+/// out any which can't be read.
+pub fn parse(text: &str) -> Vec<Trashed> {
+    let mut trash = Vec::new();
+    let mut entries: Vec<(&str, String)> = Vec::new();
+    for line in text.lines() {
+        match line.strip_prefix(HEADER).and_then(|l| l.strip_prefix('\t')) {
+            Some(header) => entries.push((header, String::new())),
+            None => match entries.last_mut() {
+                Some((_, body)) => {
+                    body.push_str(line);
+                    body.push('\n');
+                }
+                None => warn!("Line before any deleted task in the trash: {}", line),
+            },
+        }
+    }
+    for (header, body) in entries {
+        let mut fields = header.split('\t');
+        let deleted = fields
+            .next()
+            .and_then(|time| NaiveDateTime::parse_from_str(time, TIME_FORMAT).ok());
+        let index = fields.next().and_then(|index| index.parse().ok());
+        let task = crate::parse_list(&body)
+            .ok()
+            .and_then(|root| root.borrow().sub_tasks.first().cloned());
+        match (deleted, index, task) {
+            (Some(deleted), Some(index), Some(task)) => {
+                task.borrow_mut().parent = Weak::new();
+                let path = fields.map(String::from).collect();
+                trash.push(Trashed {
+                    task,
+                    deleted,
+                    index,
+                    path,
+                });
+            }
+            _ => warn!("Unable to read deleted task in the trash: {}", header),
+        }
+    }
+    trash
+}
+
+/// Write the trash to a file, replacing what was there, or removing it
+/// once the trash is empty.
+pub fn save(path: &Path, trash: &[Trashed]) -> io::Result<()> {
+    if trash.is_empty() {
+        return match fs::remove_file(path) {
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(()),
+            result => result,
+        };
+    }
+    fs::write(path, format(trash))
+}
+
+/// The tasks in the trash as text in the trash file format.
+pub fn format(trash: &[Trashed]) -> String {
+    let mut buffer = String::new();
+    for trashed in trash.iter() {
+        let mut fields = vec![
+            String::from(HEADER),
+            trashed.deleted.format(TIME_FORMAT).to_string(),
+            trashed.index.to_string(),
+        ];
+        fields.extend(trashed.path.iter().cloned());
+        buffer.push_str(&fields.join("\t"));
+        buffer.push('\n');
+
+        let mut holder = ToDo::new("", Weak::new());
+        holder.sub_tasks.push(Rc::clone(&trashed.task));
+        buffer.push_str(&holder.save_string());
+    }
+    buffer
+}
+
+/// Empty the trash of tasks deleted before a time, returning how many.
+pub fn expire(trash: &mut Vec<Trashed>, before: NaiveDateTime) -> usize {
+    let count = trash.len();
+    trash.retain(|trashed| trashed.deleted >= before);
+    count - trash.len()
+}
+
+/// Task to put a deleted task back under: the one with the same titles
+/// above it as it had, or as far along that path as is still there.
+pub fn parent(trashed: &Trashed, root: &Rc<RefCell<ToDo>>) -> Rc<RefCell<ToDo>> {
+    let mut parent = Rc::clone(root);
+    for title in trashed.path.iter() {
+        let next = parent
+            .borrow()
+            .sub_tasks
+            .iter()
+            .find(|task| task.borrow().task == *title)
+            .cloned();
+        match next {
+            Some(next) => parent = next,
+            None => break,
+        }
+    }
+    parent
+}
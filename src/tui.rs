@@ -1,24 +1,136 @@
 /// Terminal user interface (TUI) functionality, with ncurses-like API,
 /// built on top of the termion crate.
-use crate::config::Config;
-use log::{error, warn};
-use std::io::{Stdin, Stdout, Write};
-use termion::event::Key;
-use termion::input::{Keys, TermRead};
+use crate::config::{ColourChoice, Config};
+use crate::error::Error;
+use crate::layout;
+use log::{error, info, warn};
+use std::collections::VecDeque;
+use std::env;
+use std::io::{self, Stdout, Write};
+use std::os::unix::io::RawFd;
+use std::time::Duration;
+use termion::event::{self, Event, Key};
 use termion::raw::{IntoRawMode, RawTerminal};
 use termion::{clear, color, cursor, style};
-use unicode_width::UnicodeWidthStr;
+use unicode_width::UnicodeWidthChar;
+
+/// Where a window is drawn: the terminal, or a screen kept in memory.
+enum Output {
+    /// Stdout, with terminal in raw-mode (no input line buffering, no echo).
+    Terminal(RawTerminal<Stdout>),
+    Headless(Screen),
+}
+
+impl Write for Output {
+    /// Write to the terminal. Escape sequences written to a headless
+    /// screen, e.g. to hide the cursor, are discarded.
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Output::Terminal(stdout) => stdout.write(buf),
+            Output::Headless(_) => Ok(buf.len()),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Output::Terminal(stdout) => stdout.flush(),
+            Output::Headless(_) => Ok(()),
+        }
+    }
+}
+
+/// Grid of characters drawn on a headless window, as a terminal of the same
+/// size would show them.
+struct Screen {
+    /// Rows of cells, with None in the cell after a wide character.
+    rows: Vec<Vec<Option<char>>>,
+}
+
+impl Screen {
+    fn new(height: usize, width: usize) -> Screen {
+        Screen {
+            rows: vec![vec![Some(' '); width]; height],
+        }
+    }
+
+    fn clear(&mut self) {
+        for row in self.rows.iter_mut() {
+            row.iter_mut().for_each(|cell| *cell = Some(' '));
+        }
+    }
+
+    /// Print text at row y, column x, cutting it off at the edge of the
+    /// screen.
+    fn print(&mut self, y: usize, mut x: usize, text: &str) {
+        let row = match self.rows.get_mut(y) {
+            Some(row) => row,
+            None => return,
+        };
+        for ch in text.chars() {
+            let width = ch.width().unwrap_or(0);
+            if width == 0 {
+                continue;
+            }
+            if x + width > row.len() {
+                break;
+            }
+            // Overwriting half of a wide character blanks the other half
+            if row[x].is_none() && x > 0 {
+                row[x - 1] = Some(' ');
+            }
+            if row.get(x + width) == Some(&None) {
+                row[x + width] = Some(' ');
+            }
+            row[x] = Some(ch);
+            for cell in row[x + 1..x + width].iter_mut() {
+                *cell = None;
+            }
+            x += width;
+        }
+    }
+
+    /// Text of the screen, one line per row, without trailing spaces.
+    fn contents(&self) -> String {
+        let mut contents = String::new();
+        for row in self.rows.iter() {
+            let line: String = row.iter().flatten().collect();
+            contents.push_str(line.trim_end());
+            contents.push('\n');
+        }
+        contents
+    }
+}
 
 /// A wrapper around the terminal for creating a window.
 pub struct Window<'a> {
-    /// Key input from Stdin.
-    stdin: Keys<Stdin>,
-    /// Stdout, with terminal in raw-mode (no input line buffering, no echo).
-    stdout: RawTerminal<Stdout>,
+    /// Bytes read from stdin which are yet to be parsed into keys.
+    input: VecDeque<u8>,
+    stdout: Output,
     /// Yat configuration.
     pub config: Config<'a>,
+    /// Whether colour and style escapes are written to the terminal.
+    colour: bool,
+    /// Text pasted into the terminal, yet to be taken.
+    paste: Option<String>,
 }
 
+/// Key returned when text is pasted into the terminal, which can then be
+/// taken with take_paste.
+pub const PASTE: Key = Key::Null;
+
+/// Escape sequences around text pasted into a terminal in bracketed paste
+/// mode.
+const PASTE_START: &[u8] = b"[200~";
+const PASTE_END: &[u8] = b"\x1b[201~";
+
+/// Most repeats of a key taken at once by take_repeats, so that even a
+/// long burst moves a bounded distance before the screen is redrawn.
+const MAX_REPEATS: usize = 50;
+
+/// Milliseconds to wait for the rest of an escape sequence or character
+/// which has only partly been read.
+const ESCAPE_WAIT: i32 = 50;
+
 impl<'a> Drop for Window<'a> {
     /// Ensure the terminal is reset if the Window is dropped.
     fn drop(&mut self) {
@@ -29,23 +141,66 @@ impl<'a> Drop for Window<'a> {
 
 impl<'a> Window<'a> {
     /// Create a new Window, using terminal's stdin and stdout.
-    pub fn new(stdin: Stdin, stdout: Stdout, config: Config<'a>) -> Result<Window<'a>, ()> {
+    pub fn new(stdout: Stdout, mut config: Config<'a>) -> Result<Window<'a>, Error> {
+        if config.ascii {
+            info!("Using ASCII characters only.");
+            config.use_ascii();
+        }
+        if config.screen_reader {
+            info!("Using screen-reader friendly display.");
+            config.use_plain();
+        }
         let raw = match stdout.into_raw_mode() {
             Ok(out) => out,
-            Err(_) => {
+            Err(err) => {
                 error!("Unable to set terminal to raw mode.");
-                return Err(());
+                return Err(Error::Terminal(err));
             }
         };
-        Ok(Window {
-            stdin: stdin.keys(),
-            stdout: raw,
+        let mut window = Window {
+            input: VecDeque::new(),
+            stdout: Output::Terminal(raw),
             config,
-        })
+            colour: colour_enabled(),
+            paste: None,
+        };
+        window.bracketed_paste(true);
+        Ok(window)
+    }
+
+    /// Create a Window drawn in memory rather than on the terminal, of a
+    /// fixed size and without colour, e.g. to test what is displayed. It
+    /// only takes input given to it with feed.
+    pub fn headless(config: Config<'a>, dimensions: (usize, usize)) -> Window<'a> {
+        let (height, width) = dimensions;
+        Window {
+            input: VecDeque::new(),
+            stdout: Output::Headless(Screen::new(height, width)),
+            config,
+            colour: false,
+            paste: None,
+        }
+    }
+
+    /// Queue input to be read as keys, as if typed into the terminal.
+    #[cfg(test)]
+    pub fn feed(&mut self, input: &[u8]) {
+        self.input.extend(input);
+    }
+
+    /// Text displayed on a headless Window, one line per row.
+    pub fn contents(&self) -> Option<String> {
+        match &self.stdout {
+            Output::Terminal(_) => None,
+            Output::Headless(screen) => Some(screen.contents()),
+        }
     }
 
     /// Find the terminal's dimensions.
     pub fn get_max_yx(&self) -> (usize, usize) {
+        if let Output::Headless(screen) = &self.stdout {
+            return (screen.rows.len(), screen.rows.first().map_or(0, Vec::len));
+        }
         let (y, x) = termion::terminal_size().unwrap_or_else(|err| {
             warn!("Unable to determine terminal size: {}.", err);
             (0, 0)
@@ -67,6 +222,28 @@ impl<'a> Window<'a> {
         });
     }
 
+    /// Turn bracketed paste mode on or off, so that pasted text can be told
+    /// apart from typing.
+    fn bracketed_paste(&mut self, on: bool) {
+        let mode = match on {
+            true => "\x1b[?2004h",
+            false => "\x1b[?2004l",
+        };
+        write!(self.stdout, "{}", mode).unwrap_or_else(|err| {
+            warn!("Unable to set bracketed paste mode: {}", err);
+        });
+    }
+
+    /// Copy text to the clipboard, through the terminal (with an OSC 52
+    /// escape sequence), so that it works over ssh. Terminals which don't
+    /// support it ignore it.
+    pub fn copy_to_clipboard(&mut self, text: &str) {
+        write!(self.stdout, "\x1b]52;c;{}\x07", base64(text.as_bytes())).unwrap_or_else(|err| {
+            warn!("Unable to copy to clipboard: {}", err);
+        });
+        self.refresh();
+    }
+
     /// Flush stdout buffer to terminal.
     pub fn refresh(&mut self) {
         self.stdout.flush().unwrap_or_else(|err| {
@@ -76,12 +253,225 @@ impl<'a> Window<'a> {
 
     /// Return the key input from stdin.
     pub fn getch(&mut self) -> Option<Key> {
-        match self.stdin.next() {
-            Some(Ok(key)) => Some(key),
+        loop {
+            if let Some(key) = self.next_key() {
+                return Some(key);
+            }
+            if !self.read_input(-1) {
+                return None;
+            }
+        }
+    }
+
+    /// Return the key input from stdin, or None if no key is pressed
+    /// before the timeout, so the caller can update the display.
+    pub fn getch_timeout(&mut self, timeout: Duration) -> Option<Key> {
+        if let Some(key) = self.next_key() {
+            return Some(key);
+        }
+        if self.read_input(timeout.as_millis() as i32) {
+            self.next_key()
+        } else {
+            None
+        }
+    }
+
+    /// Return the key input from stdin, or None if no key is pressed before
+    /// the timeout (if any) or something can be read from another file
+    /// first, e.g. a socket, so the caller can deal with it.
+    pub fn getch_or_wake(&mut self, timeout: Option<Duration>, wake: RawFd) -> Option<Key> {
+        if let Some(key) = self.next_key() {
+            return Some(key);
+        }
+        let timeout = timeout.map_or(-1, |timeout| timeout.as_millis() as i32);
+        loop {
+            if !self.read_input_or_wake(timeout, Some(wake)) {
+                return None;
+            }
+            // Only part of a key may have been read
+            if let Some(key) = self.next_key() {
+                return Some(key);
+            }
+        }
+    }
+
+    /// Take any more of a key already waiting to be read straight after
+    /// it, e.g. from the key being held down over a slow connection,
+    /// returning how many there were, up to MAX_REPEATS.
+    pub fn take_repeats(&mut self, key: Key) -> usize {
+        while self.read_input(0) {}
+        let mut repeats = 0;
+        let mut taken = 0;
+        while repeats < MAX_REPEATS {
+            match self.peek_key(taken) {
+                Some((next, length)) if next == key => {
+                    repeats += 1;
+                    taken += length;
+                }
+                _ => break,
+            }
+        }
+        self.input.drain(..taken);
+        repeats
+    }
+
+    /// Parse the key starting at a position in the input buffer, without
+    /// taking it, returning it with how many bytes it takes up. Escape
+    /// sequences which aren't complete yet, or start a paste, are left to
+    /// next_key.
+    fn peek_key(&self, start: usize) -> Option<(Key, usize)> {
+        let first = *self.input.get(start)?;
+        let rest = self.input.range(start + 1..);
+        if first == b'\x1b'
+            && (rest.len() == 0
+                || rest.clone().take(PASTE_START.len()).eq(PASTE_START)
+                || unfinished_sequence(rest.clone().copied()))
+        {
+            return None;
+        }
+        let mut length = 1;
+        let parsed = {
+            let mut iter = rest.map(|&byte| {
+                length += 1;
+                Ok(byte)
+            });
+            event::parse_event(first, &mut iter)
+        };
+        match parsed {
+            Ok(Event::Key(key)) => Some((key, length)),
             _ => None,
         }
     }
 
+    /// Wait at most timeout milliseconds (or indefinitely if negative) for
+    /// input, and add it to the input buffer. Returns false if nothing was
+    /// read.
+    fn read_input(&mut self, timeout: i32) -> bool {
+        self.read_input_or_wake(timeout, None)
+    }
+
+    /// As read_input, but also stop waiting, reading nothing, when
+    /// something can be read from the file wake.
+    fn read_input_or_wake(&mut self, timeout: i32, wake: Option<RawFd>) -> bool {
+        if let Output::Headless(_) = self.stdout {
+            return false;
+        }
+        let poll = |fd| libc::pollfd {
+            fd,
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        let mut fds = [poll(libc::STDIN_FILENO), poll(wake.unwrap_or(-1))];
+        let count = if wake.is_some() { 2 } else { 1 };
+        if unsafe { libc::poll(fds.as_mut_ptr(), count, timeout) } <= 0 {
+            return false;
+        }
+        if fds[0].revents & libc::POLLIN == 0 {
+            return false;
+        }
+        let mut buf = [0u8; 1024];
+        let n = unsafe { libc::read(libc::STDIN_FILENO, buf.as_mut_ptr() as *mut _, buf.len()) };
+        if n <= 0 {
+            return false;
+        }
+        self.input.extend(&buf[..n as usize]);
+        true
+    }
+
+    /// Text last pasted into the terminal, if not already taken.
+    pub fn take_paste(&mut self) -> Option<String> {
+        self.paste.take()
+    }
+
+    /// Read pasted text, up to the end of the paste. A long paste may
+    /// arrive in pieces, so wait a while for the rest of it.
+    fn read_paste(&mut self) -> String {
+        let mut pasted = Vec::new();
+        loop {
+            let end = (0..self.input.len()).find(|&i| {
+                self.input
+                    .range(i..)
+                    .take(PASTE_END.len())
+                    .eq(PASTE_END.iter())
+            });
+            if let Some(end) = end {
+                pasted.extend(self.input.drain(..end));
+                self.input.drain(..PASTE_END.len());
+                break;
+            }
+            // Keep the start of a partly read end sequence for next time
+            let keep = self.input.len().min(PASTE_END.len() - 1);
+            pasted.extend(self.input.drain(..self.input.len() - keep));
+            if !self.read_input(1000) {
+                pasted.extend(self.input.drain(..));
+                break;
+            }
+        }
+        String::from_utf8_lossy(&pasted)
+            .replace("\r\n", "\n")
+            .replace('\r', "\n")
+    }
+
+    /// Parse the next key from the input buffer, skipping other events.
+    fn next_key(&mut self) -> Option<Key> {
+        while let Some(first) = self.input.pop_front() {
+            // A lone escape byte is the escape key, unless the rest of a
+            // sequence follows shortly after, e.g. split over ssh
+            if first == b'\x1b' && self.input.is_empty() && !self.read_input(ESCAPE_WAIT) {
+                return Some(Key::Esc);
+            }
+            if first == b'\x1b' && self.input.iter().take(PASTE_START.len()).eq(PASTE_START) {
+                self.input.drain(..PASTE_START.len());
+                self.paste = Some(self.read_paste());
+                return Some(PASTE);
+            }
+
+            let mut consumed = 0;
+            let mut exhausted = false;
+            let parsed = if first == b'\x1b' && unfinished_sequence(self.input.iter().copied()) {
+                // termion panics on these, rather than returning an error
+                consumed = self.input.len();
+                exhausted = true;
+                None
+            } else {
+                let input = &self.input;
+                let mut iter = std::iter::from_fn(|| {
+                    let byte = input.get(consumed).copied();
+                    match byte {
+                        Some(_) => consumed += 1,
+                        None => exhausted = true,
+                    }
+                    byte.map(Ok)
+                });
+                event::parse_event(first, &mut iter).ok()
+            };
+            match parsed {
+                Some(event) => {
+                    self.input.drain(..consumed);
+                    if let Event::Key(key) = event {
+                        return Some(key);
+                    }
+                }
+                // Only part of a sequence has been read, so wait for the rest
+                None if exhausted && self.read_input(ESCAPE_WAIT) => self.input.push_front(first),
+                None if exhausted => {
+                    self.input.drain(..consumed);
+                }
+                // Bytes after an invalid one are read as keys of their own
+                None => (),
+            }
+        }
+        None
+    }
+
+    /// Ring the terminal bell.
+    pub fn bell(&mut self) {
+        write!(self.stdout, "\x07").unwrap_or_else(|err| {
+            warn!("Unable to ring bell: {}", err);
+        });
+        self.refresh();
+    }
+
     /// Move the cursor to position at row y, column x (zero-indexed).
     pub fn mv(&mut self, y: usize, x: usize) {
         write!(self.stdout, "{}", cursor::Goto(1 + x as u16, 1 + y as u16)).unwrap_or_else(|err| {
@@ -91,6 +481,10 @@ impl<'a> Window<'a> {
 
     /// Add colour to subsequent printed text.
     pub fn colour_on(&mut self, fg: usize, bg: usize) {
+        if !self.colour {
+            return;
+        }
+
         let fgcol = match fg {
             0 => self.config.colour0,
             1 => self.config.colour1,
@@ -122,8 +516,38 @@ impl<'a> Window<'a> {
         });
     }
 
+    /// Turn on a foreground colour chosen in configuration.
+    pub fn colour_choice(&mut self, choice: &ColourChoice) {
+        match choice {
+            ColourChoice::Slot(n) => self.colour_on(*n, 8),
+            ColourChoice::Rgb(rgb) => self.colour_fg(rgb),
+        }
+    }
+
+    /// Add foreground colour to subsequent printed text, on the default
+    /// background.
+    pub fn colour_fg(&mut self, fg: &dyn color::Color) {
+        if !self.colour {
+            return;
+        }
+
+        write!(
+            self.stdout,
+            "{}{}",
+            color::Fg(fg),
+            color::Bg(self.config.colourbg)
+        )
+        .unwrap_or_else(|err| {
+            warn!("Unable to turn colour on: {}", err);
+        });
+    }
+
     /// Reset colours to default foreground and background.
     pub fn colour_off(&mut self) {
+        if !self.colour {
+            return;
+        }
+
         write!(
             self.stdout,
             "{}{}",
@@ -137,6 +561,10 @@ impl<'a> Window<'a> {
 
     /// Reset colours to terminal defaults.
     pub fn colour_reset(&mut self) {
+        if !self.colour {
+            return;
+        }
+
         write!(
             self.stdout,
             "{}{}",
@@ -148,8 +576,68 @@ impl<'a> Window<'a> {
         });
     }
 
+    /// Add foreground and background colours to subsequent printed text.
+    pub fn colour_pair(&mut self, fg: &dyn color::Color, bg: &dyn color::Color) {
+        if !self.colour {
+            return;
+        }
+
+        write!(self.stdout, "{}{}", color::Fg(fg), color::Bg(bg)).unwrap_or_else(|err| {
+            warn!("Unable to turn colour on: {}", err);
+        });
+    }
+
+    /// Turn bold text on or off.
+    pub fn bold(&mut self, on: bool) {
+        if !self.colour {
+            return;
+        }
+
+        let result = match on {
+            true => write!(self.stdout, "{}", style::Bold),
+            false => write!(self.stdout, "{}", style::NoBold),
+        };
+        result.unwrap_or_else(|err| {
+            warn!("Unable to set bold text: {}", err);
+        });
+    }
+
+    /// Turn dimmed (faint) text on or off, where the terminal supports it.
+    pub fn dim(&mut self, on: bool) {
+        if !self.colour {
+            return;
+        }
+
+        let result = match on {
+            true => write!(self.stdout, "{}", style::Faint),
+            false => write!(self.stdout, "{}", style::NoFaint),
+        };
+        result.unwrap_or_else(|err| {
+            warn!("Unable to dim text: {}", err);
+        });
+    }
+
+    /// Turn underlined text on or off.
+    pub fn underline(&mut self, on: bool) {
+        if !self.colour {
+            return;
+        }
+
+        let result = match on {
+            true => write!(self.stdout, "{}", style::Underline),
+            false => write!(self.stdout, "{}", style::NoUnderline),
+        };
+        result.unwrap_or_else(|err| {
+            warn!("Unable to underline text: {}", err);
+        });
+    }
+
     /// Print text at row y, column x (zero-indexed).
     pub fn mvprintw(&mut self, y: usize, x: usize, text: &str) {
+        if let Output::Headless(screen) = &mut self.stdout {
+            screen.print(y, x, text);
+            return;
+        }
         write!(
             self.stdout,
             "{}{}",
@@ -161,24 +649,11 @@ impl<'a> Window<'a> {
         });
     }
 
-    /// Print text at row y, column x (zero-indexed), truncated to ensure
-    /// the text does not spill beyond width.
+    /// Print text at row y, column x (zero-indexed), cut off with a marker
+    /// so that it does not spill beyond width.
     pub fn wrap_print(&mut self, y: usize, x: usize, width: usize, text: &str) {
-        let len = UnicodeWidthStr::width(text); // displayed width
-        let mut end = text.len();
-        if len > width - 3 {
-            self.mvprintw(y, x + width - 3, "...");
-            let mut n = (len - (width - 3)) as isize;
-            let mut m = end;
-            while n > 0 {
-                end -= 1;
-                if text.is_char_boundary(end) {
-                    n -= UnicodeWidthStr::width(&text[end..m]) as isize;
-                    m = end;
-                }
-            }
-        }
-        self.mvprintw(y, x, &text[..end]);
+        let text = layout::truncate(text, width, self.config.ellipsis, self.config.ellipsis_at);
+        self.mvprintw(y, x, &text);
     }
 
     /// Print a rectangular border.
@@ -219,23 +694,172 @@ impl<'a> Window<'a> {
 
     /// Clear stdout.
     pub fn clear(&mut self) {
+        if let Output::Headless(screen) = &mut self.stdout {
+            screen.clear();
+            return;
+        }
         write!(self.stdout, "{}", clear::All).unwrap_or_else(|err| {
             warn!("Unable to clear stdout: {}", err);
         });
     }
 
+    /// Temporarily hand the terminal back, e.g. to run an external program.
+    pub fn suspend(&mut self) {
+        self.endwin();
+        self.show_cursor();
+        self.refresh();
+        if let Output::Terminal(stdout) = &self.stdout {
+            stdout.suspend_raw_mode().unwrap_or_else(|err| {
+                warn!("Unable to suspend raw mode: {}", err);
+            });
+        }
+    }
+
+    /// Take back control of the terminal after a call to suspend.
+    pub fn resume(&mut self) {
+        if let Output::Terminal(stdout) = &self.stdout {
+            stdout.activate_raw_mode().unwrap_or_else(|err| {
+                warn!("Unable to activate raw mode: {}", err);
+            });
+        }
+        self.bracketed_paste(true);
+        self.colour_off();
+        self.clear();
+    }
+
     /// Reset stdout.
     pub fn endwin(&mut self) {
+        self.bracketed_paste(false);
         self.colour_reset();
-        write!(
-            self.stdout,
-            "{}{}{}",
-            clear::All,
-            style::Reset,
-            cursor::Goto(1, 1)
-        )
-        .unwrap_or_else(|err| {
+        if self.colour {
+            write!(self.stdout, "{}", style::Reset).unwrap_or_else(|err| {
+                warn!("Unable to reset style: {}", err);
+            });
+        }
+        if let Output::Headless(screen) = &mut self.stdout {
+            screen.clear();
+        }
+        write!(self.stdout, "{}{}", clear::All, cursor::Goto(1, 1)).unwrap_or_else(|err| {
             warn!("Unable to endwin: {}", err);
         });
     }
 }
+
+/// Whether input following an escape byte is only the start of a control
+/// sequence, the rest of which is yet to be read.
+fn unfinished_sequence(mut bytes: impl Iterator<Item = u8>) -> bool {
+    if bytes.next() != Some(b'[') {
+        return false;
+    }
+    match bytes.next() {
+        None => true,
+        Some(b'[') => bytes.next().is_none(),
+        // Mouse reports
+        Some(b'M') => bytes.count() < 3,
+        Some(b'<') => !bytes.any(|byte| byte == b'm' || byte == b'M'),
+        // Numbered sequences run to a final byte
+        Some(b'0'..=b'9') => !bytes.any(|byte| (64..=126).contains(&byte)),
+        Some(_) => false,
+    }
+}
+
+/// Encode bytes in base64, as used by OSC 52.
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, &byte)| n | u32::from(byte) << (16 - 8 * i));
+        for i in 0..4 {
+            match i <= chunk.len() {
+                true => encoded.push(ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize] as char),
+                false => encoded.push('='),
+            }
+        }
+    }
+    encoded
+}
+
+/// Check whether the environment allows colour output, following the
+/// NO_COLOR convention (https://no-color.org) and disabling colour on dumb
+/// terminals.
+fn colour_enabled() -> bool {
+    if let Some(val) = env::var_os("NO_COLOR") {
+        if !val.is_empty() {
+            info!("NO_COLOR set: disabling colour.");
+            return false;
+        }
+    }
+    if let Ok(term) = env::var("TERM") {
+        if term == "dumb" {
+            info!("Dumb terminal: disabling colour.");
+            return false;
+        }
+    }
+    true
+}
+
+/// Ask the terminal for its background colour (using the OSC 11 escape
+/// sequence) and determine whether it is light, waiting at most 100ms for
+/// a reply.
+pub fn light_background() -> Option<bool> {
+    if !termion::is_tty(&io::stdin()) {
+        return None;
+    }
+    let mut raw = io::stdout().into_raw_mode().ok()?;
+    write!(raw, "\x1b]11;?\x07").ok()?;
+    raw.flush().ok()?;
+
+    // Reply is terminated by BEL or ST
+    let mut reply = Vec::new();
+    let mut buf = [0u8; 64];
+    while !reply.ends_with(b"\x07") && !reply.ends_with(b"\x1b\\") {
+        let mut fds = libc::pollfd {
+            fd: libc::STDIN_FILENO,
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        if unsafe { libc::poll(&mut fds, 1, 100) } <= 0 {
+            break;
+        }
+        let n = unsafe { libc::read(libc::STDIN_FILENO, buf.as_mut_ptr() as *mut _, buf.len()) };
+        if n <= 0 {
+            break;
+        }
+        reply.extend_from_slice(&buf[..n as usize]);
+    }
+
+    let reply = String::from_utf8_lossy(&reply);
+    let light =
+        parse_osc_colour(&reply).map(|(r, g, b)| 0.2126 * r + 0.7152 * g + 0.0722 * b > 0.5);
+    if let Some(light) = light {
+        info!(
+            "Detected {} terminal background.",
+            if light { "light" } else { "dark" }
+        );
+    }
+    light
+}
+
+/// Parse a colour reply of the form "rgb:rrrr/gggg/bbbb" into (r, g, b)
+/// values between 0 and 1.
+fn parse_osc_colour(reply: &str) -> Option<(f64, f64, f64)> {
+    let start = reply.find("rgb:")? + 4;
+    let end = reply[start..]
+        .find(|c: char| !c.is_ascii_hexdigit() && c != '/')
+        .map_or(reply.len(), |i| start + i);
+
+    let mut values = reply[start..end].split('/').map(|hex| {
+        let max = 16f64.powi(hex.len() as i32) - 1.0;
+        u32::from_str_radix(hex, 16).ok().map(|x| x as f64 / max)
+    });
+    let r = values.next()??;
+    let g = values.next()??;
+    let b = values.next()??;
+    Some((r, g, b))
+}
+
+#[cfg(test)]
+mod tests;
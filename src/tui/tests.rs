@@ -0,0 +1,76 @@
+/// Tests of reading keys from the bytes typed into the terminal.
+use super::{Window, MAX_REPEATS};
+use crate::config::Config;
+use termion::event::Key;
+
+/// The keys read from some input, until it runs out.
+fn keys(input: &[u8]) -> Vec<Key> {
+    let mut window = Window::headless(Config::default(), (24, 80));
+    window.feed(input);
+    std::iter::from_fn(|| window.getch()).collect()
+}
+
+/// How many keys are left to read from a window.
+fn keys_left(window: &mut Window) -> usize {
+    std::iter::from_fn(|| window.getch()).count()
+}
+
+#[test]
+fn escape_sequences() {
+    assert_eq!(keys(b"\x1b"), [Key::Esc]);
+    assert_eq!(keys(b"\x1b[A\x1b[B"), [Key::Up, Key::Down]);
+    assert_eq!(keys(b"j\x1bk"), [Key::Char('j'), Key::Alt('k')]);
+    assert_eq!(keys(b"\xc3\xa9"), [Key::Char('\u{e9}')]);
+}
+
+#[test]
+fn unfinished_sequences_are_dropped() {
+    assert_eq!(keys(b"\x1b["), []);
+    assert_eq!(keys(b"\x1b[1;"), []);
+    assert_eq!(keys(b"\x1b[<0;12"), []);
+    assert_eq!(keys(b"\x1b[M "), []);
+    assert_eq!(keys(b"j\xc3"), [Key::Char('j')]);
+}
+
+#[test]
+fn bytes_after_invalid_sequences_are_kept() {
+    assert_eq!(
+        keys(b"\x1bOxj"),
+        [Key::Char('O'), Key::Char('x'), Key::Char('j')]
+    );
+    assert_eq!(
+        keys(b"\xc3abc"),
+        [Key::Char('a'), Key::Char('b'), Key::Char('c')]
+    );
+}
+
+#[test]
+fn repeats_taken_after_a_key() {
+    let mut window = Window::headless(Config::default(), (24, 80));
+    window.feed(b"jjjjk\x1b[A\x1b[A\x1b[A\x1b[Bj");
+    assert_eq!(window.getch(), Some(Key::Char('j')));
+    assert_eq!(window.take_repeats(Key::Char('j')), 3);
+    assert_eq!(window.take_repeats(Key::Char('j')), 0);
+    assert_eq!(window.getch(), Some(Key::Char('k')));
+    assert_eq!(window.getch(), Some(Key::Up));
+    assert_eq!(window.take_repeats(Key::Up), 2);
+    assert_eq!(window.getch(), Some(Key::Down));
+    assert_eq!(window.getch(), Some(Key::Char('j')));
+    assert_eq!(window.getch(), None);
+
+    // Not a repeat until the whole key has been read
+    window.feed(b"\x1b[A\x1b[");
+    assert_eq!(window.getch(), Some(Key::Up));
+    assert_eq!(window.take_repeats(Key::Up), 0);
+}
+
+#[test]
+fn repeats_are_capped() {
+    let mut window = Window::headless(Config::default(), (24, 80));
+    window.feed(&[b'j'; 2 * MAX_REPEATS + 10]);
+    assert_eq!(window.getch(), Some(Key::Char('j')));
+    assert_eq!(window.take_repeats(Key::Char('j')), MAX_REPEATS);
+    assert_eq!(window.getch(), Some(Key::Char('j')));
+    assert_eq!(window.take_repeats(Key::Char('j')), MAX_REPEATS);
+    assert_eq!(keys_left(&mut window), 8);
+}
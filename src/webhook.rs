@@ -0,0 +1,131 @@
+/// Webhooks, telling other services (e.g. Zapier, IFTTT or home automation)
+/// about tasks being added or completed.
+use crate::agenda;
+use crate::storage::curl_escape;
+use crate::todo::ToDo;
+use chrono::Local;
+use log::{info, warn};
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::thread;
+
+/// Something which happened to a task.
+#[derive(Debug, Clone, Copy)]
+pub enum Event {
+    Add,
+    Complete,
+}
+
+impl Event {
+    fn name(self) -> &'static str {
+        match self {
+            Event::Add => "add",
+            Event::Complete => "complete",
+        }
+    }
+}
+
+/// Describe an event as a JSON object, e.g.
+/// {"event":"complete","task":"Call Bob +work","priority":"A",...}
+pub fn payload(event: Event, todo: &ToDo, list: &str) -> String {
+    let priority = match todo.priority {
+        Some(priority) => string(&priority.letter().to_string()),
+        None => String::from("null"),
+    };
+    let due = match todo.due {
+        Some(due) => string(&due.to_string()),
+        None => String::from("null"),
+    };
+    let tags: Vec<String> = todo.tags().map(string).collect();
+    let contexts: Vec<String> = todo.contexts().map(string).collect();
+    let fields = [
+        ("event", string(event.name())),
+        ("task", string(&todo.task)),
+        ("priority", priority),
+        ("due", due),
+        ("tags", format!("[{}]", tags.join(","))),
+        ("contexts", format!("[{}]", contexts.join(","))),
+        ("project", string(&agenda::project(todo))),
+        ("notes", string(&todo.notes)),
+        ("list", string(list)),
+        ("time", string(&Local::now().to_rfc3339())),
+    ];
+    let fields: Vec<String> = fields
+        .iter()
+        .map(|(name, value)| format!("\"{}\":{}", name, value))
+        .collect();
+    format!("{{{}}}", fields.join(","))
+}
+
+/// Quote text as a JSON string.
+fn string(text: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if c.is_control() => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// POST a payload to each URL in the background, so the interface isn't
+/// held up waiting for the servers.
+pub fn post(urls: &[String], payload: String) {
+    let urls = urls.to_vec();
+    thread::spawn(move || {
+        for url in urls.iter() {
+            post_one(url, &payload);
+        }
+    });
+}
+
+/// POST a payload to a URL, passing the URL to curl on stdin so any secret
+/// in it doesn't appear in the process list.
+fn post_one(url: &str, payload: &str) {
+    let curl_config = format!(
+        "url = \"{}\"\nheader = \"Content-Type: application/json\"\ndata-binary = \"{}\"\n",
+        curl_escape(url),
+        curl_escape(payload),
+    );
+
+    let child = Command::new("curl")
+        .args([
+            "--silent",
+            "--show-error",
+            "--fail",
+            "--connect-timeout",
+            "10",
+        ])
+        .args(["--config", "-"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn();
+    let mut child = match child {
+        Ok(child) => child,
+        Err(err) => return warn!("Unable to run curl: {}", err),
+    };
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin
+            .write_all(curl_config.as_bytes())
+            .unwrap_or_else(|err| warn!("Unable to configure curl: {}", err));
+    }
+    match child.wait_with_output() {
+        Ok(output) if output.status.success() => info!("Called webhook."),
+        Ok(output) => warn!(
+            "Unable to call webhook: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ),
+        Err(err) => warn!("Unable to run curl: {}", err),
+    }
+}
+
+#[cfg(test)]
+mod tests;
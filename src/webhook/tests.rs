@@ -0,0 +1,58 @@
+/// Tests of the JSON sent to webhooks, which must stay valid whatever a
+/// task's title and notes hold.
+use super::{payload, string, Event};
+use crate::parse_list;
+use crate::storage::curl_escape;
+use std::rc::Rc;
+
+#[test]
+fn strings_escaped() {
+    assert_eq!(string(""), "\"\"");
+    assert_eq!(string("Call Bob"), "\"Call Bob\"");
+    assert_eq!(string("say \"hi\""), "\"say \\\"hi\\\"\"");
+    assert_eq!(string("C:\\todo"), "\"C:\\\\todo\"");
+    assert_eq!(string("two\nlines\r\n"), "\"two\\nlines\\r\\n\"");
+    assert_eq!(string("a\tb"), "\"a\\tb\"");
+    assert_eq!(
+        string("\u{1}\u{1b}[0m\u{7f}"),
+        "\"\\u0001\\u001b[0m\\u007f\""
+    );
+    // Only control characters are escaped, not the rest of Unicode
+    assert_eq!(string("caf\u{e9} \u{1f331}"), "\"caf\u{e9} \u{1f331}\"");
+}
+
+#[test]
+fn payload_fields() {
+    let root = parse_list(
+        "[ ] ( ) Garden\n    \
+         [ ] (A) Call \"Bob\" +work #urgent @phone due:2024-02-29\n    \
+         | back\\slash\n    \
+         | and a second line\n",
+    )
+    .unwrap();
+    let garden = Rc::clone(&root.borrow().sub_tasks[0]);
+    let task = Rc::clone(&garden.borrow().sub_tasks[0]);
+    let completed = payload(Event::Complete, &task.borrow(), "todo.txt");
+    let time = completed.find(",\"time\":\"").expect("no time in payload");
+    assert_eq!(
+        &completed[..time],
+        "{\"event\":\"complete\",\
+         \"task\":\"Call \\\"Bob\\\" +work #urgent @phone\",\
+         \"priority\":\"A\",\
+         \"due\":\"2024-02-29\",\
+         \"tags\":[\"work\",\"urgent\"],\
+         \"contexts\":[\"@phone\"],\
+         \"project\":\"Garden\",\
+         \"notes\":\"back\\\\slash\\nand a second line\",\
+         \"list\":\"todo.txt\""
+    );
+    assert!(completed.ends_with("\"}"));
+    // Passed to curl as a quoted string on one line of its config
+    assert!(!curl_escape(&completed).contains(['\n', '\r']));
+
+    let added = payload(Event::Add, &garden.borrow(), "");
+    assert!(added.starts_with(
+        "{\"event\":\"add\",\"task\":\"Garden\",\"priority\":null,\"due\":null,\
+         \"tags\":[],\"contexts\":[],\"project\":\"\",\"notes\":\"\","
+    ));
+}
@@ -0,0 +1,116 @@
+/// Interactive setup wizard, shown the first time yat is launched.
+use crate::cli::Options;
+use crate::config::{config_path, expand_home};
+use crate::theme::BUILT_IN as THEMES;
+use log::{info, warn};
+use std::fs::{create_dir_all, metadata, write};
+use std::io::{self, BufRead, Write};
+
+/// Built-in keybinding presets, as name and config.toml contents.
+const KEY_PRESETS: [(&str, &str); 2] = [
+    ("arrow keys", ""),
+    (
+        "vim keys (hjkl)",
+        include_str!("../configs/config-vi_keys.toml"),
+    ),
+];
+
+/// Default location of the save file.
+const DEFAULT_SAVE_FILE: &str = "~/.todo/save.txt";
+
+/// Check whether this is the first time yat has been launched, i.e. there
+/// is no configuration, no save file and none was given on the command line.
+pub fn first_run(options: &Options) -> bool {
+    if options.save_file.is_some()
+        || options.demo
+        || options.profile.is_some()
+        || !termion::is_tty(&io::stdin())
+    {
+        return false;
+    }
+
+    let config_exists = config_path().is_none_or(|f| metadata(f).is_ok());
+    let save_exists = expand_home(DEFAULT_SAVE_FILE).is_none_or(|f| metadata(f).is_ok());
+    !config_exists && !save_exists
+}
+
+/// Run the setup wizard, writing the chosen options to config.toml and
+/// creating the data directory.
+pub fn run() {
+    println!("Welcome to yat! Answer a few questions to set things up, or press");
+    println!("Enter to accept the default shown in brackets.\n");
+
+    let theme = choose("Theme", &THEMES);
+    let keys = choose("Keybindings", &KEY_PRESETS);
+    let save_file = ask("Save file location", DEFAULT_SAVE_FILE);
+
+    // Top-level keys have to come before the tables in the themes and presets
+    let mut buffer = String::from("# yat configuration, written by the setup wizard\n\n");
+    buffer.push_str(&format!("save_file = \"{}\"\n", escape_toml(&save_file)));
+    for text in [THEMES[theme].1, KEY_PRESETS[keys].1].iter() {
+        if !text.is_empty() {
+            buffer.push('\n');
+            buffer.push_str(text.trim_end());
+            buffer.push('\n');
+        }
+    }
+
+    if let Some(filename) = config_path() {
+        if let Some(dir) = filename.parent() {
+            create_dir_all(dir).unwrap_or_else(|err| {
+                warn!("Unable to create directory {}: {}", dir.display(), err);
+            });
+        }
+        match write(&filename, buffer) {
+            Ok(_) => info!("Configuration written to {}.", filename.display()),
+            Err(err) => warn!("Unable to write configuration: {}", err),
+        }
+    }
+
+    if let Some(dir) = expand_home(&save_file).as_ref().and_then(|f| f.parent()) {
+        create_dir_all(dir).unwrap_or_else(|err| {
+            warn!("Unable to create data directory {}: {}", dir.display(), err);
+        });
+    }
+}
+
+/// Ask the user to choose one of a list of named options.
+fn choose(prompt: &str, options: &[(&str, &str)]) -> usize {
+    println!("{}:", prompt);
+    for (i, (name, _)) in options.iter().enumerate() {
+        println!("  {}) {}", i + 1, name);
+    }
+
+    loop {
+        let answer = ask("Choice", "1");
+        match answer.parse::<usize>() {
+            Ok(n) if n >= 1 && n <= options.len() => {
+                println!();
+                return n - 1;
+            }
+            _ => println!("Please enter a number from 1 to {}.", options.len()),
+        }
+    }
+}
+
+/// Ask the user a question, returning the default if no answer is given.
+fn ask(prompt: &str, default: &str) -> String {
+    print!("{} [{}]: ", prompt, default);
+    io::stdout().flush().unwrap_or_else(|err| {
+        warn!("Unable to flush stdout: {}", err);
+    });
+
+    let mut answer = String::new();
+    if let Err(err) = io::stdin().lock().read_line(&mut answer) {
+        warn!("Unable to read answer: {}", err);
+    }
+    match answer.trim() {
+        "" => String::from(default),
+        text => String::from(text),
+    }
+}
+
+/// Escape a string for use in a TOML basic string.
+fn escape_toml(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
@@ -0,0 +1,93 @@
+/// Long operations, such as uploading the list or exporting it, run on a
+/// worker thread so that the screen keeps being drawn and escape can
+/// cancel them. The operation reports its progress, and checks whether it
+/// has been cancelled, through a shared Progress.
+use log::warn;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+/// Progress of an operation, shared between the worker thread doing it and
+/// the interface showing it.
+#[derive(Clone, Default)]
+pub struct Progress {
+    done: Arc<AtomicUsize>,
+    /// Amount of work to do in all, or 0 if not known.
+    total: Arc<AtomicUsize>,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl Progress {
+    /// Progress of an operation which can't be cancelled, e.g. when run
+    /// from the command line.
+    pub fn new() -> Progress {
+        Progress::default()
+    }
+
+    /// Record how much of the work has been done, out of how much.
+    pub fn set(&self, done: usize, total: usize) {
+        self.done.store(done, Ordering::Relaxed);
+        self.total.store(total, Ordering::Relaxed);
+    }
+
+    /// Whether the operation should stop as soon as it can.
+    pub fn cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    /// Description of the progress, e.g. "40%", or "" if the total isn't
+    /// known.
+    pub fn describe(&self) -> String {
+        let done = self.done.load(Ordering::Relaxed);
+        match self.total.load(Ordering::Relaxed) {
+            0 => String::new(),
+            total => format!("{}%", done.min(total) * 100 / total),
+        }
+    }
+}
+
+/// Handle on an operation running on a worker thread.
+pub struct Worker<T> {
+    progress: Progress,
+    result: Receiver<T>,
+}
+
+impl<T: Send + 'static> Worker<T> {
+    /// Start an operation on a new thread.
+    pub fn start(operation: impl FnOnce(&Progress) -> T + Send + 'static) -> Worker<T> {
+        let progress = Progress::new();
+        let shared = progress.clone();
+        let (sender, result) = mpsc::channel();
+        thread::spawn(move || {
+            // The interface stops listening once it has cancelled
+            if sender.send(operation(&shared)).is_err() && !shared.cancelled() {
+                warn!("Finished an operation nobody was waiting for.");
+            }
+        });
+        Worker { progress, result }
+    }
+
+    /// Progress of the operation so far.
+    pub fn progress(&self) -> &Progress {
+        &self.progress
+    }
+
+    /// Result of the operation, waiting at most timeout for it to finish,
+    /// or None if it is still running. A worker thread which panicked gives
+    /// Some(Err).
+    pub fn wait(&self, timeout: Duration) -> Option<Result<T, ()>> {
+        match self.result.recv_timeout(timeout) {
+            Ok(result) => Some(Ok(result)),
+            Err(RecvTimeoutError::Timeout) => None,
+            Err(RecvTimeoutError::Disconnected) => Some(Err(())),
+        }
+    }
+
+    /// Ask the operation to stop. Its result, if it still finishes, is
+    /// thrown away.
+    pub fn cancel(self) {
+        self.progress.cancelled.store(true, Ordering::Relaxed);
+    }
+}